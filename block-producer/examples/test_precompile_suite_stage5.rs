@@ -0,0 +1,175 @@
+//! 第五阶段集成测试：完整预编译合约套件
+//!
+//! `test_revm_deploy_contract_stage2.rs` 只覆盖了 ecrecover（0x01）。
+//! 这里沿用同样的“构造交易 -> `TransactionExecutor::execute` -> 校验输出”
+//! 模式，把以太坊标准预编译地址 0x02-0x09 都跑一遍：
+//! - SHA-256 (0x02)、RIPEMD-160 (0x03)、identity (0x04)：用已知的空输入/
+//!   固定输入摘要做断言。
+//! - modexp (0x05)：用一组可以手算验证的小整数（3^2 mod 5 = 4）。
+//! - bn128 ecAdd/ecMul (0x06/0x07)、ecPairing (0x08)：用无穷远点/空配对这类
+//!   边界输入，不依赖外部曲线运算库就能断言期望输出。
+//! - blake2f (0x09)：只用 `rounds = 12` 调用一次，校验调用成功、输出长度
+//!   正确——gas 与 `rounds` 的关系不做断言；压缩函数本身的输出没有在这里手算
+//!   验证，构造一个带哈希的测试向量需要参照实现，不在这个集成测试的范围内。
+//!
+//! 这些预编译从 Istanbul 规范起就在以太坊主网启用；`RevmAdapter` 用
+//! `SpecId::SHANGHAI` 构建 EVM，所以它们已经随 REVM 默认的预编译集合一起
+//! 加载，不需要在 `TransactionExecutor`/`RevmAdapter` 里再额外写分发代码——
+//! 跟 ecrecover 走的是同一条路径。
+
+use block_producer::db::{RedbStateDB, StateDatabase};
+use block_producer::executor::TransactionExecutor;
+use block_producer::schema::{Account, Transaction};
+use alloy_primitives::{address, U256};
+use revm::primitives::BlockEnv;
+
+const CALLER: &str = "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb";
+
+fn main() {
+    println!("🧪 开始测试完整预编译合约套件（第五阶段）\n");
+
+    run("SHA-256 (0x02)", "./data/test_precompile_sha256.redb", test_sha256);
+    run("RIPEMD-160 (0x03)", "./data/test_precompile_ripemd160.redb", test_ripemd160);
+    run("identity (0x04)", "./data/test_precompile_identity.redb", test_identity);
+    run("modexp (0x05)", "./data/test_precompile_modexp.redb", test_modexp);
+    run("bn128 ecAdd (0x06)", "./data/test_precompile_ecadd.redb", test_ecadd);
+    run("bn128 ecMul (0x07)", "./data/test_precompile_ecmul.redb", test_ecmul);
+    run("bn128 ecPairing (0x08)", "./data/test_precompile_ecpairing.redb", test_ecpairing);
+    run("blake2f (0x09)", "./data/test_precompile_blake2f.redb", test_blake2f);
+
+    println!("\n🎉 第五阶段（完整预编译合约套件）测试完成！");
+}
+
+fn run(label: &str, db_path: &str, test: fn(RedbStateDB)) {
+    println!("📌 {label}");
+    std::fs::create_dir_all("./data").unwrap();
+    let _ = std::fs::remove_file(db_path);
+    let db = RedbStateDB::new(db_path).unwrap();
+    test(db);
+    println!();
+}
+
+/// 构造一笔调用 `to` 预编译地址、携带 `input` 数据的交易并执行，返回输出
+fn call_precompile(db: RedbStateDB, to: &str, input: &[u8]) -> Option<Vec<u8>> {
+    let mut db = db;
+    let caller = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+    let mut caller_account = Account::with_balance(U256::from(10u64) * U256::from(1_000_000_000_000_000_000u64));
+    caller_account.nonce = 0;
+    db.set_account(&caller, caller_account).unwrap();
+
+    let tx = Transaction {
+        from: CALLER.to_string(),
+        to: Some(to.to_string()),
+        value: "0x0".to_string(),
+        data: format!("0x{}", hex::encode(input)),
+        gas: "0x186A0".to_string(), // 100,000 gas
+        nonce: "0x0".to_string(),
+        hash: Some(format!("0xprecompiletest{to}")),
+        gas_price: Some("0x3B9ACA00".to_string()),
+        chain_id: Some(1),
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
+    };
+
+    let mut executor = TransactionExecutor::new(db);
+    executor.db_mut().begin_transaction().unwrap();
+    let result = executor.execute(&tx, BlockEnv::default()).unwrap();
+    executor.db_mut().commit_transaction().unwrap();
+
+    assert!(result.success, "预编译调用 {to} 失败");
+    println!("   ✓ gas_used = {}", result.gas_used);
+
+    result.output.map(|bytes| bytes.to_vec())
+}
+
+fn test_sha256(db: RedbStateDB) {
+    // sha256("") = e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000002", &[]).unwrap();
+    assert_eq!(
+        hex::encode(&output),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    println!("   ✓ sha256(\"\") 摘要匹配");
+}
+
+fn test_ripemd160(db: RedbStateDB) {
+    // ripemd160("") = 9c1185a5c5e9fc54612808977ee8f548b2258d31，输出左填充到 32 字节
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000003", &[]).unwrap();
+    assert_eq!(output.len(), 32);
+    assert_eq!(
+        hex::encode(&output[12..]),
+        "9c1185a5c5e9fc54612808977ee8f548b2258d31"
+    );
+    assert!(output[..12].iter().all(|&b| b == 0), "RIPEMD-160 输出必须左填充为零");
+    println!("   ✓ ripemd160(\"\") 摘要匹配（左填充）");
+}
+
+fn test_identity(db: RedbStateDB) {
+    let input = b"avatar-walrus identity precompile".to_vec();
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000004", &input).unwrap();
+    assert_eq!(output, input, "identity 预编译必须原样返回输入");
+    println!("   ✓ identity 原样返回输入");
+}
+
+fn test_modexp(db: RedbStateDB) {
+    // base=3, exp=2, mod=5 => 3^2 mod 5 = 4；每段长度各 1 字节
+    let mut input = Vec::new();
+    input.extend_from_slice(&U256::from(1u64).to_be_bytes::<32>()); // base_len
+    input.extend_from_slice(&U256::from(1u64).to_be_bytes::<32>()); // exp_len
+    input.extend_from_slice(&U256::from(1u64).to_be_bytes::<32>()); // mod_len
+    input.push(3); // base
+    input.push(2); // exp
+    input.push(5); // modulus
+
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000005", &input).unwrap();
+    assert_eq!(output, vec![4u8], "3^2 mod 5 必须等于 4");
+    println!("   ✓ modexp(3, 2, 5) = 4");
+}
+
+fn test_ecadd(db: RedbStateDB) {
+    // 无穷远点（0,0）加无穷远点（0,0）= 无穷远点（0,0）
+    let input = [0u8; 128];
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000006", &input).unwrap();
+    assert_eq!(output, vec![0u8; 64], "zero + zero 在 bn128 上必须仍是无穷远点");
+    println!("   ✓ ecAdd(0, 0) = 0");
+}
+
+fn test_ecmul(db: RedbStateDB) {
+    // 无穷远点乘任意标量仍是无穷远点
+    let mut input = [0u8; 96];
+    input[95] = 7; // 标量 = 7，点坐标（前 64 字节）保持为零
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000007", &input).unwrap();
+    assert_eq!(output, vec![0u8; 64], "0 * 7 在 bn128 上必须仍是无穷远点");
+    println!("   ✓ ecMul(0, 7) = 0");
+}
+
+fn test_ecpairing(db: RedbStateDB) {
+    // 空输入（零个配对）按惯例视为恒真，返回左填充的 1
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000008", &[]).unwrap();
+    let mut expected = vec![0u8; 32];
+    expected[31] = 1;
+    assert_eq!(output, expected, "空配对列表必须返回 true");
+    println!("   ✓ ecPairing([]) = true");
+}
+
+fn test_blake2f(db: RedbStateDB) {
+    // blake2f 输入：4 字节 rounds + 64 字节 h + 128 字节 m + 16 字节 t + 1 字节 f
+    // 这里只验证调用成功、输出是 64 字节的压缩状态，不手算具体哈希值——
+    // 构造一组可验证的压缩输出需要参照实现，超出这个集成测试的范围。
+    let rounds: u32 = 12;
+    let mut input = Vec::with_capacity(213);
+    input.extend_from_slice(&rounds.to_be_bytes());
+    input.extend_from_slice(&[0u8; 64]); // h
+    input.extend_from_slice(&[0u8; 128]); // m
+    input.extend_from_slice(&[0u8; 16]); // t0 || t1
+    input.push(1); // f = true（最后一块）
+
+    assert_eq!(input.len(), 213, "blake2f 输入必须是 213 字节");
+    let output = call_precompile(db, "0x0000000000000000000000000000000000000009", &input).unwrap();
+    assert_eq!(output.len(), 64, "blake2f 必须返回 64 字节的压缩状态");
+    println!("   ✓ blake2f(rounds=12) 返回 64 字节状态");
+}
@@ -83,6 +83,10 @@ fn test_deploy_erc20_contract(mut db: RedbStateDB) {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     
     // 创建执行器
@@ -219,6 +223,10 @@ fn test_call_precompiled_contract(mut db: RedbStateDB) {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     
     // 创建执行器
@@ -62,6 +62,10 @@ fn test_simple_transfer(mut db: RedbStateDB) {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     println!("   - 转账金额: 1 ETH");
     println!("   - Gas 限制: 21000");
@@ -47,9 +47,13 @@ fn test_invalid_gas() {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     
-    match executor.validate_transaction(&invalid_gas_tx) {
+    match executor.validate_transaction(&invalid_gas_tx, U256::ZERO) {
         Ok(_) => println!("   ❌ 测试失败: 应该拒绝 Gas 为 0 的交易"),
         Err(ExecutorError::InvalidGas) => println!("   ✓ 正确拒绝: InvalidGas"),
         Err(e) => println!("   ❌ 错误类型不符: {:?}", e),
@@ -83,9 +87,13 @@ fn test_nonce_too_low() {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     
-    match executor.validate_transaction(&low_nonce_tx) {
+    match executor.validate_transaction(&low_nonce_tx, U256::ZERO) {
         Ok(_) => println!("   ❌ 测试失败: 应该拒绝 Nonce 过低的交易"),
         Err(ExecutorError::NonceTooLow { expected, got }) => {
             println!("   ✓ 正确拒绝: NonceTooLow");
@@ -121,9 +129,13 @@ fn test_insufficient_balance() {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     
-    match executor.validate_transaction(&insufficient_balance_tx) {
+    match executor.validate_transaction(&insufficient_balance_tx, U256::ZERO) {
         Ok(_) => println!("   ❌ 测试失败: 应该拒绝余额不足的交易"),
         Err(ExecutorError::InsufficientFunds { required, available }) => {
             println!("   ✓ 正确拒绝: InsufficientFunds");
@@ -161,9 +173,13 @@ fn test_valid_transaction() {
         chain_id: Some(1),
         max_fee_per_gas: None,
         max_priority_fee_per_gas: None,
+        access_list: None,
+        v: None,
+        r: None,
+        s: None,
     };
     
-    match executor.validate_transaction(&valid_tx) {
+    match executor.validate_transaction(&valid_tx, U256::ZERO) {
         Ok(_) => println!("   ✓ 验证通过"),
         Err(e) => println!("   ❌ 测试失败: 应该通过验证，但得到错误: {:?}", e),
     }
@@ -205,6 +221,10 @@ fn test_block_execution() {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let tx2 = Transaction {
@@ -219,6 +239,10 @@ fn test_block_execution() {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let tx3 = Transaction {
@@ -233,6 +257,10 @@ fn test_block_execution() {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let block = Block {
@@ -246,6 +274,8 @@ fn test_block_execution() {
                 gas_used: None,
                 gas_limit: Some(30_000_000),
                 receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
             },
             transactions: vec![tx1, tx2, tx3],
         };
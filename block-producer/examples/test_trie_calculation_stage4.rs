@@ -76,6 +76,10 @@ async fn test_state_root_calculation() {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         },
     ]);
     
@@ -146,6 +150,10 @@ async fn test_transactions_and_receipts_root() {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         },
         Transaction {
             from: "0x0000000000000000000000000000000000000001".to_string(),
@@ -159,6 +167,10 @@ async fn test_transactions_and_receipts_root() {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         },
         Transaction {
             from: "0x0000000000000000000000000000000000000001".to_string(),
@@ -172,6 +184,10 @@ async fn test_transactions_and_receipts_root() {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         },
     ];
     
@@ -241,6 +257,10 @@ async fn test_full_block_assembly() {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         },
         // 另一笔转账
         Transaction {
@@ -255,6 +275,10 @@ async fn test_full_block_assembly() {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         },
     ];
     
@@ -377,6 +401,8 @@ fn create_test_block(transactions: Vec<Transaction>) -> Block {
             gas_used: None,
             gas_limit: Some(30000000),
             receipts_root: None,
+            logs_bloom: None,
+            base_fee_per_gas: None,
         },
         transactions,
     }
@@ -1,13 +1,24 @@
 //! 状态缓存层
-//! 
-//! 使用 LRU Cache 减少对 Walrus 的读取次数
+//!
+//! 使用 LRU Cache 减少对 Walrus 的读取次数；同时作为一个写回（write-back）覆盖层，
+//! 将 `put` 标记为脏条目，由 `flush` 批量落盘，而不是每次写入都直接打到 Walrus。
 
 use alloy_primitives::{Address, U256, B256, Bytes};
 use lru::LruCache;
 use parking_lot::RwLock;
+use std::collections::HashMap;
 use std::num::NonZeroUsize;
+use std::time::Instant;
+use crate::db::DbError;
+use crate::metrics::{
+    BATCH_SIZE, CACHE_HITS, CACHE_MISSES, CACHE_OCCUPANCY, WALRUS_READ_DURATION,
+    WALRUS_WRITE_DURATION,
+};
 use crate::schema::Account;
 
+/// 脏条目写回函数：将一个缓存键值对持久化到后端存储（例如 Walrus）
+pub type CacheWriter = Box<dyn Fn(&CacheKey, &CacheValue) -> Result<(), DbError> + Send + Sync>;
+
 /// 缓存键类型
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum CacheKey {
@@ -15,6 +26,21 @@ pub enum CacheKey {
     Storage(Address, U256),
     Code(B256),
     BlockHash(u64),
+    /// 增量状态树节点，键是从树根到该节点的 nibble 路径（见 `crate::trie::sparse`）
+    TrieNode(Vec<u8>),
+}
+
+impl CacheKey {
+    /// 变体名称，用作 `CACHE_HITS`/`CACHE_MISSES` 指标的 `key_kind` 标签
+    fn label(&self) -> &'static str {
+        match self {
+            CacheKey::Account(_) => "account",
+            CacheKey::Storage(_, _) => "storage",
+            CacheKey::Code(_) => "code",
+            CacheKey::BlockHash(_) => "block_hash",
+            CacheKey::TrieNode(_) => "trie_node",
+        }
+    }
 }
 
 /// 缓存值类型
@@ -24,6 +50,7 @@ pub enum CacheValue {
     Storage(U256),
     Code(Bytes),
     BlockHash(B256),
+    TrieNode(B256),
 }
 
 impl CacheValue {
@@ -33,80 +60,203 @@ impl CacheValue {
             _ => panic!("Expected Account, got {:?}", self),
         }
     }
-    
+
     pub fn as_storage(&self) -> U256 {
         match self {
             CacheValue::Storage(val) => *val,
             _ => panic!("Expected Storage, got {:?}", self),
         }
     }
-    
+
     pub fn as_code(&self) -> &Bytes {
         match self {
             CacheValue::Code(code) => code,
             _ => panic!("Expected Code, got {:?}", self),
         }
     }
-    
+
     pub fn as_block_hash(&self) -> B256 {
         match self {
             CacheValue::BlockHash(hash) => *hash,
             _ => panic!("Expected BlockHash, got {:?}", self),
         }
     }
+
+    pub fn as_trie_node(&self) -> B256 {
+        match self {
+            CacheValue::TrieNode(hash) => *hash,
+            _ => panic!("Expected TrieNode, got {:?}", self),
+        }
+    }
 }
 
 /// 状态缓存
-/// 
-/// 使用 LRU 策略缓存账户、存储、代码、区块哈希
+///
+/// 使用 LRU 策略缓存账户、存储、代码、区块哈希；同时维护一张独立的脏条目表，
+/// 让写入先留在内存里、由 [`flush`](Self::flush) 批量落盘，淘汰脏条目时也会
+/// 先写回后端，避免静默丢失尚未持久化的变更。
 pub struct StateCache {
     cache: RwLock<LruCache<CacheKey, CacheValue>>,
+    /// 尚未落盘的变更；键与 `cache` 中的键同义，但生命周期独立于 LRU 淘汰
+    dirty: RwLock<HashMap<CacheKey, CacheValue>>,
+    /// 落盘后端（例如写入 Walrus）；未设置时，`flush` 返回错误，被淘汰的脏条目
+    /// 不写回，只留在 `dirty` 表中
+    writer: Option<CacheWriter>,
 }
 
 impl StateCache {
-    /// 创建缓存（默认容量 10000）
+    /// 创建缓存（默认容量 10000，不支持写回）
     pub fn new() -> Self {
         Self::with_capacity(10000)
     }
-    
-    /// 创建指定容量的缓存
+
+    /// 创建指定容量的缓存（不支持写回）
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             cache: RwLock::new(LruCache::new(
                 NonZeroUsize::new(capacity).unwrap()
             )),
+            dirty: RwLock::new(HashMap::new()),
+            writer: None,
         }
     }
-    
-    /// 获取缓存值
+
+    /// 创建支持写回的缓存：淘汰脏条目或调用 [`flush`](Self::flush) 时，
+    /// 用 `writer` 把条目持久化到后端存储
+    pub fn with_writer(capacity: usize, writer: CacheWriter) -> Self {
+        Self {
+            cache: RwLock::new(LruCache::new(
+                NonZeroUsize::new(capacity).unwrap()
+            )),
+            dirty: RwLock::new(HashMap::new()),
+            writer: Some(writer),
+        }
+    }
+
+    /// 获取缓存值，命中/未命中分别计入 `CACHE_HITS`/`CACHE_MISSES`（按 `key_kind` 标签区分）
     pub fn get(&self, key: &CacheKey) -> Option<CacheValue> {
-        self.cache.write().get(key).cloned()
+        let value = self.cache.write().get(key).cloned();
+
+        if value.is_some() {
+            CACHE_HITS.with_label_values(&[key.label()]).inc();
+        } else {
+            CACHE_MISSES.with_label_values(&[key.label()]).inc();
+        }
+
+        value
     }
-    
-    /// 设置缓存值
+
+    /// 获取缓存值，未命中时调用 `loader` 加载并填充缓存（不标记为脏，
+    /// 因为加载的是已持久化的值，不需要被 `flush` 再次写回）。
+    /// `loader` 的耗时计入 `WALRUS_READ_DURATION`，与写路径的 `WALRUS_WRITE_DURATION` 对应。
+    pub fn get_or_load<F>(&self, key: &CacheKey, loader: F) -> Result<CacheValue, DbError>
+    where
+        F: FnOnce() -> Result<CacheValue, DbError>,
+    {
+        if let Some(value) = self.get(key) {
+            return Ok(value);
+        }
+
+        let start = Instant::now();
+        let value = loader()?;
+        WALRUS_READ_DURATION
+            .with_label_values(&[key.label()])
+            .observe(start.elapsed().as_secs_f64());
+
+        self.cache.write().put(key.clone(), value.clone());
+        CACHE_OCCUPANCY.set(self.cache.read().len() as i64);
+        Ok(value)
+    }
+
+    /// 设置缓存值并标记为脏，留待 [`flush`](Self::flush) 批量落盘
     pub fn put(&self, key: CacheKey, value: CacheValue) {
-        self.cache.write().put(key, value);
+        self.dirty.write().insert(key.clone(), value.clone());
+
+        if let Some((evicted_key, _)) = self.cache.write().push(key, value) {
+            self.writeback_if_dirty(&evicted_key);
+        }
+
+        CACHE_OCCUPANCY.set(self.cache.read().len() as i64);
     }
-    
-    /// 移除缓存
+
+    /// 移除缓存（不写回：调用方需要自行决定丢弃前是否落盘）
     pub fn remove(&self, key: &CacheKey) {
         self.cache.write().pop(key);
+        self.dirty.write().remove(key);
+        CACHE_OCCUPANCY.set(self.cache.read().len() as i64);
     }
-    
-    /// 清空缓存
+
+    /// 清空缓存（丢弃尚未落盘的脏数据，调用前应先 `flush`）
     pub fn clear(&self) {
         self.cache.write().clear();
+        self.dirty.write().clear();
+        CACHE_OCCUPANCY.set(0);
     }
-    
+
     /// 获取缓存大小
     pub fn len(&self) -> usize {
         self.cache.read().len()
     }
-    
+
     /// 检查缓存是否为空
     pub fn is_empty(&self) -> bool {
         self.cache.read().is_empty()
     }
+
+    /// 当前脏条目的键集合，镜像 `StateDatabase::get_changed_accounts` 的用法：
+    /// 调用方可以据此判断哪些条目尚未落盘
+    pub fn dirty_keys(&self) -> Vec<CacheKey> {
+        self.dirty.read().keys().cloned().collect()
+    }
+
+    /// 将所有脏条目批量写回后端存储，记录批大小与耗时指标
+    ///
+    /// 返回实际落盘的条目数。没有注册 `writer` 时返回 `DbError::Walrus`，
+    /// 因为没有后端可写。
+    pub fn flush(&self) -> Result<usize, DbError> {
+        let writer = self.writer.as_ref().ok_or_else(|| {
+            DbError::Walrus("StateCache::flush called without a registered writer".to_string())
+        })?;
+
+        let entries: Vec<(CacheKey, CacheValue)> = {
+            let mut dirty = self.dirty.write();
+            if dirty.is_empty() {
+                return Ok(0);
+            }
+            dirty.drain().collect()
+        };
+
+        let start = Instant::now();
+        for (key, value) in &entries {
+            writer(key, value)?;
+        }
+
+        WALRUS_WRITE_DURATION
+            .with_label_values(&["state_cache"])
+            .observe(start.elapsed().as_secs_f64());
+        BATCH_SIZE
+            .with_label_values(&["state_cache_flush"])
+            .observe(entries.len() as f64);
+
+        Ok(entries.len())
+    }
+
+    /// LRU 淘汰了 `key` 对应的条目：如果它还没落盘，先写回再从脏表移除
+    fn writeback_if_dirty(&self, key: &CacheKey) {
+        let Some(writer) = self.writer.as_ref() else {
+            return;
+        };
+
+        let Some(value) = self.dirty.write().remove(key) else {
+            return;
+        };
+
+        if let Err(e) = writer(key, &value) {
+            tracing::warn!("cache eviction writeback failed for {:?}: {}", key, e);
+            // 写回失败时放回脏表，避免静默丢失待写数据
+            self.dirty.write().insert(key.clone(), value);
+        }
+    }
 }
 
 impl Default for StateCache {
@@ -147,4 +297,91 @@ mod tests {
         assert!(cache.get(&CacheKey::Account(addr)).is_none());
         assert!(cache.get(&CacheKey::Account(addr3)).is_some());
     }
+
+    #[test]
+    fn test_flush_drains_dirty_entries_via_writer() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let written = Arc::new(AtomicUsize::new(0));
+        let written_clone = written.clone();
+        let cache = StateCache::with_writer(
+            10,
+            Box::new(move |_key, _value| {
+                written_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }),
+        );
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        cache.put(CacheKey::Account(addr), CacheValue::Account(Account::default()));
+
+        assert_eq!(cache.dirty_keys().len(), 1);
+
+        let flushed = cache.flush().unwrap();
+        assert_eq!(flushed, 1);
+        assert_eq!(written.load(Ordering::SeqCst), 1);
+
+        // flush 之后脏表应为空，重复 flush 不应再调用 writer
+        assert!(cache.dirty_keys().is_empty());
+        assert_eq!(cache.flush().unwrap(), 0);
+        assert_eq!(written.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_flush_without_writer_errors() {
+        let cache = StateCache::with_capacity(10);
+        let addr = address!("0000000000000000000000000000000000000001");
+        cache.put(CacheKey::Account(addr), CacheValue::Account(Account::default()));
+
+        assert!(cache.flush().is_err());
+    }
+
+    #[test]
+    fn test_eviction_writes_back_dirty_entry() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let written = Arc::new(AtomicUsize::new(0));
+        let written_clone = written.clone();
+        let cache = StateCache::with_writer(
+            1,
+            Box::new(move |_key, _value| {
+                written_clone.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }),
+        );
+
+        let addr1 = address!("0000000000000000000000000000000000000001");
+        let addr2 = address!("0000000000000000000000000000000000000002");
+
+        cache.put(CacheKey::Account(addr1), CacheValue::Account(Account::default()));
+        // 容量为 1，插入第二个条目会淘汰第一个，触发写回
+        cache.put(CacheKey::Account(addr2), CacheValue::Account(Account::default()));
+
+        assert_eq!(written.load(Ordering::SeqCst), 1);
+        // 被淘汰的条目已经写回，不应再出现在脏表中
+        assert!(!cache.dirty_keys().contains(&CacheKey::Account(addr1)));
+    }
+
+    #[test]
+    fn test_get_or_load_populates_cache_without_marking_dirty() {
+        let cache = StateCache::with_capacity(10);
+        let addr = address!("0000000000000000000000000000000000000001");
+        let key = CacheKey::Account(addr);
+
+        let value = cache
+            .get_or_load(&key, || Ok(CacheValue::Account(Account::default())))
+            .unwrap();
+        assert_eq!(value.as_account().nonce, 0);
+
+        // 已经加载进缓存，第二次不应再调用 loader
+        let loaded_again = cache
+            .get_or_load(&key, || panic!("loader should not be called on a hit"))
+            .unwrap();
+        assert_eq!(loaded_again.as_account().nonce, 0);
+
+        // 通过 loader 填充的数据是已持久化的值，不需要 flush
+        assert!(cache.dirty_keys().is_empty());
+    }
 }
@@ -0,0 +1,210 @@
+//! 后台提交队列
+//!
+//! `commit_transaction` 曾经在调用方线程上同步完成 bincode 编码与 Walrus
+//! 追加写入，阻塞 EVM 执行主线程。`CommitQueue` 把这两步搬到后台工作线程：
+//! 已提交的 `TransactionBuffer` 先进入 `staged` 阶段；工作线程把它从
+//! `staged` 取出、在后台并行完成 bincode 编码（`serializing` 阶段），再把
+//! 编码结果放入按提交顺序排好的 `appending` 阶段等待写入 Walrus。
+//!
+//! Walrus 要求按写入顺序追加，因此每个缓冲在提交时都会拿到一个单调递增
+//! 的序号；即使多个线程并行完成了序列化，真正的追加写入也只会按序号
+//! 从小到大依次发生。
+
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+use parking_lot::{Condvar, Mutex};
+
+use crate::db::traits::{DbError, TransactionBuffer};
+
+/// 一条缓冲序列化后得到的 `(topic, payload)` 列表
+type SerializedEntries = Vec<(String, Vec<u8>)>;
+
+/// 把一个已提交的 `TransactionBuffer` 编码成若干 `(topic, payload)` 对
+pub type SerializeFn =
+    Box<dyn Fn(&TransactionBuffer) -> Result<SerializedEntries, DbError> + Send + Sync>;
+
+/// 按提交顺序把序列化结果追加写入 Walrus
+pub type AppendFn = Box<dyn Fn(&SerializedEntries) -> Result<(), DbError> + Send + Sync>;
+
+struct StagedJob {
+    seq: u64,
+    buffer: TransactionBuffer,
+}
+
+struct Shared {
+    staged: Mutex<VecDeque<StagedJob>>,
+    more_to_commit: Condvar,
+    /// seq -> 已序列化、等待按序追加的条目
+    appending: Mutex<BTreeMap<u64, SerializedEntries>>,
+    /// 下一个允许真正写入 Walrus 的序号
+    next_append_seq: AtomicU64,
+    /// 已提交但尚未完成追加写入的任务数；归零即队列清空
+    pending: AtomicU64,
+    empty: Condvar,
+    empty_lock: Mutex<()>,
+    last_error: Mutex<Option<DbError>>,
+    shutdown: AtomicBool,
+    serialize: SerializeFn,
+    append: AppendFn,
+}
+
+impl Shared {
+    fn worker_loop(self: Arc<Self>) {
+        loop {
+            let job = {
+                let mut staged = self.staged.lock();
+                loop {
+                    if let Some(job) = staged.pop_front() {
+                        break job;
+                    }
+                    if self.shutdown.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    self.more_to_commit.wait(&mut staged);
+                }
+            };
+
+            match (self.serialize)(&job.buffer) {
+                Ok(entries) => {
+                    self.appending.lock().insert(job.seq, entries);
+                    self.drain_appending();
+                }
+                Err(e) => {
+                    *self.last_error.lock() = Some(e);
+                    // 以空条目占住这个序号，排在它后面的缓冲才能继续按序追加
+                    self.appending.lock().insert(job.seq, Vec::new());
+                    self.drain_appending();
+                }
+            }
+        }
+    }
+
+    /// 把所有已经轮到的（即序号等于 `next_append_seq`）条目依次写入 Walrus
+    ///
+    /// 只要当前完成的是队列里积压最久的那个序号，本次调用就会顺带把此前
+    /// 已经序列化完毕、但因排在它后面而暂缓写入的条目一并追加掉。
+    fn drain_appending(&self) {
+        loop {
+            let next = self.next_append_seq.load(Ordering::SeqCst);
+            let entries = {
+                let mut appending = self.appending.lock();
+                match appending.remove(&next) {
+                    Some(entries) => entries,
+                    None => return,
+                }
+            };
+
+            if let Err(e) = (self.append)(&entries) {
+                *self.last_error.lock() = Some(e);
+            }
+
+            self.next_append_seq.fetch_add(1, Ordering::SeqCst);
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+            let _guard = self.empty_lock.lock();
+            self.empty.notify_all();
+        }
+    }
+}
+
+/// 后台提交管线
+///
+/// `submit` 把一个已提交事务的缓冲交给后台线程池并立刻返回；`flush`/`drain`
+/// 会阻塞到目前为止提交的全部缓冲都完成序列化与追加写入，供需要强持久化
+/// 保证的调用方使用。
+pub struct CommitQueue {
+    shared: Arc<Shared>,
+    next_seq: AtomicU64,
+    workers: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl CommitQueue {
+    /// 创建提交队列并启动工作线程池
+    ///
+    /// 线程数沿用上游 `BlockQueue` 的估算方式：`max(可用并行度, 3) - 2`，
+    /// 在单核/双核机器上至少保留 1 个线程。
+    pub fn new(serialize: SerializeFn, append: AppendFn) -> Self {
+        let worker_count = Self::worker_count();
+
+        let shared = Arc::new(Shared {
+            staged: Mutex::new(VecDeque::new()),
+            more_to_commit: Condvar::new(),
+            appending: Mutex::new(BTreeMap::new()),
+            next_append_seq: AtomicU64::new(0),
+            pending: AtomicU64::new(0),
+            empty: Condvar::new(),
+            empty_lock: Mutex::new(()),
+            last_error: Mutex::new(None),
+            shutdown: AtomicBool::new(false),
+            serialize,
+            append,
+        });
+
+        let workers = (0..worker_count)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared.worker_loop())
+            })
+            .collect();
+
+        Self {
+            shared,
+            next_seq: AtomicU64::new(0),
+            workers: Mutex::new(workers),
+        }
+    }
+
+    fn worker_count() -> usize {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        cpus.max(3) - 2
+    }
+
+    /// 把一个已提交的缓冲交给后台线程，立即返回
+    pub fn submit(&self, buffer: TransactionBuffer) {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        self.shared.pending.fetch_add(1, Ordering::SeqCst);
+
+        self.shared.staged.lock().push_back(StagedJob { seq, buffer });
+        self.shared.more_to_commit.notify_one();
+    }
+
+    /// 阻塞直到目前为止提交的所有缓冲都完成序列化与追加写入
+    ///
+    /// 若期间有任何一条缓冲序列化/写入失败，返回其中最近的一个错误。
+    pub fn flush(&self) -> Result<(), DbError> {
+        let mut guard = self.shared.empty_lock.lock();
+        while self.shared.pending.load(Ordering::SeqCst) != 0 {
+            self.shared.empty.wait(&mut guard);
+        }
+        drop(guard);
+
+        match self.shared.last_error.lock().take() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    /// `flush` 的别名，语义上更贴近“排空队列”
+    pub fn drain(&self) -> Result<(), DbError> {
+        self.flush()
+    }
+
+    /// 队列中尚未完成追加写入的任务数，主要用于测试/可观测性
+    pub fn pending_count(&self) -> u64 {
+        self.shared.pending.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for CommitQueue {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        self.shared.more_to_commit.notify_all();
+        for worker in self.workers.lock().drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,66 @@
+//! 日志压缩（裁剪历史版本）
+//!
+//! Walrus 的每个 topic 都是只追加的版本日志：`WalrusStateDB::set_account`/
+//! `set_storage` 每次写入都会在 [`kvdb`](super::kvdb) 里追加一条新版本，归档模式
+//! 下这些版本会无限累积。本模块只放不依赖 Walrus 的纯数据结构和计算逻辑；
+//! 实际的读取、重写物理 topic、切换压缩代号都由 `WalrusStateDB::compact`
+//! （在 `kvdb.rs`）完成，因为只有那里知道如何与 Walrus/索引打交道。
+
+/// 归档 / 裁剪模式
+///
+/// 只是一面旗子：运维层据此决定要不要、多久调用一次 [`super::WalrusStateDB::compact`]，
+/// 设置它本身不会自动触发压缩。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PruningMode {
+    /// 保留每一次写入的全部历史版本，`get_account_at`/`get_storage_at` 可以
+    /// 回溯到任意区块号
+    #[default]
+    Archive,
+    /// 定期裁剪掉压缩水位之前的历史版本，只保留归档查询所需的最小历史
+    Pruned,
+}
+
+/// 一次 `compact` 调用的统计结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompactionStats {
+    /// 被压缩（有记录被回收）的 topic 数；没有可回收记录而被跳过的 topic 不计入
+    pub topics_compacted: usize,
+    /// 被回收（丢弃）的历史版本条目数
+    pub entries_reclaimed: u64,
+    /// 回收的字节数（条目的完整编码大小，含区块号头部）
+    pub bytes_freed: u64,
+}
+
+impl CompactionStats {
+    /// 把另一个 topic 的压缩结果累加进来
+    pub(crate) fn merge(&mut self, other: CompactionStats) {
+        self.topics_compacted += other.topics_compacted;
+        self.entries_reclaimed += other.entries_reclaimed;
+        self.bytes_freed += other.bytes_freed;
+    }
+}
+
+/// 给定一个 topic 内按写入顺序排列的区块号序列，找到压缩水位对应的下标：
+/// 最新的一条满足 `block <= keep_from_block` 的记录。
+///
+/// 该下标及之后的记录都要保留（下标之前的全部是过期历史版本，可以丢弃）。
+/// 如果没有任何记录满足条件，说明压缩水位比这个 topic 里最早的写入还早，
+/// 无事可做，返回 `None`。
+pub fn compaction_cutoff(block_numbers: &[u64], keep_from_block: u64) -> Option<usize> {
+    block_numbers
+        .iter()
+        .rposition(|&block| block <= keep_from_block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cutoff_keeps_newest_at_or_before_watermark_and_everything_after() {
+        let blocks = [1, 3, 5, 7, 9];
+        assert_eq!(compaction_cutoff(&blocks, 6), Some(2));
+        assert_eq!(compaction_cutoff(&blocks, 9), Some(4));
+        assert_eq!(compaction_cutoff(&blocks, 0), None);
+    }
+}
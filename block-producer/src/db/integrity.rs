@@ -0,0 +1,95 @@
+//! 条目级内容校验（checksum-on-read）
+//!
+//! 写入 Walrus 的账户/存储/代码条目此前被无条件信任：一次截断的写入，或者
+//! 跨版本的编码变化，在读取时只会变成一个指向 bincode 内部、无法定位的反
+//! 序列化错误。这里把上游构建流水线里“产物校验”的做法搬到数据路径上：
+//! 每条持久化 payload 前都拼接一个定长头部（4 字节长度 + 32 字节 keccak256
+//! 内容哈希），读取时先核对头部再反序列化，能在第一时间把"数据本身已经
+//! 损坏"和"数据完好但反序列化逻辑有 bug"这两类故障区分开。
+//!
+//! 本模块只放不依赖 Walrus 的纯编解码逻辑；实际在哪些 topic 上启用校验、
+//! 如何枚举全部 topic 做 `verify_integrity`，由 [`super::kvdb::WalrusStateDB`]
+//! 决定。
+
+use alloy_primitives::{keccak256, B256};
+use crate::db::traits::DbError;
+
+/// 校验头部的固定长度：4 字节小端长度 + 32 字节 keccak256
+const HEADER_LEN: usize = 4 + 32;
+
+/// 在 payload 前拼接长度 + keccak256 校验头
+pub fn encode_with_checksum(payload: &[u8]) -> Vec<u8> {
+    let hash = keccak256(payload);
+    let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(hash.as_slice());
+    out.extend_from_slice(payload);
+    out
+}
+
+/// 拆出校验头并核对内容哈希，返回被校验过的 payload
+///
+/// 头部缺失/截断、声明长度与实际不符、哈希不匹配，三种情况统一报告为
+/// [`DbError::Corruption`]（截断的情况下 `expected` 取 `B256::ZERO` 作为哨兵值，
+/// 因为此时连一个完整的期望哈希都读不到）。`topic` 只用于在失败时指出是
+/// 哪个 topic 的数据损坏。
+pub fn decode_with_checksum<'a>(topic: &str, data: &'a [u8]) -> Result<&'a [u8], DbError> {
+    if data.len() < HEADER_LEN {
+        return Err(DbError::Corruption {
+            topic: topic.to_string(),
+            expected: B256::ZERO,
+            actual: keccak256(data),
+        });
+    }
+
+    let (header, rest) = data.split_at(HEADER_LEN);
+    let declared_len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let expected = B256::from_slice(&header[4..HEADER_LEN]);
+    let actual = keccak256(rest);
+
+    if declared_len != rest.len() || actual != expected {
+        return Err(DbError::Corruption { topic: topic.to_string(), expected, actual });
+    }
+
+    Ok(rest)
+}
+
+/// `WalrusStateDB::verify_integrity` 报告的一条损坏记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorruptEntry {
+    /// 条目所在的物理 topic
+    pub topic: String,
+    /// 条目在 topic 内的 offset
+    pub offset: u64,
+    /// 校验头中声明的哈希
+    pub expected: B256,
+    /// 实际内容算出的哈希
+    pub actual: B256,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_detects_flipped_byte() {
+        let payload = b"hello walrus".to_vec();
+        let mut encoded = encode_with_checksum(&payload);
+        assert_eq!(decode_with_checksum("t", &encoded).unwrap(), payload.as_slice());
+
+        let last = encoded.len() - 1;
+        encoded[last] ^= 0xFF;
+        assert!(matches!(
+            decode_with_checksum("t", &encoded),
+            Err(DbError::Corruption { .. })
+        ));
+    }
+
+    #[test]
+    fn truncated_entry_is_corruption_not_panic() {
+        assert!(matches!(
+            decode_with_checksum("t", &[1, 2, 3]),
+            Err(DbError::Corruption { .. })
+        ));
+    }
+}
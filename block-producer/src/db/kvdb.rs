@@ -3,16 +3,68 @@
 //! 使用 Walrus 作为交易排序器，提供按序写入和读取能力
 //! Walrus 本身不作为持久化存储，而是用于确保状态变更的顺序性
 
-use alloy_primitives::{Address, U256, B256, Bytes};
+use alloy_primitives::{Address, U256, B256, Bytes, keccak256};
 use parking_lot::RwLock;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use walrus_rust::{Walrus, Entry};
 use crate::db::{StateDatabase, DbError, TransactionBuffer};
+use crate::db::traits::TouchedKeyKind;
 use crate::db::cache::{StateCache, CacheKey, CacheValue};
+use crate::db::commit_queue::CommitQueue;
+use crate::db::compaction::{CompactionStats, PruningMode, compaction_cutoff};
+use crate::db::integrity::{encode_with_checksum, decode_with_checksum, CorruptEntry};
 use crate::schema::{Account, StorageSlot};
+use crate::trie::sparse;
+use crate::trie::builder::{rlp_encode_account, rlp_encode_storage_value};
+use crate::trie::TrieError;
+
+/// 每个 topic 最新条目所在的 Walrus offset，持久化到 `__index:<topic>`，
+/// 使 `read_latest_for_topic` 变成一次定位读取而不是全表扫描
+const INDEX_TOPIC_PREFIX: &str = "__index:";
+
+/// 记录全部已知的账户/存储（带区块号版本头）逻辑 topic 名，供 `compact`
+/// 枚举需要裁剪的 topic，而不必假设某种地址/key 的编码方式可枚举
+const VERSIONED_TOPICS_REGISTRY: &str = "__versioned_topics";
+
+/// 每个逻辑 topic 当前压缩代号（generation）的持久化前缀：
+/// `__compact_gen:<logical topic>`，见 [`WalrusStateDB::physical_topic`]
+const COMPACTION_GEN_PREFIX: &str = "__compact_gen:";
+
+/// 记录全部已知的、写入时带内容校验头（见 [`crate::db::integrity`]）的物理
+/// topic 名，供 [`WalrusStateDB::verify_integrity`] 枚举需要校验的 topic。
+/// 与 [`VERSIONED_TOPICS_REGISTRY`] 分开维护，因为代码 topic（`code:<hash>`）
+/// 参与校验但不参与区块号版本化/压缩。
+const CHECKSUMMED_TOPICS_REGISTRY: &str = "__checksummed_topics";
+
+/// 某个账户全部已知存储槽 key 的索引 topic 前缀：`storage_keys:<address>`，
+/// 供 [`WalrusStateDB::get_all_storage`] 枚举该账户写过的全部 key，
+/// 而不必假设 key 空间可枚举或维护一棵真正的存储树
+const STORAGE_KEYS_TOPIC_PREFIX: &str = "storage_keys:";
+
+/// 账户树中某个节点的完整路径：tag `0` + `keccak256(address)` 的 nibble 路径
+///
+/// 与 [`storage_trie_path`] 共用同一个 `CacheKey::TrieNode`/Walrus topic 命名
+/// 空间，用首字节区分，避免两棵树的节点路径互相碰撞。
+fn account_trie_path(nibbles: &[u8]) -> Vec<u8> {
+    let mut path = Vec::with_capacity(nibbles.len() + 1);
+    path.push(0u8);
+    path.extend_from_slice(nibbles);
+    path
+}
+
+/// 某个账户存储树中某个节点的完整路径：tag `1` + 地址 + nibble 路径
+fn storage_trie_path(address: &Address, nibbles: &[u8]) -> Vec<u8> {
+    let mut path = Vec::with_capacity(1 + 20 + nibbles.len());
+    path.push(1u8);
+    path.extend_from_slice(address.as_slice());
+    path.extend_from_slice(nibbles);
+    path
+}
 
 /// 键值状态数据库
-/// 
+///
 /// 使用 Walrus 作为交易排序器，配合缓存和事务支持实现状态管理
 pub struct WalrusStateDB {
     /// Walrus 实例
@@ -23,6 +75,67 @@ pub struct WalrusStateDB {
     tx_buffer: RwLock<Option<TransactionBuffer>>,
     /// 变更追踪（用于增量状态根计算）
     changed_accounts: RwLock<Vec<Address>>,
+    /// topic -> 最新一条记录的 offset，懒加载并在每次 append 后持久化
+    latest_offsets: Arc<RwLock<HashMap<String, u64>>>,
+    /// 后台提交管线：事务提交后，序列化与 Walrus 追加写入在工作线程上异步完成
+    commit_queue: CommitQueue,
+    /// 当前正在写入的区块号，由调用方在执行一个区块之前通过 [`Self::set_current_block`]
+    /// 设置；账户/存储的直接写入和事务提交的后台序列化路径都把它当作写入条目
+    /// 的版本号，使 [`Self::get_account_at`]/[`Self::get_storage_at`] 能够按区块号
+    /// 做历史回溯。是 `Arc` 是因为它需要被 [`CommitQueue`] 的序列化闭包共享。
+    current_block: Arc<AtomicU64>,
+    /// 归档 / 裁剪模式，见 [`PruningMode`]；只是运维层决定是否调用 `compact` 的依据
+    pruning_mode: RwLock<PruningMode>,
+    /// 逻辑 topic -> 当前压缩代号，懒加载自 `__compact_gen:<topic>`；`Arc` 原因
+    /// 同 `current_block`
+    topic_generation: Arc<RwLock<HashMap<String, u64>>>,
+    /// 本进程内已经登记过的账户/存储逻辑 topic，避免重复写入
+    /// [`VERSIONED_TOPICS_REGISTRY`]；`Arc` 原因同 `current_block`
+    known_versioned_topics: Arc<RwLock<HashSet<String>>>,
+    /// 本进程内已经登记过的、带校验头的物理 topic，避免重复写入
+    /// [`CHECKSUMMED_TOPICS_REGISTRY`]；`Arc` 原因同 `current_block`
+    known_checksummed_topics: Arc<RwLock<HashSet<String>>>,
+    /// 本进程内已经登记过的 `(address, key)` 存储槽，避免重复写入
+    /// `storage_keys:<address>` 索引；`Arc` 原因同 `current_block`
+    known_storage_keys: Arc<RwLock<HashSet<(Address, U256)>>>,
+}
+
+/// 把 `topic` 的索引记录追加到 `__index:<topic>`，使其在重启后仍然可用
+///
+/// 与 `append_for_topic` 本身共享同一把 `latest_offsets` 锁，既可以在调用方
+/// 线程上同步调用（直接写入模式），也可以被 [`CommitQueue`] 的后台工作
+/// 线程调用（事务提交的异步写回路径）。
+fn append_indexed_to(
+    wal: &Walrus,
+    latest_offsets: &RwLock<HashMap<String, u64>>,
+    topic: &str,
+    data: &[u8],
+) -> Result<(), DbError> {
+    if !latest_offsets.read().contains_key(topic) {
+        // 本进程内尚未见过这个 topic（例如重启后的首次写入）：先数出已有条目
+        let existing = wal
+            .batch_read_for_topic(topic, 1024 * 1024 * 10, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?
+            .len() as u64;
+        if existing > 0 {
+            latest_offsets.write().insert(topic.to_string(), existing - 1);
+        }
+    }
+
+    wal.append_for_topic(topic, data)
+        .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+    let offset = {
+        let mut offsets = latest_offsets.write();
+        let next = offsets.get(topic).copied().map(|o| o + 1).unwrap_or(0);
+        offsets.insert(topic.to_string(), next);
+        next
+    };
+
+    wal.append_for_topic(&WalrusStateDB::index_topic(topic), &offset.to_le_bytes())
+        .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+    Ok(())
 }
 
 impl WalrusStateDB {
@@ -32,113 +145,846 @@ impl WalrusStateDB {
             Walrus::new_for_key("evm_state")
                 .map_err(|e| DbError::Walrus(e.to_string()))?
         );
-        
+        let latest_offsets = Arc::new(RwLock::new(HashMap::new()));
+        let current_block = Arc::new(AtomicU64::new(0));
+        let topic_generation = Arc::new(RwLock::new(HashMap::new()));
+        let known_versioned_topics = Arc::new(RwLock::new(HashSet::new()));
+        let known_checksummed_topics = Arc::new(RwLock::new(HashSet::new()));
+        let known_storage_keys = Arc::new(RwLock::new(HashSet::new()));
+
+        let commit_queue = {
+            let wal_for_serialize = wal.clone();
+            let current_block = current_block.clone();
+            let topic_generation = topic_generation.clone();
+            let known_versioned_topics = known_versioned_topics.clone();
+            let known_checksummed_topics = known_checksummed_topics.clone();
+            let known_storage_keys = known_storage_keys.clone();
+            let wal = wal.clone();
+            let latest_offsets = latest_offsets.clone();
+            CommitQueue::new(
+                Box::new(move |buffer| {
+                    Self::serialize_buffer(
+                        &wal_for_serialize,
+                        &current_block,
+                        &topic_generation,
+                        &known_versioned_topics,
+                        &known_checksummed_topics,
+                        &known_storage_keys,
+                        buffer,
+                    )
+                }),
+                Box::new(move |entries| {
+                    for (topic, data) in entries {
+                        append_indexed_to(&wal, &latest_offsets, topic, data)?;
+                    }
+                    Ok(())
+                }),
+            )
+        };
+
         Ok(Self {
             wal,
             cache: Arc::new(StateCache::new()),
             tx_buffer: RwLock::new(None),
             changed_accounts: RwLock::new(Vec::new()),
+            latest_offsets,
+            commit_queue,
+            current_block,
+            pruning_mode: RwLock::new(PruningMode::default()),
+            topic_generation,
+            known_versioned_topics,
+            known_checksummed_topics,
+            known_storage_keys,
         })
     }
-    
-    /// 序列化账户并写入 Walrus
+
+    /// 设置当前正在写入的区块号
+    ///
+    /// 由区块生产流程在 `begin_transaction`/执行区块之前调用一次；之后这个区块内
+    /// 所有直接写入（非事务缓冲区路径）的账户/存储都会以这个区块号落盘版本头。
+    pub fn set_current_block(&self, block_number: u64) {
+        self.current_block.store(block_number, Ordering::Relaxed);
+    }
+
+    /// 读取当前区块号
+    fn current_block(&self) -> u64 {
+        self.current_block.load(Ordering::Relaxed)
+    }
+
+    /// 在 payload 前拼接 8 字节小端区块号头部
+    fn encode_with_block_header(block_number: u64, payload: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + payload.len());
+        out.extend_from_slice(&block_number.to_le_bytes());
+        out.extend_from_slice(payload);
+        out
+    }
+
+    /// 拆出区块号头部和剩余 payload
+    fn decode_block_header(data: &[u8]) -> Result<(u64, &[u8]), DbError> {
+        if data.len() < 8 {
+            return Err(DbError::Serialization(
+                "entry too short to contain block header".to_string(),
+            ));
+        }
+        let (header, payload) = data.split_at(8);
+        let block_number = u64::from_le_bytes(header.try_into().unwrap());
+        Ok((block_number, payload))
+    }
+
+    /// 设置归档 / 裁剪模式（见 [`PruningMode`]）
+    pub fn set_pruning_mode(&self, mode: PruningMode) {
+        *self.pruning_mode.write() = mode;
+    }
+
+    /// 当前的归档 / 裁剪模式
+    pub fn pruning_mode(&self) -> PruningMode {
+        *self.pruning_mode.read()
+    }
+
+    /// 某个逻辑 topic 的压缩代号持久化 topic 名
+    fn compaction_gen_topic(logical: &str) -> String {
+        format!("{}{}", COMPACTION_GEN_PREFIX, logical)
+    }
+
+    /// 取得某个逻辑 topic 当前的压缩代号；懒加载，重启后第一次访问时从
+    /// `__compact_gen:<topic>` 重建，和 [`Self::latest_offset`] 的思路一致
+    ///
+    /// 和 [`append_indexed_to`] 一样写成自由函数：既可以在调用方线程上（直接
+    /// 写入模式）调用，也需要被 `serialize_buffer` 在 [`CommitQueue`] 的后台
+    /// 序列化闭包里调用，那里拿不到 `&WalrusStateDB`，只有各字段的 `Arc` 克隆。
+    fn compaction_generation_of(
+        wal: &Walrus,
+        topic_generation: &RwLock<HashMap<String, u64>>,
+        logical: &str,
+    ) -> Result<u64, DbError> {
+        if let Some(gen) = topic_generation.read().get(logical).copied() {
+            return Ok(gen);
+        }
+
+        let gen_topic = Self::compaction_gen_topic(logical);
+        let entries = wal
+            .batch_read_for_topic(&gen_topic, 1024, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        let gen = entries
+            .into_iter()
+            .last()
+            .filter(|entry| entry.data.len() == 8)
+            .map(|entry| u64::from_le_bytes(entry.data[..8].try_into().unwrap()))
+            .unwrap_or(0);
+
+        topic_generation.write().insert(logical.to_string(), gen);
+        Ok(gen)
+    }
+
+    fn compaction_generation(&self, logical: &str) -> Result<u64, DbError> {
+        Self::compaction_generation_of(&self.wal, &self.topic_generation, logical)
+    }
+
+    /// 逻辑 topic（比如 `accounts:<address>`）当前对应的物理 Walrus topic
+    ///
+    /// 代号为 0（从未压缩过）时物理 topic 就是逻辑名本身；每次 [`Self::compact`]
+    /// 都会把保留下来的记录重写进新一代物理 topic `<logical>@c<gen>`，
+    /// 之后的读写都会透明地转到新物理 topic 上。
+    fn physical_topic_of(
+        wal: &Walrus,
+        topic_generation: &RwLock<HashMap<String, u64>>,
+        logical: &str,
+    ) -> Result<String, DbError> {
+        let gen = Self::compaction_generation_of(wal, topic_generation, logical)?;
+        Ok(if gen == 0 {
+            logical.to_string()
+        } else {
+            format!("{}@c{}", logical, gen)
+        })
+    }
+
+    fn physical_topic(&self, logical: &str) -> Result<String, DbError> {
+        Self::physical_topic_of(&self.wal, &self.topic_generation, logical)
+    }
+
+    /// 把一个账户/存储逻辑 topic 登记进 [`VERSIONED_TOPICS_REGISTRY`]（按已知集合
+    /// 去重），使它之后能被 [`Self::compact`] 枚举到；同样写成自由函数，理由见
+    /// [`Self::compaction_generation_of`]
+    fn register_versioned_topic_of(
+        wal: &Walrus,
+        known_versioned_topics: &RwLock<HashSet<String>>,
+        logical: &str,
+    ) -> Result<(), DbError> {
+        if known_versioned_topics.read().contains(logical) {
+            return Ok(());
+        }
+
+        wal.append_for_topic(VERSIONED_TOPICS_REGISTRY, logical.as_bytes())
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+        known_versioned_topics.write().insert(logical.to_string());
+        Ok(())
+    }
+
+    fn register_versioned_topic(&self, logical: &str) -> Result<(), DbError> {
+        Self::register_versioned_topic_of(&self.wal, &self.known_versioned_topics, logical)
+    }
+
+    /// 把一个带校验头的物理 topic 登记进 [`CHECKSUMMED_TOPICS_REGISTRY`]（按已知
+    /// 集合去重），使它之后能被 [`Self::verify_integrity`] 枚举到；写成自由
+    /// 函数的理由同 [`Self::compaction_generation_of`]
+    fn register_checksummed_topic_of(
+        wal: &Walrus,
+        known_checksummed_topics: &RwLock<HashSet<String>>,
+        topic: &str,
+    ) -> Result<(), DbError> {
+        if known_checksummed_topics.read().contains(topic) {
+            return Ok(());
+        }
+
+        wal.append_for_topic(CHECKSUMMED_TOPICS_REGISTRY, topic.as_bytes())
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+        known_checksummed_topics.write().insert(topic.to_string());
+        Ok(())
+    }
+
+    fn register_checksummed_topic(&self, topic: &str) -> Result<(), DbError> {
+        Self::register_checksummed_topic_of(&self.wal, &self.known_checksummed_topics, topic)
+    }
+
+    /// 枚举全部已知的带校验头物理 topic 名（去重）
+    fn all_checksummed_topics(&self) -> Result<Vec<String>, DbError> {
+        let entries = self
+            .wal
+            .batch_read_for_topic(CHECKSUMMED_TOPICS_REGISTRY, 64 * 1024 * 1024, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        let mut topics: HashSet<String> = entries
+            .into_iter()
+            .filter_map(|entry| String::from_utf8(entry.data).ok())
+            .collect();
+        topics.extend(self.known_checksummed_topics.read().iter().cloned());
+
+        Ok(topics.into_iter().collect())
+    }
+
+    /// 校验全部已知校验 topic 中的每一条记录，返回发现的损坏记录列表
+    ///
+    /// 用于运维层周期性巡检：不改变任何数据，只是对每个已登记 topic 的全部
+    /// 记录重新计算内容哈希并与写入时保存的校验头比对。
+    pub fn verify_integrity(&self) -> Result<Vec<CorruptEntry>, DbError> {
+        let mut corrupt = Vec::new();
+
+        for topic in self.all_checksummed_topics()? {
+            let entries = self
+                .wal
+                .batch_read_for_topic(&topic, 64 * 1024 * 1024, false, None)
+                .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+            for (offset, entry) in entries.into_iter().enumerate() {
+                if let Err(DbError::Corruption { expected, actual, .. }) =
+                    decode_with_checksum(&topic, &entry.data)
+                {
+                    corrupt.push(CorruptEntry {
+                        topic: topic.clone(),
+                        offset: offset as u64,
+                        expected,
+                        actual,
+                    });
+                }
+            }
+        }
+
+        Ok(corrupt)
+    }
+
+    /// 某个账户存储槽 key 索引的 topic 名
+    fn storage_keys_topic(address: &Address) -> String {
+        format!("{}{}", STORAGE_KEYS_TOPIC_PREFIX, hex::encode(address))
+    }
+
+    /// 把一个存储槽 key 登记进该账户的 `storage_keys:<address>` 索引（按已知
+    /// 集合去重），使它之后能被 [`Self::get_all_storage`] 枚举到；写成自由
+    /// 函数的理由同 [`Self::compaction_generation_of`]——`persist_storage` 的
+    /// 直接写入路径和 `serialize_buffer` 的事务提交路径都要调用它
+    fn register_storage_key_of(
+        wal: &Walrus,
+        known_storage_keys: &RwLock<HashSet<(Address, U256)>>,
+        address: &Address,
+        key: U256,
+    ) -> Result<(), DbError> {
+        if known_storage_keys.read().contains(&(*address, key)) {
+            return Ok(());
+        }
+
+        wal.append_for_topic(&Self::storage_keys_topic(address), &key.to_be_bytes::<32>())
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+        known_storage_keys.write().insert((*address, key));
+        Ok(())
+    }
+
+    fn register_storage_key(&self, address: &Address, key: U256) -> Result<(), DbError> {
+        Self::register_storage_key_of(&self.wal, &self.known_storage_keys, address, key)
+    }
+
+    /// 枚举某个账户全部已知的存储槽 key（去重）
+    fn all_storage_keys(&self, address: &Address) -> Result<Vec<U256>, DbError> {
+        let entries = self
+            .wal
+            .batch_read_for_topic(&Self::storage_keys_topic(address), 64 * 1024 * 1024, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        let mut keys: HashSet<U256> = entries
+            .into_iter()
+            .filter(|entry| entry.data.len() == 32)
+            .map(|entry| U256::from_be_slice(&entry.data))
+            .collect();
+        keys.extend(
+            self.known_storage_keys
+                .read()
+                .iter()
+                .filter(|(addr, _)| addr == address)
+                .map(|(_, key)| *key),
+        );
+
+        Ok(keys.into_iter().collect())
+    }
+
+    /// 枚举全部已知的账户/存储逻辑 topic 名（去重）
+    fn all_versioned_topics(&self) -> Result<Vec<String>, DbError> {
+        let entries = self
+            .wal
+            .batch_read_for_topic(VERSIONED_TOPICS_REGISTRY, 64 * 1024 * 1024, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        let mut topics: HashSet<String> = entries
+            .into_iter()
+            .filter_map(|entry| String::from_utf8(entry.data).ok())
+            .collect();
+        topics.extend(self.known_versioned_topics.read().iter().cloned());
+
+        Ok(topics.into_iter().collect())
+    }
+
+    /// 压缩单个逻辑 topic
+    ///
+    /// 读出该 topic 当前物理日志的全部记录，用 [`compaction_cutoff`] 找到压缩
+    /// 水位（保留 `<= keep_from_block` 的最新一条及其后全部记录），把它们重写
+    /// 进新一代物理 topic，最后才把代号指针切到新一代——读者在切换前看到的是
+    /// 旧物理 topic 的完整历史，切换后看到的是新物理 topic 的完整压缩结果，
+    /// 不存在半写的中间状态。`dry_run` 时只统计可回收量，不做任何写入。
+    fn compact_topic(
+        &self,
+        logical: &str,
+        keep_from_block: u64,
+        dry_run: bool,
+    ) -> Result<CompactionStats, DbError> {
+        let physical = self.physical_topic(logical)?;
+
+        if self.latest_offset(&physical)?.is_none() {
+            return Ok(CompactionStats::default());
+        }
+
+        let entries = self
+            .wal
+            .batch_read_for_topic(&physical, 64 * 1024 * 1024, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        let block_numbers = entries
+            .iter()
+            .map(|entry| {
+                let tagged = decode_with_checksum(&physical, &entry.data)?;
+                Self::decode_block_header(tagged).map(|(block, _)| block)
+            })
+            .collect::<Result<Vec<u64>, DbError>>()?;
+
+        let Some(cutoff) = compaction_cutoff(&block_numbers, keep_from_block) else {
+            return Ok(CompactionStats::default());
+        };
+
+        if cutoff == 0 {
+            // 压缩水位之前已经没有可丢弃的历史版本
+            return Ok(CompactionStats::default());
+        }
+
+        let (reclaimed, kept) = entries.split_at(cutoff);
+        let stats = CompactionStats {
+            topics_compacted: 1,
+            entries_reclaimed: reclaimed.len() as u64,
+            bytes_freed: reclaimed.iter().map(|entry| entry.data.len() as u64).sum(),
+        };
+
+        if dry_run {
+            return Ok(stats);
+        }
+
+        let next_gen = self.compaction_generation(logical)? + 1;
+        let new_physical = format!("{}@c{}", logical, next_gen);
+        self.register_checksummed_topic(&new_physical)?;
+        for entry in kept {
+            self.append_indexed(&new_physical, &entry.data)?;
+        }
+
+        self.wal
+            .append_for_topic(&Self::compaction_gen_topic(logical), &next_gen.to_le_bytes())
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+        self.topic_generation.write().insert(logical.to_string(), next_gen);
+
+        Ok(stats)
+    }
+
+    /// 对全部已知的账户/存储 topic 执行一次压缩
+    ///
+    /// `keep_from_block` 是压缩水位：每个 topic 只保留“`<= keep_from_block` 的
+    /// 最新一条”及其后的全部记录，更早的历史版本被视为可回收并丢弃（之后
+    /// `get_account_at`/`get_storage_at` 对早于保留下来的最早一条记录的区块号
+    /// 读不到任何版本，分别返回 `None`/零值）。
+    /// `dry_run` 为 `true` 时只统计可回收的条目数/字节数，不做任何写入或代号切换，
+    /// 可用于压缩前预估收益。
+    pub fn compact(&self, keep_from_block: u64, dry_run: bool) -> Result<CompactionStats, DbError> {
+        let mut stats = CompactionStats::default();
+        for logical in self.all_versioned_topics()? {
+            stats.merge(self.compact_topic(&logical, keep_from_block, dry_run)?);
+        }
+        Ok(stats)
+    }
+
+    /// 维护索引的专用 topic 名称
+    fn index_topic(topic: &str) -> String {
+        format!("{}{}", INDEX_TOPIC_PREFIX, topic)
+    }
+
+    /// 把一个已提交事务的缓冲区编码为 `(topic, payload)` 列表
+    ///
+    /// 在 [`CommitQueue`] 的工作线程上并行执行；真正的 Walrus 追加写入
+    /// 则在编码完成后按提交顺序串行发生。这是事务提交的主路径，账户/存储
+    /// 条目因此也要在这里——而不仅仅是直接写入模式的 `persist_account`/
+    /// `persist_storage`——加上区块号头部并解析到当前压缩代号对应的物理
+    /// topic，两条路径写出来的数据才能被同一套 `get_account_at`/`compact`
+    /// 逻辑读到。
+    fn serialize_buffer(
+        wal: &Walrus,
+        current_block: &AtomicU64,
+        topic_generation: &RwLock<HashMap<String, u64>>,
+        known_versioned_topics: &RwLock<HashSet<String>>,
+        known_checksummed_topics: &RwLock<HashSet<String>>,
+        known_storage_keys: &RwLock<HashSet<(Address, U256)>>,
+        buffer: &TransactionBuffer,
+    ) -> Result<Vec<(String, Vec<u8>)>, DbError> {
+        let block_number = current_block.load(Ordering::Relaxed);
+        let mut entries = Vec::with_capacity(
+            buffer.accounts.len() + buffer.storage.len() + buffer.codes.len() + buffer.block_hashes.len(),
+        );
+
+        for (address, account) in &buffer.accounts {
+            let logical = format!("accounts:{}", hex::encode(address));
+            Self::register_versioned_topic_of(wal, known_versioned_topics, &logical)?;
+            let physical = Self::physical_topic_of(wal, topic_generation, &logical)?;
+            Self::register_checksummed_topic_of(wal, known_checksummed_topics, &physical)?;
+            let data = bincode::serialize(account)
+                .map_err(|e| DbError::Serialization(e.to_string()))?;
+            let tagged = Self::encode_with_block_header(block_number, &data);
+            entries.push((physical, encode_with_checksum(&tagged)));
+        }
+
+        for ((address, key), value) in &buffer.storage {
+            let logical = format!("storage:{}:{}", hex::encode(address), key);
+            Self::register_versioned_topic_of(wal, known_versioned_topics, &logical)?;
+            Self::register_storage_key_of(wal, known_storage_keys, address, *key)?;
+            let physical = Self::physical_topic_of(wal, topic_generation, &logical)?;
+            Self::register_checksummed_topic_of(wal, known_checksummed_topics, &physical)?;
+            let data = bincode::serialize(value)
+                .map_err(|e| DbError::Serialization(e.to_string()))?;
+            let tagged = Self::encode_with_block_header(block_number, &data);
+            entries.push((physical, encode_with_checksum(&tagged)));
+        }
+
+        for (code_hash, code) in &buffer.codes {
+            let topic = format!("code:{}", hex::encode(code_hash));
+            Self::register_checksummed_topic_of(wal, known_checksummed_topics, &topic)?;
+            entries.push((topic, encode_with_checksum(code)));
+        }
+
+        for (block_number, block_hash) in &buffer.block_hashes {
+            let topic = format!("block_hash:{}", block_number);
+            entries.push((topic, block_hash.as_slice().to_vec()));
+        }
+
+        Ok(entries)
+    }
+
+    /// 追加一条记录，并在索引中记录/持久化其 offset
+    ///
+    /// 用 topic 内已有的条目数作为该条记录的 offset；随后把这个 offset
+    /// 追加写入专门的 `__index:<topic>` topic，使其在进程重启后仍然可用，
+    /// 避免下次启动时重新扫描整个 topic。
+    fn append_indexed(&self, topic: &str, data: &[u8]) -> Result<(), DbError> {
+        append_indexed_to(&self.wal, &self.latest_offsets, topic, data)
+    }
+
+    /// 取得某个 topic 最新记录的 offset，懒加载并在缺失时扫描一次重建
+    fn latest_offset(&self, topic: &str) -> Result<Option<u64>, DbError> {
+        if let Some(offset) = self.latest_offsets.read().get(topic).copied() {
+            return Ok(Some(offset));
+        }
+
+        // 1. 尝试从持久化的索引 topic 恢复（重启后的常见路径）
+        let index_topic = Self::index_topic(topic);
+        let max_bytes = 1024 * 1024;
+        let index_entries = self
+            .wal
+            .batch_read_for_topic(&index_topic, max_bytes, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        if let Some(entry) = index_entries.into_iter().last() {
+            if entry.data.len() == 8 {
+                let offset = u64::from_le_bytes(entry.data[..8].try_into().unwrap());
+                self.latest_offsets.write().insert(topic.to_string(), offset);
+                return Ok(Some(offset));
+            }
+        }
+
+        // 2. 索引缺失（首次见到该 topic 或索引未及时落盘）：扫描一次重建
+        let max_bytes = 1024 * 1024 * 10; // 10MB
+        let entries = self
+            .wal
+            .batch_read_for_topic(topic, max_bytes, false, None)
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        if entries.is_empty() {
+            return Ok(None);
+        }
+
+        let offset = (entries.len() - 1) as u64;
+        self.latest_offsets.write().insert(topic.to_string(), offset);
+        self.wal
+            .append_for_topic(&index_topic, &offset.to_le_bytes())
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        Ok(Some(offset))
+    }
+
+    /// 序列化账户并写入 Walrus，前置当前区块号头部（见 [`Self::encode_with_block_header`]）
+    /// 再前置内容校验头（见 [`crate::db::integrity::encode_with_checksum`]）
     fn persist_account(&self, address: &Address, account: &Account) -> Result<(), DbError> {
         let topic = format!("accounts:{}", hex::encode(address));
+        self.register_versioned_topic(&topic)?;
+        let physical = self.physical_topic(&topic)?;
+        self.register_checksummed_topic(&physical)?;
         let data = bincode::serialize(account)
             .map_err(|e| DbError::Serialization(e.to_string()))?;
-        
-        self.wal.append_for_topic(&topic, &data)
-            .map_err(|e| DbError::Walrus(e.to_string()))?;
-        
-        Ok(())
+        let tagged = Self::encode_with_block_header(self.current_block(), &data);
+
+        self.append_indexed(&physical, &encode_with_checksum(&tagged))
     }
-    
+
     /// 从 Walrus 读取账户（最新版本）
     fn load_account(&self, address: &Address) -> Result<Option<Account>, DbError> {
         // 1. 尝试从缓存读取
         if let Some(cached) = self.cache.get(&CacheKey::Account(*address)) {
             return Ok(Some(cached.as_account().clone()));
         }
-        
+
         // 2. 从 Walrus 读取最新条目
         let topic = format!("accounts:{}", hex::encode(address));
-        
-        // TODO: 优化 - 维护索引快速定位最新条目
-        // 当前实现：读取整个 topic 的最后一条记录
-        if let Some(entry) = self.read_latest_for_topic(&topic)? {
-            let account: Account = bincode::deserialize(&entry.data)
+        let physical = self.physical_topic(&topic)?;
+
+        if let Some(entry) = self.read_latest_for_topic(&physical)? {
+            let tagged = decode_with_checksum(&physical, &entry.data)?;
+            let (_, payload) = Self::decode_block_header(tagged)?;
+            let account: Account = bincode::deserialize(payload)
                 .map_err(|e| DbError::Serialization(e.to_string()))?;
-            
+
             // 3. 更新缓存
             self.cache.put(
                 CacheKey::Account(*address),
                 CacheValue::Account(account.clone())
             );
-            
+
             Ok(Some(account))
         } else {
             Ok(None)
         }
     }
-    
+
+    /// 按历史区块号读取账户：`accounts:<address>` 中从最新条目往回扫描（见
+    /// [`Self::read_payload_at_or_before`]），不经过缓存——缓存只保存"最新"版本
+    pub fn get_account_at(
+        &self,
+        address: &Address,
+        block_number: u64,
+    ) -> Result<Option<Account>, DbError> {
+        let topic = format!("accounts:{}", hex::encode(address));
+        let physical = self.physical_topic(&topic)?;
+        match self.read_payload_at_or_before(&physical, block_number)? {
+            Some(payload) => {
+                let account: Account = bincode::deserialize(&payload)
+                    .map_err(|e| DbError::Serialization(e.to_string()))?;
+                Ok(Some(account))
+            }
+            None => Ok(None),
+        }
+    }
+
     /// 读取 topic 的最新条目
-    /// 
-    /// TODO: 性能优化 - 使用单独的索引 topic 记录最新位置
+    ///
+    /// 通过 `latest_offset` 定位到索引中记录的 offset，然后只读取该条目
+    /// 附近的数据，而不是像此前那样批量拉取整个 topic 再丢弃除最后一条
+    /// 之外的全部内容。
     fn read_latest_for_topic(&self, topic: &str) -> Result<Option<Entry>, DbError> {
-        // 批量读取该 topic 的所有条目（简化实现）
-        // 生产环境应该维护索引避免全表扫描
-        let max_bytes = 1024 * 1024 * 10; // 10MB
-        let entries = self.wal.batch_read_for_topic(topic, max_bytes, false, None)
+        let Some(offset) = self.latest_offset(topic)? else {
+            return Ok(None);
+        };
+
+        let max_bytes = 1024 * 1024;
+        let entries = self
+            .wal
+            .batch_read_for_topic(topic, max_bytes, false, Some(offset))
             .map_err(|e| DbError::Walrus(e.to_string()))?;
-        
-        // 返回最后一条
+
         Ok(entries.into_iter().last())
     }
-    
-    /// 持久化存储槽
+
+    /// 读取 topic 中某个已知 offset 的单条记录
+    fn read_entry_at_offset(&self, topic: &str, offset: u64) -> Result<Option<Entry>, DbError> {
+        let max_bytes = 1024 * 1024;
+        let entries = self
+            .wal
+            .batch_read_for_topic(topic, max_bytes, false, Some(offset))
+            .map_err(|e| DbError::Walrus(e.to_string()))?;
+
+        Ok(entries.into_iter().next())
+    }
+
+    /// 在 `topic` 中找到区块号 <= `block_number` 的最新一条记录的 payload
+    ///
+    /// 每条记录都带有 [`Self::encode_with_block_header`] 写入的区块号头部，
+    /// 因此从 `latest_offset` 往回逐条扫描、按头部比较即可，不需要额外的
+    /// 二级索引——与账户/存储当前值的读路径一样，历史读也是 Walrus 原生的
+    /// 有序追加日志上的一次定位 + 线性回溯，回溯条数等于区块号大于
+    /// `block_number` 的条目数，而不是整个 topic。
+    fn read_payload_at_or_before(
+        &self,
+        topic: &str,
+        block_number: u64,
+    ) -> Result<Option<Vec<u8>>, DbError> {
+        let Some(latest) = self.latest_offset(topic)? else {
+            return Ok(None);
+        };
+
+        let mut offset = latest;
+        loop {
+            if let Some(entry) = self.read_entry_at_offset(topic, offset)? {
+                let tagged = decode_with_checksum(topic, &entry.data)?;
+                let (entry_block, payload) = Self::decode_block_header(tagged)?;
+                if entry_block <= block_number {
+                    return Ok(Some(payload.to_vec()));
+                }
+            }
+
+            if offset == 0 {
+                return Ok(None);
+            }
+            offset -= 1;
+        }
+    }
+
+    /// 持久化存储槽，前置当前区块号头部（见 [`Self::encode_with_block_header`]）
+    /// 再前置内容校验头（见 [`crate::db::integrity::encode_with_checksum`]）
     fn persist_storage(&self, address: &Address, key: U256, value: U256) -> Result<(), DbError> {
         let topic = format!("storage:{}:{}", hex::encode(address), key);
+        self.register_versioned_topic(&topic)?;
+        self.register_storage_key(address, key)?;
+        let physical = self.physical_topic(&topic)?;
+        self.register_checksummed_topic(&physical)?;
         let data = bincode::serialize(&value)
             .map_err(|e| DbError::Serialization(e.to_string()))?;
-        
-        self.wal.append_for_topic(&topic, &data)
-            .map_err(|e| DbError::Walrus(e.to_string()))?;
-        
-        Ok(())
+        let tagged = Self::encode_with_block_header(self.current_block(), &data);
+
+        self.append_indexed(&physical, &encode_with_checksum(&tagged))
     }
-    
+
     /// 加载存储槽
     fn load_storage(&self, address: &Address, key: U256) -> Result<U256, DbError> {
         // 1. 尝试从缓存读取
         if let Some(cached) = self.cache.get(&CacheKey::Storage(*address, key)) {
             return Ok(cached.as_storage());
         }
-        
+
         // 2. 从 Walrus 读取
         let topic = format!("storage:{}:{}", hex::encode(address), key);
-        
-        if let Some(entry) = self.read_latest_for_topic(&topic)? {
-            let value: U256 = bincode::deserialize(&entry.data)
+        let physical = self.physical_topic(&topic)?;
+
+        if let Some(entry) = self.read_latest_for_topic(&physical)? {
+            let tagged = decode_with_checksum(&physical, &entry.data)?;
+            let (_, payload) = Self::decode_block_header(tagged)?;
+            let value: U256 = bincode::deserialize(payload)
                 .map_err(|e| DbError::Serialization(e.to_string()))?;
-            
+
             // 3. 更新缓存
             self.cache.put(
                 CacheKey::Storage(*address, key),
                 CacheValue::Storage(value)
             );
-            
+
             Ok(value)
         } else {
             Ok(U256::ZERO)
         }
     }
+
+    /// 按历史区块号读取存储槽：不存在或该地址/槽从未写入过历史数据时返回零值，
+    /// 与 [`StateDatabase::get_storage`] 的"未设置即为零"语义保持一致
+    pub fn get_storage_at(
+        &self,
+        address: &Address,
+        key: U256,
+        block_number: u64,
+    ) -> Result<U256, DbError> {
+        let topic = format!("storage:{}:{}", hex::encode(address), key);
+        let physical = self.physical_topic(&topic)?;
+        match self.read_payload_at_or_before(&physical, block_number)? {
+            Some(payload) => {
+                let value: U256 = bincode::deserialize(&payload)
+                    .map_err(|e| DbError::Serialization(e.to_string()))?;
+                Ok(value)
+            }
+            None => Ok(U256::ZERO),
+        }
+    }
     
     /// 追踪变更的账户
+    ///
+    /// 只有首次把某地址记入 `changed_accounts` 才会同步告知当前事务缓冲区的
+    /// 最顶层 checkpoint（见 [`TransactionBuffer::record_changed_account`]）——
+    /// 这样 `revert_to_checkpoint` 才能知道该地址的"已变更"标记是本层新增的，
+    /// 需要随回滚一起撤回，而不是本来就在更外层被标记过。
     fn track_changed_account(&self, address: Address) {
         let mut changed = self.changed_accounts.write();
         if !changed.contains(&address) {
             changed.push(address);
+            if let Some(ref mut buffer) = *self.tx_buffer.write() {
+                buffer.record_changed_account(address);
+            }
+        }
+    }
+
+    /// 增量状态树节点对应的 topic 名称
+    fn trie_node_topic(path: &[u8]) -> String {
+        format!("trie_node:{}", hex::encode(path))
+    }
+
+    /// 读取稀疏树中某个节点当前的哈希；从未写入过时按约定视为
+    /// `sparse::EMPTY_NODE_HASH`（见 `crate::trie::sparse`）
+    fn trie_node_hash(&self, path: &[u8]) -> Result<B256, DbError> {
+        let key = CacheKey::TrieNode(path.to_vec());
+        if let Some(cached) = self.cache.get(&key) {
+            return Ok(cached.as_trie_node());
         }
+
+        if let Some(entry) = self.read_latest_for_topic(&Self::trie_node_topic(path))? {
+            if entry.data.len() == 32 {
+                let hash = B256::from_slice(&entry.data);
+                self.cache.put(key, CacheValue::TrieNode(hash));
+                return Ok(hash);
+            }
+        }
+
+        Ok(sparse::EMPTY_NODE_HASH)
+    }
+
+    /// 写入稀疏树中某个节点的新哈希：落盘并更新缓存
+    ///
+    /// 与账户/存储/代码不同，这里直接同步调用 `append_indexed`，而不是走
+    /// `CommitQueue`：增量状态根每次提交只触碰"变更账户数 × 树深度"条路径，
+    /// 写入量远小于整个事务缓冲区，同步完成不会重新引入 `CommitQueue`
+    /// 要解决的阻塞问题。
+    fn set_trie_node_hash(&self, path: &[u8], hash: B256) -> Result<(), DbError> {
+        self.append_indexed(&Self::trie_node_topic(path), hash.as_slice())?;
+        self.cache.put(CacheKey::TrieNode(path.to_vec()), CacheValue::TrieNode(hash));
+        Ok(())
+    }
+
+    /// 提交事务后增量维护状态树
+    ///
+    /// 对 `changed_accounts` 中的每个地址：先用本次事务里这个地址的脏存储槽
+    /// （`buffer.storage`，而非 `get_all_storage` 全量枚举）重算 `storage_root`，
+    /// 再用它和账户最新字段重算账户叶子，`sparse::update_leaf` 只重新哈希从
+    /// 叶子到根这一条路径，兄弟节点哈希复用缓存/已落盘的旧值，因此总代价与
+    /// 变更账户数 × 树深度成正比，而不是全量状态。
+    fn update_state_root(&self, buffer: &TransactionBuffer) -> Result<(), DbError> {
+        let changed = self.changed_accounts.read().clone();
+
+        for address in changed {
+            for ((addr, key), value) in &buffer.storage {
+                if addr != &address {
+                    continue;
+                }
+
+                let nibbles = sparse::key_path(&keccak256(key.to_be_bytes::<32>()));
+                let leaf = if value.is_zero() {
+                    sparse::EMPTY_NODE_HASH
+                } else {
+                    sparse::leaf_hash(&rlp_encode_storage_value(*value))
+                };
+
+                sparse::update_leaf(
+                    |path| {
+                        self.trie_node_hash(&storage_trie_path(&address, path))
+                            .map_err(|e| TrieError::Database(e.to_string()))
+                    },
+                    |path, hash| {
+                        self.set_trie_node_hash(&storage_trie_path(&address, path), hash)
+                            .map_err(|e| TrieError::Database(e.to_string()))
+                    },
+                    &nibbles,
+                    leaf,
+                )
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            let storage_root = self.trie_node_hash(&storage_trie_path(&address, &[]))?;
+
+            let leaf = if buffer.deleted_accounts.contains(&address) {
+                sparse::EMPTY_NODE_HASH
+            } else {
+                let account = self.load_account(&address)?.unwrap_or_default();
+                let account_rlp = rlp_encode_account(
+                    account.nonce,
+                    account.balance,
+                    storage_root,
+                    account.code_hash,
+                );
+                sparse::leaf_hash(&account_rlp)
+            };
+
+            let account_nibbles = sparse::key_path(&keccak256(address.as_slice()));
+            sparse::update_leaf(
+                |path| {
+                    self.trie_node_hash(&account_trie_path(path))
+                        .map_err(|e| TrieError::Database(e.to_string()))
+                },
+                |path, hash| {
+                    self.set_trie_node_hash(&account_trie_path(path), hash)
+                        .map_err(|e| TrieError::Database(e.to_string()))
+                },
+                &account_nibbles,
+                leaf,
+            )
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// 当前账户树的根哈希
+    ///
+    /// 注意：这是本库内部自洽、增量维护的根（见 `crate::trie::sparse` 模块
+    /// 文档），不是主网口径的 Patricia 树状态根，不能跨实现比对；但对同一个
+    /// `WalrusStateDB` 而言，它在任意两次提交之间都可验证地反映状态变化。
+    pub fn state_root(&self) -> Result<B256, DbError> {
+        self.trie_node_hash(&account_trie_path(&[]))
+    }
+
+    /// 阻塞直到后台提交队列排空（即此前所有 `commit_transaction` 产生的
+    /// 写入都已经按序追加到 Walrus），供需要强持久化保证的调用方使用
+    pub fn flush(&self) -> Result<(), DbError> {
+        self.commit_queue.flush()
     }
 }
 
@@ -164,6 +1010,7 @@ impl StateDatabase for WalrusStateDB {
         
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
             // 事务模式：写入缓冲区
+            buffer.record_write(TouchedKeyKind::Account(*address));
             buffer.accounts.insert(*address, account);
         } else {
             // 直接模式：立即持久化
@@ -183,6 +1030,7 @@ impl StateDatabase for WalrusStateDB {
         self.track_changed_account(*address);
         
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
+            buffer.record_write(TouchedKeyKind::Account(*address));
             buffer.accounts.remove(address);
             buffer.deleted_accounts.push(*address);
         } else {
@@ -210,6 +1058,7 @@ impl StateDatabase for WalrusStateDB {
         self.track_changed_account(*address);
         
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
+            buffer.record_write(TouchedKeyKind::Storage(*address, key));
             buffer.storage.insert((*address, key), value);
         } else {
             self.persist_storage(address, key, value)?;
@@ -222,10 +1071,15 @@ impl StateDatabase for WalrusStateDB {
         Ok(())
     }
     
-    fn get_all_storage(&self, _address: &Address) -> Result<Vec<StorageSlot>, DbError> {
-        // TODO: 实现存储槽扫描（需要索引支持）
-        // 当前简化实现：返回空
-        Ok(Vec::new())
+    fn get_all_storage(&self, address: &Address) -> Result<Vec<StorageSlot>, DbError> {
+        let mut slots = Vec::new();
+        for key in self.all_storage_keys(address)? {
+            let value = self.get_storage(address, key)?;
+            if !value.is_zero() {
+                slots.push(StorageSlot::new(*address, key, value));
+            }
+        }
+        Ok(slots)
     }
     
     fn get_code(&self, code_hash: &B256) -> Result<Option<Bytes>, DbError> {
@@ -236,9 +1090,10 @@ impl StateDatabase for WalrusStateDB {
         
         // 2. 从 Walrus 读取
         let topic = format!("code:{}", hex::encode(code_hash));
-        
+
         if let Some(entry) = self.read_latest_for_topic(&topic)? {
-            let code = Bytes::from(entry.data);
+            let payload = decode_with_checksum(&topic, &entry.data)?;
+            let code = Bytes::copy_from_slice(payload);
             self.cache.put(
                 CacheKey::Code(*code_hash),
                 CacheValue::Code(code.clone())
@@ -248,22 +1103,23 @@ impl StateDatabase for WalrusStateDB {
             Ok(None)
         }
     }
-    
+
     fn set_code(&mut self, code_hash: B256, code: Bytes) -> Result<(), DbError> {
         let topic = format!("code:{}", hex::encode(code_hash));
-        
+
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
+            buffer.record_write(TouchedKeyKind::Code(code_hash));
             buffer.codes.insert(code_hash, code);
         } else {
-            self.wal.append_for_topic(&topic, &code)
-                .map_err(|e| DbError::Walrus(e.to_string()))?;
-            
+            self.register_checksummed_topic(&topic)?;
+            self.append_indexed(&topic, &encode_with_checksum(&code))?;
+
             self.cache.put(
                 CacheKey::Code(code_hash),
                 CacheValue::Code(code)
             );
         }
-        
+
         Ok(())
     }
     
@@ -296,9 +1152,8 @@ impl StateDatabase for WalrusStateDB {
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
             buffer.block_hashes.insert(block_number, block_hash);
         } else {
-            self.wal.append_for_topic(&topic, block_hash.as_slice())
-                .map_err(|e| DbError::Walrus(e.to_string()))?;
-            
+            self.append_indexed(&topic, block_hash.as_slice())?;
+
             self.cache.put(
                 CacheKey::BlockHash(block_number),
                 CacheValue::BlockHash(block_hash)
@@ -325,60 +1180,86 @@ impl StateDatabase for WalrusStateDB {
         let mut buffer_guard = self.tx_buffer.write();
         let buffer = buffer_guard.take()
             .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
-        
-        // 持久化所有变更
-        for (address, account) in buffer.accounts {
-            self.persist_account(&address, &account)?;
+
+        // 缓存是内存态的，立刻更新即可，不必等待下面的后台持久化完成
+        for (address, account) in &buffer.accounts {
             self.cache.put(
-                CacheKey::Account(address),
-                CacheValue::Account(account)
+                CacheKey::Account(*address),
+                CacheValue::Account(account.clone())
             );
         }
-        
-        for ((address, key), value) in buffer.storage {
-            self.persist_storage(&address, key, value)?;
+        for ((address, key), value) in &buffer.storage {
             self.cache.put(
-                CacheKey::Storage(address, key),
-                CacheValue::Storage(value)
+                CacheKey::Storage(*address, *key),
+                CacheValue::Storage(*value)
             );
         }
-        
-        for (code_hash, code) in buffer.codes {
-            let topic = format!("code:{}", hex::encode(code_hash));
-            self.wal.append_for_topic(&topic, &code)
-                .map_err(|e| DbError::Walrus(e.to_string()))?;
+        for (code_hash, code) in &buffer.codes {
             self.cache.put(
-                CacheKey::Code(code_hash),
-                CacheValue::Code(code)
+                CacheKey::Code(*code_hash),
+                CacheValue::Code(code.clone())
             );
         }
-        
-        for (block_number, block_hash) in buffer.block_hashes {
-            let topic = format!("block_hash:{}", block_number);
-            self.wal.append_for_topic(&topic, block_hash.as_slice())
-                .map_err(|e| DbError::Walrus(e.to_string()))?;
+        for (block_number, block_hash) in &buffer.block_hashes {
             self.cache.put(
-                CacheKey::BlockHash(block_number),
-                CacheValue::BlockHash(block_hash)
+                CacheKey::BlockHash(*block_number),
+                CacheValue::BlockHash(*block_hash)
             );
         }
-        
+
+        // 增量更新状态树（见 `update_state_root`），必须在 `changed_accounts`
+        // 被清空、buffer 被移交给后台队列之前完成
+        self.update_state_root(&buffer)?;
+
+        // bincode 编码与 Walrus 追加写入交给后台提交队列异步完成，
+        // 调用方无需在此等待磁盘/网络 I/O
+        self.commit_queue.submit(buffer);
+
         Ok(())
     }
-    
+
     fn rollback_transaction(&mut self) -> Result<(), DbError> {
         let mut buffer = self.tx_buffer.write();
         if buffer.is_none() {
             return Err(DbError::Transaction("No active transaction".to_string()));
         }
         *buffer = None;
-        
+
         // 清空变更追踪
         self.changed_accounts.write().clear();
-        
+
         Ok(())
     }
-    
+
+    fn checkpoint(&mut self) -> Result<usize, DbError> {
+        let mut buffer = self.tx_buffer.write();
+        let buffer = buffer.as_mut()
+            .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+        Ok(buffer.checkpoint())
+    }
+
+    fn revert_to_checkpoint(&mut self, id: usize) -> Result<(), DbError> {
+        let reverted_changed_accounts = {
+            let mut buffer = self.tx_buffer.write();
+            let buffer = buffer.as_mut()
+                .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+            buffer.revert_to_checkpoint(id)?
+        };
+
+        if !reverted_changed_accounts.is_empty() {
+            let mut changed = self.changed_accounts.write();
+            changed.retain(|a| !reverted_changed_accounts.contains(a));
+        }
+        Ok(())
+    }
+
+    fn discard_checkpoint(&mut self, id: usize) -> Result<(), DbError> {
+        let mut buffer = self.tx_buffer.write();
+        let buffer = buffer.as_mut()
+            .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+        buffer.discard_checkpoint(id)
+    }
+
     fn get_changed_accounts(&self) -> Result<Vec<Address>, DbError> {
         Ok(self.changed_accounts.read().clone())
     }
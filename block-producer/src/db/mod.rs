@@ -6,8 +6,16 @@ pub mod traits;
 pub mod kvdb;
 pub mod cache;
 pub mod redb_db;
+pub mod commit_queue;
+pub mod compaction;
+pub mod integrity;
+pub mod pod;
 
-pub use traits::{StateDatabase, DbError, TransactionBuffer};
+pub use traits::{StateDatabase, BlockProvider, DbError, TransactionBuffer};
 pub use kvdb::WalrusStateDB;
-pub use cache::StateCache;
+pub use cache::{StateCache, CacheKey, CacheValue, CacheWriter};
 pub use redb_db::RedbStateDB;
+pub use commit_queue::CommitQueue;
+pub use compaction::{CompactionStats, PruningMode};
+pub use integrity::CorruptEntry;
+pub use pod::{AccountDiff, PodAccount, PodState, StateDiff};
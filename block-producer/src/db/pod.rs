@@ -0,0 +1,234 @@
+//! 纯状态快照（"pod" state）与快照间的结构化差异
+//!
+//! 生产环境下状态散布在 redb 的三张表（账户/存储/代码）里，既不便于人读，也没法
+//! 直接拿两个高度的状态相减。`PodState` 把整条链的状态摊平成一个普通的
+//! `BTreeMap`，用于测试固件（deterministic fixture）、创世状态注入、以及调试时
+//! 用 `diff` 比较两个区块之间到底变了什么——跟 go-ethereum 的 `state.Dump`/
+//! `PodState` 是同一个用途。
+//!
+//! 本模块只放不依赖 redb 的纯数据结构；实际从数据库枚举账户/存储/代码构建
+//! `PodState`，由 [`super::redb_db::RedbStateDB::to_pod`] 完成。
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, Bytes, U256};
+use serde::{Deserialize, Serialize};
+
+/// 单个账户的"纯"状态：余额、nonce、代码字节、以及全部非零存储槽
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct PodAccount {
+    /// 账户余额（以 wei 为单位）
+    pub balance: U256,
+
+    /// 交易计数器
+    pub nonce: u64,
+
+    /// 合约字节码；EOA 为空
+    pub code: Bytes,
+
+    /// 非零存储槽，按键升序排列
+    pub storage: BTreeMap<U256, U256>,
+}
+
+/// 整条链状态的纯内存快照
+///
+/// 按地址升序排列，两份快照的 [`diff`](PodState::diff) 因此是确定性的，
+/// 适合直接断言或落盘做回归基线。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct PodState(pub BTreeMap<Address, PodAccount>);
+
+/// 单个账户在两份快照之间的变化
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AccountDiff {
+    /// `Some((旧值, 新值))`，余额未变则为 `None`
+    pub balance: Option<(U256, U256)>,
+
+    /// `Some((旧值, 新值))`，nonce 未变则为 `None`
+    pub nonce: Option<(u64, u64)>,
+
+    /// `Some((旧代码, 新代码))`，代码未变则为 `None`
+    pub code: Option<(Bytes, Bytes)>,
+
+    /// 新增的存储槽（旧快照里没有，新快照里非零）
+    pub added_storage: BTreeMap<U256, U256>,
+
+    /// 被清零/移除的存储槽（旧快照里非零，新快照里没有）
+    pub removed_storage: BTreeMap<U256, U256>,
+
+    /// 值发生变化的存储槽，`(旧值, 新值)`
+    pub changed_storage: BTreeMap<U256, (U256, U256)>,
+}
+
+impl AccountDiff {
+    /// 是否存在任何实质性变化
+    pub fn is_empty(&self) -> bool {
+        self.balance.is_none()
+            && self.nonce.is_none()
+            && self.code.is_none()
+            && self.added_storage.is_empty()
+            && self.removed_storage.is_empty()
+            && self.changed_storage.is_empty()
+    }
+
+    fn between(before: &PodAccount, after: &PodAccount) -> Self {
+        let mut storage_diff = Self::default();
+
+        for (key, after_value) in &after.storage {
+            match before.storage.get(key) {
+                None => {
+                    storage_diff.added_storage.insert(*key, *after_value);
+                }
+                Some(before_value) if before_value != after_value => {
+                    storage_diff
+                        .changed_storage
+                        .insert(*key, (*before_value, *after_value));
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, before_value) in &before.storage {
+            if !after.storage.contains_key(key) {
+                storage_diff.removed_storage.insert(*key, *before_value);
+            }
+        }
+
+        Self {
+            balance: (before.balance != after.balance)
+                .then_some((before.balance, after.balance)),
+            nonce: (before.nonce != after.nonce).then_some((before.nonce, after.nonce)),
+            code: (before.code != after.code)
+                .then(|| (before.code.clone(), after.code.clone())),
+            ..storage_diff
+        }
+    }
+}
+
+/// 两份 [`PodState`] 之间的结构化差异：新增/移除/变更的账户
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StateDiff {
+    /// 只存在于新快照里的账户
+    pub added: BTreeMap<Address, PodAccount>,
+
+    /// 只存在于旧快照里的账户
+    pub removed: BTreeMap<Address, PodAccount>,
+
+    /// 两份快照都有，但内容不同的账户
+    pub changed: BTreeMap<Address, AccountDiff>,
+}
+
+impl PodState {
+    /// 计算 `self`（旧）到 `other`（新）的差异
+    pub fn diff(&self, other: &PodState) -> StateDiff {
+        let mut diff = StateDiff::default();
+
+        for (address, after) in &other.0 {
+            match self.0.get(address) {
+                None => {
+                    diff.added.insert(*address, after.clone());
+                }
+                Some(before) => {
+                    let account_diff = AccountDiff::between(before, after);
+                    if !account_diff.is_empty() {
+                        diff.changed.insert(*address, account_diff);
+                    }
+                }
+            }
+        }
+        for (address, before) in &self.0 {
+            if !other.0.contains_key(address) {
+                diff.removed.insert(*address, before.clone());
+            }
+        }
+
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    fn account(balance: u64, nonce: u64) -> PodAccount {
+        PodAccount {
+            balance: U256::from(balance),
+            nonce,
+            code: Bytes::new(),
+            storage: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn diff_detects_added_removed_and_changed_accounts() {
+        let addr_removed = address!("0000000000000000000000000000000000000001");
+        let addr_changed = address!("0000000000000000000000000000000000000002");
+        let addr_added = address!("0000000000000000000000000000000000000003");
+
+        let mut before = BTreeMap::new();
+        before.insert(addr_removed, account(100, 0));
+        before.insert(addr_changed, account(100, 0));
+        let before = PodState(before);
+
+        let mut after = BTreeMap::new();
+        after.insert(addr_changed, account(200, 1));
+        after.insert(addr_added, account(50, 0));
+        let after = PodState(after);
+
+        let diff = before.diff(&after);
+
+        assert_eq!(diff.removed.len(), 1);
+        assert!(diff.removed.contains_key(&addr_removed));
+        assert_eq!(diff.added.len(), 1);
+        assert!(diff.added.contains_key(&addr_added));
+        assert_eq!(diff.changed.len(), 1);
+        let account_diff = &diff.changed[&addr_changed];
+        assert_eq!(account_diff.balance, Some((U256::from(100), U256::from(200))));
+        assert_eq!(account_diff.nonce, Some((0, 1)));
+    }
+
+    #[test]
+    fn diff_detects_storage_slot_changes() {
+        let addr = address!("0000000000000000000000000000000000000004");
+
+        let mut before_account = account(0, 0);
+        before_account.storage.insert(U256::from(1), U256::from(10));
+        before_account.storage.insert(U256::from(2), U256::from(20));
+        let mut before = BTreeMap::new();
+        before.insert(addr, before_account);
+
+        let mut after_account = account(0, 0);
+        after_account.storage.insert(U256::from(1), U256::from(11));
+        after_account.storage.insert(U256::from(3), U256::from(30));
+        let mut after = BTreeMap::new();
+        after.insert(addr, after_account);
+
+        let diff = PodState(before).diff(&PodState(after));
+
+        let account_diff = &diff.changed[&addr];
+        assert_eq!(
+            account_diff.changed_storage.get(&U256::from(1)),
+            Some(&(U256::from(10), U256::from(11)))
+        );
+        assert_eq!(
+            account_diff.removed_storage.get(&U256::from(2)),
+            Some(&U256::from(20))
+        );
+        assert_eq!(
+            account_diff.added_storage.get(&U256::from(3)),
+            Some(&U256::from(30))
+        );
+    }
+
+    #[test]
+    fn identical_snapshots_diff_to_empty() {
+        let addr = address!("0000000000000000000000000000000000000005");
+        let mut map = BTreeMap::new();
+        map.insert(addr, account(42, 3));
+        let pod = PodState(map);
+
+        let diff = pod.diff(&pod.clone());
+        assert!(diff.added.is_empty());
+        assert!(diff.removed.is_empty());
+        assert!(diff.changed.is_empty());
+    }
+}
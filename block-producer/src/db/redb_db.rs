@@ -3,12 +3,19 @@
 //! 使用嵌入式 redb 数据库进行本地持久化存储
 
 use redb::{Database, TableDefinition, ReadableTable, ReadableDatabase};
-use alloy_primitives::{Address, B256, U256, Bytes};
-use parking_lot::RwLock;
+use alloy_primitives::{keccak256, Address, B256, U256, Bytes};
+use lru::LruCache;
+use parking_lot::{Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 
-use crate::db::{StateDatabase, DbError, TransactionBuffer};
-use crate::schema::{Account, StorageSlot, Block};
+use crate::db::{StateDatabase, BlockProvider, DbError, TransactionBuffer};
+use crate::db::pod::{PodAccount, PodState};
+use crate::db::traits::TouchedKeyKind;
+use crate::schema::{Account, StorageSlot, Block, BlockHeader, TransactionReceipt, Log};
+use crate::schema::account::{EMPTY_CODE_HASH, EMPTY_STORAGE_ROOT};
 
 // ==================== 表定义 ====================
 
@@ -29,38 +36,123 @@ const BLOCKS_TABLE: TableDefinition<u64, &[u8]> =
     TableDefinition::new("blocks");
 
 /// 区块哈希表: block_number -> block_hash (32 bytes)
-const BLOCK_HASHES_TABLE: TableDefinition<u64, &[u8; 32]> = 
+const BLOCK_HASHES_TABLE: TableDefinition<u64, &[u8; 32]> =
     TableDefinition::new("block_hashes");
 
+/// 二级索引：区块哈希 (32 bytes) -> block_number（支持按哈希查区块）
+const HASH_TO_NUMBER_TABLE: TableDefinition<&[u8; 32], u64> =
+    TableDefinition::new("hash_to_number");
+
+/// 二级索引：交易哈希 (32 bytes) -> (block_number, transaction_index)
+const TX_INDEX_TABLE: TableDefinition<&[u8; 32], (u64, u64)> =
+    TableDefinition::new("tx_index");
+
+/// 收据表: transaction_hash (32 bytes) -> 收据数据
+const RECEIPTS_TABLE: TableDefinition<&[u8; 32], &[u8]> =
+    TableDefinition::new("receipts");
+
+/// 历史状态日志表: block_number -> 该区块提交前每个被触碰的键的旧值（反向 diff，
+/// bincode 序列化后的 `Vec<JournalEntry>`），供 [`RedbStateDB::rollback_to_block`] /
+/// [`RedbStateDB::get_account_at`] 重放
+const JOURNAL_TABLE: TableDefinition<u64, &[u8]> =
+    TableDefinition::new("journal");
+
+/// 持久化 trie 节点表: 节点哈希 (32 bytes) -> bincode 编码的节点
+///
+/// 供 [`crate::trie::node_store`] 驱动的 `StateRootCalculator::calculate_incremental`
+/// 增量重建状态树使用；键即节点内容自身的 keccak256，旧节点从不覆盖
+const TRIE_NODES_TABLE: TableDefinition<&[u8; 32], &[u8]> =
+    TableDefinition::new("trie_nodes");
+
+/// trie 元数据表：固定使用单一键 `0` 存放当前状态树的根哈希
+const TRIE_META_TABLE: TableDefinition<u8, &[u8; 32]> =
+    TableDefinition::new("trie_meta");
+
+/// [`TRIE_META_TABLE`] 里根哈希对应的键
+const TRIE_ROOT_KEY: u8 = 0;
+
+/// 每个账户存储子树的根哈希表: address (20 bytes) -> 该账户存储子树的根哈希
+///
+/// 节点内容本身仍然落在共享的 [`TRIE_NODES_TABLE`] 里（键是节点自身内容的
+/// keccak256，账户树和所有账户各自的存储子树天然不会互相覆盖）；这张表只
+/// 负责记住"某个账户的存储子树现在的根是哪个哈希"，供
+/// [`crate::trie::StorageRootCalculator::calculate_persistent`] 增量折叠
+/// 变更槽位时当作起点
+const STORAGE_TRIE_META_TABLE: TableDefinition<&[u8; 20], &[u8; 32]> =
+    TableDefinition::new("storage_trie_meta");
+
+// ==================== 历史状态日志 ====================
+
+/// 反向 diff 的一条记录：某个键在某次提交（某个区块）之前的旧值
+///
+/// 回滚时按区块号降序依次把 `prior` 写回去即可撤销该区块的提交；
+/// `prior` 为 `None`/`U256::ZERO` 分别表示该账户/存储槽在提交前不存在或未设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum JournalEntry {
+    Account { address: Address, prior: Option<Account> },
+    Storage { address: Address, key: U256, prior: U256 },
+    Code { code_hash: B256, prior: Option<Bytes> },
+}
+
 // ==================== RedbStateDB ====================
 
+/// 默认的账户/存储读缓存容量（条目数），见 [`RedbStateDB::with_cache_capacity`]
+const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
 /// 基于 Redb 的状态数据库
-/// 
+///
 /// 提供本地持久化存储，支持账户、存储、代码和区块的读写
 pub struct RedbStateDB {
     /// Redb 数据库实例
     db: Arc<Database>,
-    
+
     /// 事务缓冲区（内存中暂存未提交的变更）
     tx_buffer: RwLock<Option<TransactionBuffer>>,
-    
+
     /// 变更追踪（用于状态根计算）
     changed_accounts: RwLock<Vec<Address>>,
+
+    /// 按账户记录自上次被读取（`get_changed_storage_slots`）以来变更过的存储槽
+    /// （`begin_transaction`/`rollback_transaction`/`rollback_to_block` 也会整体清空），
+    /// 供 [`StorageRootCalculator::calculate_persistent`](crate::trie::StorageRootCalculator::calculate_persistent)
+    /// 增量折叠进该账户的持久化存储子树，不必每次都把账户全部存储槽扫一遍
+    changed_storage: RwLock<HashMap<Address, Vec<U256>>>,
+
+    /// 账户读缓存：`None` 表示已确认该地址不存在（命中比再查一次 redb 便宜），
+    /// 而不是"尚未缓存"
+    account_cache: Mutex<LruCache<Address, Option<Account>>>,
+
+    /// 存储槽读缓存，键是 `(地址, 槽位)`——键本身已经确定归属哪个账户，
+    /// 不同账户的存储天然不会互相覆盖
+    storage_cache: Mutex<LruCache<(Address, U256), U256>>,
+
+    /// 代码读缓存：键是 `code_hash`，`None` 表示已确认该哈希不存在。代码一经
+    /// 部署就不可变，不需要像账户/存储缓存那样担心被覆盖后失效
+    code_cache: Mutex<LruCache<B256, Option<Bytes>>>,
 }
 
 impl RedbStateDB {
-    /// 创建或打开 Redb 数据库
+    /// 创建或打开 Redb 数据库（读缓存使用默认容量）
     pub fn new(path: &str) -> Result<Self, DbError> {
+        Self::with_cache_capacity(path, DEFAULT_CACHE_CAPACITY, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// 创建或打开 Redb 数据库，显式指定账户/存储读缓存各自的条目容量
+    pub fn with_cache_capacity(
+        path: &str,
+        account_cache_capacity: usize,
+        storage_cache_capacity: usize,
+    ) -> Result<Self, DbError> {
         // 确保父目录存在
         if let Some(parent) = std::path::Path::new(path).parent() {
             std::fs::create_dir_all(parent)
                 .map_err(|e| DbError::Io(e))?;
         }
-        
+
         // 创建数据库
         let db = Database::create(path)
             .map_err(|e| DbError::Other(format!("Failed to create database: {}", e)))?;
-        
+
         // 初始化所有表
         let write_txn = db.begin_write()
             .map_err(|e| DbError::Other(e.to_string()))?;
@@ -75,6 +167,20 @@ impl RedbStateDB {
                 .map_err(|e| DbError::Other(e.to_string()))?;
             let _ = write_txn.open_table(BLOCK_HASHES_TABLE)
                 .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(HASH_TO_NUMBER_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(TX_INDEX_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(RECEIPTS_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(JOURNAL_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(TRIE_NODES_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(TRIE_META_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let _ = write_txn.open_table(STORAGE_TRIE_META_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
         }
         write_txn.commit()
             .map_err(|e| DbError::Other(e.to_string()))?;
@@ -83,28 +189,73 @@ impl RedbStateDB {
             db: Arc::new(db),
             tx_buffer: RwLock::new(None),
             changed_accounts: RwLock::new(Vec::new()),
+            changed_storage: RwLock::new(HashMap::new()),
+            account_cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(account_cache_capacity).unwrap_or(NonZeroUsize::new(1).unwrap()),
+            )),
+            storage_cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(storage_cache_capacity).unwrap_or(NonZeroUsize::new(1).unwrap()),
+            )),
+            // 代码一经部署不可变，复用存储缓存的容量就够，不必再加一个构造参数
+            code_cache: Mutex::new(LruCache::new(
+                NonZeroUsize::new(storage_cache_capacity).unwrap_or(NonZeroUsize::new(1).unwrap()),
+            )),
         })
     }
 
-    /// 持久化区块到数据库
-    /// 
-    /// 在区块执行完成后调用此方法，将区块头和交易存储到磁盘
-    pub fn save_block(&self, block: &Block) -> Result<(), DbError> {
+    /// 持久化区块（及其收据）到数据库
+    ///
+    /// 在区块执行完成后调用此方法。区块数据、哈希二级索引、交易索引和收据
+    /// 都在同一个 redb 写事务内提交，保证区块查询与状态提交具有原子性。
+    pub fn save_block(&self, block: &Block, receipts: &[TransactionReceipt]) -> Result<(), DbError> {
         let write_txn = self.db.begin_write()
             .map_err(|e| DbError::Other(e.to_string()))?;
         {
-            let mut table = write_txn.open_table(BLOCKS_TABLE)
+            let mut blocks = write_txn.open_table(BLOCKS_TABLE)
                 .map_err(|e| DbError::Other(e.to_string()))?;
             let data = bincode::serialize(block)
                 .map_err(|e| DbError::Serialization(e.to_string()))?;
-            table.insert(block.header.number, data.as_slice())
+            blocks.insert(block.header.number, data.as_slice())
+                .map_err(|e| DbError::Other(e.to_string()))?;
+
+            if let Some(hash_bytes) = Self::parse_hash(&block.hash()) {
+                let mut hash_to_number = write_txn.open_table(HASH_TO_NUMBER_TABLE)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+                hash_to_number.insert(&hash_bytes, block.header.number)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            let mut tx_index = write_txn.open_table(TX_INDEX_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            for (index, tx) in block.transactions.iter().enumerate() {
+                let Some(hash_bytes) = tx.hash.as_deref().and_then(Self::parse_hash) else {
+                    continue;
+                };
+                tx_index.insert(&hash_bytes, (block.header.number, index as u64))
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            let mut receipts_table = write_txn.open_table(RECEIPTS_TABLE)
                 .map_err(|e| DbError::Other(e.to_string()))?;
+            for receipt in receipts {
+                let data = bincode::serialize(receipt)
+                    .map_err(|e| DbError::Serialization(e.to_string()))?;
+                let hash_bytes: [u8; 32] = receipt.transaction_hash.as_slice().try_into().unwrap();
+                receipts_table.insert(&hash_bytes, data.as_slice())
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
         }
         write_txn.commit()
             .map_err(|e| DbError::Other(e.to_string()))?;
         Ok(())
     }
 
+    /// 将十六进制哈希字符串（可带 `0x` 前缀）解析为定长字节数组，解析失败返回 `None`
+    fn parse_hash(value: &str) -> Option<[u8; 32]> {
+        let hex = value.trim_start_matches("0x");
+        hex::decode(hex).ok()?.try_into().ok()
+    }
+
     /// 从数据库读取区块
     pub fn get_block(&self, block_number: u64) -> Result<Option<Block>, DbError> {
         let read_txn = self.db.begin_read()
@@ -123,11 +274,541 @@ impl RedbStateDB {
         }
     }
 
+    /// 计算当前状态根（主网口径的安全 Merkle Patricia Trie）
+    ///
+    /// 薄封装：真正的两层 trie（账户树 + 每账户存储子树）构建与增量重算逻辑见
+    /// [`crate::trie::StateRootCalculator`]，这里只是把它绑定到本实例的
+    /// `get_changed_accounts()`/`get_changed_storage_slots()` 与 trie 节点表上，省得调用方自己构造计算器。
+    pub fn state_root(&self) -> Result<B256, DbError> {
+        crate::trie::StateRootCalculator::new(self)
+            .calculate_incremental()
+            .map_err(|e| DbError::Other(e.to_string()))
+    }
+
+    /// 把整条链状态导出为纯内存快照（测试固件、调试转储、创世状态比较）
+    ///
+    /// 遍历账户表，为每个账户经 [`StateDatabase::get_all_storage`] 收集全部非零
+    /// 存储槽，并从代码表解析字节码；EOA（`code_hash == EMPTY_CODE_HASH`）的
+    /// `code` 留空，不去查代码表。
+    pub fn to_pod(&self) -> Result<PodState, DbError> {
+        let mut pod = BTreeMap::new();
+        for (address, account) in self.get_all_accounts()? {
+            let code = if account.code_hash == EMPTY_CODE_HASH {
+                Bytes::new()
+            } else {
+                self.get_code(&account.code_hash)?.unwrap_or_default()
+            };
+            let storage = self.get_all_storage(&address)?
+                .into_iter()
+                .filter(|slot| slot.value != U256::ZERO)
+                .map(|slot| (slot.key, slot.value))
+                .collect();
+
+            pod.insert(address, PodAccount {
+                balance: account.balance,
+                nonce: account.nonce,
+                code,
+                storage,
+            });
+        }
+        Ok(PodState(pod))
+    }
+
+    /// 把一份完整的 [`PodState`] 在单个事务内加载进数据库（创世状态注入、固件加载）
+    ///
+    /// 代码按内容重新算出 `code_hash`（空代码沿用 `EMPTY_CODE_HASH`，不写代码表），
+    /// `storage_root` 留空树哈希——它只是落盘占位，真正的根由
+    /// [`crate::trie::StateRootCalculator`] 在状态根计算时重新推导。
+    pub fn apply_pod(&mut self, pod: &PodState) -> Result<(), DbError> {
+        self.begin_transaction()?;
+        for (address, pod_account) in &pod.0 {
+            let code_hash = if pod_account.code.is_empty() {
+                EMPTY_CODE_HASH
+            } else {
+                let hash = keccak256(&pod_account.code);
+                self.set_code(hash, pod_account.code.clone())?;
+                hash
+            };
+
+            self.set_account(address, Account {
+                nonce: pod_account.nonce,
+                balance: pod_account.balance,
+                storage_root: EMPTY_STORAGE_ROOT,
+                code_hash,
+            })?;
+
+            for (slot, value) in &pod_account.storage {
+                self.set_storage(address, *slot, *value)?;
+            }
+        }
+        self.commit_transaction()
+    }
+
+    /// 分页枚举某账户的存储槽，避免一次性把大合约的全部存储物化进内存
+    ///
+    /// 从 `start_key`（含）开始按键升序最多返回 `limit` 条；返回值的第二项是
+    /// 续读游标——还有更多数据时为下一页应传入的 `start_key`，枚举完该账户的
+    /// 全部存储后则为 `None`。底层复用和 [`StateDatabase::get_all_storage`]
+    /// 同样的地址前缀 range，只是提前在达到 `limit` 时截断。
+    pub fn get_storage_range(
+        &self,
+        address: &Address,
+        start_key: U256,
+        limit: usize,
+    ) -> Result<(Vec<StorageSlot>, Option<U256>), DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let table = read_txn.open_table(STORAGE_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
+        let start_bytes: [u8; 32] = start_key.to_be_bytes();
+        let end_bytes = [0xffu8; 32];
+        let range = table.range((&addr_bytes, &start_bytes)..=(&addr_bytes, &end_bytes))
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        let mut slots = Vec::with_capacity(limit);
+        let mut cursor = None;
+        for item in range {
+            let (key, value) = item.map_err(|e| DbError::Other(e.to_string()))?;
+            let (_, key_slot) = key.value();
+            let slot_key = U256::from_be_bytes(*key_slot);
+
+            if slots.len() == limit {
+                cursor = Some(slot_key);
+                break;
+            }
+            slots.push(StorageSlot {
+                address: *address,
+                key: slot_key,
+                value: U256::from_be_bytes(*value.value()),
+            });
+        }
+
+        Ok((slots, cursor))
+    }
+
+    /// 提交当前事务，并把本次提交的反向 diff 记入 `JOURNAL_TABLE`（键为 `block_number`）
+    ///
+    /// 和普通的 [`StateDatabase::commit_transaction`] 走同一条落盘路径，区别只是多记一份
+    /// 历史日志——调用方（`BlockExecutor`）知道自己正在提交哪个区块，而 `StateDatabase`
+    /// trait 本身不携带区块号，所以日志开关只能在这个具体类型上的方法里暴露。
+    pub fn commit_transaction_at_block(&mut self, block_number: u64) -> Result<(), DbError> {
+        let mut buffer_guard = self.tx_buffer.write();
+        let buffer = buffer_guard.take()
+            .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+        drop(buffer_guard);
+        self.commit_buffer(buffer, Some(block_number))
+    }
+
+    /// `commit_transaction` / `commit_transaction_at_block` 共用的落盘逻辑
+    ///
+    /// `journal_block` 为 `Some(n)` 时，在写入前先把每个被触碰的键在提交前的旧值
+    /// 记下来，和本次写入一起落进同一个 redb 写事务，保证日志与实际状态变更同生共死。
+    fn commit_buffer(&mut self, buffer: TransactionBuffer, journal_block: Option<u64>) -> Result<(), DbError> {
+        // 读缓存要同步的内容——趁 `buffer` 的字段还没被下面的写入循环消费掉，
+        // 先收集一份。`deleted_accounts` 排在 `accounts` 后面 chain，保证同一
+        // 地址若先 set 后 delete，缓存最终落地的是 `None`，和 redb 里的真实状态一致。
+        let cache_accounts: Vec<(Address, Option<Account>)> = buffer.accounts.iter()
+            .map(|(addr, acc)| (*addr, Some(acc.clone())))
+            .chain(buffer.deleted_accounts.iter().map(|addr| (*addr, None)))
+            .collect();
+        let cache_storage: Vec<((Address, U256), U256)> = buffer.storage.iter()
+            .map(|(k, v)| (*k, *v))
+            .collect();
+        let cache_codes: Vec<(B256, Option<Bytes>)> = buffer.codes.iter()
+            .map(|(hash, code)| (*hash, Some(code.clone())))
+            .collect();
+
+        // 日志：必须在任何写入之前读取旧值，否则读到的就是本次提交刚写入的新值了
+        let journal_entries = if journal_block.is_some() {
+            let mut entries = Vec::new();
+            for addr in buffer.accounts.keys().chain(buffer.deleted_accounts.iter()) {
+                entries.push(JournalEntry::Account { address: *addr, prior: self.get_account(addr)? });
+            }
+            for (addr, key) in buffer.storage.keys() {
+                entries.push(JournalEntry::Storage { address: *addr, key: *key, prior: self.get_storage(addr, *key)? });
+            }
+            for code_hash in buffer.codes.keys() {
+                entries.push(JournalEntry::Code { code_hash: *code_hash, prior: self.get_code(code_hash)? });
+            }
+            Some(entries)
+        } else {
+            None
+        };
+
+        let write_txn = self.db.begin_write()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        {
+            // 写入账户变更
+            let mut accounts = write_txn.open_table(ACCOUNTS_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            for (addr, acc) in buffer.accounts {
+                let data = bincode::serialize(&acc)
+                    .map_err(|e| DbError::Serialization(e.to_string()))?;
+                let addr_bytes: [u8; 20] = addr.as_slice().try_into().unwrap();
+                accounts.insert(&addr_bytes, data.as_slice())
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            // 删除账户
+            for addr in buffer.deleted_accounts {
+                let addr_bytes: [u8; 20] = addr.as_slice().try_into().unwrap();
+                accounts.remove(&addr_bytes)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            // 写入存储变更
+            let mut storage = write_txn.open_table(STORAGE_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            for ((addr, key), val) in buffer.storage {
+                let addr_bytes: [u8; 20] = addr.as_slice().try_into().unwrap();
+                let key_bytes: [u8; 32] = key.to_be_bytes();
+                let val_bytes: [u8; 32] = val.to_be_bytes();
+                storage.insert((&addr_bytes, &key_bytes), &val_bytes)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            // 写入代码
+            let mut codes = write_txn.open_table(CODE_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            for (code_hash, code) in buffer.codes {
+                let hash_bytes: [u8; 32] = code_hash.as_slice().try_into().unwrap();
+                codes.insert(&hash_bytes, code.as_ref())
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            // 写入区块哈希
+            let mut block_hashes = write_txn.open_table(BLOCK_HASHES_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            for (block_number, block_hash) in buffer.block_hashes {
+                let hash_bytes: [u8; 32] = block_hash.as_slice().try_into().unwrap();
+                block_hashes.insert(block_number, &hash_bytes)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+
+            // 写入历史日志（与上面的状态变更同属一个写事务，保证原子性）
+            if let (Some(block_number), Some(entries)) = (journal_block, &journal_entries) {
+                let data = bincode::serialize(entries)
+                    .map_err(|e| DbError::Serialization(e.to_string()))?;
+                let mut journal = write_txn.open_table(JOURNAL_TABLE)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+                journal.insert(block_number, data.as_slice())
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+        }
+        write_txn.commit()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        // 事务已经落盘，现在把读缓存同步到同一份结果，避免后续读到事务开始前的旧值
+        {
+            let mut acc_cache = self.account_cache.lock();
+            for (addr, acc) in cache_accounts {
+                acc_cache.put(addr, acc);
+            }
+        }
+        {
+            let mut storage_cache = self.storage_cache.lock();
+            for (key, value) in cache_storage {
+                storage_cache.put(key, value);
+            }
+        }
+        {
+            let mut code_cache = self.code_cache.lock();
+            for (hash, code) in cache_codes {
+                code_cache.put(hash, code);
+            }
+        }
+        Ok(())
+    }
+
+    /// 校验 `> target_block` 的日志条目完整覆盖 `(target_block, head]` 区间，
+    /// 没有被 [`Self::prune`] 削掉一截导致的空洞
+    ///
+    /// `blocks` 里的每个区块号都已知存在于 journal 中（来自 `journal.iter()`
+    /// 的过滤结果），所以只要数量对得上 `head - target_block`，这段区间就一定
+    /// 被逐一覆盖——区间内总共只有这么多个整数，子集数量吻合，子集就只能是
+    /// 整个区间（抽屉原理）。数量对不上说明 `target_block` 早于当前保留的
+    /// 最老日志，继续重放只会得到一个看似合理、实际不完整的状态。
+    fn check_journal_contiguous(
+        all_blocks: &[u64],
+        blocks: &[u64],
+        target_block: u64,
+    ) -> Result<(), DbError> {
+        let Some(&head) = all_blocks.iter().max() else {
+            return Ok(());
+        };
+        if head <= target_block {
+            return Ok(());
+        }
+        let expected = (head - target_block) as usize;
+        if blocks.len() != expected {
+            let oldest_retained = all_blocks.iter().copied().min().unwrap_or(head);
+            return Err(DbError::JournalGap { requested: target_block, oldest_retained });
+        }
+        Ok(())
+    }
+
+    /// 把状态回滚到 `target_block`（含）之后的所有区块都被撤销
+    ///
+    /// 按区块号降序依次重放 `JOURNAL_TABLE` 里 `> target_block` 的反向 diff；
+    /// 整个重放在一个 redb 写事务内完成，要么全部生效要么完全不生效，不会留下
+    /// 半回滚的中间状态。已重放的日志条目会被一并删除，所以重复调用是幂等的——
+    /// 第二次调用时 `> target_block` 已经没有日志可放了。
+    ///
+    /// 若 `target_block` 早于 [`Self::prune`] 保留的最老日志，`(target_block, head]`
+    /// 区间里会缺一截反向 diff，此时返回 [`DbError::JournalGap`] 而不是悄悄重放
+    /// 一个不完整的子集——宁可报错也不要返回一个看似合理但错误的状态。
+    pub fn rollback_to_block(&mut self, target_block: u64) -> Result<(), DbError> {
+        let write_txn = self.db.begin_write()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        {
+            let mut journal = write_txn.open_table(JOURNAL_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let all_blocks: Vec<u64> = journal.iter()
+                .map_err(|e| DbError::Other(e.to_string()))?
+                .filter_map(|item| item.ok().map(|(k, _)| k.value()))
+                .collect();
+            let mut blocks: Vec<u64> = all_blocks.iter()
+                .copied()
+                .filter(|&b| b > target_block)
+                .collect();
+            Self::check_journal_contiguous(&all_blocks, &blocks, target_block)?;
+            blocks.sort_unstable_by(|a, b| b.cmp(a));
+
+            let mut accounts = write_txn.open_table(ACCOUNTS_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let mut storage = write_txn.open_table(STORAGE_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let mut codes = write_txn.open_table(CODE_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+
+            for block_number in blocks {
+                let data = journal.get(block_number)
+                    .map_err(|e| DbError::Other(e.to_string()))?
+                    .map(|g| g.value().to_vec());
+                if let Some(data) = data {
+                    let entries: Vec<JournalEntry> = bincode::deserialize(&data)
+                        .map_err(|e| DbError::Serialization(e.to_string()))?;
+                    for entry in entries {
+                        match entry {
+                            JournalEntry::Account { address, prior } => {
+                                let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
+                                match prior {
+                                    Some(acc) => {
+                                        let data = bincode::serialize(&acc)
+                                            .map_err(|e| DbError::Serialization(e.to_string()))?;
+                                        accounts.insert(&addr_bytes, data.as_slice())
+                                            .map_err(|e| DbError::Other(e.to_string()))?;
+                                    }
+                                    None => {
+                                        accounts.remove(&addr_bytes)
+                                            .map_err(|e| DbError::Other(e.to_string()))?;
+                                    }
+                                }
+                            }
+                            JournalEntry::Storage { address, key, prior } => {
+                                let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
+                                let key_bytes: [u8; 32] = key.to_be_bytes();
+                                let val_bytes: [u8; 32] = prior.to_be_bytes();
+                                storage.insert((&addr_bytes, &key_bytes), &val_bytes)
+                                    .map_err(|e| DbError::Other(e.to_string()))?;
+                            }
+                            JournalEntry::Code { code_hash, prior } => {
+                                let hash_bytes: [u8; 32] = code_hash.as_slice().try_into().unwrap();
+                                match prior {
+                                    Some(code) => {
+                                        codes.insert(&hash_bytes, code.as_ref())
+                                            .map_err(|e| DbError::Other(e.to_string()))?;
+                                    }
+                                    None => {
+                                        codes.remove(&hash_bytes)
+                                            .map_err(|e| DbError::Other(e.to_string()))?;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+                journal.remove(block_number)
+                    .map_err(|e| DbError::Other(e.to_string()))?;
+            }
+        }
+        write_txn.commit()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        // 被回滚的键已经大范围变化，读缓存和变更追踪都可能持有失效数据，
+        // 干脆整体清空，交给后续读取重新从 redb 填充
+        self.account_cache.lock().clear();
+        self.storage_cache.lock().clear();
+        self.code_cache.lock().clear();
+        self.changed_accounts.write().clear();
+        self.changed_storage.write().clear();
+        Ok(())
+    }
+
+    /// 删除区块号 `<= head - keep_blocks` 的历史日志（只保留最近 `keep_blocks`
+    /// 个区块的日志），避免日志无限增长
+    ///
+    /// `head` 取 `JOURNAL_TABLE` 中已有的最大区块号；没有任何日志时直接返回。
+    /// 修剪之后，目标早于保留窗口的 [`Self::rollback_to_block`]/
+    /// [`Self::get_account_at`] 调用会遇到日志空洞，返回 [`DbError::JournalGap`]
+    /// 而不是静默重放不完整的历史。
+    pub fn prune(&mut self, keep_blocks: u64) -> Result<(), DbError> {
+        let write_txn = self.db.begin_write()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        {
+            let mut journal = write_txn.open_table(JOURNAL_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let head = journal.iter()
+                .map_err(|e| DbError::Other(e.to_string()))?
+                .next_back()
+                .transpose()
+                .map_err(|e| DbError::Other(e.to_string()))?
+                .map(|(k, _)| k.value());
+
+            if let Some(head) = head {
+                let cutoff = head.saturating_sub(keep_blocks);
+                let stale: Vec<u64> = journal.iter()
+                    .map_err(|e| DbError::Other(e.to_string()))?
+                    .filter_map(|item| item.ok().map(|(k, _)| k.value()))
+                    .filter(|&b| b <= cutoff)
+                    .collect();
+                for block_number in stale {
+                    journal.remove(block_number)
+                        .map_err(|e| DbError::Other(e.to_string()))?;
+                }
+            }
+        }
+        write_txn.commit()
+            .map_err(|e| DbError::Other(e.to_string()))
+    }
+
+    /// 读取账户在 `block_number` 这个高度上的历史值
+    ///
+    /// 从当前状态出发，按区块号降序把 `> block_number` 的每个区块的反向 diff
+    /// 里和该地址相关的条目依次应用，最终落在最老（即最贴近 `block_number`）
+    /// 的那条上，也就是目标高度时的值。
+    ///
+    /// 若 `block_number` 早于 [`Self::prune`] 保留的最老日志，`(block_number, head]`
+    /// 区间里会缺一截反向 diff，此时返回 [`DbError::JournalGap`]，而不是只用
+    /// 残存的那部分日志重放出一个看似合理但实际不完整的历史值。
+    pub fn get_account_at(&self, address: &Address, block_number: u64) -> Result<Option<Account>, DbError> {
+        let mut value = self.get_account(address)?;
+
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let journal = read_txn.open_table(JOURNAL_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let all_blocks: Vec<u64> = journal.iter()
+            .map_err(|e| DbError::Other(e.to_string()))?
+            .filter_map(|item| item.ok().map(|(k, _)| k.value()))
+            .collect();
+        let mut blocks: Vec<u64> = all_blocks.iter()
+            .copied()
+            .filter(|&b| b > block_number)
+            .collect();
+        Self::check_journal_contiguous(&all_blocks, &blocks, block_number)?;
+        blocks.sort_unstable_by(|a, b| b.cmp(a));
+
+        for block in blocks {
+            let data = journal.get(block)
+                .map_err(|e| DbError::Other(e.to_string()))?
+                .map(|g| g.value().to_vec());
+            if let Some(data) = data {
+                let entries: Vec<JournalEntry> = bincode::deserialize(&data)
+                    .map_err(|e| DbError::Serialization(e.to_string()))?;
+                for entry in entries {
+                    if let JournalEntry::Account { address: a, prior } = entry {
+                        if a == *address {
+                            value = prior;
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// 读出某个区块历史日志里，每个被触碰账户/存储槽在提交前的原始值，以及
+    /// 提交后（即数据库当前状态）的新值
+    ///
+    /// 专供 [`crate::executor::BlockExecutor::execute_block`] 在
+    /// `commit_transaction_at_block` 之后立即调用，组装成对调用方暴露的
+    /// `StateBundle`——组装逻辑本身不属于这一层，这里只负责把 `JOURNAL_TABLE`
+    /// 里的反向 diff 和"当前值"配对读出来。`JournalEntry::Code` 条目不在返回
+    /// 范围内：账户代码一经部署不可变，撤销区块不需要撤销代码表。
+    ///
+    /// 和 [`Self::get_account_at`] 一样，"新值"取的是调用时的当前状态，因此假定
+    /// 调用方没有拖延——如果在该区块提交之后、取值之前又有新区块改写了同一个
+    /// 账户，这里读到的"新值"会是最新值而不是该区块提交时的值。
+    pub fn journal_entries_for_block(
+        &self,
+        block_number: u64,
+    ) -> Result<(Vec<(Address, Option<Account>, Account)>, Vec<(Address, U256, U256, U256)>), DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let journal = read_txn.open_table(JOURNAL_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let data = journal.get(block_number)
+            .map_err(|e| DbError::Other(e.to_string()))?
+            .map(|g| g.value().to_vec());
+        drop(journal);
+        drop(read_txn);
+
+        let mut account_entries = Vec::new();
+        let mut storage_entries = Vec::new();
+        let Some(data) = data else {
+            return Ok((account_entries, storage_entries));
+        };
+        let entries: Vec<JournalEntry> = bincode::deserialize(&data)
+            .map_err(|e| DbError::Serialization(e.to_string()))?;
+
+        for entry in entries {
+            match entry {
+                JournalEntry::Account { address, prior } => {
+                    let new = self.get_account(&address)?.unwrap_or_default();
+                    account_entries.push((address, prior, new));
+                }
+                JournalEntry::Storage { address, key, prior } => {
+                    let new = self.get_storage(&address, key)?;
+                    storage_entries.push((address, key, prior, new));
+                }
+                JournalEntry::Code { .. } => {}
+            }
+        }
+
+        Ok((account_entries, storage_entries))
+    }
+
     /// 追踪变更的账户
+    ///
+    /// 只有首次把某地址记入 `changed_accounts` 才会同步告知当前事务缓冲区的
+    /// 最顶层 checkpoint（见 [`TransactionBuffer::record_changed_account`]）——
+    /// 这样 `revert_to_checkpoint` 才能知道该地址的"已变更"标记是本层新增的，
+    /// 需要随回滚一起撤回，而不是本来就在更外层被标记过。
     fn track_changed_account(&self, address: Address) {
         let mut changed = self.changed_accounts.write();
         if !changed.contains(&address) {
             changed.push(address);
+            if let Some(ref mut buffer) = *self.tx_buffer.write() {
+                buffer.record_changed_account(address);
+            }
+        }
+    }
+
+    /// 追踪某个账户变更过的存储槽，供 [`StateDatabase::get_changed_storage_slots`] 消费
+    ///
+    /// 和 [`Self::track_changed_account`] 不同，这里不做跟随 checkpoint 回滚的精细撤回——
+    /// `get_changed_storage_slots` 读出的槽位总是拿去重新查一遍当前值再折叠进持久化存储
+    /// 子树，哪怕 checkpoint 回滚之后这里多留了几个"其实没变"的槽位，顶多多算几次
+    /// 无害的增量折叠，不会算出错误的根。
+    fn track_changed_storage(&self, address: Address, key: U256) {
+        let mut changed = self.changed_storage.write();
+        let slots = changed.entry(address).or_default();
+        if !slots.contains(&key) {
+            slots.push(key);
         }
     }
 }
@@ -146,7 +827,13 @@ impl StateDatabase for RedbStateDB {
             }
         }
 
-        // 2. 从 Redb 读取
+        // 2. 再查读缓存——命中时连"确认不存在"（`None`）也直接返回，
+        // 不必为一个已知不存在的地址反复打开 redb 读事务
+        if let Some(cached) = self.account_cache.lock().get(address) {
+            return Ok(cached.clone());
+        }
+
+        // 3. 从 Redb 读取，并把结果（含未命中）填充进缓存
         let read_txn = self.db.begin_read()
             .map_err(|e| DbError::Other(e.to_string()))?;
         let table = read_txn.open_table(ACCOUNTS_TABLE)
@@ -154,25 +841,27 @@ impl StateDatabase for RedbStateDB {
         let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
         let value = table.get(&addr_bytes)
             .map_err(|e| DbError::Other(e.to_string()))?;
-        
-        if let Some(data) = value {
-            let account: Account = bincode::deserialize(data.value())
-                .map_err(|e| DbError::Serialization(e.to_string()))?;
-            Ok(Some(account))
+
+        let account = if let Some(data) = value {
+            Some(bincode::deserialize(data.value())
+                .map_err(|e| DbError::Serialization(e.to_string()))?)
         } else {
-            Ok(None)
-        }
+            None
+        };
+        self.account_cache.lock().put(*address, account.clone());
+        Ok(account)
     }
 
     fn set_account(&mut self, address: &Address, account: Account) -> Result<(), DbError> {
         self.track_changed_account(*address);
 
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
-            // 事务模式：写入缓冲区
+            // 事务模式：写入缓冲区，缓存留到 `commit_transaction` 时再同步
+            buffer.record_write(TouchedKeyKind::Account(*address));
             buffer.accounts.insert(*address, account);
             Ok(())
         } else {
-            // 直接模式：立即持久化
+            // 直接模式：立即持久化，并同步更新读缓存避免后续读到旧值
             let write_txn = self.db.begin_write()
                 .map_err(|e| DbError::Other(e.to_string()))?;
             {
@@ -185,7 +874,9 @@ impl StateDatabase for RedbStateDB {
                     .map_err(|e| DbError::Other(e.to_string()))?;
             }
             write_txn.commit()
-                .map_err(|e| DbError::Other(e.to_string()))
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            self.account_cache.lock().put(*address, Some(account));
+            Ok(())
         }
     }
 
@@ -193,6 +884,7 @@ impl StateDatabase for RedbStateDB {
         self.track_changed_account(*address);
 
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
+            buffer.record_write(TouchedKeyKind::Account(*address));
             buffer.accounts.remove(address);
             buffer.deleted_accounts.push(*address);
             Ok(())
@@ -207,7 +899,11 @@ impl StateDatabase for RedbStateDB {
                     .map_err(|e| DbError::Other(e.to_string()))?;
             }
             write_txn.commit()
-                .map_err(|e| DbError::Other(e.to_string()))
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            // 负缓存：记下"已确认不存在"，而不是把这个地址逐出缓存了事——
+            // 否则下一次 `get_account` 又要白白打开一次 redb 读事务
+            self.account_cache.lock().put(*address, None);
+            Ok(())
         }
     }
 
@@ -219,27 +915,37 @@ impl StateDatabase for RedbStateDB {
             }
         }
 
-        // 2. 从 Redb 读取
+        // 2. 再查读缓存
+        if let Some(cached) = self.storage_cache.lock().get(&(*address, key)) {
+            return Ok(*cached);
+        }
+
+        // 3. 从 Redb 读取，并把结果（未设置的槽位即 U256::ZERO）填充进缓存
         let read_txn = self.db.begin_read()
             .map_err(|e| DbError::Other(e.to_string()))?;
         let table = read_txn.open_table(STORAGE_TABLE)
             .map_err(|e| DbError::Other(e.to_string()))?;
         let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
         let key_bytes: [u8; 32] = key.to_be_bytes();
-        
+
         let value = table.get((&addr_bytes, &key_bytes))
             .map_err(|e| DbError::Other(e.to_string()))?;
-        if let Some(data) = value {
-            Ok(U256::from_be_bytes(*data.value()))
+        let value = if let Some(data) = value {
+            U256::from_be_bytes(*data.value())
         } else {
-            Ok(U256::ZERO)
-        }
+            U256::ZERO
+        };
+        self.storage_cache.lock().put((*address, key), value);
+        Ok(value)
     }
 
     fn set_storage(&mut self, address: &Address, key: U256, value: U256) -> Result<(), DbError> {
         self.track_changed_account(*address);
+        self.track_changed_storage(*address, key);
 
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
+            // 事务模式：写入缓冲区，缓存留到 `commit_transaction` 时再同步
+            buffer.record_write(TouchedKeyKind::Storage(*address, key));
             buffer.storage.insert((*address, key), value);
             Ok(())
         } else {
@@ -255,7 +961,9 @@ impl StateDatabase for RedbStateDB {
                     .map_err(|e| DbError::Other(e.to_string()))?;
             }
             write_txn.commit()
-                .map_err(|e| DbError::Other(e.to_string()))
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            self.storage_cache.lock().put((*address, key), value);
+            Ok(())
         }
     }
 
@@ -264,34 +972,37 @@ impl StateDatabase for RedbStateDB {
             .map_err(|e| DbError::Other(e.to_string()))?;
         let table = read_txn.open_table(STORAGE_TABLE)
             .map_err(|e| DbError::Other(e.to_string()))?;
-        
+
         let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
-        let mut slots = Vec::new();
-        
-        // 迭代所有条目，过滤出属于该地址的存储槽
-        let iter = table.iter()
+
+        // `STORAGE_TABLE` 的键是 `(地址, 槽位)`，redb 按元组编码逐字节比较，
+        // 地址排在前面——所以只要把范围的下界/上界都钉死在同一个地址上，
+        // 就能跳过其它账户的全部条目，而不必像之前那样扫完整张表再过滤
+        let start_key = [0u8; 32];
+        let end_key = [0xffu8; 32];
+        let range = table.range((&addr_bytes, &start_key)..=(&addr_bytes, &end_key))
             .map_err(|e| DbError::Other(e.to_string()))?;
-        
-        for item in iter {
+
+        let mut slots = Vec::new();
+        for item in range {
             let (key, value) = item.map_err(|e| DbError::Other(e.to_string()))?;
-            let (key_addr, key_slot) = key.value();
-            
-            // 检查地址是否匹配
-            if key_addr == &addr_bytes {
-                let slot_key = U256::from_be_bytes(*key_slot);
-                let slot_value = U256::from_be_bytes(*value.value());
-                slots.push(StorageSlot {
-                    address: *address,
-                    key: slot_key,
-                    value: slot_value,
-                });
-            }
+            let (_, key_slot) = key.value();
+            slots.push(StorageSlot {
+                address: *address,
+                key: U256::from_be_bytes(*key_slot),
+                value: U256::from_be_bytes(*value.value()),
+            });
         }
-        
+
         Ok(slots)
     }
 
     fn get_code(&self, code_hash: &B256) -> Result<Option<Bytes>, DbError> {
+        // 1. 先查代码读缓存——代码一经部署不可变，不需要像账户/存储那样担心缓冲区覆盖
+        if let Some(cached) = self.code_cache.lock().get(code_hash) {
+            return Ok(cached.clone());
+        }
+
         let read_txn = self.db.begin_read()
             .map_err(|e| DbError::Other(e.to_string()))?;
         let table = read_txn.open_table(CODE_TABLE)
@@ -299,11 +1010,14 @@ impl StateDatabase for RedbStateDB {
         let hash_bytes: [u8; 32] = code_hash.as_slice().try_into().unwrap();
         let value = table.get(&hash_bytes)
             .map_err(|e| DbError::Other(e.to_string()))?;
-        Ok(value.map(|d| Bytes::copy_from_slice(d.value())))
+        let code = value.map(|d| Bytes::copy_from_slice(d.value()));
+        self.code_cache.lock().put(*code_hash, code.clone());
+        Ok(code)
     }
 
     fn set_code(&mut self, code_hash: B256, code: Bytes) -> Result<(), DbError> {
         if let Some(ref mut buffer) = *self.tx_buffer.write() {
+            buffer.record_write(TouchedKeyKind::Code(code_hash));
             buffer.codes.insert(code_hash, code);
             Ok(())
         } else {
@@ -317,7 +1031,9 @@ impl StateDatabase for RedbStateDB {
                     .map_err(|e| DbError::Other(e.to_string()))?;
             }
             write_txn.commit()
-                .map_err(|e| DbError::Other(e.to_string()))
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            self.code_cache.lock().put(code_hash, Some(code));
+            Ok(())
         }
     }
 
@@ -365,6 +1081,7 @@ impl StateDatabase for RedbStateDB {
         }
         *buffer = Some(TransactionBuffer::new());
         self.changed_accounts.write().clear();
+        self.changed_storage.write().clear();
         Ok(())
     }
 
@@ -372,77 +1089,261 @@ impl StateDatabase for RedbStateDB {
         let mut buffer_guard = self.tx_buffer.write();
         let buffer = buffer_guard.take()
             .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+        drop(buffer_guard);
+        self.commit_buffer(buffer, None)
+    }
+
+    fn rollback_transaction(&mut self) -> Result<(), DbError> {
+        *self.tx_buffer.write() = None;
+        self.changed_accounts.write().clear();
+        self.changed_storage.write().clear();
+        Ok(())
+    }
+
+    fn checkpoint(&mut self) -> Result<usize, DbError> {
+        let mut buffer = self.tx_buffer.write();
+        let buffer = buffer.as_mut()
+            .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+        Ok(buffer.checkpoint())
+    }
 
+    fn revert_to_checkpoint(&mut self, id: usize) -> Result<(), DbError> {
+        let reverted_changed_accounts = {
+            let mut buffer = self.tx_buffer.write();
+            let buffer = buffer.as_mut()
+                .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+            buffer.revert_to_checkpoint(id)?
+        };
+
+        if !reverted_changed_accounts.is_empty() {
+            let mut changed = self.changed_accounts.write();
+            changed.retain(|a| !reverted_changed_accounts.contains(a));
+        }
+        Ok(())
+    }
+
+    fn discard_checkpoint(&mut self, id: usize) -> Result<(), DbError> {
+        let mut buffer = self.tx_buffer.write();
+        let buffer = buffer.as_mut()
+            .ok_or_else(|| DbError::Transaction("No active transaction".to_string()))?;
+        buffer.discard_checkpoint(id)
+    }
+
+    fn get_changed_accounts(&self) -> Result<Vec<Address>, DbError> {
+        Ok(self.changed_accounts.read().clone())
+    }
+
+    fn get_all_accounts(&self) -> Result<Vec<(Address, Account)>, DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let table = read_txn.open_table(ACCOUNTS_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        let mut accounts = Vec::new();
+        let iter = table.iter()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        for item in iter {
+            let (key, value) = item.map_err(|e| DbError::Other(e.to_string()))?;
+            let address = Address::from_slice(key.value());
+            let account: Account = bincode::deserialize(value.value())
+                .map_err(|e| DbError::Serialization(e.to_string()))?;
+            accounts.push((address, account));
+        }
+
+        Ok(accounts)
+    }
+
+    fn trie_node(&self, hash: B256) -> Result<Option<Vec<u8>>, DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let table = read_txn.open_table(TRIE_NODES_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let key: [u8; 32] = hash.as_slice().try_into().unwrap();
+        Ok(table.get(&key)
+            .map_err(|e| DbError::Other(e.to_string()))?
+            .map(|v| v.value().to_vec()))
+    }
+
+    fn put_trie_node(&self, hash: B256, data: Vec<u8>) -> Result<(), DbError> {
         let write_txn = self.db.begin_write()
             .map_err(|e| DbError::Other(e.to_string()))?;
         {
-            // 写入账户变更
-            let mut accounts = write_txn.open_table(ACCOUNTS_TABLE)
+            let mut table = write_txn.open_table(TRIE_NODES_TABLE)
                 .map_err(|e| DbError::Other(e.to_string()))?;
-            for (addr, acc) in buffer.accounts {
-                let data = bincode::serialize(&acc)
-                    .map_err(|e| DbError::Serialization(e.to_string()))?;
-                let addr_bytes: [u8; 20] = addr.as_slice().try_into().unwrap();
-                accounts.insert(&addr_bytes, data.as_slice())
-                    .map_err(|e| DbError::Other(e.to_string()))?;
-            }
-
-            // 删除账户
-            for addr in buffer.deleted_accounts {
-                let addr_bytes: [u8; 20] = addr.as_slice().try_into().unwrap();
-                accounts.remove(&addr_bytes)
-                    .map_err(|e| DbError::Other(e.to_string()))?;
-            }
-            
-            // 写入存储变更
-            let mut storage = write_txn.open_table(STORAGE_TABLE)
+            let key: [u8; 32] = hash.as_slice().try_into().unwrap();
+            table.insert(&key, data.as_slice())
                 .map_err(|e| DbError::Other(e.to_string()))?;
-            for ((addr, key), val) in buffer.storage {
-                let addr_bytes: [u8; 20] = addr.as_slice().try_into().unwrap();
-                let key_bytes: [u8; 32] = key.to_be_bytes();
-                let val_bytes: [u8; 32] = val.to_be_bytes();
-                storage.insert((&addr_bytes, &key_bytes), &val_bytes)
-                    .map_err(|e| DbError::Other(e.to_string()))?;
-            }
+        }
+        write_txn.commit().map_err(|e| DbError::Other(e.to_string()))
+    }
 
-            // 写入代码
-            let mut codes = write_txn.open_table(CODE_TABLE)
-                .map_err(|e| DbError::Other(e.to_string()))?;
-            for (code_hash, code) in buffer.codes {
-                let hash_bytes: [u8; 32] = code_hash.as_slice().try_into().unwrap();
-                codes.insert(&hash_bytes, code.as_ref())
-                    .map_err(|e| DbError::Other(e.to_string()))?;
-            }
+    fn trie_root_hash(&self) -> Result<Option<B256>, DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let table = read_txn.open_table(TRIE_META_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        Ok(table.get(TRIE_ROOT_KEY)
+            .map_err(|e| DbError::Other(e.to_string()))?
+            .map(|v| B256::from_slice(v.value())))
+    }
 
-            // 写入区块哈希
-            let mut block_hashes = write_txn.open_table(BLOCK_HASHES_TABLE)
+    fn set_trie_root_hash(&self, hash: B256) -> Result<(), DbError> {
+        let write_txn = self.db.begin_write()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        {
+            let mut table = write_txn.open_table(TRIE_META_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let value: [u8; 32] = hash.as_slice().try_into().unwrap();
+            table.insert(TRIE_ROOT_KEY, &value)
                 .map_err(|e| DbError::Other(e.to_string()))?;
-            for (block_number, block_hash) in buffer.block_hashes {
-                let hash_bytes: [u8; 32] = block_hash.as_slice().try_into().unwrap();
-                block_hashes.insert(block_number, &hash_bytes)
-                    .map_err(|e| DbError::Other(e.to_string()))?;
-            }
         }
-        write_txn.commit()
-            .map_err(|e| DbError::Other(e.to_string()))
+        write_txn.commit().map_err(|e| DbError::Other(e.to_string()))
     }
 
-    fn rollback_transaction(&mut self) -> Result<(), DbError> {
-        *self.tx_buffer.write() = None;
-        self.changed_accounts.write().clear();
-        Ok(())
+    fn get_changed_storage_slots(&self, address: &Address) -> Result<Vec<(U256, U256)>, DbError> {
+        let keys = self.changed_storage.write().remove(address).unwrap_or_default();
+        let mut slots = Vec::with_capacity(keys.len());
+        for key in keys {
+            slots.push((key, self.get_storage(address, key)?));
+        }
+        Ok(slots)
     }
 
-    fn get_changed_accounts(&self) -> Result<Vec<Address>, DbError> {
-        Ok(self.changed_accounts.read().clone())
+    fn storage_trie_root(&self, address: Address) -> Result<Option<B256>, DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let table = read_txn.open_table(STORAGE_TRIE_META_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
+        Ok(table.get(&addr_bytes)
+            .map_err(|e| DbError::Other(e.to_string()))?
+            .map(|v| B256::from_slice(v.value())))
+    }
+
+    fn set_storage_trie_root(&self, address: Address, hash: B256) -> Result<(), DbError> {
+        let write_txn = self.db.begin_write()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        {
+            let mut table = write_txn.open_table(STORAGE_TRIE_META_TABLE)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+            let addr_bytes: [u8; 20] = address.as_slice().try_into().unwrap();
+            let value: [u8; 32] = hash.as_slice().try_into().unwrap();
+            table.insert(&addr_bytes, &value)
+                .map_err(|e| DbError::Other(e.to_string()))?;
+        }
+        write_txn.commit().map_err(|e| DbError::Other(e.to_string()))
     }
 
     fn clear_cache(&mut self) -> Result<(), DbError> {
-        // RedbStateDB 不使用额外的缓存（事务缓冲区除外）
+        self.account_cache.lock().clear();
+        self.storage_cache.lock().clear();
+        self.code_cache.lock().clear();
         Ok(())
     }
 }
 
+// ==================== BlockProvider 实现 ====================
+
+impl BlockProvider for RedbStateDB {
+    fn block_by_hash(&self, hash: &B256) -> Result<Option<Block>, DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let hash_to_number = read_txn.open_table(HASH_TO_NUMBER_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let hash_bytes: [u8; 32] = hash.as_slice().try_into().unwrap();
+        let number = hash_to_number.get(&hash_bytes)
+            .map_err(|e| DbError::Other(e.to_string()))?
+            .map(|v| v.value());
+
+        match number {
+            Some(number) => self.get_block(number),
+            None => Ok(None),
+        }
+    }
+
+    fn block_by_number(&self, number: u64) -> Result<Option<Block>, DbError> {
+        self.get_block(number)
+    }
+
+    fn block_hash(&self, number: u64) -> Result<Option<B256>, DbError> {
+        match self.get_block(number)? {
+            Some(block) => Self::parse_hash(&block.hash())
+                .map(|bytes| Some(B256::from(bytes)))
+                .ok_or_else(|| DbError::Other(format!("malformed block hash for block #{}", number))),
+            None => Ok(None),
+        }
+    }
+
+    fn receipt(&self, tx_hash: &B256) -> Result<Option<TransactionReceipt>, DbError> {
+        let read_txn = self.db.begin_read()
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let table = read_txn.open_table(RECEIPTS_TABLE)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+        let hash_bytes: [u8; 32] = tx_hash.as_slice().try_into().unwrap();
+        let value = table.get(&hash_bytes)
+            .map_err(|e| DbError::Other(e.to_string()))?;
+
+        if let Some(data) = value {
+            let receipt: TransactionReceipt = bincode::deserialize(data.value())
+                .map_err(|e| DbError::Serialization(e.to_string()))?;
+            Ok(Some(receipt))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn logs_matching(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        address_filter: Option<Address>,
+        topic_filter: Option<B256>,
+    ) -> Result<Vec<Log>, DbError> {
+        let mut matched_logs = Vec::new();
+
+        for number in from_block..=to_block {
+            let Some(block) = self.get_block(number)? else {
+                continue;
+            };
+            let Some(ref bloom_hex) = block.header.logs_bloom else {
+                continue;
+            };
+            let bloom = BlockHeader::decode_bloom(&Some(bloom_hex.clone()));
+
+            // 先用区块的聚合 bloom 做快速排除，bloom 不命中就跳过整个区块，避免读取收据
+            let topics: &[B256] = topic_filter.as_ref().map_or(&[], std::slice::from_ref);
+            if !bloom.matches(address_filter.as_ref(), topics) {
+                continue;
+            }
+
+            for tx in &block.transactions {
+                let Some(hash_bytes) = tx.hash.as_deref().and_then(Self::parse_hash) else {
+                    continue;
+                };
+                let Some(receipt) = self.receipt(&B256::from(hash_bytes))? else {
+                    continue;
+                };
+                for log in receipt.logs {
+                    if let Some(address) = address_filter {
+                        if log.address != address {
+                            continue;
+                        }
+                    }
+                    if let Some(topic) = topic_filter {
+                        if !log.topics.contains(&topic) {
+                            continue;
+                        }
+                    }
+                    matched_logs.push(log);
+                }
+            }
+        }
+
+        Ok(matched_logs)
+    }
+}
+
 impl Default for RedbStateDB {
     fn default() -> Self {
         Self::new("./data/state.redb").expect("Failed to create RedbStateDB")
@@ -564,7 +1465,500 @@ mod tests {
         assert_eq!(changed.len(), 2);
         assert!(changed.contains(&addr1));
         assert!(changed.contains(&addr2));
-        
+
+        db.commit_transaction().unwrap();
+    }
+
+    #[test]
+    fn test_state_root_reflects_changed_accounts() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        // 全新数据库没有任何变更，应返回空状态根
+        assert_eq!(db.state_root().unwrap(), crate::trie::state_root::EMPTY_STATE_ROOT);
+
+        let addr = address!("000000000000000000000000000000000000000d");
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(42))).unwrap();
         db.commit_transaction().unwrap();
+
+        // 账户已落盘且被记入 `changed_accounts`，状态根不应再是空树
+        assert_ne!(db.state_root().unwrap(), crate::trie::state_root::EMPTY_STATE_ROOT);
+    }
+
+    #[test]
+    fn test_checkpoint_revert_undoes_only_nested_writes() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let parent_addr = address!("0000000000000000000000000000000000000007");
+        let child_addr = address!("0000000000000000000000000000000000000008");
+
+        db.begin_transaction().unwrap();
+
+        // 父调用帧写入
+        db.set_account(&parent_addr, Account::with_balance(U256::from(1000))).unwrap();
+
+        // 进入子调用帧（如 CALL/CREATE），子帧自己也写了一笔
+        let cp = db.checkpoint().unwrap();
+        db.set_account(&child_addr, Account::with_balance(U256::from(500))).unwrap();
+
+        // 子调用失败：回滚子帧，父帧的写入必须保留
+        db.revert_to_checkpoint(cp).unwrap();
+
+        assert_eq!(db.get_account(&parent_addr).unwrap().unwrap().balance, U256::from(1000));
+        assert_eq!(db.get_account(&child_addr).unwrap(), None);
+
+        db.commit_transaction().unwrap();
+    }
+
+    #[test]
+    fn test_checkpoint_revert_rolls_back_changed_accounts() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let parent_addr = address!("0000000000000000000000000000000000000009");
+        let child_addr = address!("000000000000000000000000000000000000000a");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&parent_addr, Account::with_balance(U256::from(1000))).unwrap();
+
+        let cp = db.checkpoint().unwrap();
+        db.set_account(&child_addr, Account::with_balance(U256::from(500))).unwrap();
+
+        // 子帧回滚前，两个地址都应在已变更集合里
+        let changed_before = db.get_changed_accounts().unwrap();
+        assert!(changed_before.contains(&parent_addr));
+        assert!(changed_before.contains(&child_addr));
+
+        db.revert_to_checkpoint(cp).unwrap();
+
+        // 回滚后，只有父帧的写入仍然算"已变更"——子帧新增的标记必须一并撤回，
+        // 否则状态根计算会把从未真正落地的写入也算进去
+        let changed_after = db.get_changed_accounts().unwrap();
+        assert!(changed_after.contains(&parent_addr));
+        assert!(!changed_after.contains(&child_addr));
+
+        db.commit_transaction().unwrap();
+    }
+
+    #[test]
+    fn test_discard_checkpoint_keeps_nested_writes_and_merges_into_parent() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let outer_addr = address!("000000000000000000000000000000000000000b");
+        let inner_addr = address!("000000000000000000000000000000000000000c");
+
+        db.begin_transaction().unwrap();
+
+        let outer_cp = db.checkpoint().unwrap();
+        db.set_account(&outer_addr, Account::with_balance(U256::from(10))).unwrap();
+
+        let inner_cp = db.checkpoint().unwrap();
+        db.set_account(&inner_addr, Account::with_balance(U256::from(20))).unwrap();
+
+        // 子调用成功：丢弃子 checkpoint（保留写入），合并进父 checkpoint
+        db.discard_checkpoint(inner_cp).unwrap();
+
+        // 之后如果外层也需要回滚，子帧的写入（连同它的 changed_accounts 标记）
+        // 必须一起被撤销，因为它已经被并入外层 checkpoint
+        db.revert_to_checkpoint(outer_cp).unwrap();
+
+        assert_eq!(db.get_account(&outer_addr).unwrap(), None);
+        assert_eq!(db.get_account(&inner_addr).unwrap(), None);
+
+        let changed = db.get_changed_accounts().unwrap();
+        assert!(!changed.contains(&outer_addr));
+        assert!(!changed.contains(&inner_addr));
+
+        db.commit_transaction().unwrap();
+    }
+
+    #[test]
+    fn test_account_cache_negative_caching_of_missing_account() {
+        let (db, _temp_dir) = create_test_db();
+        let addr = address!("000000000000000000000000000000000000000e");
+
+        // 第一次查询未命中，应把 `None` 也记入缓存
+        assert_eq!(db.get_account(&addr).unwrap(), None);
+        assert_eq!(db.account_cache.lock().peek(&addr), Some(&None));
+    }
+
+    #[test]
+    fn test_account_cache_invalidated_on_delete_and_resynced_on_commit() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("000000000000000000000000000000000000000f");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(7))).unwrap();
+        db.commit_transaction().unwrap();
+
+        // 事务提交后，缓存应直接落地为最新值，而不必再打开一次 redb 读事务
+        assert_eq!(
+            db.account_cache.lock().peek(&addr),
+            Some(&Some(Account::with_balance(U256::from(7))))
+        );
+        assert_eq!(db.get_account(&addr).unwrap().unwrap().balance, U256::from(7));
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(9))).unwrap();
+        db.delete_account(&addr).unwrap();
+        db.commit_transaction().unwrap();
+
+        // 同一笔事务里先 set 后 delete：提交后缓存和 redb 都应反映"已删除"
+        assert_eq!(db.account_cache.lock().peek(&addr), Some(&None));
+        assert_eq!(db.get_account(&addr).unwrap(), None);
+    }
+
+    #[test]
+    fn test_storage_cache_populated_on_read_and_resynced_on_commit() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000010");
+        let key = U256::from(1);
+
+        // 未设置的槽位读作零值，并填充进缓存，而不是留空
+        assert_eq!(db.get_storage(&addr, key).unwrap(), U256::ZERO);
+        assert_eq!(db.storage_cache.lock().peek(&(addr, key)), Some(&U256::ZERO));
+
+        db.begin_transaction().unwrap();
+        db.set_storage(&addr, key, U256::from(123)).unwrap();
+        db.commit_transaction().unwrap();
+
+        assert_eq!(
+            db.storage_cache.lock().peek(&(addr, key)),
+            Some(&U256::from(123))
+        );
+        assert_eq!(db.get_storage(&addr, key).unwrap(), U256::from(123));
+    }
+
+    #[test]
+    fn test_clear_cache_flushes_account_and_storage_caches() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000011");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(1))).unwrap();
+        db.set_storage(&addr, U256::from(1), U256::from(2)).unwrap();
+        db.commit_transaction().unwrap();
+
+        assert!(db.account_cache.lock().peek(&addr).is_some());
+        assert!(db.storage_cache.lock().peek(&(addr, U256::from(1))).is_some());
+
+        db.clear_cache().unwrap();
+
+        assert!(db.account_cache.lock().peek(&addr).is_none());
+        assert!(db.storage_cache.lock().peek(&(addr, U256::from(1))).is_none());
+    }
+
+    #[test]
+    fn test_with_cache_capacity_bounds_account_cache_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_small_cache.redb");
+        let mut db = RedbStateDB::with_cache_capacity(db_path.to_str().unwrap(), 2, 2).unwrap();
+
+        for i in 0..5u8 {
+            let mut addr_bytes = [0u8; 20];
+            addr_bytes[19] = i;
+            let addr = Address::from(addr_bytes);
+            db.begin_transaction().unwrap();
+            db.set_account(&addr, Account::with_balance(U256::from(i))).unwrap();
+            db.commit_transaction().unwrap();
+        }
+
+        // 容量为 2：早先写入的条目必然已被淘汰出 LRU，但数据在 redb 里依然完好
+        assert!(db.account_cache.lock().len() <= 2);
+        let first_addr = Address::from([0u8; 20]);
+        assert_eq!(db.get_account(&first_addr).unwrap().unwrap().balance, U256::ZERO);
+    }
+
+    #[test]
+    fn test_rollback_to_block_restores_prior_account_and_storage_values() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000012");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(10))).unwrap();
+        db.set_storage(&addr, U256::from(1), U256::from(100)).unwrap();
+        db.commit_transaction_at_block(1).unwrap();
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(20))).unwrap();
+        db.set_storage(&addr, U256::from(1), U256::from(200)).unwrap();
+        db.commit_transaction_at_block(2).unwrap();
+
+        db.rollback_to_block(1).unwrap();
+
+        assert_eq!(db.get_account(&addr).unwrap().unwrap().balance, U256::from(10));
+        assert_eq!(db.get_storage(&addr, U256::from(1)).unwrap(), U256::from(100));
+
+        // 幂等：再次回滚到同一高度不应出错，也不应改变结果
+        db.rollback_to_block(1).unwrap();
+        assert_eq!(db.get_account(&addr).unwrap().unwrap().balance, U256::from(10));
+    }
+
+    #[test]
+    fn test_rollback_to_block_before_account_existed_removes_it() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000013");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(5))).unwrap();
+        db.commit_transaction_at_block(1).unwrap();
+
+        db.rollback_to_block(0).unwrap();
+
+        assert_eq!(db.get_account(&addr).unwrap(), None);
+    }
+
+    #[test]
+    fn test_get_account_at_reads_historical_height_without_mutating_current_state() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000014");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(1))).unwrap();
+        db.commit_transaction_at_block(1).unwrap();
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, Account::with_balance(U256::from(2))).unwrap();
+        db.commit_transaction_at_block(2).unwrap();
+
+        assert_eq!(db.get_account_at(&addr, 1).unwrap().unwrap().balance, U256::from(1));
+        assert_eq!(db.get_account_at(&addr, 0).unwrap(), None);
+        // 历史查询不应改动当前状态
+        assert_eq!(db.get_account(&addr).unwrap().unwrap().balance, U256::from(2));
+    }
+
+    #[test]
+    fn test_prune_removes_journal_entries_older_than_keep_window() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000015");
+
+        for block in 1..=5u64 {
+            db.begin_transaction().unwrap();
+            db.set_account(&addr, Account::with_balance(U256::from(block))).unwrap();
+            db.commit_transaction_at_block(block).unwrap();
+        }
+
+        // 只保留最近 2 个区块的日志：block 1..=3 应被清掉
+        db.prune(2).unwrap();
+
+        // 修剪掉的历史无法再重放，但仍保留窗口内的回滚能力
+        db.rollback_to_block(3).unwrap();
+        assert_eq!(db.get_account(&addr).unwrap().unwrap().balance, U256::from(3));
+    }
+
+    #[test]
+    fn test_rollback_and_get_account_at_reject_target_before_pruned_horizon() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000017");
+
+        for block in 1..=5u64 {
+            db.begin_transaction().unwrap();
+            db.set_account(&addr, Account::with_balance(U256::from(block))).unwrap();
+            db.commit_transaction_at_block(block).unwrap();
+        }
+
+        // 只保留最近 2 个区块的日志：block 1..=3 的反向 diff 被清掉
+        db.prune(2).unwrap();
+
+        // target_block=1 需要重放 block 2..=5，但 2、3 已经被修剪，留下一个
+        // 缺口——必须报错，而不是只用残存的 4、5 重放出一个看似合理的状态
+        assert!(matches!(
+            db.rollback_to_block(1),
+            Err(DbError::JournalGap { requested: 1, oldest_retained: 4 })
+        ));
+        assert!(matches!(
+            db.get_account_at(&addr, 1),
+            Err(DbError::JournalGap { requested: 1, oldest_retained: 4 })
+        ));
+
+        // 当前状态未被这两次失败调用改动
+        assert_eq!(db.get_account(&addr).unwrap().unwrap().balance, U256::from(5));
+    }
+
+    #[test]
+    fn test_to_pod_includes_balance_nonce_code_and_nonzero_storage() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000016");
+        let code = Bytes::from_static(&[0x60, 0x01]);
+        let code_hash = alloy_primitives::keccak256(&code);
+
+        db.begin_transaction().unwrap();
+        db.set_code(code_hash, code.clone()).unwrap();
+        db.set_account(&addr, Account {
+            nonce: 3,
+            balance: U256::from(500),
+            storage_root: crate::schema::account::EMPTY_STORAGE_ROOT,
+            code_hash,
+        }).unwrap();
+        db.set_storage(&addr, U256::from(1), U256::from(42)).unwrap();
+        db.set_storage(&addr, U256::from(2), U256::ZERO).unwrap();
+        db.commit_transaction().unwrap();
+
+        let pod = db.to_pod().unwrap();
+        let pod_account = pod.0.get(&addr).unwrap();
+        assert_eq!(pod_account.balance, U256::from(500));
+        assert_eq!(pod_account.nonce, 3);
+        assert_eq!(pod_account.code, code);
+        assert_eq!(pod_account.storage.get(&U256::from(1)), Some(&U256::from(42)));
+        // 零值槽位不应出现在快照里
+        assert!(!pod_account.storage.contains_key(&U256::from(2)));
+    }
+
+    #[test]
+    fn test_apply_pod_round_trips_through_to_pod() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000017");
+
+        let mut storage = std::collections::BTreeMap::new();
+        storage.insert(U256::from(7), U256::from(77));
+        let mut accounts = std::collections::BTreeMap::new();
+        accounts.insert(addr, crate::db::pod::PodAccount {
+            balance: U256::from(900),
+            nonce: 2,
+            code: Bytes::from_static(&[0xfe]),
+            storage,
+        });
+        let pod = crate::db::pod::PodState(accounts);
+
+        db.apply_pod(&pod).unwrap();
+
+        let account = db.get_account(&addr).unwrap().unwrap();
+        assert_eq!(account.balance, U256::from(900));
+        assert_eq!(account.nonce, 2);
+        assert_eq!(db.get_storage(&addr, U256::from(7)).unwrap(), U256::from(77));
+
+        let round_tripped = db.to_pod().unwrap();
+        assert_eq!(round_tripped, pod);
+    }
+
+    #[test]
+    fn test_get_all_storage_range_query_isolates_one_address_among_interleaved_writes() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr_a = address!("0000000000000000000000000000000000000018");
+        let addr_b = address!("0000000000000000000000000000000000000019");
+
+        db.begin_transaction().unwrap();
+        // 交替写入两个地址的槽位，确保底层按元组编码排序后仍是"地址优先"
+        db.set_storage(&addr_a, U256::from(1), U256::from(11)).unwrap();
+        db.set_storage(&addr_b, U256::from(1), U256::from(21)).unwrap();
+        db.set_storage(&addr_a, U256::from(2), U256::from(12)).unwrap();
+        db.set_storage(&addr_b, U256::from(2), U256::from(22)).unwrap();
+        db.set_storage(&addr_a, U256::from(3), U256::from(13)).unwrap();
+        db.commit_transaction().unwrap();
+
+        let slots_a = db.get_all_storage(&addr_a).unwrap();
+        assert_eq!(slots_a.len(), 3);
+        assert!(slots_a.iter().all(|s| s.address == addr_a));
+
+        let slots_b = db.get_all_storage(&addr_b).unwrap();
+        assert_eq!(slots_b.len(), 2);
+        assert!(slots_b.iter().all(|s| s.address == addr_b));
+    }
+
+    #[test]
+    fn test_get_storage_range_paginates_with_continuation_cursor() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("000000000000000000000000000000000000001a");
+
+        db.begin_transaction().unwrap();
+        for i in 1..=5u64 {
+            db.set_storage(&addr, U256::from(i), U256::from(i * 10)).unwrap();
+        }
+        db.commit_transaction().unwrap();
+
+        let (first_page, cursor) = db.get_storage_range(&addr, U256::ZERO, 2).unwrap();
+        assert_eq!(first_page.len(), 2);
+        assert_eq!(first_page[0].key, U256::from(1));
+        assert_eq!(first_page[1].key, U256::from(2));
+        let cursor = cursor.expect("more slots remain");
+
+        let (second_page, cursor) = db.get_storage_range(&addr, cursor, 2).unwrap();
+        assert_eq!(second_page[0].key, U256::from(3));
+        assert_eq!(second_page[1].key, U256::from(4));
+        let cursor = cursor.expect("one slot remains");
+
+        let (last_page, cursor) = db.get_storage_range(&addr, cursor, 2).unwrap();
+        assert_eq!(last_page.len(), 1);
+        assert_eq!(last_page[0].key, U256::from(5));
+        assert_eq!(cursor, None);
+    }
+
+    #[test]
+    fn test_trie_node_round_trips_and_reports_missing_hashes_as_none() {
+        let (db, _temp_dir) = create_test_db();
+
+        let hash = keccak256(b"a trie node's encoded bytes");
+        assert_eq!(db.trie_node(hash).unwrap(), None);
+
+        db.put_trie_node(hash, b"node-bytes".to_vec()).unwrap();
+        assert_eq!(db.trie_node(hash).unwrap(), Some(b"node-bytes".to_vec()));
+    }
+
+    #[test]
+    fn test_trie_root_hash_persists_across_calls() {
+        let (db, _temp_dir) = create_test_db();
+
+        assert_eq!(db.trie_root_hash().unwrap(), None);
+
+        let root = keccak256(b"some state root");
+        db.set_trie_root_hash(root).unwrap();
+        assert_eq!(db.trie_root_hash().unwrap(), Some(root));
+
+        let new_root = keccak256(b"a different state root");
+        db.set_trie_root_hash(new_root).unwrap();
+        assert_eq!(db.trie_root_hash().unwrap(), Some(new_root));
+    }
+
+    #[test]
+    fn test_code_cache_populated_on_read_and_resynced_on_commit() {
+        let (mut db, _temp_dir) = create_test_db();
+        let hash = keccak256(b"some contract bytecode");
+
+        // 未写入的代码哈希读作 `None`，并把这个"确认不存在"也记入缓存
+        assert_eq!(db.get_code(&hash).unwrap(), None);
+        assert_eq!(db.code_cache.lock().peek(&hash), Some(&None));
+
+        db.begin_transaction().unwrap();
+        db.set_code(hash, Bytes::from_static(b"\x60\x00\x60\x00")).unwrap();
+        db.commit_transaction().unwrap();
+
+        assert_eq!(
+            db.code_cache.lock().peek(&hash),
+            Some(&Some(Bytes::from_static(b"\x60\x00\x60\x00")))
+        );
+        assert_eq!(db.get_code(&hash).unwrap(), Some(Bytes::from_static(b"\x60\x00\x60\x00")));
+    }
+
+    #[test]
+    fn test_get_changed_storage_slots_drains_tracked_set_and_reads_current_values() {
+        let (mut db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000014");
+
+        db.begin_transaction().unwrap();
+        db.set_storage(&addr, U256::from(1), U256::from(100)).unwrap();
+        db.set_storage(&addr, U256::from(2), U256::from(200)).unwrap();
+        db.commit_transaction().unwrap();
+
+        let mut slots = db.get_changed_storage_slots(&addr).unwrap();
+        slots.sort_by_key(|(key, _)| *key);
+        assert_eq!(slots, vec![(U256::from(1), U256::from(100)), (U256::from(2), U256::from(200))]);
+
+        // 第一次调用已经把追踪集合取走（"drain"语义），再调用一次应该是空的
+        assert_eq!(db.get_changed_storage_slots(&addr).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn test_storage_trie_root_persists_across_calls() {
+        let (db, _temp_dir) = create_test_db();
+        let addr = address!("0000000000000000000000000000000000000015");
+
+        assert_eq!(db.storage_trie_root(addr).unwrap(), None);
+
+        let root = keccak256(b"some storage subtrie root");
+        db.set_storage_trie_root(addr, root).unwrap();
+        assert_eq!(db.storage_trie_root(addr).unwrap(), Some(root));
+
+        // 另一个账户互不影响
+        let other_addr = address!("0000000000000000000000000000000000000016");
+        assert_eq!(db.storage_trie_root(other_addr).unwrap(), None);
     }
 }
@@ -3,7 +3,7 @@
 //! 定义状态数据库的核心接口，支持账户、存储、代码的 CRUD 操作
 
 use alloy_primitives::{Address, U256, B256, Bytes};
-use crate::schema::{Account, StorageSlot};
+use crate::schema::{Account, StorageSlot, Block, BlockHeader, TransactionReceipt, Log};
 use std::collections::HashMap;
 use thiserror::Error;
 
@@ -30,7 +30,13 @@ pub enum DbError {
     
     #[error("Walrus error: {0}")]
     Walrus(String),
-    
+
+    #[error("corrupt entry in topic {topic}: checksum mismatch (expected {expected}, got {actual})")]
+    Corruption { topic: String, expected: B256, actual: B256 },
+
+    #[error("journal has a gap before block {requested}: oldest retained entry is block {oldest_retained}, pruned history cannot be replayed")]
+    JournalGap { requested: u64, oldest_retained: u64 },
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -102,7 +108,34 @@ pub trait StateDatabase: Send + Sync {
     
     /// 回滚事务
     fn rollback_transaction(&mut self) -> Result<(), DbError>;
-    
+
+    // ==================== 嵌套 Checkpoint ====================
+    //
+    // 在一次 `begin_transaction`/`commit_transaction` 包裹的事务内部，再划出
+    // 更细粒度的、可以单独回滚的范围——典型场景是 EVM 调用帧：子调用失败只
+    // 需要撤销它自己写入的状态，不能把整笔外层交易一起回滚。见
+    // `TransactionBuffer` 的 `checkpoint`/`revert_to_checkpoint`/
+    // `discard_checkpoint`，本 trait 上的方法只是把它们暴露给调用方，默认
+    // 实现报告"不支持"，留给确实维护 `TransactionBuffer` 的后端（`RedbStateDB`/
+    // `WalrusStateDB`）覆盖。
+
+    /// 在当前事务内推入一个新的 checkpoint，返回其 id（供
+    /// `revert_to_checkpoint`/`discard_checkpoint` 引用）
+    fn checkpoint(&mut self) -> Result<usize, DbError> {
+        Err(DbError::Other("checkpoints not supported by this backend".to_string()))
+    }
+
+    /// 回滚到某个 checkpoint：撤销自它被推入以来记录的全部写入，并将其连同
+    /// 之上的全部 checkpoint 一并弹出
+    fn revert_to_checkpoint(&mut self, _id: usize) -> Result<(), DbError> {
+        Err(DbError::Other("checkpoints not supported by this backend".to_string()))
+    }
+
+    /// 丢弃某个 checkpoint（保留它记录的写入，不回滚），将其记录并入父 checkpoint
+    fn discard_checkpoint(&mut self, _id: usize) -> Result<(), DbError> {
+        Err(DbError::Other("checkpoints not supported by this backend".to_string()))
+    }
+
     // ==================== 辅助方法 ====================
     
     /// 获取所有变更的账户（用于增量状态根计算）
@@ -110,13 +143,178 @@ pub trait StateDatabase: Send + Sync {
         // 默认实现返回空，子类可以重写
         Ok(Vec::new())
     }
-    
+
+    /// 枚举数据库中的全部账户（地址 + 账户数据）
+    ///
+    /// 用于全量状态根计算以及 `eth_getProof` 风格的证明生成——两者都需要针对
+    /// *完整* 账户集合重建状态树，而不是 `get_changed_accounts` 返回的增量子集。
+    /// 默认实现返回空，子类可以重写（目前仅 `RedbStateDB` 支持全表扫描枚举）。
+    fn get_all_accounts(&self) -> Result<Vec<(Address, Account)>, DbError> {
+        Ok(Vec::new())
+    }
+
+    // ==================== 持久化 Trie 节点存储 ====================
+    //
+    // `StateRootCalculator::calculate_incremental`（见
+    // `crate::trie::state_root`）需要把状态树的节点持久化下来，才能只沿着
+    // 变更账户的路径重新哈希，而不用每次都用全部账户重建一棵树。这四个方法
+    // 就是它用来读写节点、读写当前根哈希的接口；默认实现不持久化任何东西
+    // （`trie_node`/`trie_root_hash` 总是返回 `None`，`put_trie_node`/
+    // `set_trie_root_hash` 是无操作的 no-op），只有 `RedbStateDB` 覆盖了它们。
+
+    /// 按哈希读取一个已持久化的 trie 节点；未找到返回 `None`
+    fn trie_node(&self, _hash: B256) -> Result<Option<Vec<u8>>, DbError> {
+        Ok(None)
+    }
+
+    /// 持久化一个 trie 节点（键即其自身哈希）
+    fn put_trie_node(&self, _hash: B256, _data: Vec<u8>) -> Result<(), DbError> {
+        Ok(())
+    }
+
+    /// 读取当前状态树的根哈希（上一次 `calculate_incremental` 持久化的结果）
+    fn trie_root_hash(&self) -> Result<Option<B256>, DbError> {
+        Ok(None)
+    }
+
+    /// 持久化新的状态树根哈希
+    fn set_trie_root_hash(&self, _hash: B256) -> Result<(), DbError> {
+        Ok(())
+    }
+
+    // ==================== 持久化存储子树（按账户） ====================
+    //
+    // `StorageRootCalculator::calculate_persistent`（见
+    // `crate::trie::storage_root`）对单个账户做同样的增量折叠：只把变更过的
+    // 存储槽插入该账户自己的持久化存储子树，不必每次都把账户全部存储槽从
+    // 数据库里扫一遍。这三个方法是它用来读写"哪些槽位变了"和"该账户存储子树
+    // 当前根哈希"的接口；默认实现不追踪任何东西，只有 `RedbStateDB` 覆盖了它们
+    // （其它后端会退回到 `StorageRootCalculator::calculate` 的全量扫描路径）。
+
+    /// 读取某个账户自上次调用本方法以来变更过的存储槽（槽位 + 当前值）；
+    /// 后端在开启/回滚事务时也可以清空追踪（`RedbStateDB` 就是这样做的）
+    fn get_changed_storage_slots(&self, _address: &Address) -> Result<Vec<(U256, U256)>, DbError> {
+        Ok(Vec::new())
+    }
+
+    /// 读取某个账户持久化存储子树当前的根哈希（`None` 表示该账户从未建立过
+    /// 存储子树，即从未持久化过任何存储槽）
+    fn storage_trie_root(&self, _address: Address) -> Result<Option<B256>, DbError> {
+        Ok(None)
+    }
+
+    /// 持久化某个账户存储子树的新根哈希
+    fn set_storage_trie_root(&self, _address: Address, _hash: B256) -> Result<(), DbError> {
+        Ok(())
+    }
+
     /// 清除缓存
     fn clear_cache(&mut self) -> Result<(), DbError> {
         Ok(())
     }
 }
 
+/// 区块查询接口
+///
+/// 在 `StateDatabase`（账户/存储/代码状态）之上，提供对已提交区块的索引查询：
+/// 按哈希/按号查区块、查收据、以及基于每个区块的 logs bloom 做粗过滤的日志查询。
+/// 与 `StateDatabase` 分离是因为并非所有状态后端都需要持久化区块索引
+/// （例如纯内存/测试用的后端可以只实现 `StateDatabase`）。
+pub trait BlockProvider: Send + Sync {
+    /// 按区块哈希查询完整区块
+    fn block_by_hash(&self, hash: &B256) -> Result<Option<Block>, DbError>;
+
+    /// 按区块号查询完整区块
+    fn block_by_number(&self, number: u64) -> Result<Option<Block>, DbError>;
+
+    /// 查询某区块号对应的区块哈希
+    fn block_hash(&self, number: u64) -> Result<Option<B256>, DbError>;
+
+    /// 按交易哈希查询收据
+    ///
+    /// 收据本身携带 `block_hash`/`block_number`/`transaction_index`
+    /// （见 [`ReceiptBuilder::build`](crate::executor::receipts::ReceiptBuilder::build)），
+    /// 即是交易哈希到（区块，索引）的索引，不需要额外维护一张映射表。
+    fn receipt(&self, tx_hash: &B256) -> Result<Option<TransactionReceipt>, DbError>;
+
+    /// 是否存在给定哈希的区块
+    ///
+    /// 默认实现委托给 [`Self::block_by_hash`]；后端如果维护了单独的哈希
+    /// 索引，可以覆盖此方法以避免反序列化整个区块。
+    fn is_known(&self, hash: &B256) -> Result<bool, DbError> {
+        Ok(self.block_by_hash(hash)?.is_some())
+    }
+
+    /// 按区块哈希只查询区块头（不携带交易列表）
+    ///
+    /// 默认实现委托给 [`Self::block_by_hash`] 后取出 `header`；当前后端把
+    /// 区块整体序列化存储，没有单独的头部索引，所以和查完整区块同样开销。
+    fn block_header(&self, hash: &B256) -> Result<Option<BlockHeader>, DbError> {
+        Ok(self.block_by_hash(hash)?.map(|block| block.header))
+    }
+
+    /// 在 `[from_block, to_block]`（含两端）范围内查找匹配地址/主题过滤条件的日志
+    ///
+    /// 先用每个区块头的 logs bloom 做快速排除（bloom 不命中则该区块一定不包含匹配项），
+    /// 仅对 bloom 命中的区块才真正加载收据、逐条扫描日志。
+    fn logs_matching(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        address_filter: Option<Address>,
+        topic_filter: Option<B256>,
+    ) -> Result<Vec<Log>, DbError>;
+}
+
+/// 账户在某个 checkpoint 推入那一刻的状态：未设置过、已设置为某个值、或
+/// 已被 `delete_account` 标记删除——`delete_account` 同时修改 `accounts` 和
+/// `deleted_accounts` 两处，因此需要一个能同时还原两者的状态快照
+#[derive(Debug, Clone)]
+enum AccountCheckpointState {
+    Unset,
+    Present(Account),
+    Deleted,
+}
+
+/// 单个 checkpoint 推入之后，第一次被写入的某个键的写入前状态；`None`
+/// 表示该键此前不存在，回滚时应当删除而不是写回旧值
+#[derive(Debug, Clone)]
+enum PriorValue {
+    Account(AccountCheckpointState),
+    Storage(Option<U256>),
+    Code(Option<Bytes>),
+}
+
+/// 可哈希比较的写入目标键，用于在同一 checkpoint 内给重复写入去重
+/// （"首次写入才捕获"语义）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum TouchedKey {
+    Account(Address),
+    Storage(Address, U256),
+    Code(B256),
+}
+
+/// 一个 checkpoint：记录它推入之后、直到被丢弃或回滚为止，每个被首次写入的
+/// 键的写入前状态。`order` 保留捕获顺序，供 `revert_to_checkpoint` 按逆序
+/// 重放——经典 checkpoint 实现的约定（OpenEthereum 等）。
+#[derive(Debug, Clone, Default)]
+struct Checkpoint {
+    entries: HashMap<TouchedKey, PriorValue>,
+    order: Vec<TouchedKey>,
+    /// 本 checkpoint 存续期间、首次被记入 `changed_accounts` 的地址——回滚时
+    /// 必须把它们连同一起撤回，否则已被撤销的写入仍会被状态根计算当成"变更过"
+    newly_changed_accounts: Vec<Address>,
+}
+
+impl Checkpoint {
+    fn record_if_absent(&mut self, key: TouchedKey, prior: PriorValue) {
+        if !self.entries.contains_key(&key) {
+            self.order.push(key.clone());
+            self.entries.insert(key, prior);
+        }
+    }
+}
+
 /// 事务缓冲区（用于支持回滚）
 #[derive(Debug, Clone, Default)]
 pub struct TransactionBuffer {
@@ -125,26 +323,206 @@ pub struct TransactionBuffer {
     pub codes: HashMap<B256, Bytes>,
     pub block_hashes: HashMap<u64, B256>,
     pub deleted_accounts: Vec<Address>,
+    /// 嵌套 checkpoint 栈，见 [`Self::checkpoint`]
+    checkpoints: Vec<Checkpoint>,
 }
 
 impl TransactionBuffer {
     pub fn new() -> Self {
         Self::default()
     }
-    
+
     pub fn clear(&mut self) {
         self.accounts.clear();
         self.storage.clear();
         self.codes.clear();
         self.block_hashes.clear();
         self.deleted_accounts.clear();
+        self.checkpoints.clear();
     }
-    
+
     pub fn is_empty(&self) -> bool {
-        self.accounts.is_empty() 
-            && self.storage.is_empty() 
+        self.accounts.is_empty()
+            && self.storage.is_empty()
             && self.codes.is_empty()
             && self.block_hashes.is_empty()
             && self.deleted_accounts.is_empty()
     }
+
+    /// 在当前最顶层 checkpoint 中记录一次账户写入前的状态（同一 checkpoint
+    /// 内对同一地址只记录第一次）。必须在实际修改 `accounts`/`deleted_accounts`
+    /// 之前调用。
+    fn record_account_write(&mut self, address: Address) {
+        if self.checkpoints.is_empty() {
+            return;
+        }
+        let state = if self.deleted_accounts.contains(&address) {
+            AccountCheckpointState::Deleted
+        } else if let Some(account) = self.accounts.get(&address) {
+            AccountCheckpointState::Present(account.clone())
+        } else {
+            AccountCheckpointState::Unset
+        };
+        self.checkpoints
+            .last_mut()
+            .unwrap()
+            .record_if_absent(TouchedKey::Account(address), PriorValue::Account(state));
+    }
+
+    /// 记录一次存储槽写入前的值，用法同 [`Self::record_account_write`]
+    fn record_storage_write(&mut self, address: Address, key: U256) {
+        if self.checkpoints.is_empty() {
+            return;
+        }
+        let prior = self.storage.get(&(address, key)).copied();
+        self.checkpoints
+            .last_mut()
+            .unwrap()
+            .record_if_absent(TouchedKey::Storage(address, key), PriorValue::Storage(prior));
+    }
+
+    /// 记录一次代码写入前的值，用法同 [`Self::record_account_write`]
+    fn record_code_write(&mut self, code_hash: B256) {
+        if self.checkpoints.is_empty() {
+            return;
+        }
+        let prior = self.codes.get(&code_hash).cloned();
+        self.checkpoints
+            .last_mut()
+            .unwrap()
+            .record_if_absent(TouchedKey::Code(code_hash), PriorValue::Code(prior));
+    }
+
+    /// 在 `set_account`/`delete_account`/`set_storage`/`set_code` 实际修改缓冲区
+    /// *之前* 调用，登记该键在当前最顶层 checkpoint 中的写入前状态。没有任何
+    /// 活跃 checkpoint 时是空操作，事务按原来的扁平语义工作。
+    pub fn record_write(&mut self, key: TouchedKeyKind) {
+        match key {
+            TouchedKeyKind::Account(address) => self.record_account_write(address),
+            TouchedKeyKind::Storage(address, slot) => self.record_storage_write(address, slot),
+            TouchedKeyKind::Code(hash) => self.record_code_write(hash),
+        }
+    }
+
+    /// 推入一个新的 checkpoint，返回其 id（供 [`Self::revert_to_checkpoint`]/
+    /// [`Self::discard_checkpoint`] 引用）
+    pub fn checkpoint(&mut self) -> usize {
+        self.checkpoints.push(Checkpoint::default());
+        self.checkpoints.len() - 1
+    }
+
+    /// 在当前最顶层 checkpoint 中记录一次"地址首次被标记为已变更"事件，
+    /// 供 [`Self::revert_to_checkpoint`] 撤销时同步从调用方的 `changed_accounts`
+    /// 中移除。没有活跃 checkpoint 时是空操作——扁平事务不需要撤销变更标记。
+    pub fn record_changed_account(&mut self, address: Address) {
+        if let Some(top) = self.checkpoints.last_mut() {
+            top.newly_changed_accounts.push(address);
+        }
+    }
+
+    /// 回滚到某个 checkpoint：撤销自它被推入以来记录的全部写入，并把它连同
+    /// 之上的全部 checkpoint 一并弹出。按"先弹出的 checkpoint 先重放"的顺序
+    /// 逐个撤销，每个 checkpoint 内部按捕获顺序的逆序重放，使后写入的键先
+    /// 被还原。返回被弹出的各 checkpoint 中新增的 `changed_accounts` 地址，
+    /// 调用方需要把它们从自己维护的已变更集合中移除。
+    pub fn revert_to_checkpoint(&mut self, id: usize) -> Result<Vec<Address>, DbError> {
+        if id >= self.checkpoints.len() {
+            return Err(DbError::Transaction(format!("no such checkpoint: {}", id)));
+        }
+
+        let mut reverted_changed_accounts = Vec::new();
+        while self.checkpoints.len() > id {
+            let mut checkpoint = self.checkpoints.pop().unwrap();
+            reverted_changed_accounts.extend(checkpoint.newly_changed_accounts.drain(..));
+            for key in checkpoint.order.drain(..).rev() {
+                if let Some(prior) = checkpoint.entries.remove(&key) {
+                    self.restore(key, prior);
+                }
+            }
+        }
+        Ok(reverted_changed_accounts)
+    }
+
+    /// 丢弃某个 checkpoint（保留它记录的写入，不回滚），必须是当前最顶层的
+    /// checkpoint。把它记录的写入前状态合并进父 checkpoint——同一键只保留
+    /// 更早（离事务开始更近）的那个值，这样最终回滚到事务开始状态时看到的
+    /// 仍是最早捕获的值。
+    pub fn discard_checkpoint(&mut self, id: usize) -> Result<(), DbError> {
+        let top_id = self
+            .checkpoints
+            .len()
+            .checked_sub(1)
+            .ok_or_else(|| DbError::Transaction("no checkpoints to discard".to_string()))?;
+        if id != top_id {
+            return Err(DbError::Transaction(format!(
+                "checkpoint {} is not the innermost checkpoint",
+                id
+            )));
+        }
+
+        let checkpoint = self.checkpoints.pop().unwrap();
+        if let Some(parent) = self.checkpoints.last_mut() {
+            for key in &checkpoint.order {
+                if let Some(prior) = checkpoint.entries.get(key) {
+                    if !parent.entries.contains_key(key) {
+                        parent.order.push(key.clone());
+                        parent.entries.insert(key.clone(), prior.clone());
+                    }
+                }
+            }
+            // 丢弃的 checkpoint 里"首次变更"的地址交给父 checkpoint 继续追踪，
+            // 这样将来回滚父 checkpoint 时仍能正确撤回这些地址的变更标记
+            parent.newly_changed_accounts.extend(checkpoint.newly_changed_accounts);
+        }
+        Ok(())
+    }
+
+    /// 把一个 [`TouchedKey`]/[`PriorValue`] 对还原回缓冲区，即撤销该键自
+    /// checkpoint 推入以来的全部写入
+    fn restore(&mut self, key: TouchedKey, prior: PriorValue) {
+        match (key, prior) {
+            (TouchedKey::Account(address), PriorValue::Account(state)) => {
+                self.deleted_accounts.retain(|a| *a != address);
+                match state {
+                    AccountCheckpointState::Unset => {
+                        self.accounts.remove(&address);
+                    }
+                    AccountCheckpointState::Present(account) => {
+                        self.accounts.insert(address, account);
+                    }
+                    AccountCheckpointState::Deleted => {
+                        self.accounts.remove(&address);
+                        self.deleted_accounts.push(address);
+                    }
+                }
+            }
+            (TouchedKey::Storage(address, slot), PriorValue::Storage(value)) => match value {
+                Some(v) => {
+                    self.storage.insert((address, slot), v);
+                }
+                None => {
+                    self.storage.remove(&(address, slot));
+                }
+            },
+            (TouchedKey::Code(hash), PriorValue::Code(code)) => match code {
+                Some(c) => {
+                    self.codes.insert(hash, c);
+                }
+                None => {
+                    self.codes.remove(&hash);
+                }
+            },
+            _ => unreachable!("TouchedKey/PriorValue variant mismatch"),
+        }
+    }
+}
+
+/// [`TransactionBuffer::record_write`] 的调用方传入的、标识一次写入目标的键；
+/// 与内部 [`TouchedKey`] 区分开是因为后者还需要承载去重用的 trait 实现细节，
+/// 调用方只需要表达"写的是哪个键"
+#[derive(Debug, Clone, Copy)]
+pub enum TouchedKeyKind {
+    Account(Address),
+    Storage(Address, U256),
+    Code(B256),
 }
@@ -2,14 +2,24 @@
 //! 
 //! 批量执行区块中的所有交易
 
-use alloy_primitives::B256;
+use alloy_primitives::{B256, U256};
 use revm::primitives::BlockEnv;
 use std::collections::HashMap;
-use crate::db::{RedbStateDB, StateDatabase};
-use crate::executor::{ExecutorError, TransactionExecutor, ExecutionResult};
-use crate::schema::{Block, TransactionReceipt};
+use crate::db::{RedbStateDB, StateDatabase, BlockProvider};
+use crate::executor::{ChainConfig, ExecutorError, TransactionExecutor, ExecutionResult, StateBundle};
+use crate::schema::{Block, BlockHeader, Bloom, TransactionReceipt};
+use crate::utils::calculate_merkle_root;
 use serde::{Deserialize, Serialize};
 
+/// 根据父区块头推导下一个区块的期望 base fee（EIP-1559）
+///
+/// 薄封装：实际算法见 [`BlockHeader::calculate_next_base_fee`]，这里只是把区块执行
+/// 层需要的 `u64` 结果暴露出来，供 [`BlockExecutor::execute_block`] 校验新区块头。
+pub fn calculate_base_fee(parent: &BlockHeader) -> u64 {
+    let next = parent.calculate_next_base_fee();
+    u64::try_from(next).unwrap_or(u64::MAX)
+}
+
 /// 区块执行结果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockExecutionResult {
@@ -24,9 +34,34 @@ pub struct BlockExecutionResult {
     
     /// 成功交易数量
     pub successful_txs: usize,
-    
+
     /// 失败交易数量
     pub failed_txs: usize,
+
+    /// 收据根（按交易索引排序后插入交易/收据树机制计算，空区块取 [`EMPTY_ROOT_HASH`](crate::utils::EMPTY_ROOT_HASH)）
+    pub receipts_root: B256,
+
+    /// 区块级 Logs Bloom：聚合（OR）区块内每笔收据各自的 Bloom
+    pub logs_bloom: Bloom,
+
+    /// EIP-1559 gas target（见 [`BlockHeader::gas_target`]），下一个区块的 base fee 由
+    /// `total_gas_used` 相对该值的偏离量决定
+    pub gas_target: u64,
+
+    /// 本区块实际用量是否高于 `gas_target`——与 `gas_target` 配套暴露，
+    /// 避免调用方各自用 `total_gas_used > gas_target` 重新判断一遍
+    pub above_target: bool,
+
+    /// 本区块销毁的 base fee 总量（`base_fee_per_gas * total_gas_used`）。
+    /// 实际的扣费早已在每笔交易执行时由 [`TransactionExecutor::execute`] 按
+    /// `effective_gas_price` 结算完毕——这里只是把销毁量在区块级别汇总暴露出来，
+    /// 供上层报告/监控使用，未声明 base fee（创世区块之前的旧格式）时为 0。
+    pub base_fee_burned: U256,
+
+    /// 本区块的状态变更包，支持重组时直接 [`StateBundle::revert`] 撤销，
+    /// 不必从创世区块重放
+    #[serde(skip)]
+    pub state_bundle: StateBundle,
 }
 
 /// 区块执行器
@@ -41,7 +76,14 @@ impl BlockExecutor {
             tx_executor: TransactionExecutor::new(db),
         }
     }
-    
+
+    /// 创建区块执行器并指定链参数
+    pub fn with_chain_config(db: RedbStateDB, chain_config: ChainConfig) -> Self {
+        Self {
+            tx_executor: TransactionExecutor::with_chain_config(db, chain_config),
+        }
+    }
+
     /// 执行区块
     /// 
     /// 按顺序执行区块中的所有交易
@@ -58,63 +100,103 @@ impl BlockExecutor {
         // 开始事务
         self.tx_executor.db_mut().begin_transaction()
             .map_err(|e| ExecutorError::Database(e.to_string()))?;
-        
+
+        // 校验 base fee：创世区块没有父区块可比对，跳过
+        if block.header.number > 0 {
+            if let Some(declared) = block.header.base_fee_per_gas {
+                let parent = self.tx_executor.db().block_by_number(block.header.number - 1)
+                    .map_err(|e| ExecutorError::Database(e.to_string()))?;
+                if let Some(parent) = parent {
+                    let expected = calculate_base_fee(&parent.header);
+                    let got = u64::try_from(declared).unwrap_or(u64::MAX);
+                    if expected != got {
+                        self.tx_executor.db_mut().rollback_transaction()
+                            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+                        return Err(ExecutorError::InvalidBaseFee { expected, got });
+                    }
+                }
+            }
+        }
+
         // 构建区块环境
         let block_env = self.build_block_env(block);
-        
+
+        // 区块 gas 上限：未声明时视为无限制，不做累计校验
+        let block_gas_limit = block.header.gas_limit.unwrap_or(u64::MAX);
+
         // 执行每笔交易
         for (index, tx) in block.transactions.iter().enumerate() {
-            let tx_hash = tx.hash.clone()
-                .unwrap_or_else(|| format!("tx_{}", index));
-                    
+            // 优先使用调用方提供的哈希（须为合法的 32 字节十六进制），
+            // 否则从交易的规范编码派生，而不是信任任意字符串
+            let tx_hash_b256 = tx.hash.as_deref()
+                .and_then(|h| h.strip_prefix("0x"))
+                .filter(|h| h.len() == 64)
+                .and_then(|h| hex::decode(h).ok())
+                .filter(|bytes| bytes.len() == 32)
+                .map(|bytes| B256::from_slice(&bytes))
+                .unwrap_or_else(|| tx.compute_hash());
+            let tx_hash = format!("0x{}", hex::encode(tx_hash_b256));
+
+            // 累计 gas 校验：本笔交易声明的 gas_limit 加上已用量不能超过区块 gas 上限，
+            // 否则区块会超支——无法解析 gas_limit 时交给下面的 `validate_transaction`
+            // 给出更具体的错误，这里不重复处理
+            if let Ok(tx_gas_limit) = tx.gas_limit() {
+                let would_use = total_gas_used.checked_add(tx_gas_limit);
+                if would_use.map_or(true, |sum| sum > block_gas_limit) {
+                    let e = ExecutorError::BlockGasLimitExceeded {
+                        total_gas_used,
+                        tx_gas_limit,
+                        block_gas_limit,
+                    };
+                    failed_txs += 1;
+                    tracing::warn!("交易验证失败 [{}]: {}", tx_hash, e);
+                    continue;
+                }
+            }
+
             // 预验证交易
-            if let Err(e) = self.tx_executor.validate_transaction(tx) {
+            if let Err(e) = self.tx_executor.validate_transaction(tx, block_env.basefee) {
+                if e.is_fatal() {
+                    // 严重错误（如状态损坏）：不能当成单笔交易失败悄悄吞掉，
+                    // 回滚整个区块事务并向上传播
+                    self.tx_executor.db_mut().rollback_transaction()
+                        .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
+                    return Err(e);
+                }
+
                 failed_txs += 1;
                 tracing::warn!("交易验证失败 [{}]: {}", tx_hash, e);
                 continue; // 跳过该交易,不影响其他交易
             }
-                    
+
+            // 给本笔交易单独划一个 checkpoint：`execute` 内部由 REVM 直接把状态
+            // 变更写入数据库，一旦它在写入之后才报错（例如 `apply_pricing` 里的
+            // gas 费用结算失败），光靠"跳过,不插入收据"是不够的——REVM 已经落盘
+            // 的那部分变更（nonce、余额、storage……）必须连同一起撤销，否则这笔
+            // "失败被跳过"的交易仍然会污染后续交易看到的状态，以及本区块的
+            // 状态根。成功的交易则把 checkpoint 折叠进外层区块事务，正常参与
+            // `commit_transaction_at_block`。
+            let checkpoint = self.tx_executor.db_mut().checkpoint()
+                .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
             // 执行交易
             match self.tx_executor.execute(tx, block_env.clone()) {
                 Ok(result) => {
+                    self.tx_executor.db_mut().discard_checkpoint(checkpoint)
+                        .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
                     total_gas_used += result.gas_used;
-                            
+
                     if result.success {
                         successful_txs += 1;
                     } else {
                         failed_txs += 1;
                     }
-                            
+
                     // 构建交易收据
                     use crate::executor::receipts::ReceiptBuilder;
-                    
-                    // 将 tx_hash 字符串转换为 B256
-                    let tx_hash_b256 = if tx_hash.starts_with("0x") && tx_hash.len() == 66 {
-                        // 有效的十六进制哈希
-                        hex::decode(tx_hash.trim_start_matches("0x"))
-                            .ok()
-                            .and_then(|bytes| {
-                                if bytes.len() == 32 {
-                                    Some(B256::from_slice(&bytes))
-                                } else {
-                                    None
-                                }
-                            })
-                            .unwrap_or_else(|| {
-                                // 解码失败，使用确定性哈希
-                                use sha2::{Digest, Sha256};
-                                let mut hasher = Sha256::new();
-                                hasher.update(tx_hash.as_bytes());
-                                B256::from_slice(&hasher.finalize())
-                            })
-                    } else {
-                        // 不是有效哈希（如 "tx_0"），使用确定性哈希
-                        use sha2::{Digest, Sha256};
-                        let mut hasher = Sha256::new();
-                        hasher.update(tx_hash.as_bytes());
-                        B256::from_slice(&hasher.finalize())
-                    };
-                    
+
                     let receipt = ReceiptBuilder::build(
                         tx_hash_b256,
                         index as u64,
@@ -122,9 +204,10 @@ impl BlockExecutor {
                         tx,
                         &result,
                         total_gas_used,
+                        block_env.basefee,
                     );
                     receipts.insert(tx_hash.clone(), receipt);
-                            
+
                     execution_results.insert(tx_hash, result);
                 }
                 Err(e) => {
@@ -133,10 +216,14 @@ impl BlockExecutor {
                         // 严重错误,回滚整个区块事务
                         self.tx_executor.db_mut().rollback_transaction()
                             .map_err(|e| ExecutorError::Database(e.to_string()))?;
-                                
+
                         return Err(e);
                     } else {
-                        // 非严重错误,跳过该交易
+                        // 非严重错误：撤销这笔交易的 checkpoint（可能已经部分写入
+                        // 的状态变更），跳过该交易，不影响前面已提交的交易
+                        self.tx_executor.db_mut().revert_to_checkpoint(checkpoint)
+                            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
                         failed_txs += 1;
                         tracing::warn!("交易执行失败 [{}]: {}", tx_hash, e);
                     }
@@ -144,16 +231,53 @@ impl BlockExecutor {
             }
         }
         
-        // 提交事务
-        self.tx_executor.db_mut().commit_transaction()
+        // 提交事务，同时把本区块的反向 diff 记入历史日志（支持重组回滚/历史查询）
+        self.tx_executor.db_mut().commit_transaction_at_block(block.header.number)
             .map_err(|e| ExecutorError::Database(e.to_string()))?;
-        
+
+        // 紧跟着提交把本区块的历史日志组装成 `StateBundle`——必须在提交之后、
+        // 任何后续区块有机会再次改写同一批账户/存储槽之前取值，见
+        // `journal_entries_for_block` 的文档
+        let (account_entries, storage_entries) = self.tx_executor.db()
+            .journal_entries_for_block(block.header.number)
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+        let mut state_bundle = StateBundle::empty();
+        for (address, original, new) in account_entries {
+            state_bundle.accounts.insert(address, (original, new));
+        }
+        for (address, key, original, new) in storage_entries {
+            state_bundle.storage.entry(address).or_default().insert(key, (original, new));
+        }
+
+        // 按交易索引排序（`receipts` 是哈希表，迭代顺序无保证），
+        // 使收据根与交易树/收据树的构建方式一致——键是索引而非哈希表的遍历顺序
+        let mut ordered_receipts: Vec<TransactionReceipt> = receipts.values().cloned().collect();
+        ordered_receipts.sort_by_key(|r| r.transaction_index);
+
+        let receipts_root = calculate_merkle_root(&ordered_receipts);
+
+        let mut logs_bloom = Bloom::zero();
+        for receipt in &ordered_receipts {
+            logs_bloom.or(&receipt.logs_bloom);
+        }
+
+        let gas_target = block.header.gas_target();
+        let above_target = total_gas_used > gas_target;
+        let base_fee_burned = block.header.base_fee_per_gas.unwrap_or_default()
+            * U256::from(total_gas_used);
+
         Ok(BlockExecutionResult {
             execution_results,
             receipts,
             total_gas_used,
             successful_txs,
             failed_txs,
+            receipts_root,
+            logs_bloom,
+            gas_target,
+            above_target,
+            base_fee_burned,
+            state_bundle,
         })
     }
     
@@ -179,7 +303,12 @@ impl BlockExecutor {
         if let Some(gas_limit) = block.header.gas_limit {
             env.gas_limit = alloy_primitives::U256::from(gas_limit);
         }
-        
+
+        // Base fee（EIP-1559）：未设置时保持 revm 默认值
+        if let Some(base_fee) = block.header.base_fee_per_gas {
+            env.basefee = base_fee;
+        }
+
         env
     }
     
@@ -187,6 +316,14 @@ impl BlockExecutor {
     pub fn db_mut(&mut self) -> &mut RedbStateDB {
         self.tx_executor.db_mut()
     }
+
+    /// 获取内部交易执行器的可变引用
+    ///
+    /// 允许上层在区块执行之外复用同一套状态做单笔执行 / 模拟 / 追踪，
+    /// 避免为不同路径各自持有一份数据库。
+    pub fn tx_executor_mut(&mut self) -> &mut TransactionExecutor {
+        &mut self.tx_executor
+    }
 }
 
 #[cfg(test)]
@@ -206,7 +343,10 @@ mod tests {
     #[tokio::test]
     async fn test_block_execution() {
         let (db, _temp_dir) = create_test_db();
-        let mut executor = BlockExecutor::new(db);
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
         
         // 构建测试区块
         let block = Block {
@@ -220,16 +360,20 @@ mod tests {
                 gas_used: None,
                 gas_limit: Some(30_000_000),
                 receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
             },
             transactions: vec![],
         };
         
         let result = executor.execute_block(&block).await.unwrap();
-        
+
         assert_eq!(result.total_gas_used, 0);
         assert_eq!(result.successful_txs, 0);
+        assert_eq!(result.receipts_root, crate::utils::EMPTY_ROOT_HASH);
+        assert_eq!(result.logs_bloom, crate::schema::Bloom::zero());
     }
-    
+
     #[tokio::test]
     async fn test_block_execution_with_invalid_tx() {
         let (mut db, _temp_dir) = create_test_db();
@@ -243,7 +387,10 @@ mod tests {
         account.nonce = 0;
         db.set_account(&from, account).unwrap();
         
-        let mut executor = BlockExecutor::new(db);
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
         
         // 构建区块,包含一笔有效交易和一笔无效交易
         let valid_tx = Transaction {
@@ -258,6 +405,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let invalid_tx = Transaction {
@@ -272,6 +423,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let block = Block {
@@ -285,6 +440,8 @@ mod tests {
                 gas_used: None,
                 gas_limit: Some(30_000_000),
                 receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
             },
             transactions: vec![valid_tx, invalid_tx],
         };
@@ -316,7 +473,10 @@ mod tests {
         account2.nonce = 0;
         db.set_account(&from2, account2).unwrap();
         
-        let mut executor = BlockExecutor::new(db);
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
         
         // 构建区块:
         // - 第1笔: from1的有效交易
@@ -334,6 +494,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let tx2 = Transaction {
@@ -348,6 +512,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let tx3 = Transaction {
@@ -362,6 +530,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let block = Block {
@@ -375,14 +547,324 @@ mod tests {
                 gas_used: None,
                 gas_limit: Some(30_000_000),
                 receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
             },
             transactions: vec![tx1, tx2, tx3],
         };
         
         let result = executor.execute_block(&block).await.unwrap();
-        
+
         // 第1和第3笔应该成功,第2笔应该失败
         assert_eq!(result.successful_txs, 2);
         assert_eq!(result.failed_txs, 1);
+
+        // 每笔交易各自的 checkpoint 在执行完后必须已经被 discard/revert 掉，
+        // 不会留下嵌套层级——区块事务提交之后,db 上再开一个全新 checkpoint
+        // 应该和从未嵌套过一样正常工作
+        executor.db_mut().begin_transaction().unwrap();
+        let checkpoint = executor.db_mut().checkpoint().unwrap();
+        executor.db_mut().discard_checkpoint(checkpoint).unwrap();
+        executor.db_mut().commit_transaction().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_execute_block_eip1559_burns_base_fee() {
+        use alloy_primitives::{address, Address, U256};
+        use crate::schema::{Account, Transaction};
+        use crate::db::StateDatabase;
+
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let mut account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64)); // 10 ETH
+        account.nonce = 0;
+        db.set_account(&from, account).unwrap();
+
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        let base_fee = U256::from(1_000_000_000u64); // 1 Gwei
+        let priority_fee = U256::from(2_000_000_000u64); // 2 Gwei
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: None,
+            chain_id: Some(1),
+            max_fee_per_gas: Some("0x1dcd65000".to_string()), // 8 Gwei，留足余量不被封顶
+            max_priority_fee_per_gas: Some(format!("0x{:x}", priority_fee)),
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let block = Block {
+            header: BlockHeader {
+                number: 1,
+                parent_hash: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                timestamp: Utc::now(),
+                tx_count: 1,
+                transactions_root: "0x".to_string(),
+                state_root: None,
+                gas_used: None,
+                gas_limit: Some(30_000_000),
+                receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: Some(base_fee),
+            },
+            transactions: vec![tx],
+        };
+
+        let result = executor.execute_block(&block).await.unwrap();
+        assert_eq!(result.successful_txs, 1);
+
+        let gas_used = U256::from(result.total_gas_used);
+        let effective_gas_price = base_fee.saturating_add(priority_fee);
+
+        // 发送方按完整的 effective_gas_price 计费（base fee + 小费都从余额扣除）
+        let sender_balance = executor
+            .db_mut()
+            .get_account(&from)
+            .unwrap()
+            .unwrap()
+            .balance;
+        let expected_sender_balance = U256::from(10_000_000_000_000_000_000u64)
+            - gas_used * effective_gas_price;
+        assert_eq!(sender_balance, expected_sender_balance);
+
+        // 出块者（默认 coinbase = 零地址）只收到小费部分，base fee 部分被销毁
+        let coinbase_balance = executor
+            .db_mut()
+            .get_account(&Address::ZERO)
+            .unwrap()
+            .map(|a| a.balance)
+            .unwrap_or_default();
+        assert_eq!(coinbase_balance, gas_used * priority_fee);
+
+        // 区块级汇总的销毁量必须等于 base_fee * total_gas_used
+        assert_eq!(result.base_fee_burned, gas_used * base_fee);
+    }
+
+    #[tokio::test]
+    async fn test_execute_block_computes_receipts_root_and_bloom() {
+        use alloy_primitives::{address, U256};
+        use crate::schema::{Account, Transaction};
+        use crate::utils::calculate_merkle_root;
+
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from1 = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let from2 = address!("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        db.set_account(&from1, Account::with_balance(U256::from(10_000_000_000_000_000_000u64))).unwrap();
+        db.set_account(&from2, Account::with_balance(U256::from(10_000_000_000_000_000_000u64))).unwrap();
+
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        let tx1 = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let tx2 = Transaction {
+            from: "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string(),
+            to: Some("0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let block = Block {
+            header: BlockHeader {
+                number: 1,
+                parent_hash: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                timestamp: Utc::now(),
+                tx_count: 2,
+                transactions_root: "0x".to_string(),
+                state_root: None,
+                gas_used: None,
+                gas_limit: Some(30_000_000),
+                receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
+            },
+            transactions: vec![tx1, tx2],
+        };
+
+        let result = executor.execute_block(&block).await.unwrap();
+        assert_eq!(result.successful_txs, 2);
+
+        // 收据根必须与按交易索引排序后的收据列表独立算出的结果一致
+        let mut ordered: Vec<_> = result.receipts.values().cloned().collect();
+        ordered.sort_by_key(|r| r.transaction_index);
+        assert_eq!(result.receipts_root, calculate_merkle_root(&ordered));
+
+        // 两笔普通转账都不产生日志，聚合出的区块 bloom 应保持全零
+        assert_eq!(result.logs_bloom, crate::schema::Bloom::zero());
+
+        // gas_target = gas_limit / 2（弹性系数 2），两笔转账远低于该值
+        assert_eq!(result.gas_target, 30_000_000 / 2);
+        assert!(!result.above_target);
+    }
+
+    #[tokio::test]
+    async fn test_execute_block_skips_tx_exceeding_block_gas_limit() {
+        use alloy_primitives::{address, U256};
+        use crate::schema::{Account, Transaction};
+
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        db.set_account(&from, Account::with_balance(U256::from(10_000_000_000_000_000_000u64))).unwrap();
+
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        // 声明的 gas（0x7530 = 30_000）超过区块 gas 上限，应在累计校验阶段就被跳过，
+        // 而不是进入 `validate_transaction`/实际执行
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x7530".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let block = Block {
+            header: BlockHeader {
+                number: 1,
+                parent_hash: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                timestamp: Utc::now(),
+                tx_count: 1,
+                transactions_root: "0x".to_string(),
+                state_root: None,
+                gas_used: None,
+                gas_limit: Some(20_000), // 小于交易声明的 gas limit
+                receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
+            },
+            transactions: vec![tx],
+        };
+
+        let result = executor.execute_block(&block).await.unwrap();
+
+        assert_eq!(result.successful_txs, 0);
+        assert_eq!(result.failed_txs, 1);
+        assert_eq!(result.total_gas_used, 0);
+        assert!(result.execution_results.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_state_bundle_reverts_block_back_to_pre_execution_balances() {
+        use alloy_primitives::{address, U256};
+        use crate::schema::{Account, Transaction};
+        use crate::db::StateDatabase;
+
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let to = address!("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed");
+        db.set_account(&from, Account::with_balance(U256::from(10_000_000_000_000_000_000u64))).unwrap();
+
+        let mut executor = BlockExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0xde0b6b3a7640000".to_string(), // 1 ETH
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let block = Block {
+            header: BlockHeader {
+                number: 1,
+                parent_hash: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
+                timestamp: Utc::now(),
+                tx_count: 1,
+                transactions_root: "0x".to_string(),
+                state_root: None,
+                gas_used: None,
+                gas_limit: Some(30_000_000),
+                receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
+            },
+            transactions: vec![tx],
+        };
+
+        let result = executor.execute_block(&block).await.unwrap();
+        assert_eq!(result.successful_txs, 1);
+        assert!(!result.state_bundle.is_empty());
+
+        // `to` 在区块执行前并不存在——撤销之后应该重新变回"不存在"
+        assert!(executor.db_mut().get_account(&to).unwrap().is_some());
+
+        executor.db_mut().begin_transaction().unwrap();
+        result.state_bundle.revert(executor.db_mut()).unwrap();
+        executor.db_mut().commit_transaction().unwrap();
+
+        let from_after_revert = executor.db_mut().get_account(&from).unwrap().unwrap();
+        assert_eq!(from_after_revert.balance, U256::from(10_000_000_000_000_000_000u64));
+        assert!(executor.db_mut().get_account(&to).unwrap().is_none());
     }
 }
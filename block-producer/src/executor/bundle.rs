@@ -0,0 +1,190 @@
+//! 区块级状态变更包（bundle state），支持重组回滚
+//!
+//! [`BlockExecutor::execute_block`](crate::executor::BlockExecutor::execute_block) 每执行完
+//! 一个区块，都会额外产出一份 [`StateBundle`]：记录本区块触碰过的每个账户/存储槽在执行前的
+//! 原始值（`None` 表示账户原本不存在）和执行后的新值。与 [`crate::db::pod::StateDiff`]（两份
+//! 全量快照相减，用于调试/断言）不同，`StateBundle` 是专门为"撤销一个已经落盘的区块"设计的：
+//! 它自带 [`StateBundle::revert`]，把原始值重新写回 [`StateDatabase`](crate::db::StateDatabase)，
+//! 调用方随后可以用 `StateRootCalculator::calculate_incremental` 直接算出撤销后的状态根，不需要
+//! 从创世区块重放。
+
+use std::collections::BTreeMap;
+
+use alloy_primitives::{Address, U256};
+
+use crate::db::{DbError, StateDatabase};
+use crate::schema::Account;
+
+/// 一份 [`StateBundle`] 里记录的原始值是否完全可信
+///
+/// 直接从单个区块的历史日志构建出来的 bundle 永远是 `Yes`——日志本身就是提交前的
+/// 真实旧值。把多个区块的 bundle 用 [`StateBundle::extend`] 串起来之后，只要链条里
+/// 每一环都是 `Yes`，合并结果仍然是 `Yes`（`extend` 保留的是链条里最早一环的原始值，
+/// 并不会凭空丢失信息）；这个标记主要是给未来"从不完整数据构建 bundle"的路径
+/// （例如只有新值、没有日志可查的场景）留的扩展点。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OriginalValuesKnown {
+    Yes,
+    No,
+}
+
+/// 一个区块（或串联起来的多个区块）的状态变更包
+///
+/// `accounts`/`storage` 都用 `BTreeMap`，保证 [`Self::reverts_iter`] 和内部遍历顺序
+/// 按地址/槽位确定性排序，而不是依赖哈希表的迭代顺序。
+#[derive(Debug, Clone, Default)]
+pub struct StateBundle {
+    /// 地址 -> (执行前的账户，`None` 表示原本不存在；执行后的账户)
+    pub accounts: BTreeMap<Address, (Option<Account>, Account)>,
+
+    /// 地址 -> (槽位 -> (执行前的值，执行后的值))
+    pub storage: BTreeMap<Address, BTreeMap<U256, (U256, U256)>>,
+
+    original_values_known: OriginalValuesKnown,
+}
+
+impl StateBundle {
+    /// 一份空的 bundle，原始值视为完全可信（没有任何变更也就无所谓信不信）
+    pub fn empty() -> Self {
+        Self {
+            accounts: BTreeMap::new(),
+            storage: BTreeMap::new(),
+            original_values_known: OriginalValuesKnown::Yes,
+        }
+    }
+
+    /// 该 bundle 记录的原始值是否完全可信，见 [`OriginalValuesKnown`]
+    pub fn original_values_known(&self) -> OriginalValuesKnown {
+        self.original_values_known
+    }
+
+    /// 这个 bundle 是否没有记录任何变更
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.storage.is_empty()
+    }
+
+    /// 把后一个区块的 bundle 并入 `self`（`self` 必须是更早的区块）
+    ///
+    /// 同一个账户/存储槽在两份 bundle 里都出现时，保留 `self`（更早）记录的原始值，
+    /// 采用 `other`（更晚）记录的新值——这正是"链式撤销"需要的语义：`self.extend(other)`
+    /// 之后调用 `revert`，效果等同于依次撤销 `other` 再撤销 `self`，而不是只撤销其中一个。
+    pub fn extend(&mut self, other: StateBundle) {
+        for (address, (original, new)) in other.accounts {
+            self.accounts
+                .entry(address)
+                .and_modify(|(_, existing_new)| *existing_new = new.clone())
+                .or_insert((original, new));
+        }
+
+        for (address, slots) in other.storage {
+            let entry = self.storage.entry(address).or_default();
+            for (slot, (original, new)) in slots {
+                entry
+                    .entry(slot)
+                    .and_modify(|(_, existing_new)| *existing_new = new)
+                    .or_insert((original, new));
+            }
+        }
+
+        if other.original_values_known == OriginalValuesKnown::No {
+            self.original_values_known = OriginalValuesKnown::No;
+        }
+    }
+
+    /// 按地址、槽位升序迭代存储回滚项：`(地址, 槽位, 原始值)`
+    pub fn reverts_iter(&self) -> impl Iterator<Item = (Address, U256, U256)> + '_ {
+        self.storage.iter().flat_map(|(address, slots)| {
+            slots
+                .iter()
+                .map(move |(slot, (original, _new))| (*address, *slot, *original))
+        })
+    }
+
+    /// 把这份 bundle 记录的原始值重新写回数据库，撤销它代表的全部变更
+    ///
+    /// 账户原本不存在（`original` 为 `None`）的，撤销即删除；存储槽一律按
+    /// [`Self::reverts_iter`] 的顺序写回原始值。调用方需要自行包一层事务
+    /// （`begin_transaction`/`commit_transaction`），`revert` 本身不处理事务边界。
+    pub fn revert(&self, db: &mut dyn StateDatabase) -> Result<(), DbError> {
+        for (address, slot, original) in self.reverts_iter() {
+            db.set_storage(&address, slot, original)?;
+        }
+
+        for (address, (original, _new)) in &self.accounts {
+            match original {
+                Some(account) => db.set_account(address, account.clone())?,
+                None => db.delete_account(address)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn extend_keeps_earliest_original_and_latest_new_value() {
+        let addr = address!("0000000000000000000000000000000000000001");
+
+        let mut bundle_n = StateBundle::empty();
+        bundle_n.accounts.insert(
+            addr,
+            (None, Account::with_balance(U256::from(100u64))),
+        );
+
+        let mut bundle_n_plus_1 = StateBundle::empty();
+        bundle_n_plus_1.accounts.insert(
+            addr,
+            (
+                Some(Account::with_balance(U256::from(100u64))),
+                Account::with_balance(U256::from(250u64)),
+            ),
+        );
+
+        bundle_n.extend(bundle_n_plus_1);
+
+        let (original, new) = &bundle_n.accounts[&addr];
+        assert_eq!(*original, None);
+        assert_eq!(new.balance, U256::from(250u64));
+        assert_eq!(bundle_n.original_values_known(), OriginalValuesKnown::Yes);
+    }
+
+    #[test]
+    fn reverts_iter_yields_storage_entries_in_deterministic_order() {
+        let addr_a = address!("0000000000000000000000000000000000000001");
+        let addr_b = address!("0000000000000000000000000000000000000002");
+
+        let mut bundle = StateBundle::empty();
+        bundle
+            .storage
+            .entry(addr_b)
+            .or_default()
+            .insert(U256::from(1u64), (U256::ZERO, U256::from(9u64)));
+        bundle
+            .storage
+            .entry(addr_a)
+            .or_default()
+            .insert(U256::from(2u64), (U256::from(1u64), U256::from(2u64)));
+
+        let reverts: Vec<_> = bundle.reverts_iter().collect();
+
+        assert_eq!(
+            reverts,
+            vec![
+                (addr_a, U256::from(2u64), U256::from(1u64)),
+                (addr_b, U256::from(1u64), U256::ZERO),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_bundle_has_no_changes() {
+        let bundle = StateBundle::empty();
+        assert!(bundle.is_empty());
+        assert_eq!(bundle.reverts_iter().count(), 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! 跨链状态读取预编译（booster 式执行）
+//!
+//! 为 [`RevmAdapter`](crate::executor::RevmAdapter) 引入若干按 `chainId`
+//! 区分的*次要* `RedbStateDB`，并在保留地址 [`CROSS_CHAIN_READ_ADDRESS`]
+//! 上安装一个自定义预编译合约，使合约代码在执行期间可以读取另一条链
+//! （L1 或兄弟链）的账户余额、nonce、代码哈希，以及任意存储槽。
+//! 这是 rollup/booster 模式的基础能力：L2 执行可以直接“看见”一份外部
+//! 状态视图，而不必先把数据桥接进自己的状态树。
+//!
+//! 调用输入由 4 个 32 字节大端字组成：`(chain_id, query_kind, address, slot)`；
+//! `address` 取字的低 20 字节，`slot` 仅在 `query_kind == Storage` 时生效，
+//! 其余情况下会被忽略。输出固定为一个右对齐的 32 字节字。
+//!
+//! 每次调用固定收取 [`BASE_GAS_COST`]，再加上一次 [`PER_ACCESS_SURCHARGE`]
+//! ——后者对应这次调用确实触发了一次跨库查找这一事实，即便将来扩展为
+//! 批量读取也应该按访问次数计费，而不是按调用次数。
+
+use alloy_primitives::{Address, Bytes, B256, U256};
+use parking_lot::RwLock;
+use revm::precompile::{PrecompileError, PrecompileErrors, PrecompileOutput, PrecompileResult};
+use revm::{ContextStatefulPrecompile, Database, InnerEvmContext};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::db::{RedbStateDB, StateDatabase};
+use crate::schema::account::EMPTY_CODE_HASH;
+
+/// 跨链读取预编译的保留地址
+///
+/// 落在 `0x0100`，避开标准以太坊预编译占用的 `0x01..=0x0a` 区间。
+pub const CROSS_CHAIN_READ_ADDRESS: Address = Address::new([
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, 0x00,
+]);
+
+/// 每次调用的固定基础 gas 开销
+const BASE_GAS_COST: u64 = 3_000;
+
+/// 在基础开销之上，为这次调用触发的跨库查找另计的附加 gas
+const PER_ACCESS_SURCHARGE: u64 = 2_000;
+
+/// 按 `chainId` 索引的次要状态库注册表
+pub type CrossChainRegistry = HashMap<u64, Arc<RwLock<RedbStateDB>>>;
+
+/// 查询类型：决定读取账户的哪个字段，或读取某个存储槽
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryKind {
+    Balance,
+    Nonce,
+    CodeHash,
+    Storage,
+}
+
+impl QueryKind {
+    fn decode(word: U256) -> Option<Self> {
+        let tag: u64 = word.try_into().ok()?;
+        match tag {
+            0 => Some(Self::Balance),
+            1 => Some(Self::Nonce),
+            2 => Some(Self::CodeHash),
+            3 => Some(Self::Storage),
+            _ => None,
+        }
+    }
+}
+
+fn precompile_error(msg: impl Into<String>) -> PrecompileErrors {
+    PrecompileErrors::Error(PrecompileError::Other(msg.into()))
+}
+
+/// 跨链状态读取预编译
+///
+/// 只读：`call` 永远不会修改本链状态，只会读取登记在 [`CrossChainRegistry`]
+/// 中的次要库。未登记的 `chainId` 视为调用失败（而不是静默返回零值），
+/// 避免把“没接这条链”和“这条链上账户确实是空的”混为一谈。
+pub struct CrossChainReadPrecompile {
+    registry: Arc<CrossChainRegistry>,
+}
+
+impl CrossChainReadPrecompile {
+    pub fn new(registry: Arc<CrossChainRegistry>) -> Self {
+        Self { registry }
+    }
+
+    fn decode_input(input: &Bytes) -> Result<(u64, QueryKind, Address, U256), PrecompileErrors> {
+        const WORD_COUNT: usize = 4;
+        if input.len() != WORD_COUNT * 32 {
+            return Err(precompile_error(format!(
+                "cross-chain read: expected {} bytes (chainId, queryKind, address, slot), got {}",
+                WORD_COUNT * 32,
+                input.len()
+            )));
+        }
+
+        let word = |i: usize| U256::from_be_slice(&input[i * 32..i * 32 + 32]);
+
+        let chain_id: u64 = word(0)
+            .try_into()
+            .map_err(|_| precompile_error("cross-chain read: chainId overflows u64"))?;
+        let query_kind = QueryKind::decode(word(1))
+            .ok_or_else(|| precompile_error("cross-chain read: unknown query kind"))?;
+        let address = Address::from_word(word(2).into());
+        let slot = word(3);
+
+        Ok((chain_id, query_kind, address, slot))
+    }
+}
+
+impl<DB: Database> ContextStatefulPrecompile<DB> for CrossChainReadPrecompile {
+    fn call(
+        &self,
+        input: &Bytes,
+        gas_limit: u64,
+        _evmctx: &mut InnerEvmContext<DB>,
+    ) -> PrecompileResult {
+        let gas_cost = BASE_GAS_COST + PER_ACCESS_SURCHARGE;
+        if gas_cost > gas_limit {
+            return Err(PrecompileErrors::Error(PrecompileError::OutOfGas));
+        }
+
+        let (chain_id, query_kind, address, slot) = Self::decode_input(input)?;
+
+        let remote = self.registry.get(&chain_id).ok_or_else(|| {
+            precompile_error(format!("cross-chain read: no state registered for chain id {chain_id}"))
+        })?;
+        let remote = remote.read();
+
+        let value: B256 = match query_kind {
+            QueryKind::Balance => {
+                let balance = remote
+                    .get_account(&address)
+                    .map_err(|e| precompile_error(e.to_string()))?
+                    .map(|acc| acc.balance)
+                    .unwrap_or(U256::ZERO);
+                balance.into()
+            }
+            QueryKind::Nonce => {
+                let nonce = remote
+                    .get_account(&address)
+                    .map_err(|e| precompile_error(e.to_string()))?
+                    .map(|acc| acc.nonce)
+                    .unwrap_or(0);
+                U256::from(nonce).into()
+            }
+            QueryKind::CodeHash => remote
+                .get_account(&address)
+                .map_err(|e| precompile_error(e.to_string()))?
+                .map(|acc| acc.code_hash)
+                .unwrap_or(EMPTY_CODE_HASH),
+            QueryKind::Storage => {
+                let value = remote
+                    .get_storage(&address, slot)
+                    .map_err(|e| precompile_error(e.to_string()))?;
+                value.into()
+            }
+        };
+
+        Ok(PrecompileOutput::new(gas_cost, Bytes::copy_from_slice(value.as_slice())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_kind_decode_rejects_unknown_tag() {
+        assert_eq!(QueryKind::decode(U256::from(0)), Some(QueryKind::Balance));
+        assert_eq!(QueryKind::decode(U256::from(3)), Some(QueryKind::Storage));
+        assert_eq!(QueryKind::decode(U256::from(4)), None);
+    }
+
+    #[test]
+    fn test_decode_input_rejects_wrong_length() {
+        let err = CrossChainReadPrecompile::decode_input(&Bytes::from(vec![0u8; 64]));
+        assert!(err.is_err());
+    }
+}
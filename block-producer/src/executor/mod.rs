@@ -6,11 +6,21 @@ pub mod revm_adapter;
 pub mod transaction;
 pub mod block_executor;
 pub mod receipts;
+pub mod tracer;
+pub mod permission;
+pub mod cross_chain_read;
+pub mod state_diff;
+pub mod bundle;
 
 pub use revm_adapter::RevmAdapter;
-pub use transaction::{TransactionExecutor, ExecutionResult};
+pub use transaction::{TransactionExecutor, ExecutionResult, ChainConfig, GasPricingPolicy, GasPricingConfig};
 pub use block_executor::{BlockExecutor, BlockExecutionResult};
 pub use receipts::ReceiptBuilder;
+pub use tracer::{StructLog, TraceConfig};
+pub use permission::{TransactionFilter, Decision, AllowAllFilter, WhitelistFilter};
+pub use cross_chain_read::{CrossChainReadPrecompile, CrossChainRegistry, CROSS_CHAIN_READ_ADDRESS};
+pub use state_diff::{AccountDiff, StateDiff};
+pub use bundle::{StateBundle, OriginalValuesKnown};
 
 use thiserror::Error;
 
@@ -35,7 +45,42 @@ pub enum ExecutorError {
     
     #[error("Insufficient funds: required {required}, available {available}")]
     InsufficientFunds { required: String, available: String },
-    
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Sender mismatch: recovered {recovered}, declared {declared}")]
+    SenderMismatch { recovered: String, declared: String },
+
+    #[error("Sender is not an EOA: {0} has contract code (EIP-3607)")]
+    SenderNotEoa(String),
+
+    #[error("Fee cap too low: max_fee_per_gas {max_fee} is below base fee {base_fee}")]
+    FeeCapTooLow { max_fee: String, base_fee: String },
+
+    #[error("Invalid chain id: expected {expected}, got {got:?} (EIP-155 replay protection)")]
+    InvalidChainId { expected: u64, got: Option<u64> },
+
+    // 区块级错误：`execute_block` 直接拒绝整个区块，不是可跳过的单笔交易错误
+    #[error("Invalid base fee: block declares {got}, expected {expected} per EIP-1559")]
+    InvalidBaseFee { expected: u64, got: u64 },
+
+    #[error("Transaction not permitted: sender {0} is not allowed to submit transactions")]
+    NotPermitted(String),
+
+    #[error("Zero gas price not permitted: sender {0} is not whitelisted for service transactions")]
+    ZeroGasPriceNotPermitted(String),
+
+    // 不可跳过：见 `is_fatal`
+    #[error("Corrupt state for account {address}: {detail}")]
+    StateCorrupt { address: String, detail: String },
+
+    #[error("Block gas limit exceeded: {total_gas_used} + {tx_gas_limit} would exceed block limit {block_gas_limit}")]
+    BlockGasLimitExceeded { total_gas_used: u64, tx_gas_limit: u64, block_gas_limit: u64 },
+
+    #[error("Transaction envelope field conflict: {0}")]
+    TxEnvelopeFieldConflict(String),
+
     // === 执行错误（严重错误） ===
     #[error("Gas limit exceeded")]
     GasLimitExceeded,
@@ -59,10 +104,15 @@ impl From<String> for ExecutorError {
 
 impl ExecutorError {
     /// 判断是否为严重错误（需要回滚整个区块）
+    ///
+    /// `StateCorrupt` 必须是致命的：它意味着已落盘的账户/trie 节点数据本身无法
+    /// 解码或引用的哈希无法解析，和"账户不存在"或"锁被占用"这类瞬时性的
+    /// `Database` 错误有本质区别——继续把它当成单笔交易失败会把数据损坏悄悄
+    /// 吞进成功/失败计数里，掩盖真正需要运维介入的问题。
     pub fn is_fatal(&self) -> bool {
         matches!(
             self,
-            ExecutorError::Database(_) | ExecutorError::Evm(_)
+            ExecutorError::Database(_) | ExecutorError::Evm(_) | ExecutorError::StateCorrupt { .. }
         )
     }
     
@@ -73,6 +123,15 @@ impl ExecutorError {
             ExecutorError::InvalidGas
                 | ExecutorError::NonceTooLow { .. }
                 | ExecutorError::InsufficientFunds { .. }
+                | ExecutorError::InvalidSignature(_)
+                | ExecutorError::SenderMismatch { .. }
+                | ExecutorError::SenderNotEoa(_)
+                | ExecutorError::FeeCapTooLow { .. }
+                | ExecutorError::InvalidChainId { .. }
+                | ExecutorError::NotPermitted(_)
+                | ExecutorError::ZeroGasPriceNotPermitted(_)
+                | ExecutorError::BlockGasLimitExceeded { .. }
+                | ExecutorError::TxEnvelopeFieldConflict(_)
         )
     }
 }
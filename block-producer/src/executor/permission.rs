@@ -0,0 +1,121 @@
+//! 交易准入（permissioning）
+//!
+//! 在 `TransactionExecutor::validate_transaction` 中，余额/nonce 检查之前，
+//! 通过 [`TransactionFilter`] 这一可插拔钩子决定某笔交易是否被允许提交。
+//! 默认部署是完全无许可的（[`AllowAllFilter`]）；运行许可链的部署方可以换成
+//! [`WhitelistFilter`]，只放行白名单地址，并为其开放 `gas_price == 0` 的
+//! “服务交易”（service transaction）——这类交易不计入 gas 费用的余额占用。
+
+use alloy_primitives::{Address, U256};
+use std::collections::HashSet;
+
+/// 准入决策
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    /// 正常放行：按交易声明的 gas_price 校验（`gas_price == 0` 仍会被拒绝）
+    Allow,
+    /// 放行为“服务交易”：即使 `gas_price == 0` 也接受，且不对 gas 计费
+    AllowServiceTransaction,
+    /// 拒绝该交易
+    Deny,
+}
+
+/// 交易准入过滤器
+///
+/// 在发送方、接收方、声明的 gas price 已知的情况下做出 [`Decision`]。
+pub trait TransactionFilter: Send + Sync {
+    /// 判断是否允许这笔交易提交
+    fn is_allowed(&self, from: &Address, to: Option<&Address>, gas_price: U256) -> Decision;
+}
+
+/// 默认过滤器：不做任何许可限制，放行所有发送方
+///
+/// 零 gas price 的交易在此过滤器下仍按常规规则处理（即被拒绝）——
+/// “服务交易”豁免只对 [`WhitelistFilter`] 认可的地址开放。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AllowAllFilter;
+
+impl TransactionFilter for AllowAllFilter {
+    fn is_allowed(&self, _from: &Address, _to: Option<&Address>, _gas_price: U256) -> Decision {
+        Decision::Allow
+    }
+}
+
+/// 白名单过滤器：只放行白名单内的发送方
+///
+/// 对白名单地址提交的 `gas_price == 0` 交易视为服务交易，免除 gas 费用占用。
+#[derive(Debug, Clone, Default)]
+pub struct WhitelistFilter {
+    allowed: HashSet<Address>,
+}
+
+impl WhitelistFilter {
+    /// 创建空白名单（放行任何地址都将被拒绝，需要配合 `with_addresses` 使用）
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 以给定地址集合创建白名单过滤器
+    pub fn with_addresses(addresses: impl IntoIterator<Item = Address>) -> Self {
+        Self {
+            allowed: addresses.into_iter().collect(),
+        }
+    }
+
+    /// 新增一个白名单地址
+    pub fn allow(&mut self, address: Address) {
+        self.allowed.insert(address);
+    }
+}
+
+impl TransactionFilter for WhitelistFilter {
+    fn is_allowed(&self, from: &Address, _to: Option<&Address>, gas_price: U256) -> Decision {
+        if !self.allowed.contains(from) {
+            return Decision::Deny;
+        }
+
+        if gas_price.is_zero() {
+            Decision::AllowServiceTransaction
+        } else {
+            Decision::Allow
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_allow_all_filter_rejects_zero_gas_price_at_decision_level() {
+        let filter = AllowAllFilter;
+        let from = address!("0000000000000000000000000000000000000001");
+        // AllowAllFilter 本身只负责“谁能发交易”，零 gas price 的豁免判断
+        // 交给调用方（validate_transaction）结合 Decision::Allow 来拒绝
+        assert_eq!(filter.is_allowed(&from, None, U256::ZERO), Decision::Allow);
+        assert_eq!(filter.is_allowed(&from, None, U256::from(1)), Decision::Allow);
+    }
+
+    #[test]
+    fn test_whitelist_filter_denies_unknown_sender() {
+        let filter = WhitelistFilter::new();
+        let from = address!("0000000000000000000000000000000000000001");
+        assert_eq!(filter.is_allowed(&from, None, U256::from(1)), Decision::Deny);
+    }
+
+    #[test]
+    fn test_whitelist_filter_allows_service_transaction_for_whitelisted_sender() {
+        let from = address!("0000000000000000000000000000000000000001");
+        let filter = WhitelistFilter::with_addresses([from]);
+
+        assert_eq!(
+            filter.is_allowed(&from, None, U256::ZERO),
+            Decision::AllowServiceTransaction
+        );
+        assert_eq!(
+            filter.is_allowed(&from, None, U256::from(1)),
+            Decision::Allow
+        );
+    }
+}
@@ -1,6 +1,6 @@
 //! 交易收据构建器
 
-use alloy_primitives::{B256, Bytes};
+use alloy_primitives::{B256, U256};
 use crate::schema::{TransactionReceipt, Log, Block, Transaction};
 use crate::executor::ExecutionResult;
 
@@ -9,6 +9,9 @@ pub struct ReceiptBuilder;
 
 impl ReceiptBuilder {
     /// 构建交易收据
+    ///
+    /// `base_fee` 取自区块环境，用于按 [`Transaction::effective_gas_price`]
+    /// 为收据填充实际支付的 gas 单价（EIP-1559 交易不等于 `max_fee_per_gas`）。
     pub fn build(
         tx_hash: B256,
         tx_index: u64,
@@ -16,8 +19,11 @@ impl ReceiptBuilder {
         tx: &Transaction,
         result: &ExecutionResult,
         cumulative_gas_used: u64,
+        base_fee: U256,
     ) -> TransactionReceipt {
-        TransactionReceipt {
+        let logs = Self::convert_logs(&result.logs, tx_hash, tx_index, block.header.number);
+
+        let mut receipt = TransactionReceipt {
             transaction_hash: tx_hash,
             transaction_index: tx_index,
             block_hash: Self::parse_block_hash(&block.hash()),
@@ -28,11 +34,15 @@ impl ReceiptBuilder {
             gas_used: result.gas_used,
             cumulative_gas_used,
             status: if result.success { 1 } else { 0 },
-            logs: Self::convert_logs(&result.logs, tx_hash, tx_index, block.header.number),
-            logs_bloom: Self::compute_logs_bloom(&result.logs),
-        }
+            logs,
+            logs_bloom: crate::schema::Bloom::zero(),
+            transaction_type: tx.tx_type().type_byte().unwrap_or(0),
+            effective_gas_price: tx.effective_gas_price(base_fee),
+        };
+        receipt.logs_bloom = receipt.compute_logs_bloom();
+        receipt
     }
-    
+
     /// 解析区块哈希
     fn parse_block_hash(hash_str: &str) -> B256 {
         let hex = hash_str.trim_start_matches("0x");
@@ -60,11 +70,4 @@ impl ReceiptBuilder {
             })
             .collect()
     }
-    
-    /// 计算 Logs Bloom 过滤器
-    fn compute_logs_bloom(_logs: &[revm::primitives::Log]) -> Bytes {
-        // TODO: 实现完整的 Bloom filter 计算
-        // 当前返回空 bloom
-        Bytes::from(vec![0u8; 256])
-    }
 }
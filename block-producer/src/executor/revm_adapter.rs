@@ -4,60 +4,155 @@
 
 use alloy_primitives::{Address, U256, B256};
 use revm::{
+    precompile::ContextPrecompile,
     primitives::{
-        AccountInfo, Bytecode, BlockEnv, TxEnv, 
+        AccountInfo, Bytecode, BlockEnv, TxEnv,
         ExecutionResult as RevmExecutionResult, Output, SpecId,
     },
     Database, Evm,
 };
 use parking_lot::RwLock;
+use rayon::prelude::*;
 use std::sync::Arc;
-use std::collections::HashMap;
+use lru::LruCache;
+use std::num::NonZeroUsize;
 
 use crate::db::{StateDatabase, RedbStateDB, DbError};
 use crate::schema::account::EMPTY_CODE_HASH;
 use crate::executor::{ExecutorError, ExecutionResult};
+use crate::executor::tracer::{TraceConfig, Tracer};
+use crate::executor::cross_chain_read::{
+    CrossChainReadPrecompile, CrossChainRegistry, CROSS_CHAIN_READ_ADDRESS,
+};
+use crate::executor::state_diff::{AccountDiff, StateDiff};
+use std::collections::BTreeMap;
+
+/// 缓存默认容量（账户/存储/代码各自独立计数，超出后按 LRU 淘汰最久未用的条目）
+const DEFAULT_CACHE_CAPACITY: usize = 10_000;
+
+/// [`CachedRedbState::prefetch`] 的分批大小：每个 rayon 任务只对这么多个
+/// 键取一次 `db` 读锁，在“每个键都加锁”和“整段地址一次性持锁”之间取折中
+const PREFETCH_BATCH_SIZE: usize = 64;
 
 /// 带缓存的 Redb 状态包装器
-/// 
-/// 为 REVM 提供 Database trait 实现，包含内存缓存优化读取性能
+///
+/// 为 REVM 提供 Database trait 实现，用容量受限的 LRU 缓存取代无界 HashMap：
+/// 账户、存储槽、代码各有一个独立的 LRU，跨交易保留热数据，只在
+/// [`RevmAdapter::apply_state_changes`] 中按实际发生变化的键精准失效/更新，
+/// 而不是整体清空。
 pub struct CachedRedbState {
     /// 数据库引用（使用 Arc + RwLock 支持并发访问）
     db: Arc<RwLock<RedbStateDB>>,
-    
-    /// 账户信息缓存（减少数据库访问）
-    cache: RwLock<HashMap<Address, AccountInfo>>,
+
+    /// 账户信息缓存
+    accounts: RwLock<LruCache<Address, AccountInfo>>,
+
+    /// 存储槽缓存
+    storage: RwLock<LruCache<(Address, U256), U256>>,
+
+    /// 合约字节码缓存
+    code: RwLock<LruCache<B256, Bytecode>>,
 }
 
 impl CachedRedbState {
-    /// 创建新的缓存状态
+    /// 创建新的缓存状态（默认容量）
     pub fn new(db: Arc<RwLock<RedbStateDB>>) -> Self {
+        Self::with_capacity(db, DEFAULT_CACHE_CAPACITY)
+    }
+
+    /// 创建指定容量的缓存状态
+    pub fn with_capacity(db: Arc<RwLock<RedbStateDB>>, capacity: usize) -> Self {
+        let cap = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::new(1).unwrap());
         Self {
             db,
-            cache: RwLock::new(HashMap::new()),
+            accounts: RwLock::new(LruCache::new(cap)),
+            storage: RwLock::new(LruCache::new(cap)),
+            code: RwLock::new(LruCache::new(cap)),
         }
     }
-    
-    /// 清除缓存
+
+    /// 清除全部缓存（整体失效；正常执行路径应优先使用下面的精准更新方法）
     pub fn clear_cache(&self) {
-        self.cache.write().clear();
+        self.accounts.write().clear();
+        self.storage.write().clear();
+        self.code.write().clear();
+    }
+
+    /// 账户信息发生变化后原地更新缓存，避免下一笔交易重新从数据库加载
+    pub fn update_account(&self, address: Address, info: AccountInfo) {
+        self.accounts.write().put(address, info);
+    }
+
+    /// 账户被销毁后从缓存中移除
+    pub fn invalidate_account(&self, address: Address) {
+        self.accounts.write().pop(&address);
+    }
+
+    /// 存储槽值发生变化后原地更新缓存
+    pub fn update_storage(&self, address: Address, slot: U256, value: U256) {
+        self.storage.write().put((address, slot), value);
+    }
+
+    /// 新部署的合约字节码写入数据库后同步进缓存
+    pub fn update_code(&self, code_hash: B256, code: Bytecode) {
+        self.code.write().put(code_hash, code);
+    }
+
+    /// 并行预取一批账户和存储槽，在 `transact` 之前热身缓存
+    ///
+    /// 把 `addresses`/`slots` 按 [`PREFETCH_BATCH_SIZE`] 切成小块，交给 rayon
+    /// 线程池并发处理；每个任务只对 `db` 取一次读锁，批量把结果写回对应的
+    /// LRU 缓存。单个键查询失败不影响其他键，也不影响后续执行——正常的
+    /// `basic`/`storage` 路径会在缓存未命中时照常回源，预取只是尽力而为的
+    /// 优化，不是正确性前提。
+    pub fn prefetch(&self, addresses: &[Address], slots: &[(Address, U256)]) {
+        addresses.par_chunks(PREFETCH_BATCH_SIZE).for_each(|batch| {
+            let db = self.db.read();
+            for address in batch {
+                let info = match db.get_account(address) {
+                    Ok(Some(acc)) => AccountInfo {
+                        balance: acc.balance,
+                        nonce: acc.nonce,
+                        code_hash: acc.code_hash,
+                        code: None,
+                    },
+                    Ok(None) => AccountInfo {
+                        balance: U256::ZERO,
+                        nonce: 0,
+                        code_hash: EMPTY_CODE_HASH,
+                        code: None,
+                    },
+                    Err(_) => continue,
+                };
+                self.accounts.write().put(*address, info);
+            }
+        });
+
+        slots.par_chunks(PREFETCH_BATCH_SIZE).for_each(|batch| {
+            let db = self.db.read();
+            for (address, slot) in batch {
+                if let Ok(value) = db.get_storage(address, *slot) {
+                    self.storage.write().put((*address, *slot), value);
+                }
+            }
+        });
     }
 }
 
 impl Database for CachedRedbState {
     type Error = DbError;
-    
+
     /// 获取账户基本信息
     fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
         // 1. 检查缓存
-        if let Some(info) = self.cache.read().get(&address) {
+        if let Some(info) = self.accounts.write().get(&address) {
             return Ok(Some(info.clone()));
         }
-        
+
         // 2. 从数据库读取
         let db = self.db.read();
         let account = db.get_account(&address)?;
-        
+
         // 3. 构建账户信息（如果不存在则返回默认空账户）
         let info = if let Some(acc) = account {
             AccountInfo {
@@ -75,34 +170,46 @@ impl Database for CachedRedbState {
                 code: None,
             }
         };
-        
+
         // 4. 更新缓存
-        self.cache.write().insert(address, info.clone());
-        
+        self.accounts.write().put(address, info.clone());
+
         Ok(Some(info))
     }
-    
+
     /// 根据哈希获取合约字节码
     fn code_by_hash(&mut self, code_hash: B256) -> Result<Bytecode, Self::Error> {
         // 空代码哈希直接返回空字节码
         if code_hash == EMPTY_CODE_HASH {
             return Ok(Bytecode::new());
         }
-        
+
+        if let Some(code) = self.code.write().get(&code_hash) {
+            return Ok(code.clone());
+        }
+
         let db = self.db.read();
         let code = db.get_code(&code_hash)?
             .ok_or_else(|| DbError::CodeNotFound(code_hash))?;
-        
+
         // 将字节码转换为 REVM 的 Bytecode 类型
-        Ok(Bytecode::new_raw(code))
+        let bytecode = Bytecode::new_raw(code);
+        self.code.write().put(code_hash, bytecode.clone());
+        Ok(bytecode)
     }
-    
+
     /// 获取存储槽值
     fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        if let Some(value) = self.storage.write().get(&(address, index)) {
+            return Ok(*value);
+        }
+
         let db = self.db.read();
-        db.get_storage(&address, index)
+        let value = db.get_storage(&address, index)?;
+        self.storage.write().put((address, index), value);
+        Ok(value)
     }
-    
+
     /// 获取区块哈希
     fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
         let db = self.db.read();
@@ -112,34 +219,76 @@ impl Database for CachedRedbState {
 }
 
 /// REVM 适配器
-/// 
+///
 /// 封装 EVM 执行引擎，提供交易执行接口
 pub struct RevmAdapter {
     /// 数据库引用
     db: Arc<RwLock<RedbStateDB>>,
-    
+
+    /// 按 chainId 索引的次要状态库，供跨链读取预编译使用；
+    /// 未调用 [`RevmAdapter::with_cross_chain_reads`] 时为空注册表
+    cross_chain: Arc<CrossChainRegistry>,
+
     /// EVM 实例（使用 CachedRedbState 作为数据库后端）
     evm: Evm<'static, (), CachedRedbState>,
 }
 
 impl RevmAdapter {
-    /// 创建新的适配器
+    /// 创建新的适配器（不注册任何跨链状态库）
     pub fn new(db: RedbStateDB) -> Self {
+        Self::with_cross_chain_reads(db, CrossChainRegistry::new())
+    }
+
+    /// 创建适配器，并注册一组按 chainId 索引的次要 `RedbStateDB`
+    ///
+    /// 注册后，合约代码可以在执行期间调用保留地址
+    /// [`CROSS_CHAIN_READ_ADDRESS`] 上的预编译合约，读取这些次要库中的
+    /// 账户余额、nonce、代码哈希与任意存储槽（见 [`cross_chain_read`] 模块），
+    /// 从而实现 rollup/booster 式的跨链状态观察。
+    ///
+    /// [`cross_chain_read`]: crate::executor::cross_chain_read
+    pub fn with_cross_chain_reads(db: RedbStateDB, cross_chain: CrossChainRegistry) -> Self {
         let db_arc = Arc::new(RwLock::new(db));
+        let cross_chain = Arc::new(cross_chain);
         let cached_state = CachedRedbState::new(Arc::clone(&db_arc));
-        
+
         // 构建 EVM 实例 - 使用 Shanghai 规范避免 EIP-3607
         let evm = Evm::builder()
             .with_db(cached_state)
             .with_spec_id(SpecId::SHANGHAI) // 使用 Shanghai 规范（在 EIP-3607 之前）
+            .append_handler_register(Self::register_cross_chain_precompile(Arc::clone(&cross_chain)))
             .build();
-        
+
         Self {
             db: db_arc,
+            cross_chain,
             evm,
         }
     }
-    
+
+    /// 构造一个 `append_handler_register` 钩子，把跨链读取预编译挂载到
+    /// [`CROSS_CHAIN_READ_ADDRESS`]，其余地址的预编译装载逻辑保持不变。
+    /// 泛型于外部上下文 `EXT`，这样普通执行（`EXT = ()`）和带
+    /// [`Tracer`] 的追踪执行（`EXT = Tracer`）可以共用同一个钩子。
+    fn register_cross_chain_precompile<EXT: 'static>(
+        registry: Arc<CrossChainRegistry>,
+    ) -> impl Fn(&mut revm::Handler<'static, EXT, CachedRedbState>) {
+        move |handler| {
+            let registry = Arc::clone(&registry);
+            let prev = handler.pre_execution.load_precompiles.clone();
+            handler.pre_execution.load_precompiles = Arc::new(move || {
+                let mut precompiles = prev();
+                precompiles.extend([(
+                    CROSS_CHAIN_READ_ADDRESS,
+                    ContextPrecompile::ContextStateful(Arc::new(CrossChainReadPrecompile::new(
+                        Arc::clone(&registry),
+                    ))),
+                )]);
+                precompiles
+            });
+        }
+    }
+
     /// 执行交易
     /// 
     /// 将交易数据转换为 REVM TxEnv，执行后返回结果
@@ -148,26 +297,179 @@ impl RevmAdapter {
         tx_env: TxEnv,
         block_env: BlockEnv,
     ) -> Result<ExecutionResult, ExecutorError> {
+        // 若交易带 EIP-2930 访问列表，先并行预取列出的账户/存储槽，
+        // 把冷状态的串行 redb 读取摊开成少数几批并发读取
+        self.prefetch_access_list(&tx_env);
+
         // 设置环境
         self.evm.context.evm.env.block = block_env;
         self.evm.context.evm.env.tx = tx_env;
-        
+
         // 执行交易
         let result_and_state = self.evm.transact()
             .map_err(|e| ExecutorError::Evm(format!("{:?}", e)))?;
         
-        // 应用状态变更到数据库
+        // 应用状态变更到数据库（同时在 apply_state_changes 内按键精准更新缓存，
+        // 确保下次交易读取到最新状态，而不必像之前那样整体清空缓存）
         self.apply_state_changes(&result_and_state)?;
-        
-        // 清除缓存，确保下次交易读取到最新状态（特别是 nonce）
-        self.evm.context.evm.db.clear_cache();
-        
+
         // 转换执行结果
         self.convert_result(result_and_state.result)
     }
-    
+
+    /// 执行交易并附带结构化的状态差异
+    ///
+    /// 语义与 [`RevmAdapter::execute`] 完全一致，只是在提交状态变更之前，
+    /// 先把每个被触达账户的"前"状态和 `ResultAndState` 里的"后"状态打包成
+    /// [`StateDiff`]。这是选择性加入的开销——默认的 `execute` 路径不付这笔
+    /// 账，只有明确需要 diff 的调用方（比如调试器、区块浏览器索引器）才用
+    /// 这个入口。
+    pub fn execute_with_diff(
+        &mut self,
+        tx_env: TxEnv,
+        block_env: BlockEnv,
+    ) -> Result<(ExecutionResult, StateDiff), ExecutorError> {
+        self.prefetch_access_list(&tx_env);
+
+        self.evm.context.evm.env.block = block_env;
+        self.evm.context.evm.env.tx = tx_env;
+
+        let result_and_state = self.evm.transact()
+            .map_err(|e| ExecutorError::Evm(format!("{:?}", e)))?;
+
+        // 在提交之前构建 diff："前"状态这时候仍是执行前的值
+        let diff = self.build_state_diff(&result_and_state);
+
+        self.apply_state_changes(&result_and_state)?;
+
+        let exec_result = self.convert_result(result_and_state.result)?;
+        Ok((exec_result, diff))
+    }
+
+    /// 带追踪地执行交易
+    ///
+    /// 使用独立的、挂载了 [`Tracer`] Inspector 的 EVM 实例执行，
+    /// 以免给常规热路径（[`RevmAdapter::execute`]）带来开销。
+    /// 执行语义与普通执行完全一致，仅额外在结果上附带 `struct_logs`。
+    pub fn execute_with_trace(
+        &mut self,
+        tx_env: TxEnv,
+        block_env: BlockEnv,
+        config: TraceConfig,
+    ) -> Result<ExecutionResult, ExecutorError> {
+        let cached_state = CachedRedbState::new(Arc::clone(&self.db));
+
+        let mut evm = Evm::builder()
+            .with_db(cached_state)
+            .with_external_context(Tracer::new(config))
+            .with_spec_id(SpecId::SHANGHAI)
+            .append_handler_register(revm::inspector_handle_register)
+            .append_handler_register(Self::register_cross_chain_precompile(Arc::clone(
+                &self.cross_chain,
+            )))
+            .build();
+
+        evm.context.evm.env.block = block_env;
+        evm.context.evm.env.tx = tx_env;
+
+        let result_and_state = evm
+            .transact()
+            .map_err(|e| ExecutorError::Evm(format!("{:?}", e)))?;
+
+        self.apply_state_changes(&result_and_state)?;
+
+        // 取回 Inspector 中累积的轨迹
+        let struct_logs = evm.into_context().external.into_logs();
+
+        let mut result = self.convert_result(result_and_state.result)?;
+        result.struct_logs = Some(struct_logs);
+        Ok(result)
+    }
+
+    /// 根据交易的 EIP-2930 访问列表并行预取账户与存储槽
+    ///
+    /// 没有访问列表（或列表为空）时什么都不做，正常的按需读取路径不受影响。
+    fn prefetch_access_list(&self, tx_env: &TxEnv) {
+        if tx_env.access_list.is_empty() {
+            return;
+        }
+
+        let addresses: Vec<Address> = tx_env
+            .access_list
+            .iter()
+            .map(|(address, _)| *address)
+            .collect();
+
+        let slots: Vec<(Address, U256)> = tx_env
+            .access_list
+            .iter()
+            .flat_map(|(address, keys)| keys.iter().map(move |key| (*address, *key)))
+            .collect();
+
+        self.evm.context.evm.db.prefetch(&addresses, &slots);
+    }
+
+    /// 构建本次交易的状态差异
+    ///
+    /// 必须在 [`RevmAdapter::apply_state_changes`] 之前调用：此时缓存/数据库
+    /// 仍保留执行前的值，`Database::basic` 的读取天然就是"前"状态快照。
+    fn build_state_diff(
+        &mut self,
+        result: &revm::primitives::result::ResultAndState,
+    ) -> StateDiff {
+        let mut diff = StateDiff::default();
+
+        for (address, account) in &result.state {
+            let before = Database::basic(&mut self.evm.context.evm.db, *address)
+                .ok()
+                .flatten()
+                .unwrap_or(AccountInfo {
+                    balance: U256::ZERO,
+                    nonce: 0,
+                    code_hash: EMPTY_CODE_HASH,
+                    code: None,
+                });
+
+            let was_empty = before.balance.is_zero()
+                && before.nonce == 0
+                && before.code_hash == EMPTY_CODE_HASH;
+            let is_empty = account.info.balance.is_zero()
+                && account.info.nonce == 0
+                && account.info.code_hash == EMPTY_CODE_HASH;
+            let created = was_empty && !is_empty;
+
+            let mut changed_storage = BTreeMap::new();
+            for (slot, value) in &account.storage {
+                if value.is_changed() {
+                    changed_storage.insert(
+                        *slot,
+                        (value.previous_or_original_value, value.present_value()),
+                    );
+                }
+            }
+
+            diff.accounts.insert(
+                *address,
+                AccountDiff {
+                    address: *address,
+                    balance_before: before.balance,
+                    balance_after: account.info.balance,
+                    nonce_before: before.nonce,
+                    nonce_after: account.info.nonce,
+                    code_hash_before: before.code_hash,
+                    code_hash_after: account.info.code_hash,
+                    changed_storage,
+                    created,
+                    self_destructed: account.is_selfdestructed(),
+                },
+            );
+        }
+
+        diff
+    }
+
     /// 应用状态变更到数据库
-    /// 
+    ///
     /// 将 REVM 的状态变更（BundleState）写入 RedbStateDB
     fn apply_state_changes(
         &mut self,
@@ -176,11 +478,13 @@ impl RevmAdapter {
         let mut db = self.db.write();
         
         // 遍历状态变更
+        let cache = &self.evm.context.evm.db;
         for (address, account) in &result.state {
             if account.is_selfdestructed() {
                 // 账户被销毁
                 db.delete_account(address)
                     .map_err(|e| ExecutorError::Database(e.to_string()))?;
+                cache.invalidate_account(*address);
             } else if account.is_touched() {
                 // 账户信息变更
                 let info = &account.info;
@@ -188,10 +492,11 @@ impl RevmAdapter {
                 acc.balance = info.balance;
                 acc.nonce = info.nonce;
                 acc.code_hash = info.code_hash;
-                
+
                 db.set_account(address, acc)
                     .map_err(|e| ExecutorError::Database(e.to_string()))?;
-                
+                cache.update_account(*address, info.clone());
+
                 // 存储合约字节码（REVM 12 关键逻辑）
                 // 当 account.info.code 有值且 code_hash 有效时，需要持久化字节码
                 if let Some(ref code) = info.code {
@@ -199,19 +504,21 @@ impl RevmAdapter {
                     if info.code_hash != EMPTY_CODE_HASH && !code.is_empty() {
                         db.set_code(info.code_hash, code.bytes().clone())
                             .map_err(|e| ExecutorError::Database(e.to_string()))?;
+                        cache.update_code(info.code_hash, code.clone());
                     }
                 }
-                
+
                 // 存储槽变更
                 for (slot, value) in &account.storage {
                     if value.is_changed() {
                         db.set_storage(address, *slot, value.present_value())
                             .map_err(|e| ExecutorError::Database(e.to_string()))?;
+                        cache.update_storage(*address, *slot, value.present_value());
                     }
                 }
             }
         }
-        
+
         Ok(())
     }
     
@@ -234,6 +541,9 @@ impl RevmAdapter {
                     contract_address,
                     gas_refund: gas_refunded,
                     logs,
+                    struct_logs: None,
+                    // 由 `TransactionExecutor::apply_pricing` 按计价策略填充
+                    fee_charged: U256::ZERO,
                 })
             }
             RevmExecutionResult::Revert { output, gas_used } => {
@@ -244,6 +554,8 @@ impl RevmAdapter {
                     contract_address: None,
                     gas_refund: 0,
                     logs: Vec::new(),
+                    struct_logs: None,
+                    fee_charged: U256::ZERO,
                 })
             }
             RevmExecutionResult::Halt { reason, gas_used } => {
@@ -252,6 +564,36 @@ impl RevmAdapter {
         }
     }
     
+    /// 直接从账户余额中扣减 `amount`，同时更新数据库与内部缓存
+    ///
+    /// 供 [`crate::executor::transaction::GasPricingPolicy::FixedCost`] 这类绕开 REVM
+    /// 自身 `gas_used * gas_price` 计费模型的场景使用：`transact` 本身不会为这笔交易
+    /// 扣任何 gas 费用（调用方需要提前把 `TxEnv::gas_price` 置零），真正的固定费用
+    /// 由这里在执行结果落盘之后单独结算。直接写 `db` 会让缓存失效，所以这里和
+    /// [`Self::apply_state_changes`] 一样，在写库的同时把缓存也一并更新。
+    pub fn debit_balance(&mut self, address: Address, amount: U256) -> Result<(), ExecutorError> {
+        let mut account = {
+            let db = self.db.read();
+            db.get_account(&address)
+                .map_err(|e| ExecutorError::Database(e.to_string()))?
+                .unwrap_or_default()
+        };
+        account.balance = account.balance.saturating_sub(amount);
+
+        self.db.write()
+            .set_account(&address, account.clone())
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
+        self.evm.context.evm.db.update_account(address, AccountInfo {
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: account.code_hash,
+            code: None,
+        });
+
+        Ok(())
+    }
+
     /// 获取内部数据库的可变引用
     pub fn db_mut(&mut self) -> &mut RedbStateDB {
         // 注意：这里需要临时获取锁，返回可变引用会有生命周期问题
@@ -0,0 +1,42 @@
+//! 交易级状态差异（state diff）
+//!
+//! [`RevmAdapter::execute_with_diff`](crate::executor::RevmAdapter::execute_with_diff)
+//! 在一笔交易提交前后各取一次快照，把被触达账户的余额/nonce/代码哈希变化、
+//! 改动过的存储槽（旧值 -> 新值），以及创建/自毁标志打包成 [`StateDiff`]，
+//! 供调试器、区块浏览器这类需要逐笔分析状态变化的工具使用。普通的
+//! [`RevmAdapter::execute`](crate::executor::RevmAdapter::execute) 不收集这些
+//! 信息，调用方不需要为此多付开销。
+
+use alloy_primitives::{Address, B256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 单个账户在一笔交易前后的状态对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountDiff {
+    pub address: Address,
+
+    pub balance_before: U256,
+    pub balance_after: U256,
+
+    pub nonce_before: u64,
+    pub nonce_after: u64,
+
+    pub code_hash_before: B256,
+    pub code_hash_after: B256,
+
+    /// 改动过的存储槽：`slot -> (旧值, 新值)`
+    pub changed_storage: BTreeMap<U256, (U256, U256)>,
+
+    /// 这笔交易中新创建的账户（执行前是空账户，执行后不再是）
+    pub created: bool,
+
+    /// 这笔交易中被 `SELFDESTRUCT` 的账户
+    pub self_destructed: bool,
+}
+
+/// 一笔交易的状态差异，按地址索引，只包含被实际触达的账户
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StateDiff {
+    pub accounts: BTreeMap<Address, AccountDiff>,
+}
@@ -0,0 +1,156 @@
+//! EVM 执行追踪
+//!
+//! 基于 REVM 的 [`Inspector`] 钩子，逐操作码采集执行轨迹
+//! （程序计数器、操作码、剩余 gas、gas 消耗、调用深度，以及可选的
+//! 栈 / 内存 / 存储快照），汇聚为 `StructLog` 序列，
+//! 用于 `debug_traceTransaction` 调试 revert 与 gas 消耗。
+//!
+//! 追踪默认关闭，以保证正常执行路径的开销；开启后也不会改变执行语义。
+
+use revm::interpreter::{Interpreter, OpCode};
+use revm::{Database, EvmContext, Inspector};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 单步执行日志（对齐 geth `StructLog` 结构）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructLog {
+    /// 程序计数器
+    pub pc: u64,
+    /// 操作码助记符
+    pub op: String,
+    /// 该步执行前的剩余 gas
+    pub gas: u64,
+    /// 该操作码消耗的 gas
+    pub gas_cost: u64,
+    /// 调用栈深度
+    pub depth: u64,
+    /// 栈快照（自底向上，十六进制），`disable_stack` 时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stack: Option<Vec<String>>,
+    /// 内存快照（按 32 字节分词），`disable_memory` 时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<Vec<String>>,
+    /// 本帧已写入（SSTORE）的存储槽及其新值，SLOAD 读取的槽位不记录；
+    /// `disable_storage` 时为 None
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage: Option<BTreeMap<String, String>>,
+}
+
+/// 追踪开关
+///
+/// 通过关闭栈 / 内存 / 存储的采集来约束输出体积。
+#[derive(Debug, Clone, Copy)]
+pub struct TraceConfig {
+    pub disable_stack: bool,
+    pub disable_memory: bool,
+    pub disable_storage: bool,
+}
+
+impl Default for TraceConfig {
+    fn default() -> Self {
+        // 默认采集栈，但关闭内存/存储，避免轨迹过大
+        Self {
+            disable_stack: false,
+            disable_memory: true,
+            disable_storage: true,
+        }
+    }
+}
+
+/// REVM Inspector：逐步采集 `StructLog`
+pub struct Tracer {
+    config: TraceConfig,
+    logs: Vec<StructLog>,
+    /// 每帧存储写入缓存（depth -> {slot -> value}），由 SSTORE 填充
+    storage: BTreeMap<u64, BTreeMap<String, String>>,
+}
+
+impl Tracer {
+    /// 创建追踪器
+    pub fn new(config: TraceConfig) -> Self {
+        Self {
+            config,
+            logs: Vec::new(),
+            storage: BTreeMap::new(),
+        }
+    }
+
+    /// 取出采集到的轨迹
+    pub fn into_logs(self) -> Vec<StructLog> {
+        self.logs
+    }
+}
+
+impl<DB: Database> Inspector<DB> for Tracer {
+    fn step(&mut self, interp: &mut Interpreter, context: &mut EvmContext<DB>) {
+        let pc = interp.program_counter() as u64;
+        let opcode = interp.current_opcode();
+        let op = OpCode::new(opcode)
+            .map(|o| o.as_str().to_string())
+            .unwrap_or_else(|| format!("UNKNOWN(0x{:02x})", opcode));
+        let gas = interp.gas().remaining();
+        let depth = context.journaled_state.depth();
+
+        let stack = if self.config.disable_stack {
+            None
+        } else {
+            Some(
+                interp
+                    .stack()
+                    .data()
+                    .iter()
+                    .map(|v| format!("0x{:x}", v))
+                    .collect(),
+            )
+        };
+
+        let memory = if self.config.disable_memory {
+            None
+        } else {
+            Some(
+                interp
+                    .shared_memory
+                    .context_memory()
+                    .chunks(32)
+                    .map(hex::encode)
+                    .collect(),
+            )
+        };
+
+        // SSTORE 执行前栈顶依次是槽位和新值，先记入当前帧，使本步快照已包含这次写入
+        if !self.config.disable_storage && opcode == revm::interpreter::opcode::SSTORE {
+            if let (Ok(slot), Ok(value)) = (interp.stack().peek(0), interp.stack().peek(1)) {
+                self.storage.entry(depth).or_default().insert(
+                    hex::encode(slot.to_be_bytes::<32>()),
+                    hex::encode(value.to_be_bytes::<32>()),
+                );
+            }
+        }
+
+        let storage = if self.config.disable_storage {
+            None
+        } else {
+            Some(self.storage.entry(depth).or_default().clone())
+        };
+
+        self.logs.push(StructLog {
+            pc,
+            op,
+            gas,
+            gas_cost: 0, // 在 step_end 回填
+            depth,
+            stack,
+            memory,
+            storage,
+        });
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, _context: &mut EvmContext<DB>) {
+        // 用步前后剩余 gas 的差值回填上一条日志的 gas_cost
+        if let Some(last) = self.logs.last_mut() {
+            let remaining = interp.gas().remaining();
+            last.gas_cost = last.gas.saturating_sub(remaining);
+        }
+    }
+}
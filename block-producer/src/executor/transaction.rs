@@ -4,9 +4,10 @@
 
 use alloy_primitives::{U256, Address, Bytes};
 use revm::primitives::{BlockEnv, TxEnv, TransactTo};
-use crate::db::{RedbStateDB, StateDatabase};
+use crate::db::{RedbStateDB, StateDatabase, DbError};
 use crate::executor::{ExecutorError, RevmAdapter};
-use crate::schema::Transaction;
+use crate::executor::permission::{TransactionFilter, Decision, AllowAllFilter};
+use crate::schema::{Transaction, TxType};
 use serde::{Deserialize, Serialize};
 
 /// 交易执行结果
@@ -26,49 +27,304 @@ pub struct ExecutionResult {
     
     /// Gas 退款
     pub gas_refund: u64,
-    
+
     /// 事件日志
     pub logs: Vec<revm::primitives::Log>,
+
+    /// 逐操作码执行轨迹（仅在开启追踪时填充）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub struct_logs: Option<Vec<crate::executor::tracer::StructLog>>,
+
+    /// 本次执行按 [`GasPricingPolicy`] 实际从发送方扣除的 wei 数额
+    ///
+    /// 计量模式下等于 `gas_used * effective_gas_price`（与 REVM 自己的扣费一致，
+    /// 这里只是把它透出来）；固定成本模式下等于配置的 `fee_wei`，与 `gas_used`
+    /// 无关。调用方（尤其是测试里的余额断言）应该按这个字段而不是重新假设
+    /// 计量模型来计算预期扣费。
+    #[serde(default)]
+    pub fee_charged: U256,
+}
+
+/// 链参数配置
+///
+/// 用于在验证阶段强制 EIP-155 重放保护：交易声明的 `chain_id` 必须与本执行器
+/// 所服务的链一致，防止在别的链上广播时被重放到这条链上。
+#[derive(Debug, Clone, Copy)]
+pub struct ChainConfig {
+    /// 本链的 chain id
+    pub chain_id: u64,
+
+    /// 是否放行未携带 `chain_id` 的交易（视为 EIP-155 之前的 legacy 交易）
+    pub allow_legacy_no_chain_id: bool,
+
+    /// EIP-3607 是否已激活：激活后拒绝由合约账户（有代码）发起的交易
+    ///
+    /// 这是一条共识规则而非无条件行为——不同链可能在不同高度/从未启用它，
+    /// 因此做成每条链可配置的激活开关，而不是硬编码成永远生效。
+    pub eip3607_active: bool,
+
+    /// 是否强制要求交易携带签名（`v`/`r`/`s`）并通过 `verify_and_recover_sender`
+    ///
+    /// 默认开启：未签名交易一律拒绝，调用方不能靠自报的 `from` 字段伪造任意
+    /// 账户发起交易。关闭后退化为"受信任本地签名者"模式——只在离线脚本/
+    /// 单测里构造未签名交易验证签名之外的其它校验规则时才应该关闭，
+    /// `rpc-gateway` 对外的 `ExecutionEngine` 永远使用默认值（`true`），
+    /// 不会给公开 RPC 入口留这个后门。
+    pub require_signature: bool,
+}
+
+impl Default for ChainConfig {
+    fn default() -> Self {
+        Self {
+            chain_id: 1,
+            allow_legacy_no_chain_id: true,
+            eip3607_active: true,
+            require_signature: true,
+        }
+    }
+}
+
+/// Gas 计价策略
+///
+/// 默认按 EVM 操作码实际消耗的 gas 计价。许可链/应用链部署方往往不想把底层
+/// EVM gas 细节暴露给用户，而是希望每笔交易收取同样一笔可预测的费用，这时
+/// 可以选用 `FixedCost`：不论 opcode 实际消耗多少 gas，都只收取配置的
+/// `fee_wei`。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasPricingPolicy {
+    /// 按 `gas_used * effective_gas_price` 计费（默认行为）
+    Metered,
+
+    /// 不论实际 gas 消耗，每笔交易固定收取 `fee_wei`
+    FixedCost {
+        /// 固定收取的费用，单位 wei
+        fee_wei: U256,
+    },
+}
+
+impl Default for GasPricingPolicy {
+    fn default() -> Self {
+        GasPricingPolicy::Metered
+    }
+}
+
+/// Gas 计价配置
+///
+/// 组合计价策略与一个可选的 gas 上限覆盖：设置了 `gas_limit_ceiling` 时，
+/// 不论交易自己声明了多大的 `gas_limit`，送入 EVM 执行的上限都不会超过这个
+/// 值。常与 [`GasPricingPolicy::FixedCost`] 搭配——费用已经固定了，仍要
+/// 限制单笔交易能占用多少计算资源，避免被当成免费的长时间执行。
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GasPricingConfig {
+    /// 计价策略
+    pub policy: GasPricingPolicy,
+
+    /// 可选的 gas 上限覆盖，覆盖交易自己声明的 `gas_limit`
+    pub gas_limit_ceiling: Option<u64>,
 }
 
 /// 交易执行器
 pub struct TransactionExecutor {
     adapter: RevmAdapter,
+    chain_config: ChainConfig,
+    filter: Box<dyn TransactionFilter>,
+    pricing: GasPricingConfig,
 }
 
 impl TransactionExecutor {
-    /// 创建交易执行器
+    /// 创建交易执行器（使用默认链参数，不做任何准入限制）
     pub fn new(db: RedbStateDB) -> Self {
+        Self::with_chain_config(db, ChainConfig::default())
+    }
+
+    /// 创建交易执行器并指定链参数（不做任何准入限制）
+    pub fn with_chain_config(db: RedbStateDB, chain_config: ChainConfig) -> Self {
+        Self::with_chain_config_and_filter(db, chain_config, Box::new(AllowAllFilter))
+    }
+
+    /// 创建交易执行器并指定链参数与准入过滤器（使用默认的计量 gas 计价策略）
+    ///
+    /// `filter` 在余额/nonce 检查之前被询问：是否允许 `from` 向 `to` 发送这笔交易。
+    /// 运行许可链的部署方可以传入 [`crate::executor::permission::WhitelistFilter`]，
+    /// 只放行白名单地址，并为其豁免 `gas_price == 0` 的“服务交易”gas 计费。
+    pub fn with_chain_config_and_filter(
+        db: RedbStateDB,
+        chain_config: ChainConfig,
+        filter: Box<dyn TransactionFilter>,
+    ) -> Self {
+        Self::with_chain_config_filter_and_pricing(
+            db,
+            chain_config,
+            filter,
+            GasPricingConfig::default(),
+        )
+    }
+
+    /// 创建交易执行器并指定 gas 计价策略（使用默认链参数，不做准入限制）
+    pub fn with_gas_pricing(db: RedbStateDB, pricing: GasPricingConfig) -> Self {
+        Self::with_chain_config_filter_and_pricing(
+            db,
+            ChainConfig::default(),
+            Box::new(AllowAllFilter),
+            pricing,
+        )
+    }
+
+    /// 创建交易执行器并指定链参数、准入过滤器与 gas 计价策略
+    ///
+    /// 这是最完整的构造函数，其余 `with_*` 构造函数都是它在某些参数上取默认值
+    /// 的简便写法。
+    pub fn with_chain_config_filter_and_pricing(
+        db: RedbStateDB,
+        chain_config: ChainConfig,
+        filter: Box<dyn TransactionFilter>,
+        pricing: GasPricingConfig,
+    ) -> Self {
         Self {
             adapter: RevmAdapter::new(db),
+            chain_config,
+            filter,
+            pricing,
         }
     }
-    
+
     /// 验证交易
-    /// 
+    ///
     /// 在执行前进行预验证,确保交易有效
-    /// 
+    ///
     /// # 验证项
-    /// - Gas limit 非零
+    /// - Gas limit 非零，且足以覆盖 EIP-2930 access list 的固有 gas（见 [`Transaction::access_list_gas`]）
+    /// - EIP-2718: 不能同时携带 legacy `gas_price` 和 1559 费用字段（信封类型必须唯一）
+    /// - EIP-1559: `max_fee_per_gas` 不低于区块 base fee
+    /// - EIP-155: `chain_id` 必须匹配本链（或按配置放行缺省 chain_id 的 legacy 交易）
+    /// - 签名：除非显式开启 `ChainConfig::require_signature = false`（受信任本地签名者模式），
+    ///   否则交易必须携带签名并能恢复出与声明 `from` 一致的发送方
+    /// - EIP-3607: 发送方不能是合约账户（必须是 EOA，受 `ChainConfig::eip3607_active` 激活开关控制）
+    /// - 准入过滤：由 [`TransactionFilter`] 决定是否允许该发送方提交交易
+    ///   （`gas_price == 0` 默认被拒绝，除非过滤器将其认定为白名单服务交易）
     /// - Nonce 有效性
-    /// - 账户余额充足
-    pub fn validate_transaction(&mut self, tx: &Transaction) -> Result<(), ExecutorError> {
+    /// - 账户余额充足（服务交易不计入 gas 费用）
+    pub fn validate_transaction(&mut self, tx: &Transaction, base_fee: U256) -> Result<(), ExecutorError> {
         // 1. Gas limit 非零检查
         let gas_limit = tx.gas_limit()
             .map_err(|e| ExecutorError::Transaction(e))?;
-        
+
         if gas_limit == 0 {
             return Err(ExecutorError::InvalidGas);
         }
-        
+
+        // 1.0a EIP-2930: access list 的固有 gas（每个地址 2400 + 每个存储槽 1900，
+        // 见 `Transaction::access_list_gas`）必须能被 `gas_limit` 覆盖，否则这笔
+        // 交易连预热列出的槽位都做不到，不应该被放行去占用执行资源
+        if gas_limit < tx.access_list_gas() {
+            return Err(ExecutorError::InvalidGas);
+        }
+
+        // 1.0 EIP-2718: legacy 的 `gas_price` 和 1559 的 `max_fee_per_gas`/
+        // `max_priority_fee_per_gas` 不能同时出现——`tx_type()` 按字段存在性推断信封
+        // 类型，两套字段都给出说明上游拼装错了信封，而不是单纯"多给一份以防万一"
+        if tx.gas_price.is_some()
+            && (tx.max_fee_per_gas.is_some() || tx.max_priority_fee_per_gas.is_some())
+        {
+            return Err(ExecutorError::TxEnvelopeFieldConflict(
+                "transaction must not carry both legacy gas_price and EIP-1559 fee fields".to_string(),
+            ));
+        }
+
+        // 1.1 EIP-1559: 承诺的 max_fee_per_gas 必须覆盖当前区块的 base fee，
+        // 否则发送方连 base fee 都付不起，交易根本不应被打包
+        if tx.tx_type() == TxType::Eip1559 {
+            let max_fee = tx.max_fee_value();
+            if max_fee < base_fee {
+                return Err(ExecutorError::FeeCapTooLow {
+                    max_fee: format!("{}", max_fee),
+                    base_fee: format!("{}", base_fee),
+                });
+            }
+        }
+
+        // 1.2 EIP-155: chain_id 必须匹配本链，否则拒绝（防止跨链重放）；
+        // 未携带 chain_id 的交易视为 EIP-155 之前的 legacy 交易，仅在配置允许时放行
+        match tx.chain_id {
+            Some(id) if id != self.chain_config.chain_id => {
+                return Err(ExecutorError::InvalidChainId {
+                    expected: self.chain_config.chain_id,
+                    got: Some(id),
+                });
+            }
+            None if !self.chain_config.allow_legacy_no_chain_id => {
+                return Err(ExecutorError::InvalidChainId {
+                    expected: self.chain_config.chain_id,
+                    got: None,
+                });
+            }
+            _ => {}
+        }
+
+        // 1.5 恢复发送方并与声明的 `from` 比对，拒绝跨链重放/可延展的签名。
+        // `require_signature` 默认开启时，未签名交易（`tx.v` 缺失）同样会被
+        // `verify_and_recover_sender` 拒绝（`SigningError::MissingSignature`），
+        // 不允许靠自报的 `from` 字段伪造发送方；只有显式关闭 `require_signature`
+        // 的受信任本地签名者模式才会对未签名交易放行。
+        if tx.v.is_some() || self.chain_config.require_signature {
+            crate::signing::verify_and_recover_sender(tx).map_err(|e| match e {
+                crate::signing::SigningError::SenderMismatch { recovered, declared } => {
+                    ExecutorError::SenderMismatch {
+                        recovered: format!("{}", recovered),
+                        declared: format!("{}", declared),
+                    }
+                }
+                crate::signing::SigningError::Transaction(detail) => {
+                    ExecutorError::Transaction(detail)
+                }
+                other => ExecutorError::InvalidSignature(other.to_string()),
+            })?;
+        }
+
         // 2. 获取账户信息
         let from_addr = tx.from_address()
             .map_err(|e| ExecutorError::Transaction(e))?;
         
+        // 反序列化失败说明落盘的账户数据本身已损坏，而不是普通的读失败，
+        // 需要单独归类为 `StateCorrupt` 以便 `execute_block` 将其当作致命错误处理
         let account = self.adapter.db_mut()
             .get_account(&from_addr)
-            .map_err(|e| ExecutorError::Database(e.to_string()))?;
-        
+            .map_err(|e| match e {
+                DbError::Serialization(detail) => ExecutorError::StateCorrupt {
+                    address: format!("{}", from_addr),
+                    detail,
+                },
+                other => ExecutorError::Database(other.to_string()),
+            })?;
+
+        // 2.5 EIP-3607: 拒绝由合约账户（有代码）发起的交易，堵住一类签名滥用攻击
+        // （受 `ChainConfig::eip3607_active` 激活开关控制，未激活的链跳过这条规则）
+        if self.chain_config.eip3607_active {
+            if let Some(ref acc) = account {
+                if acc.is_contract() {
+                    return Err(ExecutorError::SenderNotEoa(format!("{}", from_addr)));
+                }
+            }
+        }
+
+        // 2.6 准入过滤：在 nonce/余额检查之前询问 TransactionFilter 是否放行该发送方。
+        // `gas_price == 0` 默认视为非法（谁都不该免费打包交易），除非过滤器将其
+        // 认定为白名单的“服务交易”，此时豁免 gas 费用（见下方余额计算）。
+        let to_addr = tx.to_address()?;
+        let declared_gas_price = Self::declared_gas_price(tx)?;
+        let is_service_transaction = match self.filter.is_allowed(&from_addr, to_addr.as_ref(), declared_gas_price) {
+            Decision::Deny => {
+                return Err(ExecutorError::NotPermitted(format!("{}", from_addr)));
+            }
+            Decision::Allow => {
+                if declared_gas_price.is_zero() {
+                    return Err(ExecutorError::ZeroGasPriceNotPermitted(format!("{}", from_addr)));
+                }
+                false
+            }
+            Decision::AllowServiceTransaction => true,
+        };
+
         // 3. Nonce 检查
         let tx_nonce = tx.nonce_value()
             .map_err(|e| ExecutorError::Transaction(e))?;
@@ -82,8 +338,8 @@ impl TransactionExecutor {
             }
         }
         
-        // 4. 余额检查
-        let required = Self::calculate_required_balance(tx)?;
+        // 4. 余额检查（服务交易豁免 gas 费用，仅需覆盖转账 value）
+        let required = self.calculate_required_balance(tx, is_service_transaction)?;
         let available = account.map_or(U256::ZERO, |a| a.balance);
         
         if available < required {
@@ -96,36 +352,59 @@ impl TransactionExecutor {
         Ok(())
     }
     
+    /// 交易声明的 gas price
+    ///
+    /// EIP-1559 交易取 `max_fee_per_gas`（发送方承诺的上限）；Legacy/EIP-2930
+    /// 交易取声明的 `gas_price`，缺省时按 1 Gwei 估算（与历史行为保持一致）。
+    fn declared_gas_price(tx: &Transaction) -> Result<U256, ExecutorError> {
+        match tx.tx_type() {
+            TxType::Eip1559 => Ok(tx.max_fee_value()),
+            TxType::Legacy | TxType::Eip2930 => if let Some(ref gp) = tx.gas_price {
+                let hex = gp.trim_start_matches("0x");
+                U256::from_str_radix(hex, 16)
+                    .map_err(|e| ExecutorError::Transaction(format!("Invalid gas_price: {}", e)))
+            } else {
+                Ok(U256::from(1_000_000_000u64)) // 1 Gwei
+            },
+        }
+    }
+
     /// 计算交易所需的余额
-    /// 
-    /// 计算公式: gas_limit * gas_price + value
-    fn calculate_required_balance(tx: &Transaction) -> Result<U256, ExecutorError> {
-        let gas_limit = tx.gas_limit()
-            .map_err(|e| ExecutorError::Transaction(e))?;
-        
-        // 解析 gas_price (如果没有提供,默认为 1 Gwei)
-        let gas_price = if let Some(ref gp) = tx.gas_price {
-            let hex = gp.trim_start_matches("0x");
-            U256::from_str_radix(hex, 16)
-                .map_err(|e| ExecutorError::Transaction(format!("Invalid gas_price: {}", e)))?
-        } else {
-            U256::from(1_000_000_000u64) // 1 Gwei
-        };
-        
+    ///
+    /// 计量模式下计算公式为: gas_limit * gas_price + value。EIP-1559 交易按
+    /// `max_fee_per_gas` 预留（即便 base fee 更低最终少扣），这是发送方承诺
+    /// 愿意支付的上限。固定成本模式下用配置的 `fee_wei` 代替 `gas_limit *
+    /// gas_price`，因为实际执行只会按固定费用扣款（见 [`GasPricingPolicy`]）。
+    ///
+    /// `free_gas` 为 `true` 时（白名单发送方的零 gas price 服务交易），跳过 gas
+    /// 费用部分，只需覆盖转账 `value`。
+    fn calculate_required_balance(&self, tx: &Transaction, free_gas: bool) -> Result<U256, ExecutorError> {
         let value = tx.value_wei()
             .map_err(|e| ExecutorError::Transaction(e))?;
-        
-        // 计算总所需余额
-        let gas_cost = U256::from(gas_limit)
-            .checked_mul(gas_price)
-            .ok_or_else(|| ExecutorError::Transaction("Gas cost overflow".to_string()))?;
-        
+
+        if free_gas {
+            return Ok(value);
+        }
+
+        let gas_cost = match self.pricing.policy {
+            GasPricingPolicy::FixedCost { fee_wei } => fee_wei,
+            GasPricingPolicy::Metered => {
+                let gas_limit = tx.gas_limit()
+                    .map_err(|e| ExecutorError::Transaction(e))?;
+                let gas_price = Self::declared_gas_price(tx)?;
+
+                U256::from(gas_limit)
+                    .checked_mul(gas_price)
+                    .ok_or_else(|| ExecutorError::Transaction("Gas cost overflow".to_string()))?
+            }
+        };
+
         let total = gas_cost.checked_add(value)
             .ok_or_else(|| ExecutorError::Transaction("Total cost overflow".to_string()))?;
-        
+
         Ok(total)
     }
-    
+
     /// 执行交易
     /// 
     /// # 参数
@@ -140,14 +419,70 @@ impl TransactionExecutor {
         block_env: BlockEnv,
     ) -> Result<ExecutionResult, ExecutorError> {
         // 1. 构建交易环境
-        let tx_env = self.build_tx_env(tx)?;
-        
+        let tx_env = self.build_tx_env(tx, block_env.basefee)?;
+        let caller = tx_env.caller;
+        let metered_gas_price = tx_env.gas_price;
+
         // 2. 委托给 RevmAdapter 执行
-        self.adapter.execute(tx_env, block_env)
+        let mut result = self.adapter.execute(tx_env, block_env)?;
+
+        // 3. 按计价策略结算费用
+        self.apply_pricing(&mut result, caller, metered_gas_price)?;
+
+        Ok(result)
     }
-    
+
+    /// 带追踪地执行交易
+    ///
+    /// 返回的 [`ExecutionResult::struct_logs`] 携带逐操作码轨迹，
+    /// 供 `debug_traceTransaction` 使用。追踪仅影响本次调用，不改变执行语义。
+    pub fn execute_with_trace(
+        &mut self,
+        tx: &Transaction,
+        block_env: BlockEnv,
+        config: crate::executor::TraceConfig,
+    ) -> Result<ExecutionResult, ExecutorError> {
+        let tx_env = self.build_tx_env(tx, block_env.basefee)?;
+        let caller = tx_env.caller;
+        let metered_gas_price = tx_env.gas_price;
+
+        let mut result = self.adapter.execute_with_trace(tx_env, block_env, config)?;
+        self.apply_pricing(&mut result, caller, metered_gas_price)?;
+
+        Ok(result)
+    }
+
+    /// 按 [`GasPricingPolicy`] 结算本次执行实际收取的费用
+    ///
+    /// 计量模式下 REVM 已经在 `transact` 内部按 `gas_used * gas_price` 扣费
+    /// （`build_tx_env` 里填的就是这个 `gas_price`），这里只是把同样的金额记到
+    /// `ExecutionResult::fee_charged` 上。固定成本模式下 `build_tx_env` 会把
+    /// `TxEnv::gas_price` 置零，REVM 自己不会扣任何费用，真正的固定费用由这里
+    /// 通过 [`RevmAdapter::debit_balance`] 单独结算。
+    fn apply_pricing(
+        &mut self,
+        result: &mut ExecutionResult,
+        caller: Address,
+        metered_gas_price: U256,
+    ) -> Result<(), ExecutorError> {
+        result.fee_charged = match self.pricing.policy {
+            GasPricingPolicy::Metered => U256::from(result.gas_used)
+                .checked_mul(metered_gas_price)
+                .ok_or_else(|| ExecutorError::Transaction("Gas cost overflow".to_string()))?,
+            GasPricingPolicy::FixedCost { fee_wei } => {
+                self.adapter.debit_balance(caller, fee_wei)?;
+                fee_wei
+            }
+        };
+
+        Ok(())
+    }
+
     /// 构建交易环境
-    fn build_tx_env(&self, tx: &Transaction) -> Result<TxEnv, ExecutorError> {
+    ///
+    /// `base_fee` 取自区块环境，用于按 [`Transaction::effective_gas_price`]
+    /// 为 EIP-1559 交易计算实际支付的 gas 单价（销毁 base fee + 矿工小费）。
+    fn build_tx_env(&self, tx: &Transaction, base_fee: U256) -> Result<TxEnv, ExecutorError> {
         let mut tx_env = TxEnv::default();
         
         // 解析字段
@@ -167,25 +502,45 @@ impl TransactionExecutor {
         
         tx_env.gas_limit = tx.gas_limit()
             .map_err(|e| ExecutorError::Transaction(e))?;
-        
+
+        // 可选的 gas 上限覆盖：不论交易自己声明了多大的 gas_limit，送入 EVM
+        // 执行的上限都不会超过这个值
+        if let Some(ceiling) = self.pricing.gas_limit_ceiling {
+            tx_env.gas_limit = tx_env.gas_limit.min(ceiling);
+        }
+
         tx_env.nonce = Some(tx.nonce_value()
             .map_err(|e| ExecutorError::Transaction(e))?);
-        
-        // Gas price（可选）
-        if let Some(ref gas_price_str) = tx.gas_price {
-            let hex = gas_price_str.trim_start_matches("0x");
-            tx_env.gas_price = U256::from_str_radix(hex, 16)
-                .map_err(|e| ExecutorError::Transaction(format!("Invalid gas_price: {}", e)))?;
-        }
-        
+
+        // Gas price：EIP-1559 按 effective_gas_price(base_fee) 计算（销毁 base fee +
+        // 小费，封顶 max_fee）；Legacy/EIP-2930 沿用声明的 gas_price。固定成本模式下
+        // 置零，REVM 自身不扣费，真正的固定费用由 `apply_pricing` 单独结算。
+        tx_env.gas_price = match self.pricing.policy {
+            GasPricingPolicy::Metered => tx.effective_gas_price(base_fee),
+            GasPricingPolicy::FixedCost { .. } => U256::ZERO,
+        };
+
+
         // Chain ID（可选）
         if let Some(chain_id) = tx.chain_id {
             tx_env.chain_id = Some(chain_id);
         }
-        
+
+        // 访问列表（EIP-2930）：预热列出的地址/存储槽。其 intrinsic gas 已经在
+        // `validate_transaction` 里通过 `Transaction::access_list_gas` 校验过
+        // `gas_limit` 能否覆盖，这里只需要把列表本身交给 REVM 去标记预热状态
+        if let Some(ref access_list) = tx.access_list {
+            tx_env.access_list = access_list.clone();
+        }
+
         Ok(tx_env)
     }
     
+    /// 获取数据库的不可变引用
+    pub fn db(&self) -> &RedbStateDB {
+        self.adapter.db()
+    }
+
     /// 获取数据库的可变引用
     pub fn db_mut(&mut self) -> &mut RedbStateDB {
         self.adapter.db_mut()
@@ -232,6 +587,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         let mut executor = TransactionExecutor::new(db);
@@ -250,7 +609,12 @@ mod tests {
     #[test]
     fn test_validate_transaction_zero_gas() {
         let (db, _temp_dir) = create_test_db();
-        let mut executor = TransactionExecutor::new(db);
+        // 未签名交易只用来验证 gas limit 检查，关闭 `require_signature` 走受信任
+        // 本地签名者模式，避免为每个无关断言都去构造真实 ECDSA 签名
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
         
         let tx = Transaction {
             from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
@@ -264,13 +628,53 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
-        let result = executor.validate_transaction(&tx);
+        let result = executor.validate_transaction(&tx, U256::ZERO);
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), ExecutorError::InvalidGas));
     }
-    
+
+    #[test]
+    fn test_validate_transaction_rejects_gas_limit_below_access_list_cost() {
+        let (db, _temp_dir) = create_test_db();
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        // access list 声明 1 个地址 + 1 个存储槽 = 2400 + 1900 = 4300 gas，
+        // 但 gas_limit 只给了 21000 以下的 1000，连预热这些槽位都不够
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x3e8".to_string(), // 1000
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: Some(vec![(
+                address!("5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed"),
+                vec![U256::ZERO],
+            )]),
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExecutorError::InvalidGas));
+    }
+
     #[test]
     fn test_validate_transaction_nonce_too_low() {
         let (mut db, _temp_dir) = create_test_db();
@@ -280,9 +684,12 @@ mod tests {
         let mut account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
         account.nonce = 5;
         db.set_account(&from, account).unwrap();
-        
-        let mut executor = TransactionExecutor::new(db);
-        
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
         // 交易 nonce 为 3，低于账户 nonce
         let tx = Transaction {
             from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
@@ -296,9 +703,13 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
-        let result = executor.validate_transaction(&tx);
+        let result = executor.validate_transaction(&tx, U256::ZERO);
         assert!(result.is_err());
         match result.unwrap_err() {
             ExecutorError::NonceTooLow { expected, got } => {
@@ -317,9 +728,12 @@ mod tests {
         let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
         let account = Account::with_balance(U256::from(100_000_000_000_000_000u64)); // 0.1 ETH
         db.set_account(&from, account).unwrap();
-        
-        let mut executor = TransactionExecutor::new(db);
-        
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
         // 尝试转账 1 ETH
         let tx = Transaction {
             from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
@@ -333,9 +747,13 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
-        let result = executor.validate_transaction(&tx);
+        let result = executor.validate_transaction(&tx, U256::ZERO);
         assert!(result.is_err());
         assert!(matches!(
             result.unwrap_err(),
@@ -352,9 +770,12 @@ mod tests {
         let mut account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64)); // 10 ETH
         account.nonce = 0;
         db.set_account(&from, account).unwrap();
-        
-        let mut executor = TransactionExecutor::new(db);
-        
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
         let tx = Transaction {
             from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
             to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
@@ -367,9 +788,457 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
-        
-        let result = executor.validate_transaction(&tx);
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_validate_transaction_fee_cap_too_low() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let mut account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64)); // 10 ETH
+        account.nonce = 0;
+        db.set_account(&from, account).unwrap();
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        // max_fee_per_gas = 1 Gwei，但区块 base fee 为 2 Gwei，发送方出价不够覆盖 base fee
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: None,
+            chain_id: Some(1),
+            max_fee_per_gas: Some("0x3b9aca00".to_string()), // 1 Gwei
+            max_priority_fee_per_gas: Some("0x3b9aca00".to_string()),
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::from(2_000_000_000u64));
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExecutorError::FeeCapTooLow { .. }));
+    }
+
+    #[test]
+    fn test_validate_transaction_rejects_contract_sender() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        // 发送方账户带有合约代码哈希（非 EOA）
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let mut account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        account.code_hash = alloy_primitives::keccak256([0x60, 0x00]); // 任意非空字节码的哈希
+        db.set_account(&from, account).unwrap();
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExecutorError::SenderNotEoa(_)));
+    }
+
+    #[test]
+    fn test_validate_transaction_allows_contract_sender_when_eip3607_inactive() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        // 发送方账户带有合约代码哈希（非 EOA），但本链尚未激活 EIP-3607
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let mut account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        account.code_hash = alloy_primitives::keccak256([0x60, 0x00]); // 任意非空字节码的哈希
+        db.set_account(&from, account).unwrap();
+
+        let chain_config = ChainConfig {
+            chain_id: 1,
+            allow_legacy_no_chain_id: true,
+            eip3607_active: false,
+            require_signature: false,
+        };
+        let mut executor = TransactionExecutor::with_chain_config(db, chain_config);
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_validate_transaction_rejects_wrong_chain_id() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        db.set_account(&from, account).unwrap();
+
+        // 执行器服务 chain_id = 1，交易声明的是 chain_id = 999
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(999),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            ExecutorError::InvalidChainId { expected, got } => {
+                assert_eq!(expected, 1);
+                assert_eq!(got, Some(999));
+            }
+            other => panic!("Expected InvalidChainId, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_transaction_rejects_missing_chain_id_when_disallowed() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        db.set_account(&from, account).unwrap();
+
+        let chain_config = ChainConfig {
+            chain_id: 1,
+            allow_legacy_no_chain_id: false,
+            eip3607_active: true,
+            require_signature: false,
+        };
+        let mut executor = TransactionExecutor::with_chain_config(db, chain_config);
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: None,
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        match result.unwrap_err() {
+            ExecutorError::InvalidChainId { expected, got } => {
+                assert_eq!(expected, 1);
+                assert_eq!(got, None);
+            }
+            other => panic!("Expected InvalidChainId, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_validate_transaction_rejects_zero_gas_price_by_default() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        db.set_account(&from, account).unwrap();
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x0".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExecutorError::ZeroGasPriceNotPermitted(_)));
+    }
+
+    #[test]
+    fn test_validate_transaction_rejects_mixed_legacy_and_1559_fee_fields() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        db.set_account(&from, account).unwrap();
+
+        let mut executor = TransactionExecutor::with_chain_config(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+        );
+
+        // 既带 legacy 的 gas_price 又带 1559 的 max_fee_per_gas：信封类型含糊不清，
+        // 无论 `tx_type()` 推断成哪一种都应在验证阶段被拒绝
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: Some("0x77359400".to_string()),
+            max_priority_fee_per_gas: Some("0x3b9aca00".to_string()),
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExecutorError::TxEnvelopeFieldConflict(_)));
+    }
+
+    #[test]
+    fn test_validate_transaction_rejects_non_whitelisted_sender() {
+        use crate::executor::permission::WhitelistFilter;
+
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        db.set_account(&from, account).unwrap();
+
+        // 白名单不包含发送方，任何交易都应被拒绝
+        let filter = WhitelistFilter::new();
+        let mut executor = TransactionExecutor::with_chain_config_and_filter(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+            Box::new(filter),
+        );
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_err());
+        assert!(matches!(result.unwrap_err(), ExecutorError::NotPermitted(_)));
+    }
+
+    #[test]
+    fn test_validate_transaction_allows_whitelisted_zero_gas_service_transaction() {
+        use crate::executor::permission::WhitelistFilter;
+
+        let (mut db, _temp_dir) = create_test_db();
+
+        // 白名单发送方余额很低：如果零价交易真的被当成免费服务交易，仍应通过
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let account = Account::with_balance(U256::from(1));
+        db.set_account(&from, account).unwrap();
+
+        let filter = WhitelistFilter::with_addresses([from]);
+        let mut executor = TransactionExecutor::with_chain_config_and_filter(
+            db,
+            ChainConfig { require_signature: false, ..ChainConfig::default() },
+            Box::new(filter),
+        );
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x0".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let result = executor.validate_transaction(&tx, U256::ZERO);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_fixed_cost_policy_charges_flat_fee_regardless_of_gas_used() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let from_account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64)); // 10 ETH
+        db.set_account(&from, from_account).unwrap();
+
+        let fixed_fee = U256::from(1_000_000_000_000_000u64); // 0.001 ETH
+        let pricing = GasPricingConfig {
+            policy: GasPricingPolicy::FixedCost { fee_wei: fixed_fee },
+            gas_limit_ceiling: None,
+        };
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0xde0b6b3a7640000".to_string(), // 1 ETH
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(), // 21000
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()), // 1 Gwei（计量模式下会用到，固定成本模式下应被忽略）
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let mut executor = TransactionExecutor::with_gas_pricing(db, pricing);
+        let block_env = BlockEnv::default();
+
+        executor.db_mut().begin_transaction().unwrap();
+        let result = executor.execute(&tx, block_env).unwrap();
+
+        assert!(result.success);
+        assert_eq!(result.fee_charged, fixed_fee);
+
+        // 扣费应该是转账 value + 固定费用，而不是 value + gas_used * gas_price
+        let sender = executor.db_mut().get_account(&from).unwrap().unwrap();
+        let expected_balance = U256::from(10_000_000_000_000_000_000u64)
+            - U256::from(1_000_000_000_000_000_000u64) // 1 ETH 转账
+            - fixed_fee;
+        assert_eq!(sender.balance, expected_balance);
+    }
+
+    #[test]
+    fn test_gas_limit_ceiling_caps_declared_gas_limit() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let from = address!("0742d35Cc6634C0532925a3b844Bc9e7595f0bEb");
+        let from_account = Account::with_balance(U256::from(10_000_000_000_000_000_000u64));
+        db.set_account(&from, from_account).unwrap();
+
+        let pricing = GasPricingConfig {
+            policy: GasPricingPolicy::Metered,
+            gas_limit_ceiling: Some(10_000),
+        };
+        let executor = TransactionExecutor::with_gas_pricing(db, pricing);
+
+        let tx = Transaction {
+            from: "0x0742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(), // 21000，高于上限
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let tx_env = executor.build_tx_env(&tx, U256::ZERO).unwrap();
+        assert_eq!(tx_env.gas_limit, 10_000);
+    }
 }
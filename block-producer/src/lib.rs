@@ -5,5 +5,7 @@
 pub mod db;
 pub mod schema;
 pub mod executor;
+pub mod metrics;
+pub mod signing;
 // pub mod trie;
 // pub mod utils;     
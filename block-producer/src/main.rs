@@ -1,10 +1,10 @@
+use alloy_primitives::Address;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use clap::Parser;
 use distributed_walrus::cli_client::CliClient;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::VecDeque;
 use std::time::Duration;
 use tracing::{info, warn, error, debug};
 use tracing_subscriber::{fmt, EnvFilter};
@@ -12,6 +12,12 @@ use tracing_subscriber::{fmt, EnvFilter};
 // === 使用 lib 中的模块 ===
 use block_producer::{db, schema, trie, executor, utils, wallet};
 
+mod pool;
+use pool::TxPool;
+
+mod verification_queue;
+use verification_queue::VerificationQueue;
+
 // === 区块链常量配置 ===
 // 使用 lib.rs 中定义的常量，保持单一来源
 use block_producer::DEFAULT_BLOCK_GAS_LIMIT;
@@ -37,6 +43,12 @@ struct Args {
     /// 每个区块最大交易数
     #[arg(long, default_value = "10000")]
     max_txs_per_block: usize,
+
+    /// 交易根退回到旧的 SHA-256(JSON 拼接) 算法，而不是默认的以太坊兼容
+    /// Keccak256 Merkle-Patricia Trie 根——仅用于兼容依赖旧根值的现有测试，
+    /// 新部署不应打开
+    #[arg(long, default_value = "false")]
+    legacy_sha256_roots: bool,
 }
 
 /// 交易数据结构
@@ -50,6 +62,117 @@ pub struct Transaction {
     pub nonce: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hash: Option<String>,
+    /// Gas 价格（Legacy/EIP-2930，可选）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub gas_price: Option<String>,
+    /// EIP-1559：最大 gas 费用（可选）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_fee_per_gas: Option<String>,
+    /// EIP-1559：最大优先费用（可选）
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_priority_fee_per_gas: Option<String>,
+    /// 签名恢复 id（EIP-155 编码或类型化交易的 y_parity）；缺省（未签名）的
+    /// 交易会在 `verification_queue` 的签名校验中被拒绝
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub v: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub r: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub s: Option<String>,
+
+    /// `verification_queue` 校验通过后缓存的已恢复发送方地址——交易池/执行层
+    /// 据此做按发送方分桶，不需要再跑一遍 ECDSA 恢复。不随交易一起序列化/
+    /// 广播，只是本进程内的缓存。
+    #[serde(skip)]
+    pub recovered_sender: Option<Address>,
+}
+
+impl Transaction {
+    /// 转换为 `schema::Transaction`，借用其已有的 [`schema::TxType::type_byte`]/
+    /// `effective_gas_price`/`max_fee_value`/`verify_and_recover_sender` 等逻辑，
+    /// 而不是在这层重新实现一遍——这个池化层不携带 `chain_id`/访问列表，沿用
+    /// `rpc-gateway` 网关侧 `to_evm_transaction` 同样的留空方式
+    fn to_schema_tx(&self) -> schema::Transaction {
+        schema::Transaction {
+            from: self.from.clone(),
+            to: self.to.clone(),
+            value: self.value.clone(),
+            data: self.data.clone(),
+            gas: self.gas.clone(),
+            nonce: self.nonce.clone(),
+            hash: self.hash.clone(),
+            gas_price: self.gas_price.clone(),
+            chain_id: None,
+            max_fee_per_gas: self.max_fee_per_gas.clone(),
+            max_priority_fee_per_gas: self.max_priority_fee_per_gas.clone(),
+            access_list: None,
+            v: self.v,
+            r: self.r.clone(),
+            s: self.s.clone(),
+        }
+    }
+
+    /// 发送方地址：优先用 `verification_queue` 校验阶段缓存的 `recovered_sender`，
+    /// 没有缓存时（例如绕过校验队列直接构造的交易，常见于测试）才退回解析
+    /// `from` 字段本身
+    fn sender_address(&self) -> Result<Address, String> {
+        if let Some(addr) = self.recovered_sender {
+            return Ok(addr);
+        }
+        self.to_schema_tx().from_address()
+    }
+
+    /// 这笔交易声明的费用上限，用于 [`pool::TxPool`] 的 replace-by-fee 比较：
+    /// EIP-1559 交易是 `max_fee_per_gas`，Legacy/EIP-2930 是 `gas_price`；
+    /// 两者都没声明时回落到 `gas` 字段（历史上兼作价格的旧交易格式）
+    fn declared_fee(&self) -> alloy_primitives::U256 {
+        let schema_tx = self.to_schema_tx();
+        match schema_tx.tx_type() {
+            schema::TxType::Eip1559 => schema_tx.max_fee_value(),
+            schema::TxType::Legacy | schema::TxType::Eip2930 if self.gas_price.is_some() => {
+                schema_tx.gas_price_value()
+            }
+            schema::TxType::Legacy | schema::TxType::Eip2930 => {
+                alloy_primitives::U256::from(BlockProducer::parse_gas_price(&self.gas).unwrap_or(0))
+            }
+        }
+    }
+
+    /// 这笔交易在给定 base fee 下对出块者而言的小费
+    /// （EIP-1559：`effective_gas_price - base_fee`）
+    ///
+    /// 返回 `None` 表示这笔交易的 `max_fee_per_gas` 低于 `base_fee`——这样的
+    /// 交易在当前 base fee 下根本不能被打包（执行层也会把它判定为
+    /// `FeeCapTooLow` 拒绝）。调用方（[`pool::PackingRound`]）应当把它留在
+    /// 池中而不是当作候选，等下一个区块 base fee 变化后再重新评估。
+    fn tip_above_base_fee(&self, base_fee: alloy_primitives::U256) -> Option<u64> {
+        let schema_tx = self.to_schema_tx();
+        match schema_tx.tx_type() {
+            schema::TxType::Eip1559 => {
+                let max_fee = schema_tx.max_fee_value();
+                if max_fee < base_fee {
+                    return None;
+                }
+                let effective = schema_tx.effective_gas_price(base_fee);
+                u64::try_from(effective.saturating_sub(base_fee)).ok()
+            }
+            schema::TxType::Legacy | schema::TxType::Eip2930 => {
+                let gas_price = if self.gas_price.is_some() {
+                    schema_tx.gas_price_value()
+                } else {
+                    alloy_primitives::U256::from(BlockProducer::parse_gas_price(&self.gas).unwrap_or(0))
+                };
+                // 和 EIP-1559 分支一样：报价低于当前 base fee 的交易直接排除，
+                // 而不是 saturating_sub 成 0 后继续留在堆里占位——否则这笔交易会
+                // 被当成"零小费"候选打包进块，但发送方实际支付的 gas_price 还
+                // 不够 base fee，区块头报告的 base_fee_burned 会虚高。
+                if gas_price < base_fee {
+                    return None;
+                }
+                u64::try_from(gas_price.saturating_sub(base_fee)).ok()
+            }
+        }
+    }
 }
 
 /// 区块头
@@ -76,6 +199,97 @@ pub struct BlockHeader {
     /// 收据根哈希
     #[serde(skip_serializing_if = "Option::is_none")]
     pub receipts_root: Option<String>,
+    /// Logs Bloom 过滤器（聚合区块内所有收据的 Bloom）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logs_bloom: Option<String>,
+    /// EIP-1559 基础费用（每单位 gas 被销毁的部分）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee_per_gas: Option<alloy_primitives::U256>,
+}
+
+impl BlockHeader {
+    /// 解析十六进制哈希字符串为 B256（缺省/非法/长度不符时回落为零哈希）
+    fn decode_hash(value: &str) -> alloy_primitives::B256 {
+        let hex = value.trim_start_matches("0x");
+        match hex::decode(hex) {
+            Ok(bytes) if bytes.len() == 32 => alloy_primitives::B256::from_slice(&bytes),
+            _ => alloy_primitives::B256::ZERO,
+        }
+    }
+
+    /// 解析可选十六进制哈希字符串（`None` 回落为零哈希）
+    fn decode_hash_opt(value: &Option<String>) -> alloy_primitives::B256 {
+        value.as_deref().map(Self::decode_hash).unwrap_or_default()
+    }
+
+    /// 解析 Logs Bloom 十六进制字符串（`None`/长度不符时回落为全零 Bloom）
+    fn decode_bloom(value: &Option<String>) -> schema::Bloom {
+        let hex = match value.as_deref().map(|v| v.trim_start_matches("0x")) {
+            Some(hex) => hex,
+            None => return schema::Bloom::zero(),
+        };
+        match hex::decode(hex) {
+            Ok(bytes) if bytes.len() == 256 => schema::Bloom(bytes.try_into().unwrap()),
+            _ => schema::Bloom::zero(),
+        }
+    }
+
+    /// 计算区块头的规范 RLP 编码（同 `schema::BlockHeader::rlp_encode`）
+    pub fn rlp_encode(&self) -> Vec<u8> {
+        use alloy_rlp::Encodable;
+
+        let mut payload = Vec::new();
+        self.number.encode(&mut payload);
+        Self::decode_hash(&self.parent_hash).encode(&mut payload);
+        (self.timestamp.timestamp() as u64).encode(&mut payload);
+        (self.tx_count as u64).encode(&mut payload);
+        Self::decode_hash(&self.transactions_root).encode(&mut payload);
+        Self::decode_hash_opt(&self.state_root).encode(&mut payload);
+        self.gas_used.unwrap_or_default().encode(&mut payload);
+        self.gas_limit.unwrap_or_default().encode(&mut payload);
+        Self::decode_hash_opt(&self.receipts_root).encode(&mut payload);
+        (&Self::decode_bloom(&self.logs_bloom).as_bytes()[..]).encode(&mut payload);
+        self.base_fee_per_gas.unwrap_or_default().encode(&mut payload);
+
+        let mut out = Vec::new();
+        alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(&mut out);
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// 计算下一个区块的 base fee（EIP-1559 费用市场，同 `schema::BlockHeader`）
+    pub fn calculate_next_base_fee(&self) -> alloy_primitives::U256 {
+        use alloy_primitives::U256;
+
+        const ELASTICITY_MULTIPLIER: u64 = 2;
+        const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+        let parent_base_fee = self.base_fee_per_gas.unwrap_or_default();
+        let gas_limit = self.gas_limit.unwrap_or_default();
+        let gas_used = self.gas_used.unwrap_or_default();
+
+        let gas_target = gas_limit / ELASTICITY_MULTIPLIER;
+        if gas_target == 0 {
+            return parent_base_fee;
+        }
+
+        match gas_used.cmp(&gas_target) {
+            std::cmp::Ordering::Equal => parent_base_fee,
+            std::cmp::Ordering::Greater => {
+                let delta = U256::from(gas_used - gas_target);
+                let increase = (parent_base_fee * delta / U256::from(gas_target)
+                    / U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR))
+                    .max(U256::from(1));
+                parent_base_fee.saturating_add(increase)
+            }
+            std::cmp::Ordering::Less => {
+                let delta = U256::from(gas_target - gas_used);
+                let decrease = parent_base_fee * delta / U256::from(gas_target)
+                    / U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR);
+                parent_base_fee.saturating_sub(decrease)
+            }
+        }
+    }
 }
 
 /// 区块
@@ -87,29 +301,33 @@ pub struct Block {
 
 impl Block {
     /// 计算区块哈希
+    ///
+    /// keccak256(RLP(区块头))，与 `schema::Block::hash` 保持一致的方案。
     pub fn hash(&self) -> String {
-        let data = serde_json::to_string(&self.header).unwrap();
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        format!("0x{:x}", hasher.finalize())
+        format!("0x{}", hex::encode(alloy_primitives::keccak256(self.header.rlp_encode())))
     }
 }
 
 /// 区块生产者
 pub struct BlockProducer {
-    walrus_client: CliClient,
     topic: String,
     block_interval: Duration,
     max_txs_per_block: usize,
     current_block_number: u64,
     last_block_hash: String,
-    
+    /// 上一个区块头（用于按 EIP-1559 费用市场推导下一个区块的 base fee）
+    last_header: Option<BlockHeader>,
+
+    /// 多线程交易验证队列：拉取 + 无状态校验都在后台完成，见
+    /// [`verification_queue::VerificationQueue`]
+    verification_queue: VerificationQueue,
+
+    /// 交易根退回到旧的 SHA-256(JSON 拼接) 算法，而不是默认的 Keccak256 MPT 根
+    legacy_sha256_roots: bool,
+
     // ===== 交易池 (类似 Reth 设计) =====
-    /// 待处理交易池：存储从 Walrus 读取但尚未打包的交易
-    pending_pool: VecDeque<Transaction>,
-    
-    /// 交易池最大容量（避免无限增长）
-    pool_max_size: usize,
+    /// 待处理交易池：按发送方分桶、nonce 有序，见 [`pool::TxPool`]
+    pending_pool: TxPool,
 }
 
 impl BlockProducer {
@@ -118,21 +336,28 @@ impl BlockProducer {
         topic: String,
         block_interval_secs: u64,
         max_txs_per_block: usize,
+        legacy_sha256_roots: bool,
     ) -> Self {
-        let walrus_client = CliClient::new(walrus_addr);
         let pool_max_size = max_txs_per_block * 10; // 交易池容量为单区块的10倍
-        
+        let verification_queue = VerificationQueue::spawn(
+            CliClient::new(walrus_addr),
+            topic.clone(),
+            pool_max_size,
+        );
+
         Self {
-            walrus_client,
             topic,
             block_interval: Duration::from_secs(block_interval_secs),
             max_txs_per_block,
             current_block_number: 0,
             last_block_hash: "0x0000000000000000000000000000000000000000000000000000000000000000".to_string(),
-            
+            last_header: None,
+
+            verification_queue,
+            legacy_sha256_roots,
+
             // 初始化交易池
-            pending_pool: VecDeque::new(),
-            pool_max_size,
+            pending_pool: TxPool::new(pool_max_size),
         }
     }
 
@@ -142,7 +367,8 @@ impl BlockProducer {
         info!("   Walrus topic: {}", self.topic);
         info!("   出块间隔: {}s", self.block_interval.as_secs());
         info!("   每块最大交易数: {}", self.max_txs_per_block);
-        info!("   交易池容量: {} 笔", self.pool_max_size);
+        info!("   交易池容量: {} 笔", self.max_txs_per_block * 10);
+        info!("   交易根算法: {}", if self.legacy_sha256_roots { "legacy SHA-256" } else { "Keccak256 MPT (以太坊兼容)" });
         info!("");
 
         let mut interval = tokio::time::interval(self.block_interval);
@@ -165,19 +391,34 @@ impl BlockProducer {
         }
     }
 
+    /// 按 EIP-1559 费用市场，从父区块头推导本区块的 base fee；
+    /// 创世区块（无父区块头）使用 1 Gwei 的初始 base fee
+    ///
+    /// 打包阶段的交易筛选/排序和最终写入区块头必须用同一个值，否则选出来的
+    /// 交易可能在执行层因 `max_fee_per_gas < declared base_fee` 被判定
+    /// fee-cap-too-low——两处都调用这一个方法，而不是各自算一遍。
+    fn current_base_fee(&self) -> alloy_primitives::U256 {
+        const INITIAL_BASE_FEE: u64 = 1_000_000_000;
+        self.last_header.as_ref()
+            .map(|h| h.calculate_next_base_fee())
+            .unwrap_or(alloy_primitives::U256::from(INITIAL_BASE_FEE))
+    }
+
     /// 生成一个区块
     async fn produce_block(&mut self) -> Result<Block> {
+        let base_fee_per_gas = Some(self.current_base_fee());
+
         // 1. 从交易池选择交易（而不是直接从 Walrus 读取）
-        let transactions = self.select_transactions_for_block().await?;
-            
+        let transactions = self.select_transactions_for_block(base_fee_per_gas.unwrap()).await?;
+
         if transactions.is_empty() {
             info!("⭭️  交易池为空，跳过本轮出块");
             return Err(anyhow::anyhow!("No transactions in pool"));
         }
-    
+
         // 2. 计算交易根哈希
         let transactions_root = self.calculate_transactions_root(&transactions);
-    
+
         // 3. 构建区块头
         let header = BlockHeader {
             number: self.current_block_number,
@@ -189,119 +430,128 @@ impl BlockProducer {
             gas_used: None,
             gas_limit: Some(DEFAULT_BLOCK_GAS_LIMIT), // 默认 gas 限制
             receipts_root: None,
+            logs_bloom: None,
+            base_fee_per_gas,
         };
-    
+
         // 4. 构建区块
         let mut block = Block {
             header,
             transactions,
         };
-    
+
         // 5. 提交给执行层（会更新 state_root 和 gas_used）
         self.submit_to_execution_layer(&mut block).await?;
-    
+
         // 6. 更新状态
         self.last_block_hash = block.hash();
+        self.last_header = Some(block.header.clone());
         self.current_block_number += 1;
-    
+
         Ok(block)
     }
     
-    /// 从 Walrus 补充交易池
+    /// 从验证队列补充交易池
+    ///
+    /// 拉取、hex 解码、JSON 解析、签名恢复都已经在 [`VerificationQueue`] 的
+    /// 后台异步任务/线程池里完成；这里只需要等待队列产出一批已验证交易
+    /// （`ready_signal`），再搬进交易池——`insert` 自带的 replace-by-fee/
+    /// 容量校验继续生效，池满时多出来的交易留在已验证队列里等下一轮。
     async fn refill_pool(&mut self) -> Result<()> {
         let initial_size = self.pending_pool.len();
+
+        if !self.pending_pool.is_full() {
+            let queue = &self.verification_queue;
+            tokio::task::block_in_place(|| queue.wait_until_ready(Duration::from_millis(200)));
+        }
+
         let mut fetched = 0;
-        
-        while self.pending_pool.len() < self.pool_max_size {
-            match self.walrus_client.get(&self.topic).await? {
-                Some(hex_data) => {
-                    match self.parse_transaction(&hex_data) {
-                        Ok(tx) => {
-                            self.pending_pool.push_back(tx);
-                            fetched += 1;
-                        }
-                        Err(e) => {
-                            warn!("解析交易失败: {}, 数据: {}", e, hex_data);
-                            continue;
-                        }
-                    }
-                }
-                None => break,
+        for tx in self.verification_queue.drain_verified(usize::MAX) {
+            // `insert` 自带 replace-by-fee/容量校验；underpriced 的重复提交
+            // 和池满都只是正常的拒绝,不是需要中止的错误
+            match self.pending_pool.insert(tx) {
+                Ok(()) => fetched += 1,
+                Err(e) => debug!("交易未进入交易池: {}", e),
             }
         }
-        
+
         if fetched > 0 {
-            debug!("交易池补充: {} -> {} (新增 {})", 
+            debug!("交易池补充: {} -> {} (新增 {})",
                    initial_size, self.pending_pool.len(), fetched);
         }
-        
+
         Ok(())
     }
     
     /// 从交易池选择交易打包
-    async fn select_transactions_for_block(&mut self) -> Result<Vec<Transaction>> {
+    ///
+    /// `base_fee` 由调用方（[`Self::produce_block`]）按 EIP-1559 费用市场
+    /// 从父区块头推导而来，和最终写入区块头的值是同一个，以保证这里筛掉/
+    /// 排序用的 base fee 与执行层校验的 base fee 一致。交易池本身
+    /// （[`pool::TxPool`]）已经保证了同一发送方内部按 nonce 顺序弹出，这里
+    /// 只需要按 gas 上限在打包会话里反复取"全局最优的下一笔"。
+    async fn select_transactions_for_block(&mut self, base_fee: alloy_primitives::U256) -> Result<Vec<Transaction>> {
         self.refill_pool().await?;
-        
+
         if self.pending_pool.is_empty() {
             return Ok(Vec::new());
         }
-        
-        let mut candidates: Vec<Transaction> = self.pending_pool.drain(..).collect();
-        
-        info!("📋 开始交易选择: 候选交易 {} 笔", candidates.len());
-        
-        // 按 gas price 降序排序（优先打包高价交易）
-        candidates.sort_by(|a, b| {
-            let a_price = Self::parse_gas_price(&a.gas).unwrap_or(0);
-            let b_price = Self::parse_gas_price(&b.gas).unwrap_or(0);
-            b_price.cmp(&a_price)
-        });
-        
+
+        let stats_before = self.pending_pool.stats();
+        info!(
+            "📋 开始交易选择: 就绪 {} 笔, 排队 {} 笔 (来自 {} 个发送方, base fee={})",
+            stats_before.ready, stats_before.parked, stats_before.senders, base_fee
+        );
+
         let mut selected = Vec::new();
         let mut estimated_gas = 0u64;
         // 统一使用常量作为 gas limit 来源
         let block_gas_limit = DEFAULT_BLOCK_GAS_LIMIT;
         let mut skipped_by_gas = 0;
-        
+
         debug!("⛽ 区块 gas 限制: {}", block_gas_limit);
-        
-        for (idx, tx) in candidates.into_iter().enumerate() {
-            let tx_gas = Self::parse_gas_limit(&tx.gas).unwrap_or(21000);
-            let tx_hash_display = tx.hash.as_deref().unwrap_or("unknown");
-            
-            // 移除 max_txs_per_block 的硬性限制，只检查 gas
-            if estimated_gas + tx_gas <= block_gas_limit {
-                estimated_gas += tx_gas;
-                
-                debug!(
-                    "  ✓ 选择交易 #{}: hash={}, gas={}, 累计={}/{} ({:.1}%)",
-                    idx + 1,
-                    tx_hash_display,
-                    tx_gas,
-                    estimated_gas,
-                    block_gas_limit,
-                    (estimated_gas as f64 / block_gas_limit as f64) * 100.0
-                );
-                
-                selected.push(tx);
-            } else {
-                // Gas 不足，无法容纳此交易
-                skipped_by_gas += 1;
-                debug!(
-                    "  ✗ 跳过交易 #{}: hash={}, gas={} (剩余空间不足: {}/{})",
-                    idx + 1,
-                    tx_hash_display,
-                    tx_gas,
-                    block_gas_limit - estimated_gas,
-                    block_gas_limit
-                );
-                
-                // 放回队列，供下次打包
-                self.pending_pool.push_front(tx);
+
+        {
+            let mut round = self.pending_pool.begin_round(base_fee);
+            let mut idx = 0;
+            while let Some(tx) = round.pop_best() {
+                idx += 1;
+                let tx_gas = Self::parse_gas_limit(&tx.gas).unwrap_or(21000);
+                let tx_hash_display = tx.hash.clone().unwrap_or_else(|| "unknown".to_string());
+
+                if estimated_gas + tx_gas <= block_gas_limit {
+                    estimated_gas += tx_gas;
+
+                    debug!(
+                        "  ✓ 选择交易 #{}: hash={}, gas={}, 累计={}/{} ({:.1}%)",
+                        idx,
+                        tx_hash_display,
+                        tx_gas,
+                        estimated_gas,
+                        block_gas_limit,
+                        (estimated_gas as f64 / block_gas_limit as f64) * 100.0
+                    );
+
+                    selected.push(tx);
+                } else {
+                    // Gas 不足，无法容纳此交易——本轮 gas 预算只会越来越紧，
+                    // 放回池中等下一个区块；本轮继续尝试其它发送方的交易
+                    skipped_by_gas += 1;
+                    debug!(
+                        "  ✗ 跳过交易: hash={}, gas={} (剩余空间不足: {}/{})",
+                        tx_hash_display,
+                        tx_gas,
+                        block_gas_limit - estimated_gas,
+                        block_gas_limit
+                    );
+
+                    round.reinsert(tx);
+                }
             }
         }
-        
+
         // 输出详细的选择统计
+        let stats_after = self.pending_pool.stats();
         info!(
             "✅ 交易选择完成: 已选 {} 笔, 预估 gas {}/{} ({:.1}%), 跳过 {} 笔 (gas不足)",
             selected.len(),
@@ -310,28 +560,32 @@ impl BlockProducer {
             (estimated_gas as f64 / block_gas_limit as f64) * 100.0,
             skipped_by_gas
         );
-        info!("📦 交易池剩余: {} 笔", self.pending_pool.len());
-        
+        info!(
+            "📦 交易池剩余: 就绪 {} 笔, 排队 {} 笔 (来自 {} 个发送方)",
+            stats_after.ready, stats_after.parked, stats_after.senders
+        );
+
         Ok(selected)
     }
-    
+
     /// 将执行失败的交易放回池中
     fn return_to_pool(&mut self, transactions: Vec<Transaction>) {
         if transactions.is_empty() {
             return;
         }
-        
+
         debug!("️ 将 {} 笔交易放回交易池", transactions.len());
-        
+
         for tx in transactions {
-            if self.pending_pool.len() >= self.pool_max_size {
+            if self.pending_pool.is_full() {
                 warn!("️ 交易池已满，丢弃交易: {:?}", tx.hash);
                 break;
             }
-            self.pending_pool.push_front(tx);
+            self.pending_pool.reinsert(tx);
         }
     }
-    
+
+
     fn parse_gas_price(gas_hex: &str) -> Result<u64> {
         let hex = gas_hex.trim_start_matches("0x");
         u64::from_str_radix(hex, 16)
@@ -344,55 +598,30 @@ impl BlockProducer {
             .map_err(|e| anyhow::anyhow!("Invalid gas: {}", e))
     }
 
-    /// 从 Walrus 读取交易
-    async fn fetch_transactions(&self) -> Result<Vec<Transaction>> {
-        let mut transactions = Vec::new();
-
-        for _ in 0..self.max_txs_per_block {
-            match self.walrus_client.get(&self.topic).await? {
-                Some(hex_data) => {
-                    match self.parse_transaction(&hex_data) {
-                        Ok(tx) => transactions.push(tx),
-                        Err(e) => {
-                            warn!("解析交易失败: {}, 数据: {}", e, hex_data);
-                            continue;
-                        }
-                    }
-                }
-                None => break, // 没有更多交易
-            }
-        }
-
-        Ok(transactions)
-    }
-
-    /// 解析交易数据
-    fn parse_transaction(&self, hex_data: &str) -> Result<Transaction> {
-        // 移除 0x 前缀
-        let hex_clean = hex_data.trim_start_matches("0x").trim_start_matches("0X");
-        
-        // 解码十六进制
-        let bytes = hex::decode(hex_clean)?;
-        
-        // 转换为 UTF-8 字符串
-        let json_str = String::from_utf8(bytes)?;
-        
-        // 解析 JSON
-        let tx: Transaction = serde_json::from_str(&json_str)?;
-        
-        Ok(tx)
-    }
-
     /// 计算交易根哈希
+    ///
+    /// 默认按黄皮书构造：以索引的 RLP 编码为键（不做哈希）、交易的类型化
+    /// RLP 编码为值，构建一棵 Merkle-Patricia Trie，根哈希用 Keccak256——
+    /// 与 [`Self::submit_to_execution_layer`] 执行后用
+    /// `block_producer::utils::calculate_merkle_root` 重新计算、最终写入
+    /// 区块头的值完全一致，外部 explorer/light client 都能校验。
+    ///
+    /// `legacy_sha256_roots` 打开时退回到旧的 SHA-256(JSON 拼接) 算法，
+    /// 只用于兼容依赖旧根值的现有测试——新部署不应打开。
     fn calculate_transactions_root(&self, transactions: &[Transaction]) -> String {
-        let mut hasher = Sha256::new();
-        
-        for tx in transactions {
-            let tx_json = serde_json::to_string(tx).unwrap();
-            hasher.update(tx_json.as_bytes());
+        if self.legacy_sha256_roots {
+            let mut hasher = Sha256::new();
+            for tx in transactions {
+                let tx_json = serde_json::to_string(tx).unwrap();
+                hasher.update(tx_json.as_bytes());
+            }
+            return format!("0x{:x}", hasher.finalize());
         }
-        
-        format!("0x{:x}", hasher.finalize())
+
+        let schema_txs: Vec<schema::Transaction> =
+            transactions.iter().map(Transaction::to_schema_tx).collect();
+        let root = block_producer::utils::calculate_merkle_root(&schema_txs);
+        format!("0x{}", hex::encode(root.as_slice()))
     }
 
     /// 提交区块给执行层
@@ -424,23 +653,22 @@ impl BlockProducer {
         
         // 6. 计算交易根
         let transactions_root = calculate_merkle_root(&schema_block.transactions);
-        
-        // 7. 计算收据根
+
+        // 7. 收据根与区块级 logs bloom：由 `execute_block` 按交易索引聚合好直接拿来用，
+        //    而不是在这里对哈希表迭代顺序不确定的 `receipts` 重新计算一遍
         let receipts: Vec<_> = execution_result.receipts.values().cloned().collect();
-        let receipts_root = if !receipts.is_empty() {
-            calculate_merkle_root(&receipts)
-        } else {
-            block_producer::utils::EMPTY_ROOT_HASH
-        };
-        
+
         // 8. 更新区块头
         block.header.state_root = Some(format!("0x{}", hex::encode(state_root.as_slice())));
         block.header.gas_used = Some(execution_result.total_gas_used);
         block.header.transactions_root = format!("0x{}", hex::encode(transactions_root.as_slice()));
-        block.header.receipts_root = Some(format!("0x{}", hex::encode(receipts_root.as_slice())));
+        block.header.receipts_root = Some(format!("0x{}", hex::encode(execution_result.receipts_root.as_slice())));
+        block.header.logs_bloom = Some(format!("0x{}", hex::encode(execution_result.logs_bloom.as_bytes())));
         
-        // 9. 持久化区块到数据库
-        executor.db_mut().save_block(&schema_block)
+        // 9. 持久化区块（连同本区块的收据、按哈希/交易的二级索引）到数据库
+        //    用更新后的 `block` 重新转换，确保落盘的区块头携带第 8 步算出的状态根/交易根/收据根/bloom
+        let final_schema_block = self.convert_to_schema_block(block)?;
+        executor.db_mut().save_block(&final_schema_block, &receipts)
             .map_err(|e| anyhow::anyhow!("Failed to save block: {}", e))?;
         
         info!("   ✓ 执行完成: {} 成功, {} 失败",
@@ -454,24 +682,10 @@ impl BlockProducer {
     
     /// 转换区块格式
     fn convert_to_schema_block(&self, block: &Block) -> Result<block_producer::schema::Block> {
-        use block_producer::schema::{Block as SchemaBlock, BlockHeader as SchemaHeader, Transaction as SchemaTx};
-        
+        use block_producer::schema::{Block as SchemaBlock, BlockHeader as SchemaHeader};
+
         // 转换交易列表
-        let transactions: Vec<SchemaTx> = block.transactions.iter().map(|tx| {
-            SchemaTx {
-                from: tx.from.clone(),
-                to: tx.to.clone(),
-                value: tx.value.clone(),
-                data: tx.data.clone(),
-                gas: tx.gas.clone(),
-                nonce: tx.nonce.clone(),
-                hash: tx.hash.clone(),
-                gas_price: None,
-                chain_id: None,
-                max_fee_per_gas: None,
-                max_priority_fee_per_gas: None,
-            }
-        }).collect();
+        let transactions = block.transactions.iter().map(Transaction::to_schema_tx).collect();
         
         Ok(SchemaBlock {
             header: SchemaHeader {
@@ -484,6 +698,8 @@ impl BlockProducer {
                 gas_used: block.header.gas_used,
                 gas_limit: block.header.gas_limit,
                 receipts_root: block.header.receipts_root.clone(),
+                logs_bloom: block.header.logs_bloom.clone(),
+                base_fee_per_gas: block.header.base_fee_per_gas,
             },
             transactions,
         })
@@ -505,6 +721,7 @@ async fn main() -> Result<()> {
         args.topic.clone(),
         args.block_interval,
         args.max_txs_per_block,
+        args.legacy_sha256_roots,
     );
 
     // 启动
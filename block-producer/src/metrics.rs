@@ -0,0 +1,61 @@
+//! 性能监控指标模块
+//!
+//! 使用 Prometheus 格式的指标，用于监控状态缓存/存储层的性能
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_counter_vec, register_histogram_vec, register_int_gauge, CounterVec, HistogramVec,
+    IntGauge,
+};
+
+lazy_static! {
+    /// 批量写入 Walrus 的耗时（秒）
+    pub static ref WALRUS_WRITE_DURATION: HistogramVec = register_histogram_vec!(
+        "block_producer_walrus_write_duration_seconds",
+        "Walrus write operation duration in seconds",
+        &["source"],
+        vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+    )
+    .expect("Failed to register walrus_write_duration metric");
+
+    /// 单条 Walrus 读取的耗时（秒），与 `WALRUS_WRITE_DURATION` 对应
+    pub static ref WALRUS_READ_DURATION: HistogramVec = register_histogram_vec!(
+        "block_producer_walrus_read_duration_seconds",
+        "Walrus read operation duration in seconds",
+        &["source"],
+        vec![0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0]
+    )
+    .expect("Failed to register walrus_read_duration metric");
+
+    /// 批量处理统计（例如一次 flush 落盘的脏条目数量）
+    pub static ref BATCH_SIZE: HistogramVec = register_histogram_vec!(
+        "block_producer_batch_size",
+        "Size of batched operations",
+        &["operation"],
+        vec![1.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0]
+    )
+    .expect("Failed to register batch_size metric");
+
+    /// `StateCache` 命中次数，按 `CacheKey` 变体（account/storage/code/block_hash）统计
+    pub static ref CACHE_HITS: CounterVec = register_counter_vec!(
+        "block_producer_cache_hits_total",
+        "Number of StateCache lookups that hit",
+        &["key_kind"]
+    )
+    .expect("Failed to register cache_hits metric");
+
+    /// `StateCache` 未命中次数，按 `CacheKey` 变体统计
+    pub static ref CACHE_MISSES: CounterVec = register_counter_vec!(
+        "block_producer_cache_misses_total",
+        "Number of StateCache lookups that missed",
+        &["key_kind"]
+    )
+    .expect("Failed to register cache_misses metric");
+
+    /// 当前缓存中条目数量（用于判断是否需要调大容量）
+    pub static ref CACHE_OCCUPANCY: IntGauge = register_int_gauge!(
+        "block_producer_cache_occupancy",
+        "Current number of entries held in the StateCache"
+    )
+    .expect("Failed to register cache_occupancy metric");
+}
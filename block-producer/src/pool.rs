@@ -0,0 +1,381 @@
+//! 交易池：按发送方分桶、nonce 有序、跨发送方按有效小费排名
+//!
+//! 替换了此前 `VecDeque<Transaction>` + `select_transactions_for_block` 里
+//! 全局按 gas price 排序的方案——那种方案只看跨账户的 gas price，不管同一个
+//! 发送方内部的 nonce 顺序，可能把发送方 A 的 nonce 5 排到 nonce 3 前面，
+//! 产出一个执行层必然会拒绝的非法区块顺序。这里每个发送方的交易独立按
+//! nonce 排序，只有“下一个就绪 nonce”对应的那笔交易才参与跨发送方的排名；
+//! 中间有 nonce 空缺的交易自动处于非就绪（parked）状态，空缺被填上之后
+//! 才会变得就绪，不需要额外的状态迁移。
+
+use crate::Transaction;
+use alloy_primitives::{Address, U256};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+
+/// 替换同一 (发送方, nonce) 的旧交易所需的最小加价比例（默认 10%）
+pub const DEFAULT_MIN_FEE_BUMP_PERCENT: u64 = 10;
+
+/// 单个发送方在池中的全部交易
+#[derive(Debug, Default)]
+struct SenderTxs {
+    /// 按 nonce 升序排列的全部已知交易；同一 nonce 只保留胜出的那一笔
+    /// （首次插入，或 replace-by-fee 赢家），从不出现重复 key
+    by_nonce: BTreeMap<u64, Transaction>,
+    /// 下一个期望被打包的 nonce：该发送方第一笔交易进池时的 nonce 即为初始值，
+    /// 此后每次其交易被选中打包 +1。这是池内部维护的顺序锚点，和链上账户
+    /// nonce 无关——真正的链上 nonce 校验在执行层完成，这里只保证池内顺序
+    /// 不会把同一发送方的交易乱序打包。
+    next_ready_nonce: Option<u64>,
+}
+
+impl SenderTxs {
+    fn ready(&self) -> Option<&Transaction> {
+        self.next_ready_nonce.and_then(|nonce| self.by_nonce.get(&nonce))
+    }
+
+    fn len(&self) -> usize {
+        self.by_nonce.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.by_nonce.is_empty()
+    }
+}
+
+/// 池统计信息，供日志/监控读取
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PoolStats {
+    /// 就绪交易数（每个发送方至多贡献 1 笔：其 `next_ready_nonce` 对应的那笔）
+    pub ready: usize,
+    /// 因 nonce 空缺暂不就绪、排队等待的交易数
+    pub parked: usize,
+    /// 持有至少一笔交易的发送方数量
+    pub senders: usize,
+}
+
+/// 交易池操作错误
+#[derive(Debug, thiserror::Error)]
+pub enum PoolError {
+    #[error("invalid sender address: {0}")]
+    InvalidSender(String),
+
+    #[error("invalid nonce: {0}")]
+    InvalidNonce(String),
+
+    #[error("replacement transaction underpriced: must exceed existing fee by at least {required_bump_percent}%")]
+    Underpriced { required_bump_percent: u64 },
+
+    #[error("transaction pool is full ({max_size} transactions)")]
+    Full { max_size: usize },
+}
+
+/// 交易池：按发送方地址分组、nonce 有序的交易集合
+pub struct TxPool {
+    senders: HashMap<Address, SenderTxs>,
+    max_size: usize,
+    min_fee_bump_percent: u64,
+}
+
+impl TxPool {
+    pub fn new(max_size: usize) -> Self {
+        Self::with_min_fee_bump_percent(max_size, DEFAULT_MIN_FEE_BUMP_PERCENT)
+    }
+
+    pub fn with_min_fee_bump_percent(max_size: usize, min_fee_bump_percent: u64) -> Self {
+        Self {
+            senders: HashMap::new(),
+            max_size,
+            min_fee_bump_percent,
+        }
+    }
+
+    /// 池中全部交易数（就绪 + 排队）
+    pub fn len(&self) -> usize {
+        self.senders.values().map(SenderTxs::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.senders.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() >= self.max_size
+    }
+
+    /// 插入一笔新提交的交易
+    ///
+    /// 同一 (发送方, nonce) 已存在时按 replace-by-fee 规则判定：新交易的
+    /// 声明费用（[`Transaction::declared_fee`]）必须比旧交易高出至少
+    /// `min_fee_bump_percent`，否则拒绝——这和 geth/reth 的 txpool 语义一致，
+    /// 防止攻击者用几乎同价的替换交易反复驱逐池里的交易。
+    pub fn insert(&mut self, tx: Transaction) -> Result<(), PoolError> {
+        let schema_tx = tx.to_schema_tx();
+        let address = tx.sender_address().map_err(PoolError::InvalidSender)?;
+        let nonce = schema_tx.nonce_value().map_err(PoolError::InvalidNonce)?;
+
+        let is_full = self.is_full();
+        let sender = self.senders.entry(address).or_default();
+
+        if let Some(existing) = sender.by_nonce.get(&nonce) {
+            let old_fee = existing.declared_fee();
+            let new_fee = tx.declared_fee();
+            let required = old_fee + old_fee * U256::from(self.min_fee_bump_percent) / U256::from(100u64);
+            if new_fee <= required {
+                return Err(PoolError::Underpriced {
+                    required_bump_percent: self.min_fee_bump_percent,
+                });
+            }
+        } else if is_full {
+            return Err(PoolError::Full { max_size: self.max_size });
+        }
+
+        if sender.next_ready_nonce.is_none() {
+            sender.next_ready_nonce = Some(nonce);
+        }
+        sender.by_nonce.insert(nonce, tx);
+        Ok(())
+    }
+
+    /// 把一笔交易原样放回池中——用于打包之后因区块 gas 不足/执行失败而需要
+    /// 撤销"已选中"状态的交易，不是一笔新提交，因此跳过 replace-by-fee 和
+    /// 容量校验，并把该发送方的"下一个就绪 nonce"回退到这笔交易，使它重新
+    /// 变得就绪（对称于 `pop` 把它标记为已打包时所做的 `next_ready_nonce += 1`）。
+    pub fn reinsert(&mut self, tx: Transaction) {
+        let schema_tx = tx.to_schema_tx();
+        let (Ok(address), Ok(nonce)) = (schema_tx.from_address(), schema_tx.nonce_value()) else {
+            // 地址/nonce 在首次 insert 时已经校验过，这里不应该失败；
+            // 万一出现也只能静默丢弃，不能 panic 污染出块主循环
+            return;
+        };
+        let sender = self.senders.entry(address).or_default();
+        sender.next_ready_nonce = Some(match sender.next_ready_nonce {
+            Some(current) if current <= nonce => current,
+            _ => nonce,
+        });
+        sender.by_nonce.insert(nonce, tx);
+    }
+
+    /// 池统计信息：就绪/排队交易数、持有交易的发送方数
+    pub fn stats(&self) -> PoolStats {
+        let senders = self.senders.len();
+        let ready = self.senders.values().filter(|s| s.ready().is_some()).count();
+        let total = self.len();
+        PoolStats {
+            ready,
+            parked: total.saturating_sub(ready),
+            senders,
+        }
+    }
+
+    /// 每个发送方当前持有的交易数（就绪 + 排队），用于更细粒度的日志/监控
+    pub fn sender_depths(&self) -> Vec<(Address, usize)> {
+        self.senders.iter().map(|(addr, s)| (*addr, s.len())).collect()
+    }
+
+    /// 开启一轮以 `base_fee` 为准的打包会话
+    ///
+    /// base fee 在一轮打包内是固定的（由父区块头推导一次），所以堆只需要在
+    /// 会话开始时按当前各发送方的就绪交易建一次，会话内每次 `pop_best` 之后
+    /// 再按同一个 `base_fee` 把该发送方的下一笔就绪交易重新入堆——而不是每次
+    /// `pop` 都重新扫描全部发送方重建堆。
+    pub fn begin_round(&mut self, base_fee: U256) -> PackingRound<'_> {
+        let heap = self.senders.iter()
+            .filter_map(|(address, sender)| {
+                let tip = sender.ready()?.tip_above_base_fee(base_fee)?;
+                Some(HeapEntry { tip, address: *address })
+            })
+            .collect();
+        PackingRound { pool: self, base_fee, heap }
+    }
+}
+
+/// 堆中的一个条目：某个发送方的"就绪"交易相对 `base_fee` 的小费
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct HeapEntry {
+    tip: u64,
+    address: Address,
+}
+
+impl PartialOrd for HeapEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for HeapEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // tip 相同时按地址排个确定的序，避免 `BinaryHeap` 在同分值条目之间
+        // 的弹出顺序依赖哈希迭代顺序而不可复现
+        self.tip.cmp(&other.tip).then_with(|| self.address.cmp(&other.address))
+    }
+}
+
+/// 一轮固定 base fee 下的打包会话，见 [`TxPool::begin_round`]
+pub struct PackingRound<'a> {
+    pool: &'a mut TxPool,
+    base_fee: U256,
+    heap: BinaryHeap<HeapEntry>,
+}
+
+impl<'a> PackingRound<'a> {
+    /// 弹出全局最优的下一笔可打包交易：每个发送方只贡献其"下一个就绪 nonce"
+    /// 对应的那一笔，跨发送方按有效小费排名。被当前 base fee 封顶拒绝的发送方
+    /// （`max_fee_per_gas < base_fee`）本轮不参与排名——交易仍留在池里，等下一
+    /// 个区块 base fee 变化后再重新参与。
+    pub fn pop_best(&mut self) -> Option<Transaction> {
+        loop {
+            let HeapEntry { address, .. } = self.heap.pop()?;
+            let Some(sender) = self.pool.senders.get_mut(&address) else { continue };
+            let Some(nonce) = sender.next_ready_nonce else { continue };
+            let Some(tx) = sender.by_nonce.remove(&nonce) else { continue };
+
+            sender.next_ready_nonce = Some(nonce + 1);
+            if let Some(next_tip) = sender.ready().and_then(|t| t.tip_above_base_fee(self.base_fee)) {
+                self.heap.push(HeapEntry { tip: next_tip, address });
+            }
+            if sender.is_empty() {
+                self.pool.senders.remove(&address);
+            }
+            return Some(tx);
+        }
+    }
+
+    /// 把一笔本轮弹出、但因为区块 gas 不足未能装进区块的交易放回池中。
+    /// 不重新加入本轮的堆——同一轮里剩余 gas 只会越来越少，这笔交易在本轮
+    /// 不可能再适配，留给下一轮处理。
+    pub fn reinsert(&mut self, tx: Transaction) {
+        // `pop_best` 已经把该发送方的下一笔（nonce + 1）入堆；回退 next_ready_nonce
+        // 之后那个条目会再次弹出这笔交易，所以本轮把该发送方整个移出堆
+        if let Ok(address) = tx.sender_address() {
+            self.heap.retain(|entry| entry.address != address);
+        }
+        self.pool.reinsert(tx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(from: &str, nonce: u64, max_fee_gwei: u64) -> Transaction {
+        Transaction {
+            from: from.to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: format!("0x{:x}", nonce),
+            hash: None,
+            gas_price: None,
+            max_fee_per_gas: Some(format!("0x{:x}", max_fee_gwei * 1_000_000_000)),
+            max_priority_fee_per_gas: Some(format!("0x{:x}", max_fee_gwei * 1_000_000_000)),
+            v: None,
+            r: None,
+            s: None,
+            recovered_sender: None,
+        }
+    }
+
+    const SENDER_A: &str = "0x0000000000000000000000000000000000000001";
+    const SENDER_B: &str = "0x0000000000000000000000000000000000000002";
+
+    #[test]
+    fn test_pop_best_respects_sender_nonce_order() {
+        let mut pool = TxPool::new(100);
+        // 发送方 A 的 nonce 5 出价远高于 nonce 3，但 nonce 3 必须先被打包
+        pool.insert(tx(SENDER_A, 3, 10)).unwrap();
+        pool.insert(tx(SENDER_A, 5, 1000)).unwrap();
+
+        let base_fee = U256::from(1_000_000_000u64);
+        let mut round = pool.begin_round(base_fee);
+        let first = round.pop_best().unwrap();
+        assert_eq!(first.nonce, "0x3");
+        // nonce 5 还在 gap 后面，不会在这一轮出现（next_ready_nonce 现在是 4）
+        assert!(round.pop_best().is_none());
+    }
+
+    #[test]
+    fn test_pop_best_ranks_across_senders_by_tip() {
+        let mut pool = TxPool::new(100);
+        pool.insert(tx(SENDER_A, 0, 5)).unwrap();
+        pool.insert(tx(SENDER_B, 0, 50)).unwrap();
+
+        let base_fee = U256::from(1_000_000_000u64);
+        let mut round = pool.begin_round(base_fee);
+        let first = round.pop_best().unwrap();
+        assert_eq!(first.from, SENDER_B);
+        let second = round.pop_best().unwrap();
+        assert_eq!(second.from, SENDER_A);
+    }
+
+    #[test]
+    fn test_replace_by_fee_requires_minimum_bump() {
+        let mut pool = TxPool::new(100);
+        pool.insert(tx(SENDER_A, 0, 20)).unwrap();
+
+        // 只涨 5%，低于默认 10% 的最小加价要求，应当被拒绝
+        let err = pool.insert(tx(SENDER_A, 0, 20 * 105 / 100)).unwrap_err();
+        assert!(matches!(err, PoolError::Underpriced { .. }));
+        assert_eq!(pool.len(), 1);
+
+        // 涨 20%，超过最小加价要求，应当替换成功
+        pool.insert(tx(SENDER_A, 0, 24)).unwrap();
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_gapped_nonce_is_parked_until_gap_fills() {
+        let mut pool = TxPool::new(100);
+        pool.insert(tx(SENDER_A, 0, 10)).unwrap();
+        pool.insert(tx(SENDER_A, 2, 10)).unwrap(); // nonce 1 缺失，nonce 2 应当 parked
+
+        let stats = pool.stats();
+        assert_eq!(stats.ready, 1);
+        assert_eq!(stats.parked, 1);
+
+        let base_fee = U256::from(1_000_000_000u64);
+        {
+            let mut round = pool.begin_round(base_fee);
+            round.pop_best().unwrap(); // 打包 nonce 0
+            assert!(round.pop_best().is_none()); // nonce 2 仍因为 gap 未就绪
+        }
+
+        // 补上 nonce 1 之后，nonce 1 成为就绪交易，nonce 2 不再被空缺卡住、紧随其后
+        // （每个发送方只计 1 笔就绪，所以 nonce 2 在统计上仍算排队）
+        pool.insert(tx(SENDER_A, 1, 10)).unwrap();
+        let stats = pool.stats();
+        assert_eq!(stats.ready, 1);
+        assert_eq!(stats.parked, 1);
+    }
+
+    #[test]
+    fn test_fee_capped_sender_is_skipped_for_this_round_not_removed() {
+        let mut pool = TxPool::new(100);
+        pool.insert(tx(SENDER_A, 0, 1)).unwrap(); // max_fee 1 Gwei
+
+        let base_fee = U256::from(2_000_000_000u64); // 2 Gwei，高于该交易的 fee cap
+        let mut round = pool.begin_round(base_fee);
+        assert!(round.pop_best().is_none());
+        drop(round);
+
+        // 交易仍然留在池中，只是本轮没有参与排名
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_reinsert_restores_readiness_for_gas_rejected_tx() {
+        let mut pool = TxPool::new(100);
+        pool.insert(tx(SENDER_A, 0, 10)).unwrap();
+
+        let base_fee = U256::from(1_000_000_000u64);
+        let popped = {
+            let mut round = pool.begin_round(base_fee);
+            round.pop_best().unwrap()
+        };
+        assert_eq!(pool.stats().ready, 0);
+
+        pool.reinsert(popped);
+        assert_eq!(pool.stats().ready, 1);
+    }
+}
@@ -43,10 +43,15 @@ impl Account {
         self.code_hash != EMPTY_CODE_HASH
     }
     
-    /// 检查是否为空账户
+    /// 检查是否为空账户（EIP-161 口径：nonce 为 0、余额为 0、没有代码）
+    ///
+    /// 不检查 `storage_root`——EIP-161 对"空账户"的定义本就只看 nonce/余额/
+    /// 代码，状态树清理（见 [`crate::trie::state_root::CleanupMode`]）额外要求
+    /// `storage_root == EMPTY_STORAGE_ROOT` 是为了防御性地确保真的没有遗留
+    /// 存储，而不是放宽或改变这里的定义。
     pub fn is_empty(&self) -> bool {
-        self.nonce == 0 
-            && self.balance == U256::ZERO 
+        self.nonce == 0
+            && self.balance == U256::ZERO
             && self.code_hash == EMPTY_CODE_HASH
     }
     
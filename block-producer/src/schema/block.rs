@@ -2,10 +2,36 @@
 //! 
 //! 扩展原有的 Block 和 Transaction，添加 EVM 执行需要的字段
 
-use alloy_primitives::{Address, U256, B256, Bytes};
+use alloy_primitives::{Address, U256, B256, Bytes, keccak256};
 use alloy_rlp::{Encodable, BufMut};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use crate::schema::Bloom;
+
+/// 交易类型（EIP-2718）
+///
+/// 根据交易所填充的字段推断：带 `max_fee_per_gas` 的是 EIP-1559 动态费用交易；
+/// 否则若填充了 `access_list` 则为 EIP-2930；都没有时按传统（Legacy）交易处理。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxType {
+    /// 传统交易（无类型前缀）
+    Legacy,
+    /// EIP-2930 带访问列表交易（类型字节 0x01）
+    Eip2930,
+    /// EIP-1559 动态费用交易（类型字节 0x02）
+    Eip1559,
+}
+
+impl TxType {
+    /// EIP-2718 类型字节；Legacy 无前缀返回 None
+    pub fn type_byte(&self) -> Option<u8> {
+        match self {
+            TxType::Legacy => None,
+            TxType::Eip2930 => Some(0x01),
+            TxType::Eip1559 => Some(0x02),
+        }
+    }
+}
 
 /// 交易数据结构（扩展版）
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +58,22 @@ pub struct Transaction {
     
     /// EIP-1559: 最大优先费用（可选）
     pub max_priority_fee_per_gas: Option<String>,
+
+    /// EIP-2930: 访问列表（地址 -> 预热的存储槽），可选
+    #[serde(default)]
+    pub access_list: Option<Vec<(Address, Vec<U256>)>>,
+
+    /// 签名 v 值（Legacy/EIP-155: `{0,1} + chain_id*2 + 35`；类型化交易: y_parity 0/1）
+    #[serde(default)]
+    pub v: Option<u64>,
+
+    /// 签名 r 值（十六进制字符串）
+    #[serde(default)]
+    pub r: Option<String>,
+
+    /// 签名 s 值（十六进制字符串）
+    #[serde(default)]
+    pub s: Option<String>,
 }
 
 impl Transaction {
@@ -84,41 +126,222 @@ impl Transaction {
     pub fn is_create(&self) -> bool {
         self.to.is_none()
     }
+
+    /// 推断交易类型（EIP-2718）
+    pub fn tx_type(&self) -> TxType {
+        if self.max_fee_per_gas.is_some() {
+            TxType::Eip1559
+        } else if self.access_list.is_some() {
+            TxType::Eip2930
+        } else {
+            TxType::Legacy
+        }
+    }
+
+    /// 访问列表的内在 gas 贡献
+    ///
+    /// 每个列出的地址 2400 gas，每个列出的存储槽 1900 gas（EIP-2930）。
+    /// 这些槽/地址在执行时视为已预热，交由 `TransactionExecutor` 计入 intrinsic gas。
+    pub fn access_list_gas(&self) -> u64 {
+        match &self.access_list {
+            Some(list) => list.iter().fold(0u64, |acc, (_, keys)| {
+                acc + 2_400 + 1_900 * keys.len() as u64
+            }),
+            None => 0,
+        }
+    }
+
+    /// 解析 gas price（Legacy / EIP-2930）
+    pub fn gas_price_value(&self) -> U256 {
+        decode_u256_opt(self.gas_price.as_deref())
+    }
+
+    /// 解析 max_fee_per_gas（EIP-1559）
+    pub fn max_fee_value(&self) -> U256 {
+        decode_u256_opt(self.max_fee_per_gas.as_deref())
+    }
+
+    /// 解析 max_priority_fee_per_gas（EIP-1559）
+    pub fn max_priority_fee_value(&self) -> U256 {
+        decode_u256_opt(self.max_priority_fee_per_gas.as_deref())
+    }
+
+    /// 计算实际支付的 gas 单价
+    ///
+    /// EIP-1559 交易：`min(max_fee_per_gas, base_fee + max_priority_fee_per_gas)`——
+    /// 销毁 `base_fee`，矿工/出块者拿到其余部分作为小费；Legacy/EIP-2930 交易
+    /// 沿用声明的 `gas_price`，不受 base fee 影响。
+    pub fn effective_gas_price(&self, base_fee: U256) -> U256 {
+        match self.tx_type() {
+            TxType::Eip1559 => {
+                let max_fee = self.max_fee_value();
+                let priority = self.max_priority_fee_value();
+                max_fee.min(base_fee.saturating_add(priority))
+            }
+            TxType::Legacy | TxType::Eip2930 => self.gas_price_value(),
+        }
+    }
+
+    /// 编码不含签名字段的交易负载（各类型字段顺序与以太坊共识层一致）
+    fn encode_unsigned_fields(&self, payload: &mut Vec<u8>) {
+        let nonce = self.nonce_value().unwrap_or_default();
+        let gas = self.gas_limit().unwrap_or_default();
+        let to = self.to_address().ok().flatten();
+        let value = self.value_wei().unwrap_or_default();
+        let data = self.data_bytes().unwrap_or_default();
+        let chain_id = self.chain_id.unwrap_or_default();
+
+        match self.tx_type() {
+            TxType::Legacy => {
+                nonce.encode(payload);
+                self.gas_price_value().encode(payload);
+                gas.encode(payload);
+                encode_to(&to, payload);
+                value.encode(payload);
+                data.encode(payload);
+            }
+            TxType::Eip2930 => {
+                chain_id.encode(payload);
+                nonce.encode(payload);
+                self.gas_price_value().encode(payload);
+                gas.encode(payload);
+                encode_to(&to, payload);
+                value.encode(payload);
+                data.encode(payload);
+                encode_access_list(self.access_list.as_deref().unwrap_or(&[]), payload);
+            }
+            TxType::Eip1559 => {
+                chain_id.encode(payload);
+                nonce.encode(payload);
+                self.max_priority_fee_value().encode(payload);
+                self.max_fee_value().encode(payload);
+                gas.encode(payload);
+                encode_to(&to, payload);
+                value.encode(payload);
+                data.encode(payload);
+                encode_access_list(self.access_list.as_deref().unwrap_or(&[]), payload);
+            }
+        }
+    }
+
+    /// 用类型字节（类型化交易）+ RLP 列表头包裹负载
+    fn wrap_payload(&self, payload: Vec<u8>) -> Vec<u8> {
+        let mut out = Vec::new();
+        if let Some(type_byte) = self.tx_type().type_byte() {
+            out.push(type_byte);
+        }
+        alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(&mut out);
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// EIP-155 签名预映像
+    ///
+    /// Legacy 交易按 EIP-155 在未签名字段后追加 `[chain_id, 0, 0]`，使签名天然
+    /// 绑定链 ID 以防止跨链重放；类型化交易（EIP-2930/1559）的 `chain_id` 已经
+    /// 是未签名字段的一部分，签名预映像就是不含 v/r/s 的类型化负载。
+    pub fn signing_hash(&self) -> B256 {
+        let mut payload = Vec::new();
+        self.encode_unsigned_fields(&mut payload);
+        if self.tx_type() == TxType::Legacy {
+            let chain_id = self.chain_id.unwrap_or_default();
+            chain_id.encode(&mut payload);
+            0u64.encode(&mut payload);
+            0u64.encode(&mut payload);
+        }
+        keccak256(self.wrap_payload(payload))
+    }
+
+    /// 计算交易的规范 RLP 编码
+    ///
+    /// 编码的是解码后的数值/字节，而非十六进制字符串，因此与以太坊共识层一致。
+    /// 已签名交易追加真实的 `v/r/s`；未签名时按 0 编码（不构成合法签名）。
+    pub fn canonical_encoding(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.encode_unsigned_fields(&mut payload);
+
+        match (self.v, self.r.as_deref(), self.s.as_deref()) {
+            (Some(v), Some(r), Some(s)) => {
+                v.encode(&mut payload);
+                decode_u256_opt(Some(r)).encode(&mut payload);
+                decode_u256_opt(Some(s)).encode(&mut payload);
+            }
+            _ => {
+                0u64.encode(&mut payload);
+                0u64.encode(&mut payload);
+                0u64.encode(&mut payload);
+            }
+        }
+
+        self.wrap_payload(payload)
+    }
+
+    /// 从规范编码计算交易哈希
+    ///
+    /// 对 [`canonical_encoding`](Self::canonical_encoding) 做 keccak256，
+    /// 使收据与交易根都从确定的编码派生，而不是信任调用方提供的 `hash` 字符串。
+    pub fn compute_hash(&self) -> B256 {
+        keccak256(self.canonical_encoding())
+    }
+
+    /// 从 EIP-155 签名恢复发送方地址
+    ///
+    /// 委托给 [`crate::signing::recover_sender`]：对 [`signing_hash`](Self::signing_hash)
+    /// 做 secp256k1 ECDSA 恢复，而不是信任调用方填充的 `from` 字段。也会拒绝
+    /// `s` 超过 `secp256k1n/2` 的可延展签名（EIP-2）。不对恢复出的地址与
+    /// `from` 做比对——调用方需要这一步时应使用
+    /// [`crate::signing::verify_and_recover_sender`]。
+    pub fn recover_sender(&self) -> Result<Address, String> {
+        let v = self.v.ok_or_else(|| "Missing signature v".to_string())?;
+        let r = decode_u256_opt(self.r.as_deref());
+        let s = decode_u256_opt(self.s.as_deref());
+
+        crate::signing::recover_sender(self.signing_hash(), self.tx_type(), self.chain_id, v, r, s)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 解析可选十六进制字符串为 U256（缺省或非法时回落为 0）
+fn decode_u256_opt(value: Option<&str>) -> U256 {
+    value
+        .map(|v| v.trim_start_matches("0x"))
+        .and_then(|v| U256::from_str_radix(v, 16).ok())
+        .unwrap_or_default()
+}
+
+/// RLP 编码接收方地址；合约部署（None）编码为空字节串
+fn encode_to(to: &Option<Address>, out: &mut Vec<u8>) {
+    match to {
+        Some(addr) => addr.encode(out),
+        None => (&[] as &[u8]).encode(out),
+    }
+}
+
+/// RLP 编码 EIP-2930 访问列表：`[[address, [storage_key, ...]], ...]`
+fn encode_access_list(list: &[(Address, Vec<U256>)], out: &mut Vec<u8>) {
+    let mut payload = Vec::new();
+    for (addr, keys) in list {
+        let mut entry = Vec::new();
+        addr.encode(&mut entry);
+        keys.encode(&mut entry);
+        alloy_rlp::Header { list: true, payload_length: entry.len() }.encode(&mut payload);
+        payload.extend_from_slice(&entry);
+    }
+    alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(out);
+    out.extend_from_slice(&payload);
 }
 
 // 为 Transaction 实现 RLP 编码
+//
+// 委托给 `canonical_encoding`，编码解码后的数值/字节而非十六进制字符串，
+// 使 `calculate_merkle_root` 得到的 transactions_root 与共识层一致。
 impl Encodable for Transaction {
     fn encode(&self, out: &mut dyn BufMut) {
-        // 简化实现：将交易作为列表编码
-        alloy_rlp::Header {
-            list: true,
-            payload_length: self.length() - 1,
-        }
-        .encode(out);
-        
-        self.from.encode(out);
-        // 处理 Option<String> 类型
-        match &self.to {
-            Some(to) => to.encode(out),
-            None => (&[] as &[u8]).encode(out), // 空字节数组表示 None
-        }
-        self.value.encode(out);
-        self.data.encode(out);
-        self.gas.encode(out);
-        self.nonce.encode(out);
+        out.put_slice(&self.canonical_encoding());
     }
-    
+
     fn length(&self) -> usize {
-        let payload_length = self.from.length()
-            + match &self.to {
-                Some(to) => to.length(),
-                None => (&[] as &[u8]).length(),
-            }
-            + self.value.length()
-            + self.data.length()
-            + self.gas.length()
-            + self.nonce.length();
-        payload_length + alloy_rlp::length_of_length(payload_length)
+        self.canonical_encoding().len()
     }
 }
 
@@ -152,6 +375,114 @@ pub struct BlockHeader {
     
     /// 收据根哈希（执行后填充）
     pub receipts_root: Option<String>,
+
+    /// Logs Bloom 过滤器（执行后填充，聚合区块内所有收据的 Bloom）
+    pub logs_bloom: Option<String>,
+
+    /// EIP-1559 基础费用（每单位 gas 被销毁的部分）
+    pub base_fee_per_gas: Option<U256>,
+}
+
+/// EIP-1559 gas 目标相对 `gas_limit` 的弹性倍数（目标为上限的一半）
+const ELASTICITY_MULTIPLIER: u64 = 2;
+/// EIP-1559 base fee 单个区块内最大变动幅度的分母（最多变动 1/8）
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+impl BlockHeader {
+    /// 解析十六进制哈希字符串为 B256（缺省/非法/长度不符时回落为零哈希）
+    fn decode_hash(value: &str) -> B256 {
+        let hex = value.trim_start_matches("0x");
+        match hex::decode(hex) {
+            Ok(bytes) if bytes.len() == 32 => B256::from_slice(&bytes),
+            _ => B256::ZERO,
+        }
+    }
+
+    /// 解析可选十六进制哈希字符串（`None` 回落为零哈希）
+    fn decode_hash_opt(value: &Option<String>) -> B256 {
+        value.as_deref().map(Self::decode_hash).unwrap_or_default()
+    }
+
+    /// 解析 Logs Bloom 十六进制字符串（`None`/长度不符时回落为全零 Bloom）
+    ///
+    /// `pub(crate)`：db 层的 `BlockProvider::logs_matching` 需要在不反序列化整个收据的
+    /// 情况下，先用区块头里的 bloom 做快速排除。
+    pub(crate) fn decode_bloom(value: &Option<String>) -> Bloom {
+        let hex = match value.as_deref().map(|v| v.trim_start_matches("0x")) {
+            Some(hex) => hex,
+            None => return Bloom::zero(),
+        };
+        match hex::decode(hex) {
+            Ok(bytes) if bytes.len() == 256 => Bloom(bytes.try_into().unwrap()),
+            _ => Bloom::zero(),
+        }
+    }
+
+    /// 计算区块头的规范 RLP 编码
+    ///
+    /// 将十六进制字符串字段解码为真实的哈希/字节类型再编码，
+    /// 使 [`Block::hash`] 得到的区块哈希与共识层一致。
+    pub fn rlp_encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.number.encode(&mut payload);
+        Self::decode_hash(&self.parent_hash).encode(&mut payload);
+        (self.timestamp.timestamp() as u64).encode(&mut payload);
+        (self.tx_count as u64).encode(&mut payload);
+        Self::decode_hash(&self.transactions_root).encode(&mut payload);
+        Self::decode_hash_opt(&self.state_root).encode(&mut payload);
+        self.gas_used.unwrap_or_default().encode(&mut payload);
+        self.gas_limit.unwrap_or_default().encode(&mut payload);
+        Self::decode_hash_opt(&self.receipts_root).encode(&mut payload);
+        (&Self::decode_bloom(&self.logs_bloom).as_bytes()[..]).encode(&mut payload);
+        self.base_fee_per_gas.unwrap_or_default().encode(&mut payload);
+
+        let mut out = Vec::new();
+        alloy_rlp::Header { list: true, payload_length: payload.len() }.encode(&mut out);
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// EIP-1559 gas 目标：`gas_limit / ELASTICITY_MULTIPLIER`
+    ///
+    /// 与 [`calculate_next_base_fee`](Self::calculate_next_base_fee) 用的是
+    /// 同一个目标，供区块执行层据此判断本区块是"高于/低于目标"运行——那正是
+    /// 下一个区块 base fee 涨跌所依据的同一个信号。
+    pub fn gas_target(&self) -> u64 {
+        self.gas_limit.unwrap_or_default() / ELASTICITY_MULTIPLIER
+    }
+
+    /// 计算下一个区块的 base fee（EIP-1559 费用市场）
+    ///
+    /// 以 `self`（父区块头）已执行完的 `gas_used`/`gas_limit`/`base_fee_per_gas`
+    /// 为输入：`gas_target = gas_limit / 2`；用量等于目标时 base fee 不变；
+    /// 高于目标时按差值比例上调（至少涨 1 wei）；低于目标时按差值比例下调。
+    pub fn calculate_next_base_fee(&self) -> U256 {
+        let parent_base_fee = self.base_fee_per_gas.unwrap_or_default();
+        let gas_limit = self.gas_limit.unwrap_or_default();
+        let gas_used = self.gas_used.unwrap_or_default();
+
+        let gas_target = gas_limit / ELASTICITY_MULTIPLIER;
+        if gas_target == 0 {
+            return parent_base_fee;
+        }
+
+        match gas_used.cmp(&gas_target) {
+            std::cmp::Ordering::Equal => parent_base_fee,
+            std::cmp::Ordering::Greater => {
+                let delta = U256::from(gas_used - gas_target);
+                let increase = (parent_base_fee * delta / U256::from(gas_target)
+                    / U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR))
+                    .max(U256::from(1));
+                parent_base_fee.saturating_add(increase)
+            }
+            std::cmp::Ordering::Less => {
+                let delta = U256::from(gas_target - gas_used);
+                let decrease = parent_base_fee * delta / U256::from(gas_target)
+                    / U256::from(BASE_FEE_MAX_CHANGE_DENOMINATOR);
+                parent_base_fee.saturating_sub(decrease)
+            }
+        }
+    }
 }
 
 /// 区块
@@ -163,14 +494,12 @@ pub struct Block {
 
 impl Block {
     /// 计算区块哈希
+    ///
+    /// keccak256(RLP(区块头))，与共识层一致——而非对头部 JSON 做 SHA256。
     pub fn hash(&self) -> String {
-        use sha2::{Digest, Sha256};
-        let data = serde_json::to_string(&self.header).unwrap();
-        let mut hasher = Sha256::new();
-        hasher.update(data.as_bytes());
-        format!("0x{:x}", hasher.finalize())
+        format!("0x{}", hex::encode(keccak256(self.header.rlp_encode())))
     }
-    
+
     /// 获取区块号
     pub fn number(&self) -> u64 {
         self.header.number
@@ -217,9 +546,30 @@ pub struct TransactionReceipt {
     
     /// 事件日志
     pub logs: Vec<Log>,
-    
+
     /// Logs Bloom 过滤器
-    pub logs_bloom: Bytes,
+    pub logs_bloom: Bloom,
+
+    /// 交易类型（EIP-2718 类型字节；Legacy 交易记为 0，与 JSON-RPC 惯例一致）
+    pub transaction_type: u8,
+
+    /// 实际支付的 gas 单价（[`Transaction::effective_gas_price`]）
+    ///
+    /// Legacy/EIP-2930 交易等于声明的 `gas_price`；EIP-1559 交易等于
+    /// `min(max_fee_per_gas, base_fee + max_priority_fee_per_gas)`——这是
+    /// 余额扣除实际使用的单价，而不是 `max_fee_per_gas`。
+    pub effective_gas_price: U256,
+}
+
+impl TransactionReceipt {
+    /// 聚合本收据所有日志的 Bloom（每个日志的地址 + 全部 topics）
+    pub fn compute_logs_bloom(&self) -> Bloom {
+        let mut bloom = Bloom::zero();
+        for log in &self.logs {
+            bloom.or(&log.bloom());
+        }
+        bloom
+    }
 }
 
 /// 事件日志
@@ -227,26 +577,38 @@ pub struct TransactionReceipt {
 pub struct Log {
     /// 合约地址
     pub address: Address,
-    
+
     /// Topics（索引字段）
     pub topics: Vec<B256>,
-    
+
     /// Data（非索引字段）
     pub data: Bytes,
-    
+
     /// 区块号
     pub block_number: u64,
-    
+
     /// 交易哈希
     pub transaction_hash: B256,
-    
+
     /// 交易索引
     pub transaction_index: u64,
-    
+
     /// 日志索引
     pub log_index: u64,
 }
 
+impl Log {
+    /// 该日志单独的 Bloom：折叠合约地址与每个 topic
+    pub fn bloom(&self) -> Bloom {
+        let mut bloom = Bloom::zero();
+        bloom.add(self.address.as_slice());
+        for topic in &self.topics {
+            bloom.add(topic.as_slice());
+        }
+        bloom
+    }
+}
+
 // 为 TransactionReceipt 实现 RLP 编码
 impl Encodable for TransactionReceipt {
     fn encode(&self, out: &mut dyn BufMut) {
@@ -259,14 +621,14 @@ impl Encodable for TransactionReceipt {
         
         self.status.encode(out);
         self.cumulative_gas_used.encode(out);
-        self.logs_bloom.encode(out);
+        (&self.logs_bloom.as_bytes()[..]).encode(out);
         self.logs.encode(out);
     }
-    
+
     fn length(&self) -> usize {
         let payload_length = self.status.length()
             + self.cumulative_gas_used.length()
-            + self.logs_bloom.length()
+            + (&self.logs_bloom.as_bytes()[..]).length()
             + self.logs.length();
         payload_length + alloy_rlp::length_of_length(payload_length)
     }
@@ -312,6 +674,10 @@ mod tests {
             chain_id: Some(1),
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         assert!(tx.from_address().is_ok());
@@ -336,8 +702,224 @@ mod tests {
             chain_id: None,
             max_fee_per_gas: None,
             max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
         };
         
         assert!(tx.is_create());
     }
+
+    #[test]
+    fn test_tx_type_inference() {
+        let legacy = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: None,
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+        assert_eq!(legacy.tx_type(), TxType::Legacy);
+        assert_eq!(legacy.tx_type().type_byte(), None);
+
+        let eip1559 = Transaction {
+            max_fee_per_gas: Some("0x77359400".to_string()),
+            max_priority_fee_per_gas: Some("0x3b9aca00".to_string()),
+            ..legacy
+        };
+        assert_eq!(eip1559.tx_type(), TxType::Eip1559);
+        assert_eq!(eip1559.tx_type().type_byte(), Some(0x02));
+    }
+
+    #[test]
+    fn test_compute_hash_is_deterministic_and_type_sensitive() {
+        let legacy = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0xde0b6b3a7640000".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let hash_a = legacy.compute_hash();
+        let hash_b = legacy.compute_hash();
+        assert_eq!(hash_a, hash_b);
+
+        let eip1559 = Transaction {
+            max_fee_per_gas: Some("0x77359400".to_string()),
+            max_priority_fee_per_gas: Some("0x3b9aca00".to_string()),
+            ..legacy
+        };
+        assert_ne!(eip1559.compute_hash(), hash_a);
+        assert_eq!(eip1559.canonical_encoding()[0], 0x02);
+    }
+
+    #[test]
+    fn test_access_list_gas_and_type_inference() {
+        let addr_a: Address = "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".parse().unwrap();
+        let addr_b: Address = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse().unwrap();
+
+        let tx = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: Some(vec![
+                (addr_a, vec![U256::from(1), U256::from(2)]),
+                (addr_b, vec![]),
+            ]),
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        assert_eq!(tx.tx_type(), TxType::Eip2930);
+        assert_eq!(tx.tx_type().type_byte(), Some(0x01));
+        // 2 个地址 * 2400 + 2 个存储槽 * 1900
+        assert_eq!(tx.access_list_gas(), 2 * 2_400 + 2 * 1_900);
+
+        let no_list = Transaction { access_list: None, ..tx.clone() };
+        assert_eq!(no_list.access_list_gas(), 0);
+        assert_eq!(no_list.tx_type(), TxType::Legacy);
+    }
+
+    #[test]
+    fn test_recover_sender_requires_signature() {
+        let tx = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        assert!(tx.recover_sender().is_err());
+
+        let partially_signed = Transaction { v: Some(37), ..tx };
+        assert!(partially_signed.recover_sender().is_err());
+    }
+
+    fn sample_header(gas_used: u64, gas_limit: u64, base_fee: u64) -> BlockHeader {
+        BlockHeader {
+            number: 1,
+            parent_hash: "0x0".to_string(),
+            timestamp: Utc::now(),
+            tx_count: 0,
+            transactions_root: "0x".to_string(),
+            state_root: None,
+            gas_used: Some(gas_used),
+            gas_limit: Some(gas_limit),
+            receipts_root: None,
+            logs_bloom: None,
+            base_fee_per_gas: Some(U256::from(base_fee)),
+        }
+    }
+
+    #[test]
+    fn test_calculate_next_base_fee_stays_at_target() {
+        let header = sample_header(10_000_000, 20_000_000, 1_000_000_000);
+        assert_eq!(header.calculate_next_base_fee(), U256::from(1_000_000_000u64));
+    }
+
+    #[test]
+    fn test_calculate_next_base_fee_increases_above_target() {
+        // gas_used = gas_limit（满载），超出目标（gas_limit/2）的那一半
+        let header = sample_header(20_000_000, 20_000_000, 1_000_000_000);
+        let next = header.calculate_next_base_fee();
+        assert!(next > U256::from(1_000_000_000u64));
+    }
+
+    #[test]
+    fn test_calculate_next_base_fee_decreases_below_target() {
+        let header = sample_header(0, 20_000_000, 1_000_000_000);
+        let next = header.calculate_next_base_fee();
+        assert!(next < U256::from(1_000_000_000u64));
+    }
+
+    #[test]
+    fn test_effective_gas_price_eip1559_caps_at_max_fee() {
+        let tx = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: None,
+            chain_id: Some(1),
+            max_fee_per_gas: Some("0x3b9aca00".to_string()), // 1 Gwei
+            max_priority_fee_per_gas: Some("0x77359400".to_string()), // 2 Gwei
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        // base_fee(0.5 Gwei) + priority(2 Gwei) 超过 max_fee(1 Gwei)，应封顶于 max_fee
+        let price = tx.effective_gas_price(U256::from(500_000_000u64));
+        assert_eq!(price, U256::from(1_000_000_000u64));
+    }
+
+    #[test]
+    fn test_effective_gas_price_legacy_ignores_base_fee() {
+        let tx = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()), // 1 Gwei
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        let price = tx.effective_gas_price(U256::from(999_999_999_999u64));
+        assert_eq!(price, U256::from(1_000_000_000u64));
+    }
 }
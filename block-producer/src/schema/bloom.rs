@@ -0,0 +1,164 @@
+//! Logs Bloom 过滤器
+//!
+//! 以太坊风格的 2048 位（256 字节）Bloom filter，用于无需扫描每笔收据
+//! 即可快速判断某个地址/主题是否 *可能* 出现在一个区块/收据的日志中。
+
+use alloy_primitives::{keccak256, Address, B256};
+use serde::{Deserialize, Serialize};
+
+/// 2048 位 Bloom filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bloom(#[serde(with = "serde_bytes_256")] pub [u8; 256]);
+
+impl Bloom {
+    /// 全零 Bloom
+    pub fn zero() -> Self {
+        Self([0u8; 256])
+    }
+
+    /// 将一项（合约地址或 topic 的字节表示）折叠进 Bloom
+    ///
+    /// 对该项做 keccak256，取哈希中偏移 (0,1)/(2,3)/(4,5) 三对字节，
+    /// 各自与 `0x7FF` 相与得到 0..2047 的位索引并置位——与以太坊
+    /// `M3:2048` 规范一致。
+    pub fn add(&mut self, item: &[u8]) {
+        let hash = keccak256(item);
+        for pair in [(0, 1), (2, 3), (4, 5)] {
+            let (hi, lo) = pair;
+            let bit_index = (((hash[hi] as u16) << 8 | hash[lo] as u16) & 0x7FF) as usize;
+            let byte_index = 255 - bit_index / 8;
+            let bit = bit_index % 8;
+            self.0[byte_index] |= 1 << bit;
+        }
+    }
+
+    /// 将另一个 Bloom 的位或运算进本 Bloom（用于聚合多笔收据/交易）
+    pub fn or(&mut self, other: &Bloom) {
+        for (a, b) in self.0.iter_mut().zip(other.0.iter()) {
+            *a |= b;
+        }
+    }
+
+    /// 成员测试：是否 *可能* 包含该项（存在假阳性，不存在假阴性）
+    pub fn contains(&self, item: &[u8]) -> bool {
+        let mut probe = Bloom::zero();
+        probe.add(item);
+        probe.0.iter().zip(self.0.iter()).all(|(p, s)| p & s == *p)
+    }
+
+    /// 便捷方法：测试地址是否可能出现在日志中
+    pub fn contains_address(&self, address: &Address) -> bool {
+        self.contains(address.as_slice())
+    }
+
+    /// 便捷方法：测试 topic 是否可能出现在日志中
+    pub fn contains_topic(&self, topic: &B256) -> bool {
+        self.contains(topic.as_slice())
+    }
+
+    /// 组合查询：地址（可选）与全部给定 topic 是否都可能出现在本 Bloom
+    /// 聚合的日志集合中
+    ///
+    /// `address` 为 `None` 时跳过地址检查；`topics` 为空时跳过 topic 检查。
+    /// 调用方（例如 `eth_getLogs` 风格的区块/收据过滤）应先用这个方法做
+    /// 候选排除，命中之后才去扫描完整日志，避免对每一条收据都重复解码。
+    pub fn matches(&self, address: Option<&Address>, topics: &[B256]) -> bool {
+        if let Some(address) = address {
+            if !self.contains_address(address) {
+                return false;
+            }
+        }
+        topics.iter().all(|topic| self.contains_topic(topic))
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 256] {
+        &self.0
+    }
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+/// `[u8; 256]` 没有内建 serde 支持（serde 数组实现止步于 32），
+/// 借道 `Vec<u8>` 往返序列化
+mod serde_bytes_256 {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8; 256], serializer: S) -> Result<S::Ok, S::Error> {
+        bytes.to_vec().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[u8; 256], D::Error> {
+        let vec = Vec::<u8>::deserialize(deserializer)?;
+        vec.try_into()
+            .map_err(|_| serde::de::Error::custom("logs bloom must be exactly 256 bytes"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_primitives::address;
+
+    #[test]
+    fn test_add_and_contains() {
+        let addr = address!("0000000000000000000000000000000000000001");
+        let mut bloom = Bloom::zero();
+        bloom.add(addr.as_slice());
+
+        assert!(bloom.contains_address(&addr));
+
+        let other = address!("0000000000000000000000000000000000000002");
+        // 不保证一定为假，但对这对固定地址预期不命中
+        if bloom.contains_address(&other) {
+            panic!("unexpected false positive for this fixed test vector");
+        }
+    }
+
+    #[test]
+    fn test_or_aggregates_blooms() {
+        let addr_a = address!("0000000000000000000000000000000000000001");
+        let addr_b = address!("0000000000000000000000000000000000000002");
+
+        let mut bloom_a = Bloom::zero();
+        bloom_a.add(addr_a.as_slice());
+
+        let mut bloom_b = Bloom::zero();
+        bloom_b.add(addr_b.as_slice());
+
+        let mut combined = bloom_a;
+        combined.or(&bloom_b);
+
+        assert!(combined.contains_address(&addr_a));
+        assert!(combined.contains_address(&addr_b));
+    }
+
+    #[test]
+    fn test_zero_bloom_contains_nothing() {
+        let addr = address!("0000000000000000000000000000000000000001");
+        assert!(!Bloom::zero().contains_address(&addr));
+    }
+
+    #[test]
+    fn test_matches_requires_address_and_all_topics() {
+        let addr = address!("0000000000000000000000000000000000000001");
+        let topic_a = B256::with_last_byte(1);
+        let topic_b = B256::with_last_byte(2);
+        let missing_topic = B256::with_last_byte(3);
+
+        let mut bloom = Bloom::zero();
+        bloom.add(addr.as_slice());
+        bloom.add(topic_a.as_slice());
+        bloom.add(topic_b.as_slice());
+
+        assert!(bloom.matches(Some(&addr), &[topic_a, topic_b]));
+        assert!(bloom.matches(None, &[topic_a]));
+        assert!(bloom.matches(Some(&addr), &[]));
+        if bloom.matches(Some(&addr), &[missing_topic]) {
+            panic!("unexpected false positive for this fixed test vector");
+        }
+    }
+}
@@ -6,7 +6,9 @@ pub mod account;
 pub mod storage;
 pub mod code;
 pub mod block;
+pub mod bloom;
 
 pub use account::{Account, EMPTY_CODE_HASH};
 pub use storage::StorageSlot;
-pub use block::{Block, BlockHeader, Transaction, TransactionReceipt, Log};
+pub use block::{Block, BlockHeader, Transaction, TransactionReceipt, Log, TxType};
+pub use bloom::Bloom;
@@ -43,39 +43,132 @@ impl StorageSlot {
     }
 }
 
+/// SSTORE：首次将空槽设置为非零值的成本
+const SSTORE_SET_GAS: i64 = 20000;
+/// SSTORE：首次修改一个非零槽的成本
+const SSTORE_RESET_GAS: i64 = 5000;
+/// SSTORE：将槽清零时记入的 gas 退款
+const SSTORE_CLEARS_REFUND: i64 = 15000;
+/// 槽位在本交易内已被触碰过时的热读成本（EIP-2929）
+const SLOAD_GAS: i64 = 100;
+
 /// 存储变更
-/// 
-/// 记录存储槽的变更（原值 -> 新值）
+///
+/// 记录一次 SSTORE 写入所涉及的三个值：`original`（事务开始时的值）、
+/// `current`（本次写入前的值）、`new_value`（写入后的值）——
+/// EIP-2200 净计量模型需要三者才能正确计费。
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StorageChange {
     pub address: Address,
     pub key: U256,
-    pub old_value: U256,
+    pub original: U256,
+    pub current: U256,
     pub new_value: U256,
 }
 
 impl StorageChange {
-    pub fn new(address: Address, key: U256, old_value: U256, new_value: U256) -> Self {
+    pub fn new(address: Address, key: U256, original: U256, current: U256, new_value: U256) -> Self {
         Self {
             address,
             key,
-            old_value,
+            original,
+            current,
             new_value,
         }
     }
-    
-    /// 检查是否真的发生了变更
+
+    /// 检查本次写入相对于写入前的值是否真的发生了变更
     pub fn is_changed(&self) -> bool {
-        self.old_value != self.new_value
+        self.current != self.new_value
     }
-    
-    /// 计算 gas 退款（SSTORE 操作）
+
+    /// 按 EIP-2200 净计量模型计算本次写入对退款计数器的净影响
+    ///
+    /// 返回值与旧版 ±20000/15000 的符号约定一致：负数表示净消耗的 gas，
+    /// 正数表示净增加的退款。规则：
+    /// - `current == new_value`：未发生实际写入，只计入热读成本；
+    /// - `original == current`（本交易内首次写入该槽）：按 `original`
+    ///   是否为零收取 SET/RESET 成本，若把非零值清零则额外计入清除退款；
+    /// - 否则（脏槽，本交易内已写过）：只再计入一次热读成本，并按
+    ///   之前的清除状态、以及是否写回 `original`，修正已经计入的退款。
     pub fn gas_refund(&self) -> i64 {
-        match (self.old_value == U256::ZERO, self.new_value == U256::ZERO) {
-            (false, true) => 15000,  // 清除存储
-            (true, false) => -20000, // 设置新存储
-            _ => 0,
+        if self.current == self.new_value {
+            return -SLOAD_GAS;
+        }
+
+        if self.original == self.current {
+            let mut refund = if self.original == U256::ZERO {
+                -SSTORE_SET_GAS
+            } else {
+                -SSTORE_RESET_GAS
+            };
+            if self.original != U256::ZERO && self.new_value == U256::ZERO {
+                refund += SSTORE_CLEARS_REFUND;
+            }
+            return refund;
+        }
+
+        let mut refund = -SLOAD_GAS;
+        if self.original != U256::ZERO {
+            if self.current == U256::ZERO {
+                // 撤销之前对该槽的清除退款
+                refund += SSTORE_CLEARS_REFUND;
+            } else if self.new_value == U256::ZERO {
+                // 重新清零该槽
+                refund -= SSTORE_CLEARS_REFUND;
+            }
+        }
+        if self.new_value == self.original {
+            refund += if self.original == U256::ZERO {
+                SSTORE_SET_GAS - SLOAD_GAS
+            } else {
+                SSTORE_RESET_GAS - SLOAD_GAS
+            };
         }
+        refund
+    }
+}
+
+/// 单笔交易内的存储写入跟踪器
+///
+/// 按 EIP-2200 的要求，记录每个槽位在交易开始时的原始值（`original`），
+/// 以便同一交易内对同一槽位的后续写入能够正确地按净计量模型计费，
+/// 并维护整笔交易累计的净退款。
+#[derive(Debug, Default)]
+pub struct StorageTracker {
+    originals: std::collections::HashMap<(Address, U256), U256>,
+    net_refund: i64,
+}
+
+impl StorageTracker {
+    /// 创建一个空的跟踪器
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 记录一次 SSTORE 写入
+    ///
+    /// 若该槽位在本交易内尚未被记录过，则 `current` 被视为其 `original`
+    /// 值。返回对应的 `StorageChange` 并将其净退款累加到跟踪器中。
+    pub fn record_write(
+        &mut self,
+        address: Address,
+        key: U256,
+        current: U256,
+        new_value: U256,
+    ) -> StorageChange {
+        let original = *self
+            .originals
+            .entry((address, key))
+            .or_insert(current);
+        let change = StorageChange::new(address, key, original, current, new_value);
+        self.net_refund += change.gas_refund();
+        change
+    }
+
+    /// 整笔交易累计的净退款（正数为退款，负数为额外消耗的 gas）
+    pub fn net_refund(&self) -> i64 {
+        self.net_refund
     }
 }
 
@@ -101,24 +194,60 @@ mod tests {
     #[test]
     fn test_storage_change() {
         let addr = address!("0000000000000000000000000000000000000001");
-        
-        // 设置新存储
-        let change1 = StorageChange::new(addr, U256::from(1), U256::ZERO, U256::from(100));
+
+        // 首次写入：设置空槽为非零值
+        let change1 = StorageChange::new(addr, U256::from(1), U256::ZERO, U256::ZERO, U256::from(100));
         assert!(change1.is_changed());
         assert_eq!(change1.gas_refund(), -20000);
-        
-        // 清除存储
-        let change2 = StorageChange::new(addr, U256::from(1), U256::from(100), U256::ZERO);
+
+        // 首次写入：清除一个非零槽
+        let change2 = StorageChange::new(addr, U256::from(1), U256::from(100), U256::from(100), U256::ZERO);
         assert!(change2.is_changed());
-        assert_eq!(change2.gas_refund(), 15000);
-        
-        // 修改存储
-        let change3 = StorageChange::new(addr, U256::from(1), U256::from(100), U256::from(200));
+        assert_eq!(change2.gas_refund(), -5000 + 15000);
+
+        // 首次写入：修改一个非零槽为另一个非零值
+        let change3 = StorageChange::new(addr, U256::from(1), U256::from(100), U256::from(100), U256::from(200));
         assert!(change3.is_changed());
-        assert_eq!(change3.gas_refund(), 0);
-        
-        // 无变更
-        let change4 = StorageChange::new(addr, U256::from(1), U256::from(100), U256::from(100));
+        assert_eq!(change3.gas_refund(), -5000);
+
+        // 无变更（current == new_value）
+        let change4 = StorageChange::new(addr, U256::from(1), U256::from(100), U256::from(100), U256::from(100));
         assert!(!change4.is_changed());
+        assert_eq!(change4.gas_refund(), -100);
+    }
+
+    #[test]
+    fn test_storage_tracker_dirty_slot_reversed_to_original() {
+        let addr = address!("0000000000000000000000000000000000000001");
+        let mut tracker = StorageTracker::new();
+
+        // 第一次写入：100 -> 0（清除，原值非零，获得 15000 退款）
+        let change1 = tracker.record_write(addr, U256::from(1), U256::from(100), U256::ZERO);
+        assert_eq!(change1.gas_refund(), -5000 + 15000);
+
+        // 第二次写入（脏槽）：0 -> 100，写回 original，撤销之前的清除退款
+        let change2 = tracker.record_write(addr, U256::from(1), U256::ZERO, U256::from(100));
+        assert_eq!(change2.original, U256::from(100));
+        assert_eq!(change2.gas_refund(), -100 + 15000 + (5000 - 100));
+
+        // 净退款 = 两次 gas_refund 之和
+        assert_eq!(tracker.net_refund(), change1.gas_refund() + change2.gas_refund());
+    }
+
+    #[test]
+    fn test_storage_tracker_dirty_slot_reclear() {
+        let addr = address!("0000000000000000000000000000000000000001");
+        let mut tracker = StorageTracker::new();
+
+        // 第一次写入：100 -> 200（脏槽标记为 original==current，非清零）
+        let change1 = tracker.record_write(addr, U256::from(1), U256::from(100), U256::from(200));
+        assert_eq!(change1.gas_refund(), -5000);
+
+        // 第二次写入（脏槽）：200 -> 0，首次对该槽清零，扣减退款
+        let change2 = tracker.record_write(addr, U256::from(1), U256::from(200), U256::ZERO);
+        assert_eq!(change2.original, U256::from(100));
+        assert_eq!(change2.gas_refund(), -100 - 15000);
+
+        assert_eq!(tracker.net_refund(), change1.gas_refund() + change2.gas_refund());
     }
 }
@@ -0,0 +1,188 @@
+//! 交易签名验证
+//!
+//! 给定交易的签名哈希（[`Transaction::signing_hash`](crate::schema::Transaction::signing_hash)）
+//! 与 `(v, r, s)`，恢复 secp256k1 公钥并派生发送方地址，校验其与交易声明的
+//! `from` 一致。网关在接受一笔交易之前应当调用 [`verify_and_recover_sender`]，
+//! 而不是信任调用方填充的 `from` 字符串。
+
+use alloy_primitives::{keccak256, Address, B256, U256};
+use thiserror::Error;
+
+use crate::schema::{Transaction, TxType};
+
+/// secp256k1 阶 n 的一半，用于拒绝高 s 值签名（EIP-2 可延展性保护）
+const SECP256K1N_HALF: U256 = U256::from_be_bytes([
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d,
+    0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+]);
+
+/// 签名验证错误
+#[derive(Debug, Error)]
+pub enum SigningError {
+    #[error("Missing signature v/r/s")]
+    MissingSignature,
+
+    #[error("Invalid EIP-155/2718 recovery id derived from v={0}")]
+    InvalidRecoveryId(u64),
+
+    #[error("Signature s is above secp256k1n/2 (malleable signature rejected per EIP-2)")]
+    HighS,
+
+    #[error("Signature recovery failed: {0}")]
+    RecoveryFailed(String),
+
+    #[error("Sender mismatch: recovered {recovered}, declared {declared}")]
+    SenderMismatch { recovered: Address, declared: Address },
+
+    #[error("Transaction error: {0}")]
+    Transaction(String),
+}
+
+/// 从签名哈希和 `(v, r, s)` 恢复发送方地址
+///
+/// `v` 按交易类型解释：Legacy 为 EIP-155 编码（`{0,1} + chain_id*2 + 35`，
+/// 未带 chain_id 时退化为 27/28）；类型化交易（EIP-2930/1559）的 `v` 就是
+/// `y_parity`（0/1）。`s` 必须不超过 `secp256k1n/2`，否则拒绝（同一笔交易
+/// 存在两个同样有效但哈希不同的签名，会破坏以 tx hash 去重/重放检测的假设）。
+pub fn recover_sender(
+    signing_hash: B256,
+    tx_type: TxType,
+    chain_id: Option<u64>,
+    v: u64,
+    r: U256,
+    s: U256,
+) -> Result<Address, SigningError> {
+    if r.is_zero() || s.is_zero() {
+        return Err(SigningError::MissingSignature);
+    }
+
+    if s > SECP256K1N_HALF {
+        return Err(SigningError::HighS);
+    }
+
+    let recovery_id = match tx_type {
+        TxType::Legacy => match chain_id {
+            Some(chain_id) => v
+                .checked_sub(chain_id * 2 + 35)
+                .ok_or(SigningError::InvalidRecoveryId(v))?,
+            None => v.checked_sub(27).ok_or(SigningError::InvalidRecoveryId(v))?,
+        },
+        TxType::Eip2930 | TxType::Eip1559 => v,
+    };
+
+    let recovery_id = k256::ecdsa::RecoveryId::from_byte(recovery_id as u8)
+        .ok_or(SigningError::InvalidRecoveryId(v))?;
+
+    let mut sig_bytes = [0u8; 64];
+    sig_bytes[..32].copy_from_slice(&r.to_be_bytes::<32>());
+    sig_bytes[32..].copy_from_slice(&s.to_be_bytes::<32>());
+    let signature = k256::ecdsa::Signature::from_slice(&sig_bytes)
+        .map_err(|e| SigningError::RecoveryFailed(e.to_string()))?;
+
+    let verifying_key = k256::ecdsa::VerifyingKey::recover_from_prehash(
+        signing_hash.as_slice(),
+        &signature,
+        recovery_id,
+    )
+    .map_err(|e| SigningError::RecoveryFailed(e.to_string()))?;
+
+    let public_key = verifying_key.to_encoded_point(false);
+    let hash = keccak256(&public_key.as_bytes()[1..]);
+    Ok(Address::from_slice(&hash[12..]))
+}
+
+/// 验证一笔交易的签名并返回恢复出的发送方地址
+///
+/// 在 [`recover_sender`] 的基础上，额外把恢复出的地址与交易声明的 `from`
+/// 字段比对，不一致则拒绝。网关应当在 `send_raw_transaction`/提交交易前
+/// 调用本函数，而不是直接信任 `from`。
+pub fn verify_and_recover_sender(tx: &Transaction) -> Result<Address, SigningError> {
+    let v = tx.v.ok_or(SigningError::MissingSignature)?;
+    let r = tx
+        .r
+        .as_deref()
+        .and_then(|v| U256::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+        .unwrap_or_default();
+    let s = tx
+        .s
+        .as_deref()
+        .and_then(|v| U256::from_str_radix(v.trim_start_matches("0x"), 16).ok())
+        .unwrap_or_default();
+
+    let recovered = recover_sender(tx.signing_hash(), tx.tx_type(), tx.chain_id, v, r, s)?;
+
+    let declared = tx
+        .from_address()
+        .map_err(SigningError::Transaction)?;
+
+    if recovered != declared {
+        return Err(SigningError::SenderMismatch { recovered, declared });
+    }
+
+    Ok(recovered)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tx(v: Option<u64>, r: Option<&str>, s: Option<&str>) -> Transaction {
+        Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v,
+            r: r.map(str::to_string),
+            s: s.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_verify_and_recover_sender_requires_signature() {
+        let tx = sample_tx(None, None, None);
+        assert!(matches!(
+            verify_and_recover_sender(&tx),
+            Err(SigningError::MissingSignature)
+        ));
+    }
+
+    #[test]
+    fn test_recover_sender_rejects_high_s() {
+        // 比 secp256k1n/2 大 1 的 s 值应被拒绝
+        let s_plus_one = SECP256K1N_HALF + U256::from(1u64);
+
+        let result = recover_sender(
+            B256::ZERO,
+            TxType::Legacy,
+            Some(1),
+            37,
+            U256::from(1u64),
+            s_plus_one,
+        );
+        assert!(matches!(result, Err(SigningError::HighS)));
+    }
+
+    #[test]
+    fn test_recover_sender_rejects_zero_r_or_s() {
+        let result = recover_sender(
+            B256::ZERO,
+            TxType::Legacy,
+            Some(1),
+            37,
+            U256::ZERO,
+            U256::from(1u64),
+        );
+        assert!(matches!(result, Err(SigningError::MissingSignature)));
+    }
+}
@@ -2,13 +2,14 @@
 //! 
 //! 使用 alloy-trie 的 HashBuilder 构建 Merkle Patricia Trie
 
-use alloy_primitives::{B256, keccak256};
+use alloy_primitives::{B256, Bytes, keccak256};
 use alloy_trie::{HashBuilder, Nibbles};
+use alloy_trie::proof::ProofRetainer;
 use alloy_rlp::Encodable;
 use super::TrieError;
 
 /// Trie 构建器包装
-/// 
+///
 /// 简化 alloy-trie 的使用
 pub struct TrieBuilder {
     builder: HashBuilder,
@@ -21,9 +22,21 @@ impl TrieBuilder {
             builder: HashBuilder::default(),
         }
     }
-    
+
+    /// 创建带证明保留器的 Trie 构建器
+    ///
+    /// 构建过程中会记录 `targets` 路径上经过的所有原始节点（RLP 编码），
+    /// 供 `StateProofGenerator` 在完整重建 trie 后取出作为 eth_getProof 的证明节点。
+    /// 即使 `targets` 对应的键不在 trie 中，也会保留路径上最近的分支/扩展节点（排除证明）。
+    pub fn new_with_proof_targets(targets: Vec<Nibbles>) -> Self {
+        Self {
+            builder: HashBuilder::default()
+                .with_proof_retainer(ProofRetainer::new(targets)),
+        }
+    }
+
     /// 添加叶子节点
-    /// 
+    ///
     /// # 参数
     /// - `key`: 键（通常是地址或存储槽的哈希）
     /// - `value`: RLP 编码后的值
@@ -31,18 +44,31 @@ impl TrieBuilder {
         let nibbles = Nibbles::from_bytes_unchecked(key.as_slice());
         self.builder.add_leaf(nibbles, value);
     }
-    
+
     /// 添加分支节点（用于增量更新）
     pub fn add_branch(&mut self, key: B256, value: B256, children_are_in_trie: bool) {
         let nibbles = Nibbles::from_bytes_unchecked(key.as_slice());
         self.builder.add_branch(nibbles, value, children_are_in_trie);
     }
-    
+
     /// 计算根哈希
     pub fn root(&mut self) -> B256 {
         self.builder.root()
     }
-    
+
+    /// 取出证明保留器记录下的节点列表（按 trie 路径排序）
+    ///
+    /// 必须在 `root()` 之后调用，且构建器需以 `new_with_proof_targets` 创建，
+    /// 否则返回空列表。
+    pub fn take_proof_nodes(&mut self) -> Vec<Bytes> {
+        self.builder
+            .take_proof_nodes()
+            .into_nodes_sorted()
+            .into_iter()
+            .map(|(_, node)| node)
+            .collect()
+    }
+
     /// 重置构建器
     pub fn reset(&mut self) {
         self.builder = HashBuilder::default();
@@ -6,29 +6,52 @@ pub mod builder;
 pub mod state_root;
 pub mod storage_root;
 pub mod proof;
+pub mod state_proof;
+pub mod sparse;
+pub mod node_store;
 
 pub use builder::TrieBuilder;
-pub use state_root::StateRootCalculator;
+pub use state_root::{StateRootCalculator, StateTrie, CleanupMode, calculate_state_root};
 pub use storage_root::StorageRootCalculator;
-pub use proof::{MerkleProof, ProofVerifier};
+pub use proof::{MerkleProof, ProofVerifier, verify_proof, verify_proof_raw};
+pub use state_proof::StateProofGenerator;
 
+use alloy_primitives::{Address, B256};
 use thiserror::Error;
 
 /// Trie 错误类型
+///
+/// 区分三类原本都挤在 `Database`/`Corrupt` 里的情形，好让调用方（尤其是
+/// `StateRootCalculator::calculate_incremental_with_cleanup` 的并行
+/// `par_iter` 收集）决定哪些是必须中止整个区块的致命错误，哪些只是正常的
+/// "这个账户不存在"：
+/// - [`TrieError::StateCorrupt`]：已落盘的数据本身解码失败——必须中止
+/// - [`TrieError::MissingNode`]：根哈希引用的某个 trie 节点查不到——必须
+///   中止，但携带了具体哈希，调用方可以据此只对该子树触发重新同步
+/// - [`TrieError::AccountAbsent`]：账户被正常删除（selfdestruct/清理空账户）
+///   之后确实不存在了——不是错误，只是调用方（`process_account`）借用
+///   `Result` 的 `Err` 分支把"这个地址没有账户数据可插入"这个信号带出来，
+///   收集逻辑要把它从 `to_delete` 集合里捞出来，而不是当成中止信号向上传播
 #[derive(Debug, Error)]
 pub enum TrieError {
     #[error("Database error: {0}")]
     Database(String),
-    
+
     #[error("Invalid proof")]
     InvalidProof,
-    
-    #[error("Node not found: {0}")]
-    NodeNotFound(String),
-    
+
+    #[error("trie node {hash} referenced but missing from store")]
+    MissingNode { hash: B256 },
+
     #[error("RLP encoding error: {0}")]
     RlpEncoding(String),
-    
+
+    #[error("corrupt trie state: {0}")]
+    StateCorrupt(String),
+
+    #[error("account {address} has no data (deleted or never existed)")]
+    AccountAbsent { address: Address },
+
     #[error("Other error: {0}")]
     Other(String),
 }
@@ -0,0 +1,353 @@
+//! 持久化 Merkle-Patricia 节点存储与路径增量更新
+//!
+//! 和 `sparse` 模块（[`super::sparse`]，`WalrusStateDB` 专用的定深 16 叉哈希树，
+//! 不做前缀压缩，根哈希也和主网口径不兼容）不同，本模块实现的是带前缀压缩的
+//! 真正 Patricia 结构——`Leaf`/`Extension`/`Branch` 三种节点。插入或更新一个
+//! 叶子只需要重新计算从根到该叶子这条路径上的节点，路径之外完全没有改动的
+//! 子树哈希原样复用，调用方甚至不需要把它们读出来。这是
+//! [`super::state_root::StateRootCalculator::calculate_incremental`] 过去
+//! "只把变更账户塞进一棵新 `TrieBuilder`" 的直接替代——后者产出的根只有在
+//! *全部*账户都变更时才等于真实状态根，本模块的 `insert` 则保证任意子集的
+//! 增量更新都收敛到与"一次性插入全部叶子"完全相同的根。
+//!
+//! 节点用 bincode 序列化后按其自身内容的 keccak256 哈希持久化——和本文件所在
+//! crate 里其它每一张 redb 表（账户、存储、代码……）一样用 bincode 而不是
+//! RLP，这只是内部存储格式，不需要能被外部 Ethereum 客户端按字节解析。旧节点
+//! 写入后永不覆盖（新内容产生新哈希），只要节点还留在存储里，历史根就仍然
+//! 可以重新遍历。
+//!
+//! 局限：目前只实现插入/更新叶子，不支持删除。把一个账户从树里摘除（EIP-158
+//! 空账户清理会需要这个）留给后续工作。
+
+use alloy_primitives::{keccak256, B256};
+use serde::{Deserialize, Serialize};
+
+use super::TrieError;
+
+/// 单个 Patricia 节点：叶子（剩余 nibble 路径 + 值）、扩展（公共 nibble 前缀 +
+/// 唯一子节点），或分支（16 个子节点槽位 + 恰好在此终止的键对应的可选值）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Node {
+    Leaf { path: Vec<u8>, value: Vec<u8> },
+    Extension { path: Vec<u8>, child: B256 },
+    Branch { children: [Option<B256>; 16], value: Option<Vec<u8>> },
+}
+
+fn encode(node: &Node) -> Vec<u8> {
+    bincode::serialize(node).expect("Node serialization is infallible")
+}
+
+fn decode(data: &[u8]) -> Result<Node, TrieError> {
+    bincode::deserialize(data)
+        .map_err(|e| TrieError::StateCorrupt(format!("corrupt trie node: {e}")))
+}
+
+fn missing_node(hash: B256) -> TrieError {
+    TrieError::MissingNode { hash }
+}
+
+/// 两个 nibble 切片的公共前缀长度
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// 把一个叶子 `(path, value)` 插入以 `root` 为根的子树（`root` 为 `None` 表示
+/// 空子树），返回新的根哈希。
+///
+/// `load(hash)` 读取某个已持久化节点的原始字节，未命中返回 `None`；
+/// `store(hash, data)` 把一个新算出的节点写入持久化存储，键即 `data` 自身的
+/// keccak256。调用方（通常是 redb 表）负责实际的读写，本函数只操作 nibble
+/// 路径和节点结构本身。
+pub fn insert<L, S>(
+    mut load: L,
+    mut store: S,
+    root: Option<B256>,
+    path: &[u8],
+    value: Vec<u8>,
+) -> Result<B256, TrieError>
+where
+    L: FnMut(B256) -> Result<Option<Vec<u8>>, TrieError>,
+    S: FnMut(B256, Vec<u8>) -> Result<(), TrieError>,
+{
+    let node = match root {
+        None => Node::Leaf { path: path.to_vec(), value },
+        Some(hash) => {
+            let data = load(hash)?.ok_or_else(|| missing_node(hash))?;
+            insert_into(&mut load, &mut store, decode(&data)?, path, value)?
+        }
+    };
+    persist(&mut store, &node)
+}
+
+/// 把一个节点序列化、算出哈希、写入存储，返回该哈希
+fn persist<S>(store: &mut S, node: &Node) -> Result<B256, TrieError>
+where
+    S: FnMut(B256, Vec<u8>) -> Result<(), TrieError>,
+{
+    let data = encode(node);
+    let hash = keccak256(&data);
+    store(hash, data)?;
+    Ok(hash)
+}
+
+/// 把 `(path, value)` 插入 `node` 代表的子树，返回更新后的（未持久化的）节点；
+/// 调用方负责把返回值 `persist`
+fn insert_into<L, S>(
+    load: &mut L,
+    store: &mut S,
+    node: Node,
+    path: &[u8],
+    value: Vec<u8>,
+) -> Result<Node, TrieError>
+where
+    L: FnMut(B256) -> Result<Option<Vec<u8>>, TrieError>,
+    S: FnMut(B256, Vec<u8>) -> Result<(), TrieError>,
+{
+    match node {
+        Node::Leaf { path: leaf_path, value: leaf_value } => {
+            if leaf_path == path {
+                // 同一个键再次写入：原地更新值，路径不变
+                return Ok(Node::Leaf { path: leaf_path, value });
+            }
+
+            let cp = common_prefix_len(&leaf_path, path);
+            let mut branch = Node::Branch { children: [None; 16], value: None };
+            branch = place_in_branch(store, branch, &leaf_path[cp..], leaf_value)?;
+            branch = place_in_branch(store, branch, &path[cp..], value)?;
+            wrap_with_extension(store, cp, &leaf_path, branch)
+        }
+
+        Node::Extension { path: ext_path, child } => {
+            if path.len() >= ext_path.len() && path[..ext_path.len()] == ext_path[..] {
+                let data = load(child)?.ok_or_else(|| missing_node(child))?;
+                let new_child =
+                    insert_into(load, store, decode(&data)?, &path[ext_path.len()..], value)?;
+                let new_child_hash = persist(store, &new_child)?;
+                return Ok(Node::Extension { path: ext_path, child: new_child_hash });
+            }
+
+            // 扩展节点的前缀和新路径在 `cp` 处分叉：把公共前缀之后的部分拆成
+            // 一个分支节点，原来的子节点和新叶子各自挂在分支的对应槽位上
+            let cp = common_prefix_len(&ext_path, path);
+            let mut branch = Node::Branch { children: [None; 16], value: None };
+
+            let ext_remainder = &ext_path[cp..];
+            let existing_hash = if ext_remainder.len() == 1 {
+                child
+            } else {
+                persist(store, &Node::Extension { path: ext_remainder[1..].to_vec(), child })?
+            };
+            set_branch_child(&mut branch, ext_remainder[0], existing_hash);
+
+            branch = place_in_branch(store, branch, &path[cp..], value)?;
+            wrap_with_extension(store, cp, &ext_path, branch)
+        }
+
+        Node::Branch { mut children, value: branch_value } => {
+            if path.is_empty() {
+                return Ok(Node::Branch { children, value: Some(value) });
+            }
+
+            let idx = path[0] as usize;
+            let new_child_hash = match children[idx] {
+                None => persist(store, &Node::Leaf { path: path[1..].to_vec(), value })?,
+                Some(child_hash) => {
+                    let data = load(child_hash)?.ok_or_else(|| missing_node(child_hash))?;
+                    let new_child = insert_into(load, store, decode(&data)?, &path[1..], value)?;
+                    persist(store, &new_child)?
+                }
+            };
+            children[idx] = Some(new_child_hash);
+            Ok(Node::Branch { children, value: branch_value })
+        }
+    }
+}
+
+/// 把 `(remaining, value)` 放进一个刚创建的分支节点：`remaining` 为空说明键
+/// 恰好在该分支终止，写入 `branch.value`；否则 `remaining[0]` 是子节点槽位，
+/// 剩下的 nibble 作为一个新叶子的路径
+fn place_in_branch<S>(
+    store: &mut S,
+    branch: Node,
+    remaining: &[u8],
+    value: Vec<u8>,
+) -> Result<Node, TrieError>
+where
+    S: FnMut(B256, Vec<u8>) -> Result<(), TrieError>,
+{
+    let Node::Branch { mut children, value: branch_value } = branch else {
+        unreachable!("place_in_branch only operates on freshly created Branch nodes")
+    };
+
+    if remaining.is_empty() {
+        return Ok(Node::Branch { children, value: Some(value) });
+    }
+
+    let hash = persist(store, &Node::Leaf { path: remaining[1..].to_vec(), value })?;
+    children[remaining[0] as usize] = Some(hash);
+    Ok(Node::Branch { children, value: branch_value })
+}
+
+fn set_branch_child(branch: &mut Node, nibble: u8, hash: B256) {
+    let Node::Branch { children, .. } = branch else {
+        unreachable!("set_branch_child only operates on Branch nodes")
+    };
+    children[nibble as usize] = Some(hash);
+}
+
+/// 公共前缀非空时，把新建的 `branch` 包在一个 `Extension` 之下并持久化分支；
+/// 公共前缀为空（`cp == 0`）时分支本身就是这层的节点，直接返回，调用方负责
+/// 持久化
+fn wrap_with_extension<S>(
+    store: &mut S,
+    cp: usize,
+    full_path: &[u8],
+    branch: Node,
+) -> Result<Node, TrieError>
+where
+    S: FnMut(B256, Vec<u8>) -> Result<(), TrieError>,
+{
+    if cp == 0 {
+        Ok(branch)
+    } else {
+        let branch_hash = persist(store, &branch)?;
+        Ok(Node::Extension { path: full_path[..cp].to_vec(), child: branch_hash })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::sparse::key_path;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// 测试用的内存节点存储，模拟调用方（`RedbStateDB`）持久化节点的方式
+    struct MemStore {
+        nodes: RefCell<HashMap<B256, Vec<u8>>>,
+    }
+
+    impl MemStore {
+        fn new() -> Self {
+            Self { nodes: RefCell::new(HashMap::new()) }
+        }
+
+        fn insert(&self, root: Option<B256>, path: &[u8], value: Vec<u8>) -> B256 {
+            insert(
+                |hash| Ok(self.nodes.borrow().get(&hash).cloned()),
+                |hash, data| {
+                    self.nodes.borrow_mut().insert(hash, data);
+                    Ok(())
+                },
+                root,
+                path,
+                value,
+            )
+            .unwrap()
+        }
+    }
+
+    #[test]
+    fn test_insert_single_leaf_into_empty_root() {
+        let store = MemStore::new();
+        let path = key_path(&keccak256(b"alice"));
+        let root = store.insert(None, &path, b"alice-account".to_vec());
+        assert_ne!(root, B256::ZERO);
+    }
+
+    #[test]
+    fn test_insert_is_deterministic() {
+        let path = key_path(&keccak256(b"alice"));
+
+        let store_a = MemStore::new();
+        let root_a = store_a.insert(None, &path, b"account-rlp".to_vec());
+
+        let store_b = MemStore::new();
+        let root_b = store_b.insert(None, &path, b"account-rlp".to_vec());
+
+        assert_eq!(root_a, root_b);
+    }
+
+    #[test]
+    fn test_different_keys_produce_different_roots() {
+        let store = MemStore::new();
+        let root_after_alice =
+            store.insert(None, &key_path(&keccak256(b"alice")), b"alice-account".to_vec());
+        let root_after_bob = store.insert(
+            Some(root_after_alice),
+            &key_path(&keccak256(b"bob")),
+            b"bob-account".to_vec(),
+        );
+        assert_ne!(root_after_alice, root_after_bob);
+    }
+
+    #[test]
+    fn test_updating_existing_leaf_changes_root_and_is_idempotent() {
+        let store = MemStore::new();
+        let path = key_path(&keccak256(b"alice"));
+        let root_v1 = store.insert(None, &path, b"v1".to_vec());
+        let root_v2 = store.insert(Some(root_v1), &path, b"v2".to_vec());
+        assert_ne!(root_v1, root_v2);
+
+        // 同一笔更新重做一次应该收敛到相同的根
+        let root_v2_again = store.insert(Some(root_v1), &path, b"v2".to_vec());
+        assert_eq!(root_v2, root_v2_again);
+    }
+
+    #[test]
+    fn test_insert_order_is_irrelevant_to_final_root() {
+        let alice_path = key_path(&keccak256(b"alice"));
+        let bob_path = key_path(&keccak256(b"bob"));
+        let carol_path = key_path(&keccak256(b"carol"));
+
+        let store_a = MemStore::new();
+        let mut root = store_a.insert(None, &alice_path, b"alice".to_vec());
+        root = store_a.insert(Some(root), &bob_path, b"bob".to_vec());
+        let root_a = store_a.insert(Some(root), &carol_path, b"carol".to_vec());
+
+        let store_b = MemStore::new();
+        let mut root = store_b.insert(None, &carol_path, b"carol".to_vec());
+        root = store_b.insert(Some(root), &alice_path, b"alice".to_vec());
+        let root_b = store_b.insert(Some(root), &bob_path, b"bob".to_vec());
+
+        assert_eq!(root_a, root_b);
+    }
+
+    #[test]
+    fn test_updating_one_leaf_reuses_untouched_sibling_node_unchanged() {
+        let store = MemStore::new();
+        let alice_path = key_path(&keccak256(b"alice"));
+        let bob_path = key_path(&keccak256(b"bob"));
+
+        let root = store.insert(None, &alice_path, b"alice-v1".to_vec());
+        let _ = store.insert(Some(root), &bob_path, b"bob-account".to_vec());
+
+        // bob 的叶子节点是内容寻址的，它的哈希只取决于 (path, value)，
+        // 与树里其它任何更新无关——提前算出它应有的哈希。alice/bob 的键哈希
+        // 首个 nibble 就不同（0x9 / 0x3），bob 的叶子直接挂在根分支下，路径是其余 63 个 nibble
+        let bob_leaf_hash = keccak256(bincode::serialize(&Node::Leaf {
+            path: bob_path[1..].to_vec(),
+            value: b"bob-account".to_vec(),
+        }).unwrap());
+        assert!(store.nodes.borrow().contains_key(&bob_leaf_hash));
+
+        let root = store.insert(Some(root), &bob_path, b"bob-account".to_vec());
+        let _ = store.insert(Some(root), &alice_path, b"alice-v2".to_vec());
+
+        // 更新 alice 完全没有碰到 bob 这条路径，bob 的叶子节点原样留在存储里，
+        // 没有被重新计算、覆盖或删除
+        assert!(store.nodes.borrow().contains_key(&bob_leaf_hash));
+    }
+
+    #[test]
+    fn test_insert_with_missing_referenced_node_reports_node_not_found() {
+        let result = insert(
+            |_hash| Ok(None),
+            |_hash, _data| Ok(()),
+            Some(B256::from([1u8; 32])),
+            &key_path(&keccak256(b"alice")),
+            b"account".to_vec(),
+        );
+        assert!(matches!(result, Err(TrieError::MissingNode { .. })));
+    }
+}
@@ -2,7 +2,7 @@
 //! 
 //! 用于轻节点验证账户状态和存储值
 
-use alloy_primitives::{Address, U256, B256, Bytes};
+use alloy_primitives::{Address, U256, B256, Bytes, keccak256};
 use serde::{Deserialize, Serialize};
 use super::TrieError;
 
@@ -36,14 +36,267 @@ impl MerkleProof {
     }
     
     /// 验证证明
+    ///
+    /// 沿着 `keccak256(key)` 的 nibble 路径走 `proof` 中按「根 -> 叶」顺序排列
+    /// 的原始 trie 节点：每到一个哈希引用的节点，先校验 `keccak256(node) ==
+    /// 期望哈希`（首个节点对照 `root`），再按分支/扩展/叶子三种节点类型之一
+    /// 解码并推进路径；小于 32 字节、被内联在父节点里的子节点直接复用父节点
+    /// 携带的字节，不消耗 `proof` 列表中的下一项。路径提前终止于空分支槽位
+    /// 或前缀不匹配的扩展/叶子节点时，视为排除证明（exclusion proof）——此时
+    /// 要求 `value` 必须为空，否则说明证明与声明的值自相矛盾。
     pub fn verify(&self) -> Result<bool, TrieError> {
-        // TODO: 实现完整的 Merkle proof 验证
-        // 需要：
-        // 1. 从叶子节点开始
-        // 2. 沿着证明路径向上计算哈希
-        // 3. 验证最终哈希是否等于根哈希
-        Ok(true)
+        let hashed_key = keccak256(self.key.as_ref());
+        verify_path(self.root, hashed_key.as_slice(), &self.value, &self.proof)
+    }
+}
+
+/// 独立的证明验证函数：直接接受已经哈希过的 key，不经过 [`MerkleProof`]
+/// 结构体，方便调用方（比如只从 `eth_getProof` 风格的 RPC 响应里拿到
+/// `root`/`proof`/`value` 几个字段的远程客户端）在不构造完整证明对象的
+/// 情况下做同样的校验。`root`/`expected_value`/`proof` 的含义与
+/// [`MerkleProof`] 对应字段相同；实际的路径遍历逻辑见 [`verify_path`]。
+pub fn verify_proof(
+    root: B256,
+    hashed_key: B256,
+    expected_value: &Bytes,
+    proof: &[Bytes],
+) -> Result<bool, TrieError> {
+    verify_path(root, hashed_key.as_slice(), expected_value, proof)
+}
+
+/// 与 [`verify_proof`] 对称，但接受任意长度的原始键字节，不要求是 32 字节的
+/// `B256`——`state_root`/`storage_root` 的键是 `keccak256(地址/存储槽)`，固定
+/// 32 字节；而交易树/收据树的键是索引的 RLP 编码本身（见
+/// `crate::utils::merkle::calculate_merkle_root`），未经哈希且长度随索引变化。
+/// 两者共用同一套 [`verify_path`] 路径遍历逻辑，区别只在键是否需要先哈希。
+pub fn verify_proof_raw(
+    root: B256,
+    key: &[u8],
+    expected_value: &Bytes,
+    proof: &[Bytes],
+) -> Result<bool, TrieError> {
+    verify_path(root, key, expected_value, proof)
+}
+
+/// 沿着 `hashed_key` 的 nibble 路径走 `proof` 中按「根 -> 叶」顺序排列的原始
+/// trie 节点：每到一个哈希引用的节点，先校验 `keccak256(node) == 期望哈希`
+/// （首个节点对照 `root`），再按分支/扩展/叶子三种节点类型之一解码并推进
+/// 路径；小于 32 字节、被内联在父节点里的子节点直接复用父节点携带的字节，
+/// 不消耗 `proof` 列表中的下一项。路径提前终止于空分支槽位或前缀不匹配的
+/// 扩展/叶子节点时，视为排除证明（exclusion proof）——此时要求
+/// `expected_value` 必须为空，否则说明证明与声明的值自相矛盾。
+fn verify_path(
+    root: B256,
+    hashed_key: &[u8],
+    expected_value: &Bytes,
+    proof: &[Bytes],
+) -> Result<bool, TrieError> {
+    let nibbles = bytes_to_nibbles(hashed_key);
+
+    // 空树（根为空树哈希）没有任何节点可给：空证明列表即排除证明
+    if proof.is_empty() && root == super::storage_root::EMPTY_STORAGE_ROOT {
+        return Ok(expected_value.is_empty());
+    }
+
+    let mut current_ref = NodeRef::Hash(root);
+    let mut nibble_idx = 0usize;
+    let mut proof_nodes = proof.iter();
+
+    // 上限：每一步要么消费一个证明节点，要么至少推进一个 nibble，
+    // 二者之和是一条合法路径能走的最大步数；防止构造畸形证明时死循环。
+    let max_steps = proof.len() + nibbles.len() + 1;
+
+    for _ in 0..max_steps {
+        let node_bytes: Vec<u8> = match current_ref {
+            NodeRef::Hash(expected_hash) => {
+                let node = proof_nodes.next().ok_or(TrieError::InvalidProof)?;
+                if keccak256(node.as_ref()) != expected_hash {
+                    return Ok(false);
+                }
+                node.to_vec()
+            }
+            NodeRef::Inline(bytes) => bytes,
+        };
+
+        let items = split_rlp_list(&node_bytes)?;
+
+        match items.len() {
+            17 => {
+                if nibble_idx == nibbles.len() {
+                    // 路径恰好在分支节点处耗尽：值存放在第 17 项
+                    let stored = rlp_item_bytes(&items[16]);
+                    return Ok(bytes_equal(stored, expected_value));
+                }
+
+                let nibble = nibbles[nibble_idx] as usize;
+                nibble_idx += 1;
+
+                match child_ref(&items[nibble])? {
+                    Some(next_ref) => current_ref = next_ref,
+                    // 空分支槽位：路径到此为止，说明键不存在
+                    None => return Ok(expected_value.is_empty()),
+                }
+            }
+            2 => {
+                let (path_nibbles, is_leaf) = decode_compact_nibbles(rlp_item_bytes(&items[0]))?;
+                let remaining = &nibbles[nibble_idx..];
+
+                if is_leaf {
+                    if remaining == path_nibbles.as_slice() {
+                        let stored = rlp_item_bytes(&items[1]);
+                        return Ok(bytes_equal(stored, expected_value));
+                    }
+                    // 叶子节点的编码路径与剩余 nibble 不符：发散叶子，排除证明
+                    return Ok(expected_value.is_empty());
+                }
+
+                // 扩展节点：共享前缀必须与剩余路径的前缀完全一致
+                if remaining.len() < path_nibbles.len()
+                    || remaining[..path_nibbles.len()] != path_nibbles[..]
+                {
+                    return Ok(expected_value.is_empty());
+                }
+                nibble_idx += path_nibbles.len();
+
+                match child_ref(&items[1])? {
+                    Some(next_ref) => current_ref = next_ref,
+                    None => return Ok(expected_value.is_empty()),
+                }
+            }
+            _ => return Err(TrieError::InvalidProof),
+        }
+    }
+
+    Err(TrieError::InvalidProof)
+}
+
+/// 子节点引用：要么是需要到 `proof` 列表中按哈希核对的下一个节点，
+/// 要么是因编码小于 32 字节而直接内联在父节点里的原始字节
+#[derive(Debug, Clone)]
+enum NodeRef {
+    Hash(B256),
+    Inline(Vec<u8>),
+}
+
+/// 单个 RLP 顶层元素，保留其是字符串还是列表（内联子节点总是以列表形式出现）
+#[derive(Debug, Clone, Copy)]
+enum RlpItem<'a> {
+    String(&'a [u8]),
+    /// 整个元素的原始编码（含自身的 RLP 头），可以直接当成一个完整节点递归解码
+    List(&'a [u8]),
+}
+
+fn rlp_item_bytes<'a>(item: &RlpItem<'a>) -> &'a [u8] {
+    match item {
+        RlpItem::String(s) => s,
+        RlpItem::List(l) => l,
+    }
+}
+
+fn bytes_equal(a: &[u8], b: &Bytes) -> bool {
+    a == b.as_ref()
+}
+
+/// 把一个分支/扩展节点的子引用项归一化为 [`NodeRef`]
+///
+/// - 32 字节字符串 => 哈希引用，需要到 `proof` 中找到对应节点
+/// - 空字符串 => 空槽位（`None`）
+/// - 列表（或其他短字符串）=> 内联节点，其原始编码直接可用
+fn child_ref(item: &RlpItem<'_>) -> Result<Option<NodeRef>, TrieError> {
+    match item {
+        RlpItem::String(s) if s.is_empty() => Ok(None),
+        RlpItem::String(s) if s.len() == 32 => Ok(Some(NodeRef::Hash(B256::from_slice(s)))),
+        RlpItem::String(s) => Ok(Some(NodeRef::Inline(s.to_vec()))),
+        RlpItem::List(l) => Ok(Some(NodeRef::Inline(l.to_vec()))),
+    }
+}
+
+/// 解析一个 RLP 编码的字节串顶层的 RLP 头
+///
+/// 返回 `(is_list, header_len, payload_len)`：`header_len` 是头部占用的字节数，
+/// `payload_len` 是紧随其后的有效载荷长度。
+fn decode_rlp_header(data: &[u8]) -> Result<(bool, usize, usize), TrieError> {
+    let prefix = *data.first().ok_or(TrieError::InvalidProof)?;
+
+    if prefix <= 0x7f {
+        Ok((false, 0, 1))
+    } else if prefix <= 0xb7 {
+        Ok((false, 1, (prefix - 0x80) as usize))
+    } else if prefix <= 0xbf {
+        let len_of_len = (prefix - 0xb7) as usize;
+        let len = be_bytes_to_usize(data.get(1..1 + len_of_len).ok_or(TrieError::InvalidProof)?);
+        Ok((false, 1 + len_of_len, len))
+    } else if prefix <= 0xf7 {
+        Ok((true, 1, (prefix - 0xc0) as usize))
+    } else {
+        let len_of_len = (prefix - 0xf7) as usize;
+        let len = be_bytes_to_usize(data.get(1..1 + len_of_len).ok_or(TrieError::InvalidProof)?);
+        Ok((true, 1 + len_of_len, len))
+    }
+}
+
+fn be_bytes_to_usize(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0usize, |acc, b| (acc << 8) | (*b as usize))
+}
+
+/// 把一段 RLP 编码的列表拆成顶层元素（保留每个元素是字符串还是（内联）列表）
+fn split_rlp_list(data: &[u8]) -> Result<Vec<RlpItem<'_>>, TrieError> {
+    let (is_list, header_len, payload_len) = decode_rlp_header(data)?;
+    if !is_list {
+        return Err(TrieError::InvalidProof);
+    }
+
+    let mut payload = data
+        .get(header_len..header_len + payload_len)
+        .ok_or(TrieError::InvalidProof)?;
+
+    let mut items = Vec::new();
+    while !payload.is_empty() {
+        let (item_is_list, item_header_len, item_payload_len) = decode_rlp_header(payload)?;
+        let total = item_header_len + item_payload_len;
+        let full = payload.get(..total).ok_or(TrieError::InvalidProof)?;
+
+        items.push(if item_is_list {
+            RlpItem::List(full)
+        } else {
+            RlpItem::String(&full[item_header_len..])
+        });
+
+        payload = &payload[total..];
+    }
+
+    Ok(items)
+}
+
+/// 把字节串拆成 nibble 序列（每个字节拆成高、低两个 nibble）
+///
+/// `pub(crate)`：[`crate::trie::sparse`] 复用同一套 nibble 表示，避免重复实现。
+pub(crate) fn bytes_to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        nibbles.push(b >> 4);
+        nibbles.push(b & 0x0f);
     }
+    nibbles
+}
+
+/// 解码 MPT 的 hex-prefix（compact）编码，返回 `(路径 nibbles, 是否为叶子节点)`
+fn decode_compact_nibbles(encoded: &[u8]) -> Result<(Vec<u8>, bool), TrieError> {
+    let first = *encoded.first().ok_or(TrieError::InvalidProof)?;
+    let flag = first >> 4;
+    let is_leaf = flag & 0x2 != 0;
+    let is_odd = flag & 0x1 != 0;
+
+    let mut nibbles = Vec::new();
+    if is_odd {
+        nibbles.push(first & 0x0f);
+    }
+    for &byte in &encoded[1..] {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+
+    Ok((nibbles, is_leaf))
 }
 
 /// 账户证明
@@ -95,32 +348,19 @@ impl ProofVerifier {
     }
 }
 
-// TODO: 实现证明生成器
-// pub struct ProofGenerator<'a> {
-//     db: &'a dyn StateDatabase,
-// }
-//
-// impl<'a> ProofGenerator<'a> {
-//     pub fn generate_account_proof(&self, address: &Address) -> Result<AccountProof, TrieError> {
-//         // 1. 获取账户信息
-//         // 2. 构建从叶子到根的路径
-//         // 3. 收集路径上的节点哈希
-//         todo!()
-//     }
-//     
-//     pub fn generate_storage_proof(
-//         &self,
-//         address: &Address,
-//         key: U256,
-//     ) -> Result<StorageProof, TrieError> {
-//         todo!()
-//     }
-// }
+// 证明生成器见 `crate::trie::state_proof::StateProofGenerator`：
+// 它需要通过 `StateDatabase::get_all_accounts`/`get_all_storage` 重建完整的
+// 状态/存储树，因此放在独立模块中，避免 proof.rs 依赖全量账户枚举。
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    
+    use crate::db::{RedbStateDB, StateDatabase};
+    use crate::schema::Account;
+    use crate::trie::state_proof::StateProofGenerator;
+    use alloy_primitives::address;
+    use tempfile::TempDir;
+
     #[test]
     fn test_merkle_proof_creation() {
         let proof = MerkleProof::new(
@@ -129,8 +369,87 @@ mod tests {
             vec![],
             B256::ZERO,
         );
-        
+
         assert_eq!(proof.key.len(), 3);
         assert_eq!(proof.value.len(), 3);
     }
+
+    fn create_test_db() -> (RedbStateDB, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+        (db, temp_dir)
+    }
+
+    #[test]
+    fn test_verify_account_inclusion_proof_generated_by_state_proof_generator() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        let account = Account::with_balance(U256::from(1_000_000u64));
+        db.set_account(&addr, account).unwrap();
+
+        // 插入第二个账户，确保生成的树不是单叶子的退化情况
+        let other = address!("0000000000000000000000000000000000000002");
+        db.set_account(&other, Account::with_balance(U256::from(42u64))).unwrap();
+
+        let generator = StateProofGenerator::new(&db);
+        let proof = generator.generate_proof(&addr, &[]).unwrap();
+
+        assert!(proof.account_proof.verify().unwrap());
+    }
+
+    #[test]
+    fn test_verify_account_exclusion_proof_for_missing_account() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let existing = address!("0000000000000000000000000000000000000001");
+        db.set_account(&existing, Account::with_balance(U256::from(1u64))).unwrap();
+
+        let missing = address!("00000000000000000000000000000000000099ff");
+        let generator = StateProofGenerator::new(&db);
+        let proof = generator.generate_proof(&missing, &[]).unwrap();
+
+        assert!(proof.account_proof.value.is_empty());
+        assert!(proof.account_proof.verify().unwrap());
+    }
+
+    #[test]
+    fn test_standalone_verify_proof_matches_method_form() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        db.set_account(&addr, Account::with_balance(U256::from(1_000_000u64))).unwrap();
+        let other = address!("0000000000000000000000000000000000000002");
+        db.set_account(&other, Account::with_balance(U256::from(42u64))).unwrap();
+
+        let generator = StateProofGenerator::new(&db);
+        let proof = generator.generate_proof(&addr, &[]).unwrap();
+        let account_proof = &proof.account_proof;
+
+        let hashed_key = keccak256(account_proof.key.as_ref());
+        assert!(verify_proof(account_proof.root, hashed_key, &account_proof.value, &account_proof.proof).unwrap());
+    }
+
+    #[test]
+    fn test_verify_rejects_tampered_proof_node() {
+        let (mut db, _temp_dir) = create_test_db();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        db.set_account(&addr, Account::with_balance(U256::from(1_000_000u64))).unwrap();
+        let other = address!("0000000000000000000000000000000000000002");
+        db.set_account(&other, Account::with_balance(U256::from(42u64))).unwrap();
+
+        let generator = StateProofGenerator::new(&db);
+        let mut proof = generator.generate_proof(&addr, &[]).unwrap();
+
+        assert!(!proof.account_proof.proof.is_empty());
+        // 篡改第一个证明节点的最后一个字节，哈希校验应当失败
+        let mut tampered = proof.account_proof.proof[0].to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        proof.account_proof.proof[0] = Bytes::from(tampered);
+
+        assert!(!proof.account_proof.verify().unwrap());
+    }
 }
@@ -0,0 +1,207 @@
+//! 增量维护的稀疏 Merkle 树
+//!
+//! [`TrieBuilder`](super::TrieBuilder)（基于 `alloy_trie::HashBuilder`）是一次性
+//! 流式构建器：重新计算根哈希需要按 nibble 排序把全部叶子重新喂一遍，不支持
+//! "只改一条路径"的增量更新。`WalrusStateDB::state_root` 要求更新代价只与
+//! 变更账户数 × 树深度成正比，因此这里换成一棵固定 16 叉、深度 64（对应
+//! `keccak256` 输出的 64 个 nibble）的稀疏树：每个内部节点总是满的 16 个子
+//! 节点哈希槽位，从未写入的子树统一约定为 [`EMPTY_NODE_HASH`]，更新一个叶子
+//! 时只需要重新计算从叶子到根这一条路径上的节点，兄弟节点哈希由调用方的
+//! `load` 回调提供（通常直接来自缓存，未命中才视为空子树）。
+//!
+//! 代价是放弃了以太坊主网 Patricia 树的十六进制前缀路径压缩（extension/leaf
+//! 节点合并与短值内联），因此这里算出的根哈希不是主网口径的状态根，而是
+//! 本库内部自洽、可增量维护、可验证的根——`state_root()` 返回值的含义仅限于
+//! "同一份数据在这套编码下的根哈希"，不能跨实现比对。
+
+use alloy_primitives::{keccak256, B256};
+use alloy_rlp::{Encodable, Header};
+
+use super::proof::bytes_to_nibbles;
+use super::TrieError;
+
+/// 未写入子树的哈希约定值：真实哈希几乎不可能撞上全零
+pub const EMPTY_NODE_HASH: B256 = B256::ZERO;
+
+/// 叶子路径的 nibble 长度（`keccak256` 输出 32 字节 = 64 个 nibble）
+pub const LEAF_PATH_LEN: usize = 64;
+
+/// 把一个 32 字节的 key（通常是 `keccak256(address)` 或 `keccak256(slot)`）
+/// 拆成 64 个 nibble，作为稀疏树中从根到叶的路径
+pub fn key_path(key: &B256) -> Vec<u8> {
+    bytes_to_nibbles(key.as_slice())
+}
+
+/// 叶子节点的哈希：直接对叶子值做 keccak256，不做短值内联优化
+///
+/// 与 `proof.rs` 中真实 MPT 节点"小于 32 字节可内联"的规则不同——这里统一
+/// 走哈希，换取子节点类型单一（都是 `B256`），是树结构简化的直接结果。
+pub fn leaf_hash(value: &[u8]) -> B256 {
+    keccak256(value)
+}
+
+/// 把一个分支节点的 16 个子哈希 RLP 编码为一个列表，再取 keccak256
+fn branch_hash(children: &[B256; 16]) -> B256 {
+    let mut payload = Vec::with_capacity(16 * 33);
+    for child in children {
+        child.encode(&mut payload);
+    }
+
+    let mut out = Vec::with_capacity(payload.len() + 4);
+    Header { list: true, payload_length: payload.len() }.encode(&mut out);
+    out.extend_from_slice(&payload);
+
+    keccak256(&out)
+}
+
+/// 沿着 `leaf_path`（64 个 nibble）从叶子向根重新计算哈希，只触碰这条路径上
+/// 的节点；兄弟节点哈希通过 `load` 从调用方的缓存/存储中取（未命中则按约定
+/// 视为 [`EMPTY_NODE_HASH`]），新算出的每个节点哈希都通过 `store` 写回。
+///
+/// `load`/`store` 的 key 是从根到该节点的 nibble 路径（长度 `0..=64`，0 即
+/// 树根）；调用方负责给不同的树（账户树、每个账户各自的存储树）分配互不相交
+/// 的路径命名空间，本函数本身不关心这一点。
+///
+/// 返回新的根哈希（即路径长度为 0 的那个节点的哈希）。
+pub fn update_leaf<L, S>(
+    mut load: L,
+    mut store: S,
+    leaf_path: &[u8],
+    hash: B256,
+) -> Result<B256, TrieError>
+where
+    L: FnMut(&[u8]) -> Result<B256, TrieError>,
+    S: FnMut(&[u8], B256) -> Result<(), TrieError>,
+{
+    if leaf_path.len() != LEAF_PATH_LEN {
+        return Err(TrieError::Other(format!(
+            "sparse trie leaf path must have exactly {} nibbles, got {}",
+            LEAF_PATH_LEN,
+            leaf_path.len()
+        )));
+    }
+
+    store(leaf_path, hash)?;
+    let mut current_hash = hash;
+
+    for depth in (0..LEAF_PATH_LEN).rev() {
+        let parent_path = &leaf_path[..depth];
+        let child_index = leaf_path[depth] as usize;
+
+        let mut sibling_path = parent_path.to_vec();
+        sibling_path.push(0);
+        let mut children = [EMPTY_NODE_HASH; 16];
+        for i in 0..16u8 {
+            *sibling_path.last_mut().unwrap() = i;
+            children[i as usize] = if i as usize == child_index {
+                current_hash
+            } else {
+                load(&sibling_path)?
+            };
+        }
+
+        current_hash = branch_hash(&children);
+        store(parent_path, current_hash)?;
+    }
+
+    Ok(current_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn store_of() -> HashMap<Vec<u8>, B256> {
+        HashMap::new()
+    }
+
+    fn update(
+        store: &mut HashMap<Vec<u8>, B256>,
+        leaf_path: &[u8],
+        hash: B256,
+    ) -> B256 {
+        update_leaf(
+            |path| Ok(store.get(path).copied().unwrap_or(EMPTY_NODE_HASH)),
+            |path, hash| {
+                store.insert(path.to_vec(), hash);
+                Ok(())
+            },
+            leaf_path,
+            hash,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_root_of_never_written_tree_is_empty() {
+        let store = store_of();
+        let root = store.get(&Vec::<u8>::new()).copied().unwrap_or(EMPTY_NODE_HASH);
+        assert_eq!(root, EMPTY_NODE_HASH);
+    }
+
+    #[test]
+    fn test_update_leaf_rejects_wrong_path_length() {
+        let mut store = store_of();
+        let result = update_leaf(
+            |path| Ok(store.get(path).copied().unwrap_or(EMPTY_NODE_HASH)),
+            |path, hash| {
+                store.insert(path.to_vec(), hash);
+                Ok(())
+            },
+            &[0u8; 10],
+            B256::from([1u8; 32]),
+        );
+        assert!(matches!(result, Err(TrieError::Other(_))));
+    }
+
+    #[test]
+    fn test_update_leaf_is_deterministic() {
+        let path = key_path(&keccak256(b"alice"));
+        let value = leaf_hash(b"account-rlp-bytes");
+
+        let mut store_a = store_of();
+        let root_a = update(&mut store_a, &path, value);
+
+        let mut store_b = store_of();
+        let root_b = update(&mut store_b, &path, value);
+
+        assert_eq!(root_a, root_b);
+        assert_ne!(root_a, EMPTY_NODE_HASH);
+    }
+
+    #[test]
+    fn test_different_leaves_produce_different_roots() {
+        let mut store = store_of();
+        let root_after_alice = update(
+            &mut store,
+            &key_path(&keccak256(b"alice")),
+            leaf_hash(b"alice-account"),
+        );
+
+        let root_after_bob = update(
+            &mut store,
+            &key_path(&keccak256(b"bob")),
+            leaf_hash(b"bob-account"),
+        );
+
+        assert_ne!(root_after_alice, root_after_bob);
+    }
+
+    #[test]
+    fn test_updating_one_leaf_does_not_disturb_unrelated_sibling_subtree() {
+        let mut store = store_of();
+        let alice_path = key_path(&keccak256(b"alice"));
+        update(&mut store, &alice_path, leaf_hash(b"alice-v1"));
+
+        // 记录一个与 alice 路径早早分叉的兄弟子树哈希
+        let divergent_nibble = (alice_path[0] + 1) % 16;
+        let sibling_path = vec![divergent_nibble];
+        let sibling_hash_before = store.get(&sibling_path).copied().unwrap_or(EMPTY_NODE_HASH);
+
+        update(&mut store, &alice_path, leaf_hash(b"alice-v2"));
+
+        let sibling_hash_after = store.get(&sibling_path).copied().unwrap_or(EMPTY_NODE_HASH);
+        assert_eq!(sibling_hash_before, sibling_hash_after);
+    }
+}
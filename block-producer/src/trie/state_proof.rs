@@ -0,0 +1,206 @@
+//! 状态证明生成（eth_getProof 风格）
+//!
+//! 给定一个地址和一组存储键，重建完整的状态树（以及该账户的存储树），
+//! 收集沿途经过的全部原始 trie 节点，使外部验证者无需访问数据库即可
+//! 独立重新哈希、校验某个账户/存储值是否确实属于给定的状态根。
+//!
+//! 与 `StateRootCalculator`/`StorageRootCalculator` 不同，证明生成必须基于
+//! *完整* 的账户/存储集合重建 trie（`get_all_accounts`/`get_all_storage`），
+//! 而不能像增量状态根计算那样只处理变更账户——缺失兄弟叶子会让构建出的根
+//! 哈希偏离真实状态根，证明也就无法通过验证。
+
+use alloy_primitives::{Address, U256, Bytes};
+use alloy_trie::Nibbles;
+use crate::db::StateDatabase;
+use crate::trie::{TrieBuilder, TrieError, StorageRootCalculator};
+use crate::trie::builder::{hash_key, rlp_encode_account, rlp_encode_storage_value};
+use crate::trie::proof::{AccountProof, StorageProof, MerkleProof};
+use crate::trie::storage_root::EMPTY_STORAGE_ROOT;
+
+/// 状态证明生成器
+pub struct StateProofGenerator<'a> {
+    db: &'a dyn StateDatabase,
+}
+
+impl<'a> StateProofGenerator<'a> {
+    /// 创建状态证明生成器
+    pub fn new(db: &'a dyn StateDatabase) -> Self {
+        Self { db }
+    }
+
+    /// 生成账户证明，以及 `storage_keys` 中每个存储槽的证明
+    ///
+    /// 对不存在的地址或存储键同样返回一个证明：证明节点列表在 trie 中
+    /// 最近的分支/扩展节点处终止，验证者据此可以确认该键确实不在树中
+    /// （即排除证明，exclusion proof）。
+    pub fn generate_proof(
+        &self,
+        address: &Address,
+        storage_keys: &[U256],
+    ) -> Result<AccountProof, TrieError> {
+        let account_proof = self.generate_account_proof(address)?;
+
+        let mut storage_proofs = Vec::with_capacity(storage_keys.len());
+        for &key in storage_keys {
+            storage_proofs.push(self.generate_storage_proof(address, key)?);
+        }
+
+        Ok(AccountProof {
+            address: *address,
+            account_proof,
+            storage_proofs,
+        })
+    }
+
+    /// 生成账户在全局状态树中的 Merkle 证明
+    ///
+    /// 重建完整状态树（全部账户，而非增量子集），并让 `TrieBuilder` 的证明
+    /// 保留器记录下通往该账户哈希地址叶子（或其应处位置）路径上的所有节点。
+    fn generate_account_proof(&self, address: &Address) -> Result<MerkleProof, TrieError> {
+        let accounts = self.db.get_all_accounts()
+            .map_err(|e| TrieError::Database(e.to_string()))?;
+
+        let hashed_addr = hash_key(address.as_slice());
+        let target = Nibbles::from_bytes_unchecked(hashed_addr.as_slice());
+        let mut builder = TrieBuilder::new_with_proof_targets(vec![target]);
+
+        let storage_calculator = StorageRootCalculator::new(self.db);
+        let mut entries = Vec::with_capacity(accounts.len());
+        for (addr, account) in &accounts {
+            let storage_root = storage_calculator.calculate(addr)?;
+            let key = hash_key(addr.as_slice());
+            let value = rlp_encode_account(account.nonce, account.balance, storage_root, account.code_hash);
+            entries.push((key, value));
+        }
+        entries.sort_by_key(|(key, _)| *key);
+
+        // 在插入前记录目标账户的 RLP 值（若不存在则留空，作为排除证明的值）
+        let account_value = entries.iter()
+            .find(|(key, _)| *key == hashed_addr)
+            .map(|(_, value)| Bytes::copy_from_slice(value));
+
+        for (key, value) in &entries {
+            builder.add_leaf(*key, value);
+        }
+
+        let root = builder.root();
+        let proof_nodes = builder.take_proof_nodes();
+
+        Ok(MerkleProof::new(
+            Bytes::copy_from_slice(address.as_slice()),
+            account_value.unwrap_or_default(),
+            proof_nodes,
+            root,
+        ))
+    }
+
+    /// 生成账户某个存储槽的 Merkle 证明
+    ///
+    /// 做法与 `generate_account_proof` 对称：重建该账户完整的存储树
+    /// （全部非零存储槽），保留通往目标存储键路径上的节点。
+    fn generate_storage_proof(&self, address: &Address, key: U256) -> Result<StorageProof, TrieError> {
+        let slots = self.db.get_all_storage(address)
+            .map_err(|e| TrieError::Database(e.to_string()))?;
+
+        let key_bytes = key.to_be_bytes::<32>();
+        let hashed_key = hash_key(&key_bytes);
+
+        let mut entries: Vec<_> = slots.into_iter()
+            .filter(|slot| slot.value != U256::ZERO) // 与 StorageRootCalculator 保持一致，跳过零值
+            .map(|slot| {
+                let slot_key_bytes = slot.key.to_be_bytes::<32>();
+                (hash_key(&slot_key_bytes), slot.value)
+            })
+            .collect();
+        entries.sort_by_key(|(hash, _)| *hash);
+
+        let value = entries.iter()
+            .find(|(hash, _)| *hash == hashed_key)
+            .map(|(_, value)| *value)
+            .unwrap_or(U256::ZERO);
+
+        // 空存储树：沿用 StorageRootCalculator 的早退约定，直接返回空存储根，
+        // 此时不存在任何节点可以收集，证明节点列表为空。
+        if entries.is_empty() {
+            return Ok(StorageProof {
+                key,
+                value,
+                proof: MerkleProof::new(
+                    Bytes::copy_from_slice(&key_bytes),
+                    Bytes::new(),
+                    Vec::new(),
+                    EMPTY_STORAGE_ROOT,
+                ),
+            });
+        }
+
+        let target = Nibbles::from_bytes_unchecked(hashed_key.as_slice());
+        let mut builder = TrieBuilder::new_with_proof_targets(vec![target]);
+        for (hash, slot_value) in &entries {
+            let value_rlp = rlp_encode_storage_value(*slot_value);
+            builder.add_leaf(*hash, &value_rlp);
+        }
+
+        let root = builder.root();
+        let proof_nodes = builder.take_proof_nodes();
+        // 零值槽不在树中：排除证明的值留空（与账户证明一致），而不是 rlp(0)
+        let value_rlp = if value.is_zero() { Vec::new() } else { rlp_encode_storage_value(value) };
+
+        Ok(StorageProof {
+            key,
+            value,
+            proof: MerkleProof::new(
+                Bytes::copy_from_slice(&key_bytes),
+                Bytes::from(value_rlp),
+                proof_nodes,
+                root,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::RedbStateDB;
+    use crate::schema::Account;
+    use alloy_primitives::address;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_generate_account_proof_for_existing_account() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        let account = Account {
+            nonce: 1,
+            balance: U256::from(1000),
+            code_hash: alloy_primitives::B256::ZERO,
+            storage_root: alloy_primitives::B256::ZERO,
+        };
+        db.set_account(&addr, account.clone()).unwrap();
+
+        let generator = StateProofGenerator::new(&db);
+        let proof = generator.generate_proof(&addr, &[]).unwrap();
+
+        assert_eq!(proof.address, addr);
+        assert!(!proof.account_proof.value.is_empty());
+        assert!(proof.storage_proofs.is_empty());
+    }
+
+    #[test]
+    fn test_generate_account_proof_for_missing_account_is_exclusion_proof() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000002");
+        let generator = StateProofGenerator::new(&db);
+        let proof = generator.generate_proof(&addr, &[]).unwrap();
+
+        // 账户不存在：值为空，但仍然返回一个（可能为空的）证明路径与根哈希
+        assert!(proof.account_proof.value.is_empty());
+    }
+}
@@ -2,114 +2,436 @@
 //! 
 //! 计算全局状态树的根哈希
 
-use alloy_primitives::{Address, B256};
+use alloy_primitives::{Address, U256, B256};
 use rayon::prelude::*;
-use crate::db::StateDatabase;
+use std::collections::BTreeMap;
+use crate::db::{StateDatabase, DbError};
+use crate::schema::{Account, StorageSlot};
 use crate::trie::{TrieBuilder, TrieError, StorageRootCalculator};
-use crate::trie::builder::{hash_key, rlp_encode_account};
+use crate::trie::{node_store, sparse};
+use crate::trie::builder::{hash_key, rlp_encode_account, rlp_encode_storage_value};
+use crate::trie::storage_root::EMPTY_STORAGE_ROOT;
+use crate::trie::proof::{AccountProof, StorageProof};
+use crate::trie::state_proof::StateProofGenerator;
+
+/// EIP-158/161 空账户清理策略
+///
+/// 名字和语义都照搬自 go-ethereum 的 `state.CleanupMode`：
+/// - `NoEmpty`：不做任何清理，空账户照常写入状态树（Spurious Dragon 之前的
+///   行为，这里保留下来主要供历史区块/测试固件复现用）
+/// - `ForceCreate`：即使账户"看起来空"，也强制当作刚创建、必须保留在树里
+///   （go-ethereum 用它处理 DAO 硬分叉那批特殊退款账户）。本层目前没有
+///   "显式创建"信号可以利用，实际效果与 `NoEmpty` 相同——保留下来是为了让
+///   将来接上 EVM 创建事件时只需要改调用方传参，不需要改这里的判定逻辑
+/// - `KillEmpty`：`nonce == 0 && balance.is_zero() && code_hash == EMPTY_CODE_HASH
+///   && storage_root == EMPTY_STORAGE_ROOT` 的账户一律不写入状态树，改为进入
+///   "待删除"集合——这是 Spurious Dragon 之后主网的标准行为，也是
+///   [`StateRootCalculator`] 的默认策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupMode {
+    NoEmpty,
+    ForceCreate,
+    KillEmpty,
+}
+
+impl CleanupMode {
+    /// 该策略下，一个"看起来空"的账户是否应该从状态树里排除
+    fn excludes_empty_accounts(self) -> bool {
+        matches!(self, CleanupMode::KillEmpty)
+    }
+}
+
+/// 单个账户处理完毕后，攒够了插入状态树所需的一切：哈希地址、RLP 编码的账户
+/// 值，以及清理判定需要用到的原始地址和"是否为空"标记
+struct ProcessedAccount {
+    address: Address,
+    hashed_addr: B256,
+    account_rlp: Vec<u8>,
+    is_empty: bool,
+}
 
 /// 状态根计算器
 pub struct StateRootCalculator<'a> {
     db: &'a dyn StateDatabase,
     /// 是否使用并行计算
     parallel: bool,
+    /// EIP-158 空账户清理策略，见 [`CleanupMode`]
+    cleanup_mode: CleanupMode,
 }
 
 impl<'a> StateRootCalculator<'a> {
-    /// 创建状态根计算器
+    /// 创建状态根计算器（默认按 [`CleanupMode::KillEmpty`] 清理空账户，
+    /// 即 Spurious Dragon 之后的主网行为）
     pub fn new(db: &'a dyn StateDatabase) -> Self {
         Self {
             db,
             parallel: true,
+            cleanup_mode: CleanupMode::KillEmpty,
         }
     }
-    
+
     /// 创建串行计算器（用于调试）
     pub fn new_serial(db: &'a dyn StateDatabase) -> Self {
         Self {
             db,
             parallel: false,
+            cleanup_mode: CleanupMode::KillEmpty,
         }
     }
-    
-    /// 计算状态根（全量计算）
-    /// 
-    /// 遍历所有账户，计算状态树根哈希
+
+    /// 显式指定空账户清理策略
+    pub fn with_cleanup_mode(mut self, cleanup_mode: CleanupMode) -> Self {
+        self.cleanup_mode = cleanup_mode;
+        self
+    }
+
+    /// 生成一份账户的 Merkle 包含证明（账户不存在时为排除证明），外部验证者
+    /// 拿着它和一个已知的状态根即可独立校验账户的 nonce/余额/代码哈希，不需要
+    /// 访问完整数据库
+    ///
+    /// 薄封装：真正的证明生成（重建完整状态树、收集路径节点）已经在
+    /// [`StateProofGenerator`] 里实现并有测试覆盖，这里只是按本类型惯用的
+    /// `prove`/`prove_storage` 命名把它暴露出来，避免证明生成逻辑重复一份。
+    pub fn prove(&self, address: &Address) -> Result<AccountProof, TrieError> {
+        StateProofGenerator::new(self.db).generate_proof(address, &[])
+    }
+
+    /// 生成账户某个存储槽的 Merkle 证明（槽位未设置时为排除证明），与
+    /// [`Self::prove`] 返回的账户证明中携带的 `storage_root` 相绑定——两者
+    /// 合在一起就能完整验证"状态根 -> 账户 -> 存储槽"这条链路
+    pub fn prove_storage(&self, address: &Address, slot: U256) -> Result<StorageProof, TrieError> {
+        let proof = StateProofGenerator::new(self.db).generate_proof(address, &[slot])?;
+        proof.storage_proofs.into_iter().next()
+            .ok_or_else(|| TrieError::Other(format!(
+                "expected exactly one storage proof for slot {slot} of account {address}"
+            )))
+    }
+
+    /// 计算状态根
+    ///
+    /// 直接委托给 [`Self::calculate_incremental`]——后者现在对"只有部分账户
+    /// 变更"和"全部账户都变更"同样正确，不需要区分全量/增量两条路径。
     pub fn calculate(&self) -> Result<B256, TrieError> {
-        // TODO: 实现完整的账户遍历
-        // 当前简化实现：仅计算变更账户
         self.calculate_incremental()
     }
-    
-    /// 增量计算状态根（仅计算变更账户）
-    /// 
-    /// 性能优化：只重新计算变更的账户及其路径
+
+    /// 增量计算状态根：只重新计算变更账户的路径，未变更的子树全部复用
+    ///
+    /// 薄封装：丢弃 [`Self::calculate_incremental_with_cleanup`] 返回的待删除
+    /// 账户集合，只暴露根哈希——大多数调用方（`RedbStateDB::state_root`、
+    /// `BlockExecutor::calculate_state_root`）只关心根本身
     pub fn calculate_incremental(&self) -> Result<B256, TrieError> {
+        self.calculate_incremental_with_cleanup().map(|(root, _)| root)
+    }
+
+    /// 增量计算状态根，同时返回按当前 [`CleanupMode`] 判定需要从数据库里删除
+    /// 的空账户地址集合
+    ///
+    /// 状态树持久化在 [`StateDatabase::trie_node`]/[`StateDatabase::trie_root_hash`]
+    /// 背后的节点存储里（`RedbStateDB` 用一张按节点哈希为键的 redb 表实现），
+    /// 每个变更账户的 RLP 编码值通过 [`node_store::insert`] 沿着
+    /// `keccak256(address)` 对应的 64-nibble 路径走到底，只重新哈希路径上
+    /// 经过的 `Leaf`/`Extension`/`Branch` 节点，路径之外的子树原样复用存储里
+    /// 已有的哈希——这才是真正的"只算变更路径"，而不是把变更账户之外的全部
+    /// 账户都丢掉重建一棵新树。
+    ///
+    /// 限制：`node_store` 目前不支持删除叶子（见其模块文档）。所以
+    /// `KillEmpty` 对"这次变更之前状态树里还没有这个账户"的情形是完全正确
+    /// 的——本次插入直接跳过，根本不会出现在树里；但如果一个账户在更早的
+    /// 提交里已经写入过状态树，之后才变空，目前还是会留在树里，只会出现在
+    /// 返回的待删除集合中，等待 `node_store` 支持删除后再补上真正的移除。
+    pub fn calculate_incremental_with_cleanup(&self) -> Result<(B256, Vec<Address>), TrieError> {
         // 1. 获取变更的账户列表
         let changed_accounts = self.db.get_changed_accounts()
             .map_err(|e| TrieError::Database(e.to_string()))?;
-        
+
+        // 2. 读取上一次持久化的根（未持久化过，或从未写入任何账户，视为空状态根）
+        let current_root = self.db.trie_root_hash()
+            .map_err(|e| TrieError::Database(e.to_string()))?;
+
         if changed_accounts.is_empty() {
-            // 没有变更，返回空状态根
-            return Ok(EMPTY_STATE_ROOT);
+            return Ok((current_root.unwrap_or(EMPTY_STATE_ROOT), Vec::new()));
         }
-        
-        // 2. 为每个账户计算存储根（并行）
-        let accounts_with_storage: Vec<_> = if self.parallel {
+
+        // 3. 为每个变更账户计算存储根（可并行，互不依赖）。`AccountAbsent` 不是
+        // 致命错误——它是账户被这次变更正常删除（selfdestruct、EIP-158 清理）
+        // 之后留下的信号，单独捞出来记入待删除集合；其余错误（`StateCorrupt`/
+        // `MissingNode`/`Database`）仍然让整批计算立即失败，不能静默吞掉
+        let raw_results: Vec<Result<ProcessedAccount, TrieError>> = if self.parallel {
             changed_accounts
                 .par_iter()
                 .map(|addr| self.process_account(addr))
-                .collect::<Result<Vec<_>, _>>()?
+                .collect()
         } else {
             changed_accounts
                 .iter()
                 .map(|addr| self.process_account(addr))
-                .collect::<Result<Vec<_>, _>>()?
+                .collect()
         };
-        
-        // 3. 构建状态树
-        let mut builder = TrieBuilder::new();
-        
-        // 4. 按地址哈希排序
-        let mut sorted_accounts = accounts_with_storage;
-        sorted_accounts.sort_by_key(|(hashed_addr, _, _, _, _)| *hashed_addr);
-        
-        // 5. 插入状态树
-        for (hashed_addr, nonce, balance, storage_root, code_hash) in sorted_accounts {
-            let account_rlp = rlp_encode_account(nonce, balance, storage_root, code_hash);
-            builder.add_leaf(hashed_addr, &account_rlp);
+
+        let mut processed = Vec::with_capacity(raw_results.len());
+        let mut to_delete = Vec::new();
+        for (address, result) in changed_accounts.iter().zip(raw_results) {
+            match result {
+                Ok(account) => processed.push(account),
+                Err(TrieError::AccountAbsent { .. }) => to_delete.push(*address),
+                Err(e) => return Err(e),
+            }
         }
-        
-        // 6. 计算根哈希
-        Ok(builder.root())
+
+        // 4. 依次把每个账户的叶子插入持久化节点存储，沿途节点都落盘。插入必须
+        // 串行进行——每一步都依赖上一步算出的新根，并发插入会互相踩踏同一批
+        // 路径节点。按当前清理策略该排除的空账户跳过插入，改记入待删除集合
+        let mut root = current_root;
+        for account in processed {
+            if self.cleanup_mode.excludes_empty_accounts() && account.is_empty {
+                to_delete.push(account.address);
+                continue;
+            }
+
+            let path = sparse::key_path(&account.hashed_addr);
+            root = Some(node_store::insert(
+                |hash| {
+                    self.db.trie_node(hash)
+                        .map_err(|e| TrieError::Database(e.to_string()))
+                },
+                |hash, data| {
+                    self.db.put_trie_node(hash, data)
+                        .map_err(|e| TrieError::Database(e.to_string()))
+                },
+                root,
+                &path,
+                account.account_rlp,
+            )?);
+        }
+
+        // 5. 持久化新根，供下一次增量计算或查询使用
+        let new_root = root.unwrap_or(EMPTY_STATE_ROOT);
+        self.db.set_trie_root_hash(new_root)
+            .map_err(|e| TrieError::Database(e.to_string()))?;
+        Ok((new_root, to_delete))
     }
-    
-    /// 处理单个账户
-    /// 
-    /// 返回：(哈希地址, nonce, balance, storage_root, code_hash)
-    fn process_account(
-        &self,
-        address: &Address,
-    ) -> Result<(B256, u64, alloy_primitives::U256, B256, B256), TrieError> {
+
+    /// 处理单个账户：读取账户数据、计算存储根、RLP 编码，并判定是否为空
+    ///
+    /// 返回 `Err(TrieError::AccountAbsent)` 并不代表出错——`track_changed_account`
+    /// 同时被 `set_account` 和 `delete_account` 调用，账户被正常删除
+    /// （selfdestruct、EIP-158 清理空账户）之后本来就该在 `get_account` 里查不
+    /// 到，调用方 `calculate_incremental_with_cleanup` 会把这个信号专门捞出来
+    /// 记入待删除集合，而不是当成致命错误中止整个区块。真正的数据损坏——落盘的
+    /// 账户记录本身反序列化失败——才归类为 `StateCorrupt`
+    fn process_account(&self, address: &Address) -> Result<ProcessedAccount, TrieError> {
         // 1. 获取账户信息
         let account = self.db.get_account(address)
-            .map_err(|e| TrieError::Database(e.to_string()))?
-            .ok_or_else(|| TrieError::Database(format!("Account not found: {}", address)))?;
-        
-        // 2. 计算存储根
+            .map_err(|e| match e {
+                DbError::Serialization(detail) => TrieError::StateCorrupt(
+                    format!("account {} failed to decode: {}", address, detail)
+                ),
+                other => TrieError::Database(other.to_string()),
+            })?
+            .ok_or(TrieError::AccountAbsent { address: *address })?;
+
+        // 2. 计算存储根：优先走持久化增量路径（只折叠变更槽位），不依赖
+        // `StorageRootCalculator` 的进程内缓存——`process_account` 每次都是
+        // 新建的计算器，那份缓存天生是冷的
         let storage_calculator = StorageRootCalculator::new(self.db);
-        let storage_root = storage_calculator.calculate(address)?;
-        
+        let storage_root = storage_calculator.calculate_persistent(address)?;
+
         // 3. 哈希地址
         let hashed_addr = hash_key(address.as_slice());
-        
-        Ok((
-            hashed_addr,
+
+        // 4. 是否为空：EIP-161 的 nonce/余额/代码判定（`Account::is_empty`）
+        // 再加上存储根也必须是空——防御性地确保这个账户确实没有任何遗留存储
+        let is_empty = account.is_empty() && storage_root == EMPTY_STORAGE_ROOT;
+
+        let account_rlp = rlp_encode_account(
             account.nonce,
             account.balance,
             storage_root,
             account.code_hash,
-        ))
+        );
+
+        Ok(ProcessedAccount {
+            address: *address,
+            hashed_addr,
+            account_rlp,
+            is_empty,
+        })
+    }
+}
+
+/// 从内存中的账户/存储条目集合计算状态根
+///
+/// 与 [`StateRootCalculator`] 不同，本函数不依赖 [`StateDatabase`]，适用于调用方
+/// 已经持有账户及其存储条目（例如创世区块构建、测试 fixture）的场景。每个账户
+/// 先按 `keccak256(slot)` 对其存储条目建立"安全"存储树，算出 `storage_root`，
+/// 再据此 RLP 编码账户并按 `keccak256(address)` 插入世界状态树。
+pub fn calculate_state_root<I, S>(accounts: I) -> B256
+where
+    I: IntoIterator<Item = (Address, Account, S)>,
+    S: IntoIterator<Item = StorageSlot>,
+{
+    let mut builder = TrieBuilder::new();
+
+    let mut leaves: Vec<(B256, Vec<u8>)> = accounts
+        .into_iter()
+        .map(|(address, account, storage)| {
+            let storage_root = calculate_storage_root(storage);
+            let account_rlp = rlp_encode_account(
+                account.nonce,
+                account.balance,
+                storage_root,
+                account.code_hash,
+            );
+            (hash_key(address.as_slice()), account_rlp)
+        })
+        .collect();
+
+    if leaves.is_empty() {
+        return EMPTY_STATE_ROOT;
+    }
+
+    leaves.sort_by_key(|(hashed_addr, _)| *hashed_addr);
+
+    for (hashed_addr, account_rlp) in leaves {
+        builder.add_leaf(hashed_addr, &account_rlp);
+    }
+
+    builder.root()
+}
+
+/// 从内存中的存储槽集合计算单个账户的存储根
+fn calculate_storage_root<S>(storage: S) -> B256
+where
+    S: IntoIterator<Item = StorageSlot>,
+{
+    let mut sorted_slots: Vec<_> = storage
+        .into_iter()
+        .filter(|slot| slot.value != U256::ZERO) // 跳过零值（gas 优化）
+        .map(|slot| {
+            let key_bytes = slot.key.to_be_bytes::<32>();
+            (hash_key(&key_bytes), slot.value)
+        })
+        .collect();
+
+    if sorted_slots.is_empty() {
+        return EMPTY_STORAGE_ROOT;
+    }
+
+    sorted_slots.sort_by_key(|(hash, _)| *hash);
+
+    let mut builder = TrieBuilder::new();
+    for (hashed_key, value) in sorted_slots {
+        let value_rlp = rlp_encode_storage_value(value);
+        builder.add_leaf(hashed_key, &value_rlp);
+    }
+
+    builder.root()
+}
+
+/// 两层嵌套的状态树构建器：每个账户自带一棵独立的存储树
+///
+/// [`calculate_state_root`] 接受一次性迭代器，适合"已经有全部账户"的一次性
+/// 计算；`StateTrie` 则是增量累积式的构建器——调用方逐个账户插入（创世区块
+/// 构建、测试 fixture 经常是边读配置边插入），并显式拒绝重复插入同一地址。
+///
+/// ## 不变式：`HashBuilder::add_leaf` 要求严格升序、互不相同的叶子键
+///
+/// 无论是账户树（键 `hash_key(address)`）还是每个账户自己的存储树
+/// （键 `hash_key(slot)`），底层的 `alloy_trie::HashBuilder` 都要求叶子按
+/// nibble 严格升序插入，重复或乱序的键会导致构建出错误的根（或 panic）。
+/// `accounts`/每个账户的 `storage` 都用 `BTreeMap` 保证**原始**键（地址/槽位）
+/// 互不相同，但顶层树真正插入的键是哈希后的值，哈希并不保序，所以仍需要在
+/// `root()` 里按哈希值显式重新排序，不能依赖 `BTreeMap` 的迭代顺序。
+pub struct StateTrie {
+    accounts: BTreeMap<Address, (Account, BTreeMap<U256, U256>)>,
+}
+
+impl StateTrie {
+    /// 创建一个空的状态树构建器
+    pub fn new() -> Self {
+        Self {
+            accounts: BTreeMap::new(),
+        }
+    }
+
+    /// 插入一个账户及其全部存储槽
+    ///
+    /// 重复插入同一地址会返回错误而不是静默覆盖：调用方多半是弄错了地址，
+    /// 覆盖只会让最终状态根悄悄偏离调用方的预期。
+    pub fn insert_account(
+        &mut self,
+        address: Address,
+        account: Account,
+        storage: BTreeMap<U256, U256>,
+    ) -> Result<(), TrieError> {
+        if self.accounts.contains_key(&address) {
+            return Err(TrieError::Other(format!(
+                "duplicate account {address} inserted into StateTrie"
+            )));
+        }
+        self.accounts.insert(address, (account, storage));
+        Ok(())
+    }
+
+    /// 计算全局状态根
+    ///
+    /// 先为每个账户独立构建存储树得到 `storage_root`，再把 RLP 编码后的账户
+    /// （按 `hash_key(address)` 排序）插入顶层账户树。
+    pub fn root(&self) -> B256 {
+        if self.accounts.is_empty() {
+            return EMPTY_STATE_ROOT;
+        }
+
+        let mut leaves: Vec<(B256, Vec<u8>)> = self.accounts
+            .iter()
+            .map(|(address, (account, storage))| {
+                let storage_root = Self::storage_root(storage);
+                let account_rlp = rlp_encode_account(
+                    account.nonce,
+                    account.balance,
+                    storage_root,
+                    account.code_hash,
+                );
+                (hash_key(address.as_slice()), account_rlp)
+            })
+            .collect();
+
+        leaves.sort_by_key(|(hashed_addr, _)| *hashed_addr);
+
+        let mut builder = TrieBuilder::new();
+        for (hashed_addr, account_rlp) in leaves {
+            builder.add_leaf(hashed_addr, &account_rlp);
+        }
+        builder.root()
+    }
+
+    /// 为单个账户的存储槽集合构建存储树，返回其根哈希
+    fn storage_root(storage: &BTreeMap<U256, U256>) -> B256 {
+        let mut leaves: Vec<(B256, U256)> = storage
+            .iter()
+            .filter(|(_, value)| **value != U256::ZERO) // 零值槽等价于未设置
+            .map(|(slot, value)| (hash_key(&slot.to_be_bytes::<32>()), *value))
+            .collect();
+
+        if leaves.is_empty() {
+            return EMPTY_STORAGE_ROOT;
+        }
+
+        leaves.sort_by_key(|(hash, _)| *hash);
+
+        let mut builder = TrieBuilder::new();
+        for (hashed_key, value) in leaves {
+            let value_rlp = rlp_encode_storage_value(value);
+            builder.add_leaf(hashed_key, &value_rlp);
+        }
+        builder.root()
+    }
+}
+
+impl Default for StateTrie {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -139,4 +461,366 @@ mod tests {
         // 空状态应该返回空状态根
         assert_eq!(root, EMPTY_STATE_ROOT);
     }
+
+    #[test]
+    fn test_calculate_incremental_preserves_prior_commits_when_only_one_account_changes() {
+        use alloy_primitives::address;
+        use crate::schema::Account as SchemaAccount;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr_a = address!("0000000000000000000000000000000000000001");
+        let addr_b = address!("0000000000000000000000000000000000000002");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr_a, SchemaAccount::with_balance(U256::from(100u64))).unwrap();
+        db.commit_transaction().unwrap();
+        let _root_after_a = StateRootCalculator::new(&db).calculate_incremental().unwrap();
+
+        // 第二次提交只改了 B，`changed_accounts` 里也只有 B——这正是曾经的
+        // bug 会复现的场景：旧实现只把 B 塞进一棵新 TrieBuilder，产出的根会
+        // 等价于"只有 B"的状态，悄悄丢掉第一次提交的 A
+        db.begin_transaction().unwrap();
+        db.set_account(&addr_b, SchemaAccount::with_balance(U256::from(200u64))).unwrap();
+        db.commit_transaction().unwrap();
+        let root_after_b = StateRootCalculator::new(&db).calculate_incremental().unwrap();
+
+        // 把同样两个账户一次性提交到一个全新数据库，应该得到完全相同的根：
+        // 分两次增量提交、还是一次性全部提交，最终状态相同，根就必须相同
+        let temp_dir2 = TempDir::new().unwrap();
+        let db_path2 = temp_dir2.path().join("test.redb");
+        let mut db2 = RedbStateDB::new(db_path2.to_str().unwrap()).unwrap();
+        db2.begin_transaction().unwrap();
+        db2.set_account(&addr_a, SchemaAccount::with_balance(U256::from(100u64))).unwrap();
+        db2.set_account(&addr_b, SchemaAccount::with_balance(U256::from(200u64))).unwrap();
+        db2.commit_transaction().unwrap();
+        let root_batch = StateRootCalculator::new(&db2).calculate_incremental().unwrap();
+
+        assert_eq!(root_after_b, root_batch);
+    }
+
+    #[test]
+    fn test_calculate_incremental_is_idempotent_when_nothing_changed_since_last_call() {
+        use alloy_primitives::address;
+        use crate::schema::Account as SchemaAccount;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000003");
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, SchemaAccount::with_balance(U256::from(7u64))).unwrap();
+        db.commit_transaction().unwrap();
+
+        let root_first_call = StateRootCalculator::new(&db).calculate_incremental().unwrap();
+        // 重新计算一次，但这次 `changed_accounts` 里仍是同一个账户（还没有新的
+        // begin_transaction 清空它）——根必须保持不变，而不是把同一个账户的
+        // 叶子重复插入导致出错或产出不同的值
+        let root_second_call = StateRootCalculator::new(&db).calculate_incremental().unwrap();
+
+        assert_eq!(root_first_call, root_second_call);
+    }
+
+    #[test]
+    fn test_kill_empty_excludes_freshly_touched_empty_account_from_root_and_collects_it() {
+        use alloy_primitives::address;
+        use crate::schema::Account as SchemaAccount;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr_a = address!("0000000000000000000000000000000000000001");
+        let addr_empty = address!("0000000000000000000000000000000000000002");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr_a, SchemaAccount::with_balance(U256::from(100u64))).unwrap();
+        // 一个自转账到空地址之后留下的典型空账户：nonce 0、余额 0、无代码、无存储
+        db.set_account(&addr_empty, SchemaAccount::default()).unwrap();
+        db.commit_transaction().unwrap();
+
+        let (root, to_delete) = StateRootCalculator::new(&db)
+            .calculate_incremental_with_cleanup()
+            .unwrap();
+
+        assert_eq!(to_delete, vec![addr_empty]);
+
+        // 这个根必须和"只提交了 A"的情形完全一样——空账户从未真正进入状态树
+        let temp_dir2 = TempDir::new().unwrap();
+        let db_path2 = temp_dir2.path().join("test.redb");
+        let mut db2 = RedbStateDB::new(db_path2.to_str().unwrap()).unwrap();
+        db2.begin_transaction().unwrap();
+        db2.set_account(&addr_a, SchemaAccount::with_balance(U256::from(100u64))).unwrap();
+        db2.commit_transaction().unwrap();
+        let root_without_empty = StateRootCalculator::new(&db2).calculate_incremental().unwrap();
+
+        assert_eq!(root, root_without_empty);
+    }
+
+    #[test]
+    fn test_kill_empty_leaves_non_empty_account_untouched() {
+        use alloy_primitives::address;
+        use crate::schema::Account as SchemaAccount;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        db.begin_transaction().unwrap();
+        db.set_account(&addr, SchemaAccount::with_balance(U256::from(1u64))).unwrap();
+        db.commit_transaction().unwrap();
+
+        let (root, to_delete) = StateRootCalculator::new(&db)
+            .calculate_incremental_with_cleanup()
+            .unwrap();
+
+        assert!(to_delete.is_empty());
+        assert_ne!(root, EMPTY_STATE_ROOT);
+    }
+
+    #[test]
+    fn test_no_empty_mode_keeps_empty_account_in_trie() {
+        use alloy_primitives::address;
+        use crate::schema::Account as SchemaAccount;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr_empty = address!("0000000000000000000000000000000000000002");
+        db.begin_transaction().unwrap();
+        db.set_account(&addr_empty, SchemaAccount::default()).unwrap();
+        db.commit_transaction().unwrap();
+
+        let (root, to_delete) = StateRootCalculator::new(&db)
+            .with_cleanup_mode(CleanupMode::NoEmpty)
+            .calculate_incremental_with_cleanup()
+            .unwrap();
+
+        // `NoEmpty` 下，即使账户完全空也照常写入状态树——根不应该是空状态根，
+        // 也没有账户被标记为待删除
+        assert!(to_delete.is_empty());
+        assert_ne!(root, EMPTY_STATE_ROOT);
+    }
+
+    #[test]
+    fn test_calculate_state_root_empty() {
+        let accounts: Vec<(Address, Account, Vec<StorageSlot>)> = Vec::new();
+        assert_eq!(calculate_state_root(accounts), EMPTY_STATE_ROOT);
+    }
+
+    #[test]
+    fn test_calculate_state_root_eoa_matches_account_rlp() {
+        use alloy_primitives::address;
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        let account = Account {
+            nonce: 1,
+            balance: U256::from(1000u64),
+            storage_root: EMPTY_STORAGE_ROOT,
+            code_hash: alloy_primitives::keccak256([]),
+        };
+
+        let root = calculate_state_root(vec![(addr, account.clone(), Vec::new())]);
+
+        let mut expected = TrieBuilder::new();
+        let account_rlp = rlp_encode_account(
+            account.nonce,
+            account.balance,
+            EMPTY_STORAGE_ROOT,
+            account.code_hash,
+        );
+        expected.add_leaf(hash_key(addr.as_slice()), &account_rlp);
+
+        assert_eq!(root, expected.root());
+    }
+
+    #[test]
+    fn test_calculate_state_root_order_independent() {
+        use alloy_primitives::address;
+
+        let addr_a = address!("0000000000000000000000000000000000000001");
+        let addr_b = address!("0000000000000000000000000000000000000002");
+        let account = Account {
+            nonce: 0,
+            balance: U256::from(1u64),
+            storage_root: EMPTY_STORAGE_ROOT,
+            code_hash: alloy_primitives::keccak256([]),
+        };
+
+        let root1 = calculate_state_root(vec![
+            (addr_a, account.clone(), Vec::new()),
+            (addr_b, account.clone(), Vec::new()),
+        ]);
+        let root2 = calculate_state_root(vec![
+            (addr_b, account.clone(), Vec::new()),
+            (addr_a, account, Vec::new()),
+        ]);
+
+        assert_eq!(root1, root2);
+    }
+
+    #[test]
+    fn test_state_trie_empty_matches_known_empty_root() {
+        // 空状态树的根哈希是 MPT 规范中众所周知的常量，
+        // 与 `EMPTY_STATE_ROOT`（以及空存储根）共用同一个值
+        let trie = StateTrie::new();
+        assert_eq!(trie.root(), EMPTY_STATE_ROOT);
+    }
+
+    #[test]
+    fn test_state_trie_single_account_single_slot_matches_manual_build() {
+        use alloy_primitives::address;
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        let account = Account {
+            nonce: 1,
+            balance: U256::from(1000u64),
+            storage_root: EMPTY_STORAGE_ROOT,
+            code_hash: alloy_primitives::keccak256([]),
+        };
+
+        let mut storage = BTreeMap::new();
+        storage.insert(U256::from(1u64), U256::from(42u64));
+
+        let mut trie = StateTrie::new();
+        trie.insert_account(addr, account.clone(), storage).unwrap();
+
+        // 手工按两层结构重建期望值：先算存储根，再把账户 RLP 插进顶层树
+        let mut storage_builder = TrieBuilder::new();
+        storage_builder.add_leaf(
+            hash_key(&U256::from(1u64).to_be_bytes::<32>()),
+            &rlp_encode_storage_value(U256::from(42u64)),
+        );
+        let expected_storage_root = storage_builder.root();
+
+        let mut state_builder = TrieBuilder::new();
+        let account_rlp = rlp_encode_account(
+            account.nonce,
+            account.balance,
+            expected_storage_root,
+            account.code_hash,
+        );
+        state_builder.add_leaf(hash_key(addr.as_slice()), &account_rlp);
+
+        assert_eq!(trie.root(), state_builder.root());
+        assert_ne!(expected_storage_root, EMPTY_STORAGE_ROOT);
+    }
+
+    #[test]
+    fn test_state_trie_rejects_duplicate_account_insertion() {
+        use alloy_primitives::address;
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        let account = Account {
+            nonce: 0,
+            balance: U256::ZERO,
+            storage_root: EMPTY_STORAGE_ROOT,
+            code_hash: alloy_primitives::keccak256([]),
+        };
+
+        let mut trie = StateTrie::new();
+        trie.insert_account(addr, account.clone(), BTreeMap::new()).unwrap();
+
+        let err = trie.insert_account(addr, account, BTreeMap::new()).unwrap_err();
+        assert!(matches!(err, TrieError::Other(_)));
+    }
+
+    #[test]
+    fn test_deleted_account_is_collected_for_deletion_not_treated_as_corrupt() {
+        use alloy_primitives::address;
+        use crate::schema::Account as SchemaAccount;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr_a = address!("0000000000000000000000000000000000000001");
+        let addr_b = address!("0000000000000000000000000000000000000002");
+
+        db.begin_transaction().unwrap();
+        db.set_account(&addr_a, SchemaAccount::with_balance(U256::from(100u64))).unwrap();
+        db.set_account(&addr_b, SchemaAccount::with_balance(U256::from(1u64))).unwrap();
+        db.commit_transaction().unwrap();
+        StateRootCalculator::new(&db).calculate_incremental_with_cleanup().unwrap();
+
+        // selfdestruct: 账户被真正删除，但 `track_changed_account` 依然把地址
+        // 记在 `changed_accounts` 里——这是 `process_account` 里 `AccountAbsent`
+        // 的典型触发场景，不应该被当成数据损坏
+        db.begin_transaction().unwrap();
+        db.delete_account(&addr_b).unwrap();
+        db.commit_transaction().unwrap();
+
+        let (_, to_delete) = StateRootCalculator::new(&db)
+            .calculate_incremental_with_cleanup()
+            .unwrap();
+
+        assert_eq!(to_delete, vec![addr_b]);
+    }
+
+    #[test]
+    fn test_prove_generates_verifiable_account_proof() {
+        use alloy_primitives::address;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        db.set_account(&addr, Account::with_balance(U256::from(1_000u64))).unwrap();
+        let other = address!("0000000000000000000000000000000000000002");
+        db.set_account(&other, Account::with_balance(U256::from(7u64))).unwrap();
+
+        let proof = StateRootCalculator::new(&db).prove(&addr).unwrap();
+        assert!(proof.account_proof.verify().unwrap());
+        assert!(!proof.account_proof.value.is_empty());
+    }
+
+    #[test]
+    fn test_prove_storage_generates_verifiable_storage_proof_bound_to_account() {
+        use alloy_primitives::address;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        db.set_account(&addr, Account::with_balance(U256::from(1_000u64))).unwrap();
+        db.set_storage(&addr, U256::from(5), U256::from(99)).unwrap();
+
+        let calculator = StateRootCalculator::new(&db);
+        let account_proof = calculator.prove(&addr).unwrap();
+        let storage_proof = calculator.prove_storage(&addr, U256::from(5)).unwrap();
+
+        assert!(account_proof.account_proof.verify().unwrap());
+        assert!(storage_proof.proof.verify().unwrap());
+        assert_eq!(storage_proof.value, U256::from(99));
+        // 存储根应当与单独用 StorageRootCalculator 对该账户算出的根一致——
+        // 这正是账户证明里 RLP 编码的 storage_root 字段应该绑定的值
+        let expected_storage_root = StorageRootCalculator::new(&db).calculate(&addr).unwrap();
+        assert_eq!(storage_proof.proof.root, expected_storage_root);
+    }
+
+    #[test]
+    fn test_prove_storage_for_unset_slot_is_exclusion_proof() {
+        use alloy_primitives::address;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.redb");
+        let mut db = RedbStateDB::new(db_path.to_str().unwrap()).unwrap();
+
+        let addr = address!("0000000000000000000000000000000000000001");
+        db.set_account(&addr, Account::with_balance(U256::from(1_000u64))).unwrap();
+
+        let storage_proof = StateRootCalculator::new(&db)
+            .prove_storage(&addr, U256::from(123))
+            .unwrap();
+
+        assert_eq!(storage_proof.value, U256::ZERO);
+        assert!(storage_proof.proof.verify().unwrap());
+    }
 }
@@ -17,20 +17,28 @@ pub fn sha256_hash(data: &[u8]) -> [u8; 32] {
 }
 
 /// 计算交易哈希
+///
+/// 委托给 [`Transaction::compute_hash`]：对类型化交易的规范 RLP 编码
+/// （已带 EIP-2718 类型字节前缀）做 keccak256，而非对 JSON 字符串取哈希，
+/// 这样哈希与共识层一致、且不受序列化字段顺序影响。
 pub fn compute_tx_hash(tx: &Transaction) -> Result<B256, String> {
-    let json = serde_json::to_string(tx)
-        .map_err(|e| format!("Failed to serialize transaction: {}", e))?;
-    
-    Ok(keccak256_hash(json.as_bytes()))
+    Ok(tx.compute_hash())
 }
 
 /// 计算区块哈希
-pub fn compute_block_hash(header: &BlockHeader) -> Result<String, String> {
-    let json = serde_json::to_string(header)
-        .map_err(|e| format!("Failed to serialize block header: {}", e))?;
-    
-    let hash = sha256_hash(json.as_bytes());
-    Ok(format!("0x{}", hex::encode(hash)))
+///
+/// 委托给 [`BlockHeader::rlp_encode`]：keccak256(RLP(区块头))，而非对
+/// 头部 JSON 做 SHA256。
+pub fn compute_block_hash(header: &BlockHeader) -> Result<B256, String> {
+    Ok(alloy_keccak256(header.rlp_encode()))
+}
+
+/// 调试用 JSON 哈希（非共识编码，仅用于日志/调试场景下快速区分载荷）
+pub fn debug_json_hash(value: &impl serde::Serialize) -> Result<B256, String> {
+    let json = serde_json::to_string(value)
+        .map_err(|e| format!("Failed to serialize value: {}", e))?;
+
+    Ok(keccak256_hash(json.as_bytes()))
 }
 
 /// 将十六进制字符串转换为字节数组
@@ -71,7 +79,55 @@ mod tests {
         let original = vec![0x01, 0x02, 0x03, 0xff];
         let hex_str = bytes_to_hex(&original);
         let decoded = hex_to_bytes(&hex_str).unwrap();
-        
+
         assert_eq!(original, decoded);
     }
+
+    #[test]
+    fn test_compute_tx_hash_matches_canonical_encoding() {
+        let tx = Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: None,
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: "0x5208".to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            chain_id: Some(1),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            access_list: None,
+            v: None,
+            r: None,
+            s: None,
+        };
+
+        // 与 `from` 字段无关：哈希由规范 RLP 编码派生，而不是 JSON 序列化
+        assert_eq!(compute_tx_hash(&tx).unwrap(), tx.compute_hash());
+    }
+
+    #[test]
+    fn test_compute_block_hash_is_deterministic_and_not_json() {
+        use chrono::Utc;
+
+        let header = BlockHeader {
+            number: 1,
+            parent_hash: format!("0x{}", hex::encode(B256::ZERO)),
+            timestamp: Utc::now(),
+            tx_count: 0,
+            transactions_root: format!("0x{}", hex::encode(B256::ZERO)),
+            state_root: None,
+            gas_used: None,
+            gas_limit: Some(30_000_000),
+            receipts_root: None,
+            logs_bloom: None,
+            base_fee_per_gas: None,
+        };
+
+        let hash1 = compute_block_hash(&header).unwrap();
+        let hash2 = keccak256_hash(&header.rlp_encode());
+
+        assert_eq!(hash1, hash2);
+    }
 }
@@ -1,73 +1,144 @@
 //! Merkle Tree 工具
-//! 
+//!
 //! 用于计算 transactions_root 和 receipts_root
 //!
 //! ## 实现说明
 //!
-//! 本模块实现了符合以太坊规范的 Merkle Patricia Trie 根哈希计算。
-//!
-//! ### 键的选择
-//!
-//! 为了确保 Trie 构建时键的顺序性（HashBuilder 要求键必须递增），
-//! 我们使用以下策略：
-//!
-//! 1. 将列表索引进行 RLP 编码
-//! 2. 计算 RLP 编码后的 keccak256 哈希
-//! 3. 使用哈希值作为 Trie 键
-//! 4. 按哈希值排序后插入 Trie
+//! 本模块实现了符合以太坊规范的 Merkle Patricia Trie 根哈希计算：
+//! 对列表中第 `index` 项，键是 `rlp(index)` 本身（*不*做哈希），
+//! 值是该项的 RLP 编码——与共识层的交易树/收据树完全一致。
 //!
-//! 这种方法解决了 RLP 编码的顺序问题：
-//! - 0 的 RLP 编码为 `0x80`（空字符串）
-//! - 1-127 的 RLP 编码为自身 `0x01` - `0x7f`
-//! - 这导致 `0x80 > 0x01`，违反了递增约束
-//!
-//! 使用哈希后，所有键都是 32 字节的均匀分布值，
-//! 可以通过排序确保顺序性。
+//! 这保留了共识层一个广为人知的「怪癖」：`0` 的 RLP 编码是 `0x80`
+//! （空字符串），而 `1..=127` 编码为自身 `0x01..=0x7f`，因此索引 0
+//! 在字典序上排在索引 1-127 之后。这不是 bug，是协议行为——
+//! `state_root`/`storage_root` 才对键做哈希（见 `trie::state_root`/
+//! `trie::storage_root`，键分别是 `keccak256(address)`/
+//! `keccak256(slot_key)`），交易树和收据树不做哈希。
 
-use alloy_primitives::B256;
+use alloy_primitives::{B256, Bytes};
 use alloy_trie::{HashBuilder, Nibbles};
+use alloy_trie::proof::ProofRetainer;
 use alloy_rlp::Encodable;
+use crate::trie::{TrieError, verify_proof_raw};
 
 /// 计算 Merkle root（通用方法）
-/// 
-/// 对列表中的每个元素进行 RLP 编码后，构建 Merkle Patricia Trie
-/// 使用索引的哈希作为键，确保键的顺序性
+///
+/// 对列表中的每个元素进行 RLP 编码后，以索引的 RLP 编码（而非其哈希）
+/// 为键构建 Merkle Patricia Trie，与以太坊交易树/收据树的构建方式一致。
 pub fn calculate_merkle_root<T: Encodable>(items: &[T]) -> B256 {
-    use alloy_primitives::keccak256;
-    
     if items.is_empty() {
         return EMPTY_ROOT_HASH;
     }
-    
+
     let mut builder = HashBuilder::default();
-    
-    // 收集所有键值对并按哈希键排序
-    let mut entries: Vec<(B256, Vec<u8>)> = items.iter().enumerate().map(|(index, item)| {
+
+    // 收集所有键值对并按键（索引的 RLP 编码）排序
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = items.iter().enumerate().map(|(index, item)| {
         // RLP 编码项
         let mut value_buf = Vec::new();
         item.encode(&mut value_buf);
-        
-        // 使用索引的 RLP 编码，然后计算其 keccak256 哈希作为键
-        // 这确保了键的顺序性，因为哈希值是均匀分布的
+
+        // 键就是索引本身的 RLP 编码，不做哈希——与共识层一致
         let mut key_buf = Vec::new();
         index.encode(&mut key_buf);
-        let key_hash = keccak256(&key_buf);
-        
-        (key_hash, value_buf)
+
+        (key_buf, value_buf)
     }).collect();
-    
-    // 按键排序（哈希值的字典序）
+
+    // 按键排序（RLP 编码字节的字典序）
     entries.sort_by(|a, b| a.0.cmp(&b.0));
-    
+
     // 按排序后的顺序插入 Trie
-    for (key_hash, value) in entries {
-        let nibbles = Nibbles::unpack(key_hash);
+    for (key, value) in entries {
+        let nibbles = Nibbles::unpack(&key);
         builder.add_leaf(nibbles, &value);
     }
-    
+
     builder.root()
 }
 
+/// 交易树/收据树上某一项的 Merkle 包含证明（light client 风格）
+///
+/// 键是目标项索引的 RLP 编码（与 [`calculate_merkle_root`] 构建时一致，
+/// *不* 做哈希），因此验证走 [`verify_proof_raw`] 而非
+/// `trie::proof::verify_proof`——后者固定对键先 `keccak256`，只适用于
+/// `state_root`/`storage_root` 那种键经过哈希的树。
+#[derive(Debug, Clone)]
+pub struct MerkleInclusionProof {
+    /// 根哈希
+    pub root: B256,
+    /// 目标项索引的 RLP 编码
+    pub key: Bytes,
+    /// 目标项的 RLP 编码
+    pub value: Bytes,
+    /// 从根到叶经过的原始 trie 节点（按路径顺序）
+    pub nodes: Vec<Bytes>,
+}
+
+impl MerkleInclusionProof {
+    /// 校验该证明是否确实证明 `value` 在根为 `root` 的树中对应 `key`
+    pub fn verify(&self) -> Result<bool, TrieError> {
+        verify_proof_raw(self.root, self.key.as_ref(), &self.value, &self.nodes)
+    }
+}
+
+/// 为 `items[target_index]` 生成 Merkle 包含证明
+///
+/// 构建方式与 [`calculate_merkle_root`] 完全一致（键为未哈希的索引 RLP 编码），
+/// 区别只是给 `HashBuilder` 挂上 [`ProofRetainer`]，记录下通往目标索引路径上
+/// 的全部节点。`target_index` 越界（或列表为空）时返回 `None`。
+pub fn calculate_merkle_root_with_proof<T: Encodable>(
+    items: &[T],
+    target_index: usize,
+) -> Option<MerkleInclusionProof> {
+    if target_index >= items.len() {
+        return None;
+    }
+
+    let mut target_key = Vec::new();
+    target_index.encode(&mut target_key);
+    let target = Nibbles::unpack(&target_key);
+
+    let mut builder = HashBuilder::default().with_proof_retainer(ProofRetainer::new(vec![target]));
+
+    let mut entries: Vec<(Vec<u8>, Vec<u8>)> = items.iter().enumerate().map(|(index, item)| {
+        let mut value_buf = Vec::new();
+        item.encode(&mut value_buf);
+
+        let mut key_buf = Vec::new();
+        index.encode(&mut key_buf);
+
+        (key_buf, value_buf)
+    }).collect();
+
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let target_value = entries.iter()
+        .find(|(key, _)| *key == target_key)
+        .map(|(_, value)| value.clone())
+        .expect("target_index < items.len() guarantees an entry with that key");
+
+    for (key, value) in &entries {
+        let nibbles = Nibbles::unpack(key);
+        builder.add_leaf(nibbles, value);
+    }
+
+    let root = builder.root();
+    let nodes = builder
+        .take_proof_nodes()
+        .into_nodes_sorted()
+        .into_iter()
+        .map(|(_, node)| node)
+        .collect();
+
+    Some(MerkleInclusionProof {
+        root,
+        key: Bytes::from(target_key),
+        value: Bytes::from(target_value),
+        nodes,
+    })
+}
+
 /// 空根哈希
 pub const EMPTY_ROOT_HASH: B256 = B256::new([
     0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6,
@@ -125,7 +196,47 @@ mod tests {
         
         let root1 = calculate_merkle_root(&items1);
         let root2 = calculate_merkle_root(&items2);
-        
+
         assert_ne!(root1, root2);
     }
+
+    #[test]
+    fn test_merkle_root_handles_multi_byte_index() {
+        // 超过 128 项时，索引 128 起的 RLP 编码从单字节变为 0x81 字符串头加一个字节，
+        // 确保该分支也能正常构建（不 panic）并产生确定性结果
+        let items: Vec<u64> = (0..200).collect();
+        let root1 = calculate_merkle_root(&items);
+        let root2 = calculate_merkle_root(&items);
+
+        assert_eq!(root1, root2);
+        assert_ne!(root1, EMPTY_ROOT_HASH);
+    }
+
+    #[test]
+    fn test_merkle_proof_root_matches_plain_calculation() {
+        let items: Vec<u64> = (0..50).collect();
+        let proof = calculate_merkle_root_with_proof(&items, 7).unwrap();
+
+        assert_eq!(proof.root, calculate_merkle_root(&items));
+        assert!(proof.verify().unwrap());
+    }
+
+    #[test]
+    fn test_merkle_proof_out_of_bounds_index_returns_none() {
+        let items: Vec<u64> = vec![1, 2, 3];
+        assert!(calculate_merkle_root_with_proof(&items, 3).is_none());
+    }
+
+    #[test]
+    fn test_merkle_proof_rejects_tampered_node() {
+        let items: Vec<u64> = (0..50).collect();
+        let mut proof = calculate_merkle_root_with_proof(&items, 7).unwrap();
+
+        let mut tampered = proof.nodes[0].to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 0xff;
+        proof.nodes[0] = Bytes::from(tampered);
+
+        assert!(!proof.verify().unwrap());
+    }
 }
@@ -2,6 +2,8 @@
 
 pub mod serialization;
 pub mod hash;
+pub mod merkle;
 
 pub use serialization::{serialize_to_bytes, deserialize_from_bytes};
-pub use hash::{keccak256_hash, compute_tx_hash, compute_block_hash};
+pub use hash::{keccak256_hash, compute_tx_hash, compute_block_hash, debug_json_hash};
+pub use merkle::{calculate_merkle_root, calculate_merkle_root_with_proof, MerkleInclusionProof, EMPTY_ROOT_HASH};
@@ -0,0 +1,323 @@
+//! 交易验证队列：三级流水线（待验证 → 验证中 → 已验证）
+//!
+//! 此前 `refill_pool` 在出块者自己的异步任务里逐笔从 Walrus 拉取、
+//! hex 解码、JSON 解析，验证延迟直接计入每次出块的耗时，也无法利用多核。
+//! `VerificationQueue` 把这两件事分拆成独立的阶段：一个异步任务专门负责
+//! 网络 IO（从 Walrus 拉取 hex blob），`max(可用并行度, 3) - 2` 个工作线程
+//! （沿用 [`block_producer::db::commit_queue::CommitQueue`] 的线程数估算方式）专门
+//! 负责 CPU 密集的无状态校验——hex 解码、JSON 解析、字段格式、gas 范围、
+//! 签名恢复——校验通过的交易进入已验证队列，出块者打包前只需要把它搬进
+//! [`crate::pool::TxPool`]。签名恢复出的发送方地址会缓存到
+//! `Transaction::recovered_sender` 上，交易池按发送方分桶时直接读取缓存，
+//! 不会为同一笔交易重复跑 ECDSA 恢复。
+
+use crate::Transaction;
+use block_producer::signing::verify_and_recover_sender;
+use block_producer::DEFAULT_BLOCK_GAS_LIMIT;
+use distributed_walrus::cli_client::CliClient;
+use parking_lot::{Condvar, Mutex};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::warn;
+
+/// 单笔交易允许的最小 gas——低于这个值的交易连一次最简单的转账都不够
+const MIN_TX_GAS: u64 = 21_000;
+
+/// 拉取/校验循环的轮询间隔：topic 暂无数据、或背压生效时的睡眠时长
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 三级队列各自的深度，用于可观测性与背压判断
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct QueueInfo {
+    pub unverified_queue_size: usize,
+    pub verifying_queue_size: usize,
+    pub verified_queue_size: usize,
+}
+
+impl QueueInfo {
+    /// 三个阶段加总的队列总深度，背压以此为准
+    pub fn total_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size + self.verified_queue_size
+    }
+
+    /// 尚未进入"已验证"阶段的部分——这部分还不能被打包
+    pub fn incomplete_queue_size(&self) -> usize {
+        self.unverified_queue_size + self.verifying_queue_size
+    }
+}
+
+struct Shared {
+    unverified: Mutex<VecDeque<String>>,
+    verifying: AtomicUsize,
+    verified: Mutex<VecDeque<Transaction>>,
+    /// 有新的未验证 blob 入队时唤醒工作线程
+    has_unverified: Condvar,
+    /// 未验证队列清空时唤醒等待方
+    became_empty: Condvar,
+    /// 已验证队列新增交易时唤醒出块者的 `ready_signal` 等待
+    has_verified: Condvar,
+    shutdown: AtomicBool,
+}
+
+impl Shared {
+    fn info(&self) -> QueueInfo {
+        QueueInfo {
+            unverified_queue_size: self.unverified.lock().len(),
+            verifying_queue_size: self.verifying.load(Ordering::SeqCst),
+            verified_queue_size: self.verified.lock().len(),
+        }
+    }
+
+    fn worker_loop(self: Arc<Self>) {
+        loop {
+            let blob = {
+                let mut unverified = self.unverified.lock();
+                loop {
+                    if let Some(blob) = unverified.pop_front() {
+                        if unverified.is_empty() {
+                            self.became_empty.notify_all();
+                        }
+                        break blob;
+                    }
+                    if self.shutdown.load(Ordering::SeqCst) {
+                        return;
+                    }
+                    self.has_unverified.wait(&mut unverified);
+                }
+            };
+
+            self.verifying.fetch_add(1, Ordering::SeqCst);
+            let result = validate_hex_blob(&blob);
+            self.verifying.fetch_sub(1, Ordering::SeqCst);
+
+            match result {
+                Ok(tx) => {
+                    self.verified.lock().push_back(tx);
+                    self.has_verified.notify_all();
+                }
+                Err(e) => warn!("交易未通过无状态校验: {}, 数据: {}", e, blob),
+            }
+        }
+    }
+}
+
+/// 一笔交易在进入交易池前需要满足的无状态校验：字段格式、gas 范围、签名恢复
+///
+/// "无状态"指这里只看交易自身携带的数据，不查询账户 nonce/余额等链上状态——
+/// 那些校验留给执行层，这里只负责把明显畸形或伪造的交易挡在池子外面。
+fn validate_hex_blob(hex_data: &str) -> Result<Transaction, String> {
+    let hex_clean = hex_data.trim_start_matches("0x").trim_start_matches("0X");
+    let bytes = hex::decode(hex_clean).map_err(|e| format!("无效的十六进制编码: {}", e))?;
+    let json_str = String::from_utf8(bytes).map_err(|e| format!("无效的 UTF-8: {}", e))?;
+    let mut tx: Transaction =
+        serde_json::from_str(&json_str).map_err(|e| format!("JSON 解析失败: {}", e))?;
+
+    let schema_tx = tx.to_schema_tx();
+    schema_tx
+        .from_address()
+        .map_err(|e| format!("字段格式错误: {}", e))?;
+    schema_tx
+        .to_address()
+        .map_err(|e| format!("字段格式错误: {}", e))?;
+    schema_tx
+        .nonce_value()
+        .map_err(|e| format!("字段格式错误: {}", e))?;
+    schema_tx
+        .value_wei()
+        .map_err(|e| format!("字段格式错误: {}", e))?;
+    let gas = schema_tx
+        .gas_limit()
+        .map_err(|e| format!("字段格式错误: {}", e))?;
+    if !(MIN_TX_GAS..=DEFAULT_BLOCK_GAS_LIMIT).contains(&gas) {
+        return Err(format!(
+            "gas 超出合理范围: {} (允许 {}..={})",
+            gas, MIN_TX_GAS, DEFAULT_BLOCK_GAS_LIMIT
+        ));
+    }
+    let recovered = verify_and_recover_sender(&schema_tx).map_err(|e| format!("签名校验失败: {}", e))?;
+    tx.recovered_sender = Some(recovered);
+
+    Ok(tx)
+}
+
+/// 多线程交易验证队列
+pub struct VerificationQueue {
+    shared: Arc<Shared>,
+    fetch_handle: Option<tokio::task::JoinHandle<()>>,
+    workers: Vec<std::thread::JoinHandle<()>>,
+}
+
+impl VerificationQueue {
+    /// 启动验证队列：一个异步任务负责从 Walrus 拉取 blob（纯网络 IO），
+    /// 工作线程池负责 CPU 密集的校验。`high_water_mark` 是三级队列加总的
+    /// 软上限——超过后暂停从 Walrus 拉取新 blob，直到出块者把已验证队列
+    /// 消费下去，避免内存随验证延迟无限增长。
+    pub fn spawn(walrus_client: CliClient, topic: String, high_water_mark: usize) -> Self {
+        let shared = Arc::new(Shared {
+            unverified: Mutex::new(VecDeque::new()),
+            verifying: AtomicUsize::new(0),
+            verified: Mutex::new(VecDeque::new()),
+            has_unverified: Condvar::new(),
+            became_empty: Condvar::new(),
+            has_verified: Condvar::new(),
+            shutdown: AtomicBool::new(false),
+        });
+
+        let fetch_handle = {
+            let shared = shared.clone();
+            Some(tokio::spawn(async move {
+                loop {
+                    if shared.shutdown.load(Ordering::SeqCst) {
+                        return;
+                    }
+
+                    if shared.info().total_queue_size() >= high_water_mark {
+                        tokio::time::sleep(POLL_INTERVAL).await;
+                        continue;
+                    }
+
+                    match walrus_client.get(&topic).await {
+                        Ok(Some(hex_data)) => {
+                            shared.unverified.lock().push_back(hex_data);
+                            shared.has_unverified.notify_one();
+                        }
+                        Ok(None) => tokio::time::sleep(POLL_INTERVAL).await,
+                        Err(e) => {
+                            warn!("从 Walrus 拉取交易失败: {}", e);
+                            tokio::time::sleep(POLL_INTERVAL).await;
+                        }
+                    }
+                }
+            }))
+        };
+
+        let worker_count = Self::worker_count();
+        let workers = (0..worker_count)
+            .map(|_| {
+                let shared = shared.clone();
+                std::thread::spawn(move || shared.worker_loop())
+            })
+            .collect();
+
+        Self {
+            shared,
+            fetch_handle,
+            workers,
+        }
+    }
+
+    /// 线程数沿用 [`block_producer::db::commit_queue::CommitQueue`] 的估算方式：
+    /// `max(可用并行度, 3) - 2`，在单核/双核机器上至少保留 1 个线程
+    fn worker_count() -> usize {
+        let cpus = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1);
+        cpus.max(3) - 2
+    }
+
+    /// 各阶段队列深度
+    pub fn info(&self) -> QueueInfo {
+        self.shared.info()
+    }
+
+    /// 阻塞等待直到已验证队列中至少有一笔交易，或超时返回——出块者打包前
+    /// 用它替代此前在 `refill_pool` 里同步拉取/解析交易的 `ready_signal`
+    pub fn wait_until_ready(&self, timeout: Duration) -> bool {
+        let mut verified = self.shared.verified.lock();
+        if !verified.is_empty() {
+            return true;
+        }
+        self.shared.has_verified.wait_for(&mut verified, timeout);
+        !verified.is_empty()
+    }
+
+    /// 阻塞等待直到未验证队列清空——主要用于测试/可观测性
+    pub fn wait_until_unverified_drained(&self, timeout: Duration) {
+        let mut unverified = self.shared.unverified.lock();
+        if !unverified.is_empty() {
+            self.shared.became_empty.wait_for(&mut unverified, timeout);
+        }
+    }
+
+    /// 取出一批已验证交易（最多 `max` 笔），交给调用方插入交易池
+    pub fn drain_verified(&self, max: usize) -> Vec<Transaction> {
+        let mut verified = self.shared.verified.lock();
+        let n = verified.len().min(max);
+        verified.drain(..n).collect()
+    }
+}
+
+impl Drop for VerificationQueue {
+    fn drop(&mut self) {
+        self.shared.shutdown.store(true, Ordering::SeqCst);
+        self.shared.has_unverified.notify_all();
+        if let Some(handle) = self.fetch_handle.take() {
+            handle.abort();
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_tx(gas: &str) -> Transaction {
+        Transaction {
+            from: "0x742d35Cc6634C0532925a3b844Bc9e7595f0bEb".to_string(),
+            to: Some("0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".to_string()),
+            value: "0x0".to_string(),
+            data: "0x".to_string(),
+            gas: gas.to_string(),
+            nonce: "0x0".to_string(),
+            hash: None,
+            gas_price: Some("0x3b9aca00".to_string()),
+            max_fee_per_gas: None,
+            max_priority_fee_per_gas: None,
+            v: None,
+            r: None,
+            s: None,
+            recovered_sender: None,
+        }
+    }
+
+    fn hex_blob(tx: &Transaction) -> String {
+        format!("0x{}", hex::encode(serde_json::to_vec(tx).unwrap()))
+    }
+
+    #[test]
+    fn test_queue_info_total_and_incomplete() {
+        let info = QueueInfo {
+            unverified_queue_size: 3,
+            verifying_queue_size: 2,
+            verified_queue_size: 5,
+        };
+        assert_eq!(info.total_queue_size(), 10);
+        assert_eq!(info.incomplete_queue_size(), 5);
+    }
+
+    #[test]
+    fn test_validate_hex_blob_rejects_invalid_hex() {
+        let err = validate_hex_blob("0xzz").unwrap_err();
+        assert!(err.contains("十六进制"));
+    }
+
+    #[test]
+    fn test_validate_hex_blob_rejects_gas_out_of_bounds() {
+        let tx = sample_tx("0x1"); // 远低于 MIN_TX_GAS
+        let err = validate_hex_blob(&hex_blob(&tx)).unwrap_err();
+        assert!(err.contains("gas"));
+    }
+
+    #[test]
+    fn test_validate_hex_blob_rejects_missing_signature() {
+        // gas 合理但没有 v/r/s，签名恢复这一步应当拒绝
+        let tx = sample_tx("0x5208");
+        let err = validate_hex_blob(&hex_blob(&tx)).unwrap_err();
+        assert!(err.contains("签名校验失败"));
+    }
+}
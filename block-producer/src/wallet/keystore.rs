@@ -0,0 +1,235 @@
+//! Web3 Secret Storage V3 加密密钥库
+//!
+//! 把 32 字节私钥加密存储为标准 V3 JSON，字段命名和 KDF 参数与
+//! geth/ethers 等主流实现保持一致，生成的文件可以被其它钱包工具直接导入：
+//! 用口令通过 scrypt 派生出一个 32 字节的派生密钥（随机 32 字节 salt），
+//! 派生密钥前 16 字节作为 AES-128-CTR 的加密密钥（随机 16 字节 IV）加密
+//! 私钥，派生密钥后 16 字节与密文拼接做 keccak256 得到 MAC——校验口令
+//! 是否正确时只需要重新算一遍 MAC，不需要先解密。
+
+use aes::cipher::{KeyIvInit, StreamCipher};
+use aes::Aes128;
+use alloy_primitives::{keccak256, Address, B256};
+use ctr::Ctr128BE;
+use k256::ecdsa::SigningKey;
+use rand::RngCore;
+use scrypt::Params as ScryptParams;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+type Aes128Ctr = Ctr128BE<Aes128>;
+
+/// scrypt 代价参数 `n = 2^13`，与 geth 默认 keystore 参数一致
+const SCRYPT_LOG_N: u8 = 13;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SCRYPT_DKLEN: usize = 32;
+
+/// Keystore 加解密错误
+#[derive(Debug, Error)]
+pub enum KeystoreError {
+    #[error("scrypt key derivation failed: {0}")]
+    Kdf(String),
+
+    #[error("MAC mismatch: incorrect passphrase or corrupted keystore")]
+    MacMismatch,
+
+    #[error("invalid private key: {0}")]
+    InvalidKey(String),
+
+    #[error("unsupported KDF: {0}")]
+    UnsupportedKdf(String),
+
+    #[error("keystore file I/O error: {0}")]
+    Io(String),
+}
+
+/// `crypto.cipherparams`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CipherParamsJson {
+    pub iv: String,
+}
+
+/// `crypto.kdfparams`（scrypt）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KdfParamsJson {
+    pub dklen: usize,
+    pub n: u32,
+    pub p: u32,
+    pub r: u32,
+    pub salt: String,
+}
+
+/// `crypto` 字段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CryptoJson {
+    pub cipher: String,
+    pub ciphertext: String,
+    pub cipherparams: CipherParamsJson,
+    pub kdf: String,
+    pub kdfparams: KdfParamsJson,
+    pub mac: String,
+}
+
+/// Web3 Secret Storage V3 JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreJson {
+    pub version: u8,
+    pub id: String,
+    pub address: String,
+    pub crypto: CryptoJson,
+}
+
+/// 用口令把 32 字节私钥加密为标准 V3 keystore JSON
+pub fn encrypt(private_key: &B256, passphrase: &str) -> Result<KeystoreJson, KeystoreError> {
+    let mut salt = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut salt);
+    let mut iv = [0u8; 16];
+    rand::thread_rng().fill_bytes(&mut iv);
+
+    let derived_key = derive_key(passphrase, &salt, SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P)?;
+
+    let mut ciphertext = private_key.as_slice().to_vec();
+    let mut cipher = Aes128Ctr::new(derived_key[..16].into(), (&iv).into());
+    cipher.apply_keystream(&mut ciphertext);
+
+    let mac = compute_mac(&derived_key, &ciphertext);
+    let address = derive_address(private_key)?;
+
+    Ok(KeystoreJson {
+        version: 3,
+        id: uuid_v4_like(&salt),
+        address: hex::encode(address.as_slice()),
+        crypto: CryptoJson {
+            cipher: "aes-128-ctr".to_string(),
+            ciphertext: hex::encode(&ciphertext),
+            cipherparams: CipherParamsJson { iv: hex::encode(iv) },
+            kdf: "scrypt".to_string(),
+            kdfparams: KdfParamsJson {
+                dklen: SCRYPT_DKLEN,
+                n: 1u32 << SCRYPT_LOG_N,
+                p: SCRYPT_P,
+                r: SCRYPT_R,
+                salt: hex::encode(salt),
+            },
+            mac: hex::encode(mac),
+        },
+    })
+}
+
+/// 用口令从 V3 keystore JSON 解出签名私钥；口令错误或文件损坏返回
+/// [`KeystoreError::MacMismatch`]
+pub fn decrypt(keystore: &KeystoreJson, passphrase: &str) -> Result<SigningKey, KeystoreError> {
+    if keystore.crypto.kdf != "scrypt" {
+        return Err(KeystoreError::UnsupportedKdf(keystore.crypto.kdf.clone()));
+    }
+
+    let salt = hex::decode(&keystore.crypto.kdfparams.salt).map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    let log_n = (keystore.crypto.kdfparams.n as f64).log2().round() as u8;
+    let derived_key = derive_key(
+        passphrase,
+        &salt,
+        log_n,
+        keystore.crypto.kdfparams.r,
+        keystore.crypto.kdfparams.p,
+    )?;
+
+    let mut ciphertext = hex::decode(&keystore.crypto.ciphertext).map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+
+    let expected_mac = compute_mac(&derived_key, &ciphertext);
+    if hex::encode(expected_mac) != keystore.crypto.mac.to_lowercase() {
+        return Err(KeystoreError::MacMismatch);
+    }
+
+    let iv = hex::decode(&keystore.crypto.cipherparams.iv).map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    if iv.len() != 16 {
+        return Err(KeystoreError::InvalidKey(format!(
+            "invalid IV length: expected 16 bytes, got {}",
+            iv.len()
+        )));
+    }
+    let mut iv_arr = [0u8; 16];
+    iv_arr.copy_from_slice(&iv);
+
+    let mut cipher = Aes128Ctr::new(derived_key[..16].into(), (&iv_arr).into());
+    cipher.apply_keystream(&mut ciphertext);
+
+    SigningKey::from_slice(&ciphertext).map_err(|e| KeystoreError::InvalidKey(e.to_string()))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8, r: u32, p: u32) -> Result<[u8; 32], KeystoreError> {
+    let params = ScryptParams::new(log_n, r, p, SCRYPT_DKLEN).map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    let mut output = [0u8; 32];
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut output).map_err(|e| KeystoreError::Kdf(e.to_string()))?;
+    Ok(output)
+}
+
+/// MAC = keccak256(derived_key[16..32] || ciphertext)
+fn compute_mac(derived_key: &[u8; 32], ciphertext: &[u8]) -> [u8; 32] {
+    let mut preimage = Vec::with_capacity(16 + ciphertext.len());
+    preimage.extend_from_slice(&derived_key[16..32]);
+    preimage.extend_from_slice(ciphertext);
+    *keccak256(preimage)
+}
+
+/// 从私钥派生以太坊地址：keccak256(uncompressed_pubkey[1..])[12..32]
+fn derive_address(private_key: &B256) -> Result<Address, KeystoreError> {
+    let signing_key = SigningKey::from_slice(private_key.as_slice()).map_err(|e| KeystoreError::InvalidKey(e.to_string()))?;
+    let public_key = signing_key.verifying_key().to_encoded_point(false);
+    let hash = keccak256(&public_key.as_bytes()[1..]);
+    Ok(Address::from_slice(&hash[12..]))
+}
+
+/// 形如 UUID v4 的 `id` 字段，仅用于人类识别，不承担密码学用途
+fn uuid_v4_like(entropy: &[u8]) -> String {
+    let b = &entropy[..16];
+    format!(
+        "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:02x}{:02x}{:02x}",
+        b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7], b[8], b[9], b[10], b[11], b[12], b[13], b[14], b[15],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let private_key = B256::from_slice(&[0x11u8; 32]);
+        let keystore = encrypt(&private_key, "correct horse battery staple").unwrap();
+
+        let recovered = decrypt(&keystore, "correct horse battery staple").unwrap();
+        assert_eq!(recovered.to_bytes().as_slice(), private_key.as_slice());
+    }
+
+    #[test]
+    fn test_decrypt_wrong_passphrase_rejected() {
+        let private_key = B256::from_slice(&[0x22u8; 32]);
+        let keystore = encrypt(&private_key, "right passphrase").unwrap();
+
+        let err = decrypt(&keystore, "wrong passphrase").unwrap_err();
+        assert!(matches!(err, KeystoreError::MacMismatch));
+    }
+
+    #[test]
+    fn test_decrypt_rejects_truncated_iv_instead_of_panicking() {
+        let private_key = B256::from_slice(&[0x44u8; 32]);
+        let mut keystore = encrypt(&private_key, "pw").unwrap();
+
+        // MAC 只覆盖派生密钥和密文，篡改 IV 长度不会让 MAC 校验提前拦住它——
+        // 必须在 copy_from_slice 之前显式校验长度，否则这里会直接 panic
+        keystore.crypto.cipherparams.iv.truncate(10);
+
+        let err = decrypt(&keystore, "pw").unwrap_err();
+        assert!(matches!(err, KeystoreError::InvalidKey(_)));
+    }
+
+    #[test]
+    fn test_encrypt_records_correct_address() {
+        let private_key = B256::from_slice(&[0x33u8; 32]);
+        let keystore = encrypt(&private_key, "pw").unwrap();
+
+        let expected_address = derive_address(&private_key).unwrap();
+        assert_eq!(keystore.address, hex::encode(expected_address.as_slice()));
+    }
+}
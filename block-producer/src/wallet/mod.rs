@@ -1,9 +1,11 @@
 //! 内置钱包模块
-//! 
+//!
 //! 提供预配置的测试账户，用于自动交易发送。
 //! 这些账户在数据库初始化时自动创建并预存充足余额。
 
-use alloy_primitives::{Address, U256};
+pub mod keystore;
+
+use alloy_primitives::{Address, B256, U256};
 use std::str::FromStr;
 
 /// 内置钱包账户配置
@@ -31,6 +33,42 @@ impl BuiltInWallet {
     pub fn initial_balance_wei(&self) -> U256 {
         U256::from(self.initial_balance_eth) * U256::from(10u64.pow(18))
     }
+
+    /// 从加密的 Web3 V3 keystore 文件加载钱包，而不是直接传入明文私钥
+    ///
+    /// 用于非测试场景：[`get_builtin_wallets`] 里硬编码的明文私钥只适合
+    /// 本地开发/测试，真实部署应当把私钥以 [`keystore::encrypt`] 生成的
+    /// 加密 JSON 形式落盘，启动时再用口令解开。
+    pub fn from_keystore_file(
+        path: &str,
+        passphrase: &str,
+        initial_balance_eth: u64,
+    ) -> Result<Self, keystore::KeystoreError> {
+        let contents = std::fs::read_to_string(path).map_err(|e| keystore::KeystoreError::Io(e.to_string()))?;
+        let keystore_json: keystore::KeystoreJson =
+            serde_json::from_str(&contents).map_err(|e| keystore::KeystoreError::Io(e.to_string()))?;
+
+        let signing_key = keystore::decrypt(&keystore_json, passphrase)?;
+        let private_key = format!("0x{}", hex::encode(signing_key.to_bytes()));
+        let address = Address::from_str(&format!("0x{}", keystore_json.address))
+            .map_err(|e| keystore::KeystoreError::InvalidKey(e.to_string()))?;
+
+        Ok(Self {
+            address,
+            private_key,
+            initial_balance_eth,
+        })
+    }
+
+    /// 私钥的 32 字节形式，供 [`keystore::encrypt`] 使用
+    pub fn private_key_bytes(&self) -> Result<B256, String> {
+        let hex_str = self.private_key.trim_start_matches("0x");
+        let bytes = hex::decode(hex_str).map_err(|e| format!("Invalid private key: {}", e))?;
+        if bytes.len() != 32 {
+            return Err("Private key must be 32 bytes".to_string());
+        }
+        Ok(B256::from_slice(&bytes))
+    }
 }
 
 /// 获取所有预配置的内置钱包账户
@@ -1,17 +1,40 @@
 use crate::controller::NodeController;
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use std::sync::Arc;
+use thiserror::Error;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
 use tracing::{info, warn};
 
 const MAX_FRAME_LEN: usize = 64 * 1024;
 
+/// TCP 客户端协议层错误：帧格式、命令分派本身出的错，和
+/// `NodeController` 内部的存储/连接错误分属不同来源——调用方可以按分类
+/// 区分"客户端发错了帧/命令"还是"存储层出了问题"，而不必解析错误字符串。
+/// `handle_command`/`parse_hexstring` 构造的具体变体经由 `?` 自动转换为
+/// `anyhow::Error`（`anyhow` 对任意 `std::error::Error` 都有覆盖实现），
+/// 所以沿途函数签名仍是现有的 `anyhow::Result`，调用方不需要跟着改。
+#[derive(Debug, Error)]
+pub enum ProtocolError {
+    #[error("invalid frame length: {0}")]
+    InvalidFrameLength(usize),
+    #[error("unknown command: {0}")]
+    UnknownCommand(String),
+    #[error("non-hex payload: {0}")]
+    NonHexPayload(String),
+    #[error("{command} requires a {argument}")]
+    MissingArgument {
+        command: &'static str,
+        argument: &'static str,
+    },
+}
+
 /// 将hexstring (如 "0x1234" 或 "0X1234") 解析为字节数组
 fn parse_hexstring(s: &str) -> Result<Vec<u8>> {
     let hex_part = s.strip_prefix("0x")
         .or_else(|| s.strip_prefix("0X"))
-        .ok_or_else(|| anyhow!("hexstring must start with 0x or 0X"))?;
+        .ok_or_else(|| ProtocolError::NonHexPayload(s.to_string()))?;
 
     // 处理奇数长度的hex字符串，在前面补0
     let hex_clean = if hex_part.len() % 2 == 1 {
@@ -24,7 +47,7 @@ fn parse_hexstring(s: &str) -> Result<Vec<u8>> {
     for i in (0..hex_clean.len()).step_by(2) {
         let byte_str = &hex_clean[i..i + 2];
         let byte = u8::from_str_radix(byte_str, 16)
-            .map_err(|e| anyhow!("invalid hex byte '{}': {}", byte_str, e))?;
+            .map_err(|_| ProtocolError::NonHexPayload(s.to_string()))?;
         bytes.push(byte);
     }
 
@@ -69,7 +92,8 @@ async fn handle_connection(mut socket: TcpStream, controller: Arc<NodeController
 
         let frame_len = u32::from_le_bytes(len_buf) as usize;
         if frame_len == 0 || frame_len > MAX_FRAME_LEN {
-            send_response(&mut socket, "ERR invalid frame length").await?;
+            let err = ProtocolError::InvalidFrameLength(frame_len);
+            send_response(&mut socket, &format!("ERR {}", err)).await?;
             continue;
         }
 
@@ -82,8 +106,22 @@ async fn handle_connection(mut socket: TcpStream, controller: Arc<NodeController
                 continue;
             }
         };
+        let line = text.trim_end();
+
+        // SUBSCRIBE 不走一问一答的 handle_command：一旦确认，连接转入纯推送
+        // 模式，由 handle_subscription 接管剩余的生命周期，不再回到这个循环。
+        if let Some(topic) = line.strip_prefix("SUBSCRIBE ") {
+            let topic = topic.to_string();
+            return match controller.subscribe_topic(&topic).await {
+                Ok(rx) => {
+                    send_response(&mut socket, "OK").await?;
+                    handle_subscription(socket, rx, &topic).await
+                }
+                Err(e) => send_response(&mut socket, &format!("ERR {}", e)).await,
+            };
+        }
 
-        let response = match handle_command(text.trim_end(), controller.clone()).await {
+        let response = match handle_command(line, controller.clone()).await {
             Ok(msg) => msg,
             Err(e) => format!("ERR {}", e),
         };
@@ -92,32 +130,87 @@ async fn handle_connection(mut socket: TcpStream, controller: Arc<NodeController
     }
 }
 
+/// `SUBSCRIBE <topic>` 确认后的推送阶段
+///
+/// 在 socket 读半部（仅用来探测客户端断开，不再解析任何命令）和该 topic 的
+/// 广播接收端之间 `select!`：`NodeController::append_for_topic` 每发布一条
+/// 新负载，就立即编码为 hexstring、按既有的 4 字节长度前缀帧格式推给客户端；
+/// 读到 EOF 或对端重置即结束订阅。单条负载编码后若超过 `MAX_FRAME_LEN`
+/// 则跳过并记录告警，而不是打破帧格式。
+async fn handle_subscription(
+    mut socket: TcpStream,
+    mut rx: broadcast::Receiver<Vec<u8>>,
+    topic: &str,
+) -> Result<()> {
+    let mut cancel_buf = [0u8; 1];
+
+    loop {
+        tokio::select! {
+            read_result = socket.read(&mut cancel_buf) => {
+                match read_result {
+                    Ok(0) => return Ok(()), // 客户端断开连接
+                    Ok(_) => continue, // 订阅期间忽略客户端发来的任何字节，只用读侧探测断开
+                    Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            payload = rx.recv() => {
+                match payload {
+                    Ok(bytes) => {
+                        let message = format!("OK {}", encode_hexstring(&bytes));
+                        if message.len() > MAX_FRAME_LEN {
+                            warn!(
+                                "dropping oversized topic payload for {} ({} bytes > MAX_FRAME_LEN)",
+                                topic, message.len()
+                            );
+                            continue;
+                        }
+                        send_response(&mut socket, &message).await?;
+                    }
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("subscriber for topic {} lagged, skipped {} messages", topic, skipped);
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return Ok(()),
+                }
+            }
+        }
+    }
+}
+
 async fn handle_command(line: &str, controller: Arc<NodeController>) -> Result<String> {
     let mut parts = line.splitn(3, ' ');
     let Some(op) = parts.next() else {
-        return Err(anyhow!("empty command"));
+        return Err(ProtocolError::MissingArgument {
+            command: "<empty>",
+            argument: "command name",
+        }
+        .into());
     };
     tracing::info!("client command received: {}", line);
 
     match op {
         "REGISTER" => {
-            let topic = parts
-                .next()
-                .ok_or_else(|| anyhow!("REGISTER requires a topic"))?;
+            let topic = parts.next().ok_or_else(|| ProtocolError::MissingArgument {
+                command: "REGISTER",
+                argument: "topic",
+            })?;
             controller.ensure_topic(topic).await?;
             Ok("OK".into())
         }
         "PUT" => {
-            let topic = parts
-                .next()
-                .ok_or_else(|| anyhow!("PUT requires a topic"))?;
-            let payload = parts
-                .next()
-                .ok_or_else(|| anyhow!("PUT requires a payload"))?;
+            let topic = parts.next().ok_or_else(|| ProtocolError::MissingArgument {
+                command: "PUT",
+                argument: "topic",
+            })?;
+            let payload = parts.next().ok_or_else(|| ProtocolError::MissingArgument {
+                command: "PUT",
+                argument: "payload",
+            })?;
 
             // 只接受hexstring格式（必须以0x或0X开头）
             if !payload.starts_with("0x") && !payload.starts_with("0X") {
-                return Err(anyhow!("payload must be hexstring format (start with 0x or 0X)"));
+                return Err(ProtocolError::NonHexPayload(payload.to_string()).into());
             }
 
             // 解析hexstring为字节数组并直接存储
@@ -126,9 +219,10 @@ async fn handle_command(line: &str, controller: Arc<NodeController>) -> Result<S
             Ok("OK".into())
         }
         "GET" => {
-            let topic = parts
-                .next()
-                .ok_or_else(|| anyhow!("GET requires a topic"))?;
+            let topic = parts.next().ok_or_else(|| ProtocolError::MissingArgument {
+                command: "GET",
+                argument: "topic",
+            })?;
             match controller.read_one_for_topic_shared(topic).await? {
                 Some(bytes) => {
                     // 总是将字节数组编码为hexstring格式返回
@@ -139,13 +233,14 @@ async fn handle_command(line: &str, controller: Arc<NodeController>) -> Result<S
             }
         }
         "STATE" => {
-            let topic = parts
-                .next()
-                .ok_or_else(|| anyhow!("STATE requires a topic"))?;
+            let topic = parts.next().ok_or_else(|| ProtocolError::MissingArgument {
+                command: "STATE",
+                argument: "topic",
+            })?;
             Ok(controller.topic_snapshot(topic)?)
         }
         "METRICS" => Ok(controller.get_metrics()?),
-        _ => Err(anyhow!("unknown command")),
+        _ => Err(ProtocolError::UnknownCommand(op.to_string()).into()),
     }
 }
 
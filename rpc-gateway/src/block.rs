@@ -0,0 +1,63 @@
+//! 区块装配
+//!
+//! 将网关收到的一批交易组装成 `block_producer` 的 `Block`，
+//! 交给 `BlockExecutor` 统一执行。装配器负责维护父哈希链与区块头字段，
+//! 使执行层看到的区块结构与真实链一致。
+
+use block_producer::schema::{Block, BlockHeader, Transaction as EvmTransaction};
+use chrono::Utc;
+use parking_lot::Mutex;
+
+/// 默认区块 gas 限制（与 BlockExecutor 测试保持一致）
+const DEFAULT_GAS_LIMIT: u64 = 30_000_000;
+
+/// 零哈希（创世父哈希）
+const ZERO_HASH: &str = "0x0000000000000000000000000000000000000000000000000000000000000000";
+
+/// 区块装配器
+pub struct BlockAssembler {
+    /// 上一个区块的哈希，用于串联父哈希链
+    parent_hash: Mutex<String>,
+}
+
+impl BlockAssembler {
+    /// 创建装配器（父哈希从创世零哈希开始）
+    pub fn new() -> Self {
+        Self {
+            parent_hash: Mutex::new(ZERO_HASH.to_string()),
+        }
+    }
+
+    /// 将交易组装成区块
+    ///
+    /// 区块头携带区块号、父哈希、交易数量与 gas 限制；执行层会在执行后
+    /// 回填 `state_root` / `gas_used` / `receipts_root`。组装完成后更新父哈希。
+    pub fn assemble(&self, number: u64, transactions: Vec<EvmTransaction>) -> Block {
+        let parent_hash = self.parent_hash.lock().clone();
+        let block = Block {
+            header: BlockHeader {
+                number,
+                parent_hash,
+                timestamp: Utc::now(),
+                tx_count: transactions.len(),
+                transactions_root: ZERO_HASH.to_string(),
+                state_root: None,
+                gas_used: None,
+                gas_limit: Some(DEFAULT_GAS_LIMIT),
+                receipts_root: None,
+                logs_bloom: None,
+                base_fee_per_gas: None,
+            },
+            transactions,
+        };
+
+        *self.parent_hash.lock() = block.hash();
+        block
+    }
+}
+
+impl Default for BlockAssembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,33 +1,211 @@
-/// RPC 错误码定义
-#[repr(i32)]
-#[derive(Debug, Clone, Copy)]
-pub enum RpcError {
-    SerializationError = -32000,
-    WalrusConnectionFailed = -32002,
-    WalrusWriteFailed = -32003,
-    RequestTimeout = -32008,
-    InternalError = -32603,
-}
-
-impl RpcError {
-    fn message(&self) -> &'static str {
+use thiserror::Error;
+
+/// 给每个子系统错误枚举生成 `code()`/`into_error_object()`/
+/// `into_simple_error_object()`，行为与过去的单一 `RpcError` 完全一致
+/// （保留既有的 `-320xx` 错误码），只是按子系统拆成了可以分别 `match` 的
+/// 具体类型。
+macro_rules! impl_error_object_conversion {
+    ($ty:ty { $($variant:ident => $code:expr),+ $(,)? }) => {
+        impl $ty {
+            fn code(&self) -> i32 {
+                match self {
+                    $(<$ty>::$variant => $code,)+
+                }
+            }
+
+            /// 转换为 JSON-RPC 错误对象，补充具体的出错详情
+            pub fn into_error_object(self, detail: impl Into<String>) -> jsonrpsee::types::ErrorObjectOwned {
+                jsonrpsee::types::ErrorObjectOwned::owned(
+                    self.code(),
+                    format!("{}: {}", self, detail.into()),
+                    None::<String>,
+                )
+            }
+
+            /// 转换为 JSON-RPC 错误对象，不附加额外详情
+            pub fn into_simple_error_object(self) -> jsonrpsee::types::ErrorObjectOwned {
+                jsonrpsee::types::ErrorObjectOwned::owned(self.code(), self.to_string(), None::<String>)
+            }
+        }
+    };
+}
+
+/// Walrus 存储连接层错误（连接建立、读、写）
+#[derive(Debug, Error, Clone, Copy)]
+pub enum WalrusError {
+    #[error("Walrus 连接失败")]
+    ConnectionFailed,
+    #[error("写入失败")]
+    WriteFailed,
+}
+
+impl_error_object_conversion!(WalrusError {
+    ConnectionFailed => -32002,
+    WriteFailed => -32003,
+});
+
+/// 编解码错误（hex/RLP/serde 序列化）
+#[derive(Debug, Error, Clone, Copy)]
+pub enum CodecError {
+    #[error("序列化失败")]
+    Serialization,
+    #[error("反序列化失败")]
+    Deserialization,
+}
+
+impl_error_object_conversion!(CodecError {
+    Serialization => -32000,
+    Deserialization => -32001,
+});
+
+/// 交易层错误（签名、nonce、无效信封等，由网关自身校验产生；执行器内部
+/// 产生的校验错误走 [`executor_error_to_object`]，两者共用同一套应用层
+/// 错误码区间但分属不同来源）
+#[derive(Debug, Error, Clone, Copy)]
+pub enum TransactionError {
+    #[error("无效的交易")]
+    InvalidTransaction,
+}
+
+impl_error_object_conversion!(TransactionError {
+    InvalidTransaction => -32006,
+});
+
+/// 协议/调度层错误（请求超时、内部调度失败等，与具体交易内容无关）
+#[derive(Debug, Error, Clone, Copy)]
+pub enum ProtocolError {
+    #[error("请求超时")]
+    RequestTimeout,
+    #[error("内部错误")]
+    InternalError,
+}
+
+impl_error_object_conversion!(ProtocolError {
+    RequestTimeout => -32008,
+    InternalError => -32603,
+});
+
+/// 按子系统划分的网关错误的统一外壳：调用方可以按分类（而不是裸整数错误码）
+/// 区分失败属于存储层、编解码层、交易层还是协议/调度层——类似轻节点区分
+/// 共识错误、执行错误与 RPC 层错误。各分支转换为
+/// [`jsonrpsee::types::ErrorObjectOwned`] 时仍保留对应子类型既有的 `-320xx`
+/// 错误码，对已有客户端的线上行为没有影响。
+#[derive(Debug, Error, Clone, Copy)]
+pub enum GatewayError {
+    #[error(transparent)]
+    Walrus(#[from] WalrusError),
+    #[error(transparent)]
+    Codec(#[from] CodecError),
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+    #[error(transparent)]
+    Protocol(#[from] ProtocolError),
+}
+
+impl GatewayError {
+    /// 转换为 JSON-RPC 错误对象，补充具体的出错详情
+    pub fn into_error_object(self, detail: impl Into<String>) -> jsonrpsee::types::ErrorObjectOwned {
+        match self {
+            GatewayError::Walrus(e) => e.into_error_object(detail),
+            GatewayError::Codec(e) => e.into_error_object(detail),
+            GatewayError::Transaction(e) => e.into_error_object(detail),
+            GatewayError::Protocol(e) => e.into_error_object(detail),
+        }
+    }
+
+    /// 转换为 JSON-RPC 错误对象，不附加额外详情
+    pub fn into_simple_error_object(self) -> jsonrpsee::types::ErrorObjectOwned {
         match self {
-            RpcError::SerializationError => "序列化失败",
-            RpcError::WalrusConnectionFailed => "Walrus 连接失败",
-            RpcError::WalrusWriteFailed => "写入失败",
-            RpcError::RequestTimeout => "请求超时",
-            RpcError::InternalError => "内部错误",
+            GatewayError::Walrus(e) => e.into_simple_error_object(),
+            GatewayError::Codec(e) => e.into_simple_error_object(),
+            GatewayError::Transaction(e) => e.into_simple_error_object(),
+            GatewayError::Protocol(e) => e.into_simple_error_object(),
         }
     }
+}
+
+/// 以太坊约定的执行层错误码（application range）
+///
+/// 参考主流节点（geth/erigon）的做法，将用户侧可纠正的校验错误放在
+/// `-32000..-32003`，超出 gas 限制与签名错误分别单独编码（`-32004`/`-32005`），
+/// 其余致命错误归为内部错误。
+mod exec_code {
+    pub const INVALID_GAS: i32 = -32000;
+    pub const NONCE_TOO_LOW: i32 = -32001;
+    pub const INSUFFICIENT_FUNDS: i32 = -32002;
+    pub const INVALID_NONCE: i32 = -32003;
+    pub const GAS_LIMIT_EXCEEDED: i32 = -32004;
+    pub const INVALID_SIGNATURE: i32 = -32005;
+    pub const INTERNAL: i32 = -32603;
+}
+
+/// 将执行层错误映射为 JSON-RPC 错误对象
+///
+/// 校验类错误带上结构化的 `data` 字段（如 nonce 错误的 `{expected, got}`），
+/// 方便钱包解析并提示用户；`Database`/`Evm` 等致命错误收敛为内部错误。
+/// 调用方可结合 [`block_producer::executor::ExecutorError::is_fatal`] 决定
+/// 是中止整个批次还是仅按单笔交易上报此错误。
+pub fn executor_error_to_object(
+    e: &block_producer::executor::ExecutorError,
+) -> jsonrpsee::types::ErrorObjectOwned {
+    use block_producer::executor::ExecutorError::*;
+
+    let (code, data) = match e {
+        InvalidGas => (exec_code::INVALID_GAS, None),
+        NonceTooLow { expected, got } => (
+            exec_code::NONCE_TOO_LOW,
+            Some(serde_json::json!({ "expected": expected, "got": got })),
+        ),
+        InsufficientFunds {
+            required,
+            available,
+        } => (
+            exec_code::INSUFFICIENT_FUNDS,
+            Some(serde_json::json!({ "required": required, "available": available })),
+        ),
+        InvalidNonce => (exec_code::INVALID_NONCE, None),
+        GasLimitExceeded => (exec_code::GAS_LIMIT_EXCEEDED, None),
+        InvalidSignature(_) | SenderMismatch { .. } => (exec_code::INVALID_SIGNATURE, None),
+        // Database / Evm / 其余一律视为内部错误
+        _ => (exec_code::INTERNAL, None),
+    };
+
+    jsonrpsee::types::ErrorObjectOwned::owned(code, e.to_string(), data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_preserved() {
+        assert_eq!(CodecError::Serialization.into_error_object("x").code(), -32000);
+        assert_eq!(CodecError::Deserialization.into_error_object("x").code(), -32001);
+        assert_eq!(WalrusError::ConnectionFailed.into_error_object("x").code(), -32002);
+        assert_eq!(WalrusError::WriteFailed.into_error_object("x").code(), -32003);
+        assert_eq!(TransactionError::InvalidTransaction.into_error_object("x").code(), -32006);
+        assert_eq!(ProtocolError::RequestTimeout.into_error_object("x").code(), -32008);
+        assert_eq!(ProtocolError::InternalError.into_error_object("x").code(), -32603);
+    }
+
+    #[test]
+    fn test_into_error_object_includes_detail() {
+        let error_obj = CodecError::Serialization.into_error_object("测试详情");
+        assert!(error_obj.message().contains("序列化失败"));
+        assert!(error_obj.message().contains("测试详情"));
+    }
+
+    #[test]
+    fn test_simple_error_object() {
+        let error_obj = TransactionError::InvalidTransaction.into_simple_error_object();
+        assert_eq!(error_obj.code(), -32006);
+        assert_eq!(error_obj.message(), "无效的交易");
+    }
 
-    pub fn into_error_object(
-        self,
-        detail: impl Into<String>,
-    ) -> jsonrpsee::types::ErrorObjectOwned {
-        jsonrpsee::types::ErrorObjectOwned::owned(
-            self as i32,
-            format!("{}: {}", self.message(), detail.into()),
-            None::<String>,
-        )
+    #[test]
+    fn test_gateway_error_preserves_category_code_via_from() {
+        let via_gateway: GatewayError = WalrusError::WriteFailed.into();
+        let direct = WalrusError::WriteFailed.into_error_object("detail");
+        assert_eq!(via_gateway.into_error_object("detail").code(), direct.code());
     }
 }
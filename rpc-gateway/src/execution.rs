@@ -0,0 +1,209 @@
+//! 交易执行引擎
+//!
+//! 在将交易写入 Walrus 之前，先通过 `block_producer` 的 REVM 执行器
+//! 对交易进行验证与执行，使网关表现得像一个真正的 EVM 节点前端，
+//! 而不是一个只写的数据汇聚点。
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use alloy_primitives::{Address, B256, U256};
+use block_producer::db::{BlockProvider, StateDatabase};
+use block_producer::executor::{
+    BlockExecutionResult, BlockExecutor, ExecutionResult, ExecutorError, StructLog, TraceConfig,
+};
+use block_producer::schema::{Log, Transaction as EvmTransaction};
+use revm::primitives::BlockEnv;
+use tokio::sync::Mutex;
+
+use crate::block::BlockAssembler;
+use crate::Transaction;
+
+/// 将网关的精简交易结构转换为执行层使用的完整交易结构
+///
+/// 网关侧的 `Transaction` 只携带钱包直接提供的字段，
+/// 其余 EVM 字段（chain_id、EIP-1559 费用）在此填充为默认值。
+fn to_evm_transaction(tx: &Transaction) -> EvmTransaction {
+    EvmTransaction {
+        from: tx.from.clone(),
+        to: tx.to.clone(),
+        value: tx.value.clone(),
+        data: tx.data.clone(),
+        gas: tx.gas.clone(),
+        nonce: tx.nonce.clone(),
+        hash: tx.hash.clone(),
+        gas_price: tx.gas_price.clone(),
+        chain_id: None,
+        max_fee_per_gas: None,
+        max_priority_fee_per_gas: None,
+        access_list: None,
+        v: tx.v,
+        r: tx.r.clone(),
+        s: tx.s.clone(),
+    }
+}
+
+/// 交易执行引擎
+///
+/// 封装 `BlockExecutor`，对外提供“单笔提交执行”“只读模拟”“追踪”以及
+/// “区块装配执行”四条路径。区块执行与单笔执行共享同一套状态（内部的
+/// `TransactionExecutor`），使用 `Mutex` 串行化访问。
+pub struct ExecutionEngine {
+    executor: Mutex<BlockExecutor>,
+    /// 区块装配器，为累积到的交易分配区块号
+    assembler: BlockAssembler,
+    /// 下一个区块号
+    next_block: AtomicU64,
+}
+
+impl ExecutionEngine {
+    /// 基于给定的状态数据库路径创建执行引擎
+    pub fn new(state_db_path: &str) -> Result<Self, ExecutorError> {
+        let db = block_producer::db::RedbStateDB::new(state_db_path)
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+        Ok(Self {
+            executor: Mutex::new(BlockExecutor::new(db)),
+            assembler: BlockAssembler::new(),
+            next_block: AtomicU64::new(1),
+        })
+    }
+
+    /// 将一组收到的交易装配成区块并通过 `BlockExecutor` 执行
+    ///
+    /// 交易按收到顺序进入区块，区块号单调递增。返回区块执行结果
+    /// （各笔收据、成功/失败计数、总 gas）。
+    pub async fn execute_block(
+        &self,
+        txs: &[Transaction],
+    ) -> Result<BlockExecutionResult, ExecutorError> {
+        let number = self.next_block.fetch_add(1, Ordering::SeqCst);
+        let evm_txs: Vec<EvmTransaction> = txs.iter().map(to_evm_transaction).collect();
+        let block = self.assembler.assemble(number, evm_txs);
+
+        let mut executor = self.executor.lock().await;
+        executor.execute_block(&block).await
+    }
+
+    /// 执行并提交交易
+    ///
+    /// 先进行预验证（nonce、余额、gas），随后在事务中执行，成功后提交。
+    /// 任一环节失败都会回滚，确保不会持久化被拒绝的交易。
+    pub async fn execute_and_commit(
+        &self,
+        tx: &Transaction,
+    ) -> Result<ExecutionResult, ExecutorError> {
+        let evm_tx = to_evm_transaction(tx);
+        let mut guard = self.executor.lock().await;
+        let executor = guard.tx_executor_mut();
+
+        executor.validate_transaction(&evm_tx, BlockEnv::default().basefee)?;
+
+        executor
+            .db_mut()
+            .begin_transaction()
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
+        match executor.execute(&evm_tx, BlockEnv::default()) {
+            Ok(result) => {
+                executor
+                    .db_mut()
+                    .commit_transaction()
+                    .map_err(|e| ExecutorError::Database(e.to_string()))?;
+                Ok(result)
+            }
+            Err(e) => {
+                let _ = executor.db_mut().rollback_transaction();
+                Err(e)
+            }
+        }
+    }
+
+    /// 只读模拟执行（`eth_call` / `eth_estimateGas`）
+    ///
+    /// 在一个临时事务中执行交易以得到 `ExecutionResult`，随后回滚，
+    /// 不对状态产生任何持久化影响，供钱包在发送前预演。
+    pub async fn simulate(&self, tx: &Transaction) -> Result<ExecutionResult, ExecutorError> {
+        let evm_tx = to_evm_transaction(tx);
+        let mut guard = self.executor.lock().await;
+        let executor = guard.tx_executor_mut();
+
+        executor.validate_transaction(&evm_tx, BlockEnv::default().basefee)?;
+
+        executor
+            .db_mut()
+            .begin_transaction()
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
+        let result = executor.execute(&evm_tx, BlockEnv::default());
+
+        // 无论成功与否都回滚，保证只读语义
+        let _ = executor.db_mut().rollback_transaction();
+
+        result
+    }
+
+    /// 查询账户余额（`eth_getBalance`）
+    pub async fn balance(&self, address: Address) -> Result<U256, ExecutorError> {
+        let mut guard = self.executor.lock().await;
+        let account = guard
+            .db_mut()
+            .get_account(&address)
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+        Ok(account.map(|a| a.balance).unwrap_or_default())
+    }
+
+    /// 查询账户代码（`eth_getCode`）
+    pub async fn code(&self, address: Address) -> Result<Vec<u8>, ExecutorError> {
+        let mut guard = self.executor.lock().await;
+        let db = guard.db_mut();
+        let Some(account) = db.get_account(&address).map_err(|e| ExecutorError::Database(e.to_string()))? else {
+            return Ok(Vec::new());
+        };
+        let code = db
+            .get_code(&account.code_hash)
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+        Ok(code.map(|bytes| bytes.to_vec()).unwrap_or_default())
+    }
+
+    /// 按区块范围/地址/主题查询日志（`eth_getLogs`）
+    ///
+    /// 委托给 [`BlockProvider::logs_matching`]，它先用每个区块的 logs bloom
+    /// （[`Bloom::matches`](block_producer::schema::Bloom::matches)）做粗过滤，
+    /// 只对命中的区块才真正扫描收据。
+    pub async fn get_logs(
+        &self,
+        from_block: u64,
+        to_block: u64,
+        address: Option<Address>,
+        topic: Option<B256>,
+    ) -> Result<Vec<Log>, ExecutorError> {
+        let mut guard = self.executor.lock().await;
+        guard
+            .db_mut()
+            .logs_matching(from_block, to_block, address, topic)
+            .map_err(|e| ExecutorError::Database(e.to_string()))
+    }
+
+    /// 带追踪地模拟执行（`debug_traceTransaction`）
+    ///
+    /// 在临时事务中执行并采集逐操作码轨迹，随后回滚，不影响持久状态。
+    pub async fn trace(
+        &self,
+        tx: &Transaction,
+        config: TraceConfig,
+    ) -> Result<Vec<StructLog>, ExecutorError> {
+        let evm_tx = to_evm_transaction(tx);
+        let mut guard = self.executor.lock().await;
+        let executor = guard.tx_executor_mut();
+
+        executor
+            .db_mut()
+            .begin_transaction()
+            .map_err(|e| ExecutorError::Database(e.to_string()))?;
+
+        let result = executor.execute_with_trace(&evm_tx, BlockEnv::default(), config);
+
+        let _ = executor.db_mut().rollback_transaction();
+
+        result.map(|r| r.struct_logs.unwrap_or_default())
+    }
+}
@@ -1,22 +1,32 @@
+mod block;
 mod error;
+mod execution;
 mod metrics;
+mod nonce;
+mod status;
 
 use anyhow::Result;
 use clap::Parser;
 use distributed_walrus::cli_client::CliClient;
-use error::RpcError;
+use error::{CodecError, ProtocolError, TransactionError, WalrusError};
+use execution::ExecutionEngine;
+use nonce::NonceLatchManager;
+use status::{TransactionStore, TxStatus};
 use jsonrpsee::core::async_trait;
+use jsonrpsee::core::SubscriptionResult;
 use jsonrpsee::proc_macros::rpc;
 use jsonrpsee::server::{Server, ServerHandle};
+use jsonrpsee::{PendingSubscriptionSink, SubscriptionMessage};
 use metrics::{
-    BATCH_SIZE, TRANSACTIONS_FAILED, TRANSACTIONS_TOTAL, TRANSACTION_DURATION,
-    WALRUS_WRITE_DURATION,
+    ConcurrentRequestGuard, BATCH_SIZE, TRANSACTIONS_FAILED, TRANSACTIONS_TOTAL,
+    TRANSACTION_DURATION, WALRUS_WRITE_DURATION,
 };
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{mpsc, OnceCell, Semaphore};
+use tokio::sync::{mpsc, Mutex, Notify, OnceCell, Semaphore};
+use tokio::task::JoinHandle;
 use tokio::time::Instant;
 use tracing::{debug, error, info, warn};
 use tracing_subscriber::{fmt, EnvFilter};
@@ -62,6 +72,17 @@ struct Args {
     /// 请求超时时间（秒）
     #[arg(long, default_value = "30")]
     request_timeout_secs: u64,
+
+    /// 状态数据库路径（启用 REVM 执行路径）
+    ///
+    /// 指定后，交易会先经过 REVM 执行器验证并执行，再写入 Walrus；
+    /// 留空则退回到旧的“只写”行为。
+    #[arg(long)]
+    state_db: Option<String>,
+
+    /// 启用 debug_traceTransaction（默认关闭以保持热路径开销）
+    #[arg(long, default_value = "false")]
+    enable_debug_trace: bool,
 }
 
 /// 区块链交易数据结构（简化版）
@@ -76,6 +97,25 @@ pub struct Transaction {
     pub nonce: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub hash: Option<String>,
+    /// 签名恢复 id（EIP-155 编码或类型化交易的 y_parity）；启用执行引擎时，
+    /// 缺省（未签名）的交易会被签名校验拒绝
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub v: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub r: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub s: Option<String>,
+}
+
+/// debug_traceTransaction 的可选开关
+///
+/// 用于在追踪时裁剪输出体积；字段缺省时沿用 [`TraceConfig`] 的默认值。
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TraceOptions {
+    pub disable_stack: Option<bool>,
+    pub disable_memory: Option<bool>,
+    pub disable_storage: Option<bool>,
 }
 
 /// 批量处理任务
@@ -85,11 +125,25 @@ struct BatchTask {
     response_tx: tokio::sync::oneshot::Sender<Result<String, jsonrpsee::types::ErrorObjectOwned>>,
 }
 
+/// 计算交易哈希
+///
+/// 网关以写入内容的 SHA-256 作为交易句柄，读写两侧必须使用同一算法，
+/// 这样状态存储里的 `Pending` 条目才能和批量/直写路径的返回值对应上。
+fn compute_tx_hash(data: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(data.as_bytes());
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
 /// 批量处理器
 ///
 /// 将短时间内收到的多个交易批量提交到 Walrus，减少网络往返次数
 struct BatchProcessor {
     tx: mpsc::Sender<BatchTask>,
+    /// 关闭信号：触发后批量循环会做最后一次 flush 并退出
+    shutdown: Arc<Notify>,
+    /// 后台循环句柄，用于在关闭时等待其排空完成
+    handle: Mutex<Option<JoinHandle<()>>>,
 }
 
 impl BatchProcessor {
@@ -98,11 +152,14 @@ impl BatchProcessor {
         topic: String,
         batch_interval: Duration,
         max_batch_size: usize,
+        store: Option<Arc<TransactionStore>>,
     ) -> Self {
         let (tx, mut rx) = mpsc::channel::<BatchTask>(10000);
+        let shutdown = Arc::new(Notify::new());
+        let shutdown_loop = Arc::clone(&shutdown);
 
         // 启动批量处理任务
-        tokio::spawn(async move {
+        let handle = tokio::spawn(async move {
             let mut batch: Vec<BatchTask> = Vec::new();
             let mut interval = tokio::time::interval(batch_interval);
             interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
@@ -115,23 +172,53 @@ impl BatchProcessor {
 
                         // 如果批量大小达到上限，立即处理
                         if batch.len() >= max_batch_size {
-                            Self::process_batch(&walrus_client, &topic, &mut batch).await;
+                            Self::process_batch(&walrus_client, &topic, &mut batch, store.as_deref()).await;
                         }
                     }
                     // 定时器触发
                     _ = interval.tick() => {
                         if !batch.is_empty() {
-                            Self::process_batch(&walrus_client, &topic, &mut batch).await;
+                            Self::process_batch(&walrus_client, &topic, &mut batch, store.as_deref()).await;
                         }
                     }
+                    // 收到关闭信号：排空通道里已入队的任务并做最后一次 flush
+                    _ = shutdown_loop.notified() => {
+                        while let Ok(task) = rx.try_recv() {
+                            batch.push(task);
+                        }
+                        info!("批量处理器收到关闭信号, 正在 flush {} 个在途交易", batch.len());
+                        Self::process_batch(&walrus_client, &topic, &mut batch, store.as_deref()).await;
+                        break;
+                    }
                 }
             }
         });
 
-        Self { tx }
+        Self {
+            tx,
+            shutdown,
+            handle: Mutex::new(Some(handle)),
+        }
+    }
+
+    /// 触发有序关闭：通知循环 flush 剩余批量并等待其结束
+    ///
+    /// 保证所有在途交易都被写入并对等待中的 `oneshot` 调用方作出应答，
+    /// 避免进程退出时静默丢数据、让调用方一直阻塞到超时。
+    async fn shutdown(&self) {
+        self.shutdown.notify_one();
+        let handle = self.handle.lock().await.take();
+        if let Some(handle) = handle {
+            let _ = handle.await;
+        }
     }
 
-    async fn process_batch(walrus_client: &CliClient, topic: &str, batch: &mut Vec<BatchTask>) {
+    async fn process_batch(
+        walrus_client: &CliClient,
+        topic: &str,
+        batch: &mut Vec<BatchTask>,
+        store: Option<&TransactionStore>,
+    ) {
         if batch.is_empty() {
             return;
         }
@@ -169,12 +256,18 @@ impl BatchProcessor {
         for (task, result) in results {
             let response = match result {
                 Ok(_) => {
-                    let mut hasher = Sha256::new();
-                    hasher.update(task.data.as_bytes());
-                    let hash_bytes = hasher.finalize();
-                    Ok(format!("0x{}", hex::encode(hash_bytes)))
+                    let hash = compute_tx_hash(&task.data);
+                    if let Some(store) = store {
+                        store.mark_written(&hash);
+                    }
+                    Ok(hash)
+                }
+                Err(e) => {
+                    if let Some(store) = store {
+                        store.set_status(&compute_tx_hash(&task.data), TxStatus::Failed);
+                    }
+                    Err(WalrusError::WriteFailed.into_error_object(e.to_string()))
                 }
-                Err(e) => Err(RpcError::WalrusWriteFailed.into_error_object(e.to_string())),
             };
 
             let _ = task.response_tx.send(response);
@@ -194,10 +287,10 @@ impl BatchProcessor {
         self.tx
             .send(task)
             .await
-            .map_err(|_| RpcError::InternalError.into_error_object("批量处理器已关闭"))?;
+            .map_err(|_| ProtocolError::InternalError.into_error_object("批量处理器已关闭"))?;
 
         rx.await
-            .map_err(|_| RpcError::InternalError.into_error_object("批量处理响应丢失"))?
+            .map_err(|_| ProtocolError::InternalError.into_error_object("批量处理响应丢失"))?
     }
 }
 
@@ -218,9 +311,90 @@ pub trait WalrusRpcApi {
         data: String,
     ) -> Result<String, jsonrpsee::types::ErrorObjectOwned>;
 
+    /// 只读执行交易（不提交状态），返回调用输出的十六进制
+    #[method(name = "eth_call")]
+    async fn call(
+        &self,
+        tx: Transaction,
+    ) -> Result<String, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 估算交易所需 gas（不提交状态）
+    #[method(name = "eth_estimateGas")]
+    async fn estimate_gas(
+        &self,
+        tx: Transaction,
+    ) -> Result<String, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 查询交易收据
+    #[method(name = "eth_getTransactionReceipt")]
+    async fn get_transaction_receipt(
+        &self,
+        hash: String,
+    ) -> Result<Option<serde_json::Value>, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 查询交易状态（轻量版，仅返回状态枚举）
+    #[method(name = "eth_getTransactionStatus")]
+    async fn get_transaction_status(
+        &self,
+        hash: String,
+    ) -> Result<Option<String>, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 追踪交易执行，返回逐操作码的 StructLog 轨迹
+    #[method(name = "debug_traceTransaction")]
+    async fn debug_trace_transaction(
+        &self,
+        tx: Transaction,
+        options: Option<TraceOptions>,
+    ) -> Result<serde_json::Value, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 将一批交易装配成区块并通过 BlockExecutor 执行
+    #[method(name = "eth_executeBlock")]
+    async fn execute_block(
+        &self,
+        txs: Vec<Transaction>,
+    ) -> Result<serde_json::Value, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 查询账户余额，返回十六进制 wei 数额
+    #[method(name = "eth_getBalance")]
+    async fn get_balance(&self, address: String) -> Result<String, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 查询账户代码，返回十六进制字节串（EOA 返回 `0x`）
+    #[method(name = "eth_getCode")]
+    async fn get_code(&self, address: String) -> Result<String, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 按区块范围/地址/主题查询日志，先用每个区块的 logs bloom 做粗过滤
+    #[method(name = "eth_getLogs")]
+    async fn get_logs(
+        &self,
+        filter: GetLogsFilter,
+    ) -> Result<Vec<serde_json::Value>, jsonrpsee::types::ErrorObjectOwned>;
+
     /// 健康检查
     #[method(name = "health")]
     async fn health(&self) -> Result<String, jsonrpsee::types::ErrorObjectOwned>;
+
+    /// 订阅一笔交易的确认事件：交易收据落地后通过 WebSocket 推送一次，
+    /// 随后自动结束订阅；调用方不再需要轮询 `eth_getTransactionReceipt`。
+    #[subscription(
+        name = "subscribeTransactionConfirmation" => "transactionConfirmation",
+        unsubscribe = "unsubscribeTransactionConfirmation",
+        item = serde_json::Value
+    )]
+    async fn subscribe_transaction_confirmation(&self, hash: String) -> SubscriptionResult;
+}
+
+/// `eth_getLogs` 的过滤条件
+///
+/// 只支持单个地址和单个 topic（与底层 [`BlockProvider::logs_matching`] 的
+/// 查询能力一致），而不是完整的 JSON-RPC `eth_getLogs` 过滤器（多地址、
+/// 按位置的多 topic 数组）。
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetLogsFilter {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub address: Option<String>,
+    pub topic: Option<String>,
 }
 
 /// RPC 服务实现
@@ -235,6 +409,16 @@ pub struct WalrusRpcServer {
     semaphore: Arc<Semaphore>,
     /// 请求超时时间
     request_timeout: Duration,
+    /// REVM 执行引擎（可选）
+    ///
+    /// 启用后，交易会在写入 Walrus 之前经过执行与验证。
+    execution: Option<Arc<ExecutionEngine>>,
+    /// 交易状态 / 收据存储
+    store: Arc<TransactionStore>,
+    /// 是否启用 debug_traceTransaction
+    enable_debug_trace: bool,
+    /// 按发送方的 nonce 顺序闩锁
+    nonce_latches: Arc<NonceLatchManager>,
 }
 
 impl WalrusRpcServer {
@@ -246,9 +430,14 @@ impl WalrusRpcServer {
         max_batch_size: usize,
         request_timeout: Duration,
         enable_batching: bool,
+        execution: Option<Arc<ExecutionEngine>>,
+        enable_debug_trace: bool,
     ) -> Self {
         let walrus_client = CliClient::new(walrus_addr);
 
+        // 状态存储：默认保留 10 分钟
+        let store = Arc::new(TransactionStore::new(Duration::from_secs(600)));
+
         // 创建批量处理器
         let batch_processor = if enable_batching {
             Some(Arc::new(BatchProcessor::new(
@@ -256,6 +445,7 @@ impl WalrusRpcServer {
                 default_topic.clone(),
                 batch_interval,
                 max_batch_size,
+                Some(Arc::clone(&store)),
             )))
         } else {
             None
@@ -268,6 +458,10 @@ impl WalrusRpcServer {
             batch_processor,
             semaphore: Arc::new(Semaphore::new(max_concurrent_requests)),
             request_timeout,
+            execution,
+            store,
+            enable_debug_trace,
+            nonce_latches: Arc::new(NonceLatchManager::new()),
         }
     }
 
@@ -295,7 +489,7 @@ impl WalrusRpcServer {
                             Ok(())
                         } else {
                             error!("注册 topic '{}' 失败: {}", self.default_topic, err_msg);
-                            Err(RpcError::WalrusWriteFailed.into_error_object(err_msg))
+                            Err(WalrusError::WriteFailed.into_error_object(err_msg))
                         }
                     }
                 }
@@ -320,14 +514,55 @@ impl WalrusRpcServer {
         &self,
         tx: Transaction,
     ) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
+        // 按发送方获取 nonce 顺序闩锁：同一发送方的交易在执行与写入期间串行，
+        // 保证先提交的 nonce 先落盘；不同发送方互不阻塞。
+        // guard 在本函数结束（写入完成）后释放。
+        let _nonce_guard = self.nonce_latches.acquire(&tx.from).await;
+
         // 序列化交易为 JSON
         let tx_json = serde_json::to_string(&tx)
-            .map_err(|e| RpcError::SerializationError.into_error_object(e.to_string()))?;
+            .map_err(|e| CodecError::Serialization.into_error_object(e.to_string()))?;
 
         // 转换为十六进制字符串
         let hex_data = hex::encode(tx_json.as_bytes());
         let hex_data = Self::ensure_hex_format(&hex_data);
 
+        // 登记为 Pending，便于后续通过 eth_getTransactionReceipt 查询
+        let hash = compute_tx_hash(&hex_data);
+        self.store.insert_pending(&hash);
+
+        // 若启用了执行引擎，先执行并验证交易，再决定是否写入
+        if let Some(engine) = &self.execution {
+            match engine.execute_and_commit(&tx).await {
+                Ok(result) => {
+                    debug!(
+                        "交易执行完成: success={}, gas_used={}",
+                        result.success, result.gas_used
+                    );
+                    let receipt = serde_json::json!({
+                        "transactionHash": hash,
+                        "status": if result.success { "0x1" } else { "0x0" },
+                        "gasUsed": format!("0x{:x}", result.gas_used),
+                        "contractAddress": result.contract_address,
+                    });
+                    let status = if result.success {
+                        TxStatus::Executed
+                    } else {
+                        TxStatus::Failed
+                    };
+                    self.store.set_receipt(&hash, status, receipt);
+                    if !result.success {
+                        return Err(TransactionError::InvalidTransaction
+                            .into_error_object("交易执行回滚 (revert)"));
+                    }
+                }
+                Err(e) => {
+                    self.store.set_status(&hash, TxStatus::Failed);
+                    return Err(error::executor_error_to_object(&e));
+                }
+            }
+        }
+
         // 确保 topic 已注册（只会执行一次）
         self.ensure_topic_registered().await?;
 
@@ -340,21 +575,29 @@ impl WalrusRpcServer {
     }
 
     /// 处理原始交易
+    ///
+    /// `data` 是 hex(JSON(`Transaction`)) 编码的信封，和 `process_transaction`
+    /// 写入 Walrus 时用的格式完全一致——解出内层 `Transaction` 之后直接复用
+    /// `process_transaction` 的校验/执行/写入全流程，而不是绕过
+    /// `ExecutionEngine` 直接落盘。这样 `eth_sendRawTransaction` 才能享受和
+    /// `eth_sendTransaction` 一样的 nonce/余额/gas/签名校验，不会沦为绕开
+    /// REVM 执行路径的后门。
     async fn process_raw_transaction(
         &self,
         data: String,
     ) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
         let hex_data = Self::ensure_hex_format(&data);
-
-        // 确保 topic 已注册（只会执行一次）
-        self.ensure_topic_registered().await?;
-
-        // 使用批量处理器或直接写入
-        if let Some(batch_processor) = &self.batch_processor {
-            batch_processor.submit(hex_data).await
-        } else {
-            self.write_to_walrus(hex_data).await
-        }
+        let hex_clean = hex_data
+            .trim_start_matches("0x")
+            .trim_start_matches("0X");
+        let bytes = hex::decode(hex_clean)
+            .map_err(|e| CodecError::Deserialization.into_error_object(format!("无效的十六进制编码: {}", e)))?;
+        let json_str = String::from_utf8(bytes)
+            .map_err(|e| CodecError::Deserialization.into_error_object(format!("无效的 UTF-8: {}", e)))?;
+        let tx: Transaction = serde_json::from_str(&json_str)
+            .map_err(|e| CodecError::Deserialization.into_error_object(format!("JSON 解析失败: {}", e)))?;
+
+        self.process_transaction(tx).await
     }
 
     /// 直接写入 Walrus
@@ -367,7 +610,7 @@ impl WalrusRpcServer {
         self.walrus_client
             .put(&self.default_topic, &hex_data)
             .await
-            .map_err(|e| RpcError::WalrusWriteFailed.into_error_object(e.to_string()))?;
+            .map_err(|e| WalrusError::WriteFailed.into_error_object(e.to_string()))?;
 
         let duration = start.elapsed();
         WALRUS_WRITE_DURATION
@@ -375,10 +618,9 @@ impl WalrusRpcServer {
             .observe(duration.as_secs_f64());
 
         // 返回交易哈希
-        let mut hasher = Sha256::new();
-        hasher.update(hex_data.as_bytes());
-        let hash_bytes = hasher.finalize();
-        Ok(format!("0x{}", hex::encode(hash_bytes)))
+        let hash = compute_tx_hash(&hex_data);
+        self.store.mark_written(&hash);
+        Ok(hash)
     }
 }
 
@@ -389,6 +631,7 @@ impl WalrusRpcApiServer for WalrusRpcServer {
         tx: Transaction,
     ) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
         let start = Instant::now();
+        let _concurrency = ConcurrentRequestGuard::new("send_transaction");
         TRANSACTIONS_TOTAL
             .with_label_values(&["send_transaction"])
             .inc();
@@ -398,7 +641,7 @@ impl WalrusRpcApiServer for WalrusRpcServer {
             .semaphore
             .acquire()
             .await
-            .map_err(|_| RpcError::InternalError.into_error_object("获取并发许可失败"))?;
+            .map_err(|_| ProtocolError::InternalError.into_error_object("获取并发许可失败"))?;
 
         debug!("收到交易: from={}, to={:?}", tx.from, tx.to);
 
@@ -425,7 +668,7 @@ impl WalrusRpcApiServer for WalrusRpcServer {
                 TRANSACTIONS_FAILED
                     .with_label_values(&["send_transaction", "timeout"])
                     .inc();
-                Err(RpcError::RequestTimeout.into_error_object("请求超时"))
+                Err(ProtocolError::RequestTimeout.into_error_object("请求超时"))
             }
         }
     }
@@ -435,6 +678,7 @@ impl WalrusRpcApiServer for WalrusRpcServer {
         data: String,
     ) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
         let start = Instant::now();
+        let _concurrency = ConcurrentRequestGuard::new("send_raw_transaction");
         TRANSACTIONS_TOTAL
             .with_label_values(&["send_raw_transaction"])
             .inc();
@@ -444,7 +688,7 @@ impl WalrusRpcApiServer for WalrusRpcServer {
             .semaphore
             .acquire()
             .await
-            .map_err(|_| RpcError::InternalError.into_error_object("获取并发许可失败"))?;
+            .map_err(|_| ProtocolError::InternalError.into_error_object("获取并发许可失败"))?;
 
         debug!("收到原始交易数据: {} bytes", data.len());
 
@@ -472,11 +716,184 @@ impl WalrusRpcApiServer for WalrusRpcServer {
                 TRANSACTIONS_FAILED
                     .with_label_values(&["send_raw_transaction", "timeout"])
                     .inc();
-                Err(RpcError::RequestTimeout.into_error_object("请求超时"))
+                Err(ProtocolError::RequestTimeout.into_error_object("请求超时"))
             }
         }
     }
 
+    async fn call(
+        &self,
+        tx: Transaction,
+    ) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let result = engine
+            .simulate(&tx)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        let output = result.output.unwrap_or_default();
+        Ok(format!("0x{}", hex::encode(output)))
+    }
+
+    async fn estimate_gas(
+        &self,
+        tx: Transaction,
+    ) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let result = engine
+            .simulate(&tx)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        if !result.success {
+            return Err(TransactionError::InvalidTransaction.into_error_object("交易执行回滚 (revert)"));
+        }
+
+        Ok(format!("0x{:x}", result.gas_used))
+    }
+
+    async fn get_transaction_receipt(
+        &self,
+        hash: String,
+    ) -> Result<Option<serde_json::Value>, jsonrpsee::types::ErrorObjectOwned> {
+        Ok(self.store.receipt(&hash))
+    }
+
+    async fn get_transaction_status(
+        &self,
+        hash: String,
+    ) -> Result<Option<String>, jsonrpsee::types::ErrorObjectOwned> {
+        Ok(self.store.status(&hash).map(|s| s.as_str().to_string()))
+    }
+
+    async fn debug_trace_transaction(
+        &self,
+        tx: Transaction,
+        options: Option<TraceOptions>,
+    ) -> Result<serde_json::Value, jsonrpsee::types::ErrorObjectOwned> {
+        if !self.enable_debug_trace {
+            return Err(ProtocolError::InternalError.into_error_object("debug_traceTransaction 未启用"));
+        }
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let defaults = block_producer::executor::TraceConfig::default();
+        let options = options.unwrap_or_default();
+        let config = block_producer::executor::TraceConfig {
+            disable_stack: options.disable_stack.unwrap_or(defaults.disable_stack),
+            disable_memory: options.disable_memory.unwrap_or(defaults.disable_memory),
+            disable_storage: options.disable_storage.unwrap_or(defaults.disable_storage),
+        };
+
+        let logs = engine
+            .trace(&tx, config)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        serde_json::to_value(logs)
+            .map_err(|e| CodecError::Serialization.into_error_object(e.to_string()))
+    }
+
+    async fn execute_block(
+        &self,
+        txs: Vec<Transaction>,
+    ) -> Result<serde_json::Value, jsonrpsee::types::ErrorObjectOwned> {
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let result = engine
+            .execute_block(&txs)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        serde_json::to_value(result)
+            .map_err(|e| CodecError::Serialization.into_error_object(e.to_string()))
+    }
+
+    async fn get_balance(&self, address: String) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let address: alloy_primitives::Address = address
+            .parse()
+            .map_err(|e| TransactionError::InvalidTransaction.into_error_object(format!("Invalid address: {}", e)))?;
+
+        let balance = engine
+            .balance(address)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        Ok(format!("0x{:x}", balance))
+    }
+
+    async fn get_code(&self, address: String) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let address: alloy_primitives::Address = address
+            .parse()
+            .map_err(|e| TransactionError::InvalidTransaction.into_error_object(format!("Invalid address: {}", e)))?;
+
+        let code = engine
+            .code(address)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        Ok(format!("0x{}", hex::encode(code)))
+    }
+
+    async fn get_logs(
+        &self,
+        filter: GetLogsFilter,
+    ) -> Result<Vec<serde_json::Value>, jsonrpsee::types::ErrorObjectOwned> {
+        let engine = self
+            .execution
+            .as_ref()
+            .ok_or_else(|| ProtocolError::InternalError.into_error_object("执行引擎未启用"))?;
+
+        let address = filter
+            .address
+            .as_deref()
+            .map(|a| a.parse::<alloy_primitives::Address>())
+            .transpose()
+            .map_err(|e| TransactionError::InvalidTransaction.into_error_object(format!("Invalid address: {}", e)))?;
+
+        let topic = filter
+            .topic
+            .as_deref()
+            .map(|t| t.parse::<alloy_primitives::B256>())
+            .transpose()
+            .map_err(|e| TransactionError::InvalidTransaction.into_error_object(format!("Invalid topic: {}", e)))?;
+
+        let logs = engine
+            .get_logs(filter.from_block, filter.to_block, address, topic)
+            .await
+            .map_err(|e| error::executor_error_to_object(&e))?;
+
+        logs.into_iter()
+            .map(|log| {
+                serde_json::to_value(log)
+                    .map_err(|e| CodecError::Serialization.into_error_object(e.to_string()))
+            })
+            .collect()
+    }
+
     async fn health(&self) -> Result<String, jsonrpsee::types::ErrorObjectOwned> {
         // 通过调用 Walrus METRICS 命令验证连接状态
         match self.walrus_client.metrics().await {
@@ -486,13 +903,65 @@ impl WalrusRpcApiServer for WalrusRpcServer {
             }
             Err(e) => {
                 warn!("❌ 健康检查失败: Walrus 连接异常 - {}", e);
-                Err(RpcError::WalrusConnectionFailed.into_error_object(e.to_string()))
+                Err(WalrusError::ConnectionFailed.into_error_object(e.to_string()))
             }
         }
     }
+
+    async fn subscribe_transaction_confirmation(
+        &self,
+        pending: PendingSubscriptionSink,
+        hash: String,
+    ) -> SubscriptionResult {
+        let sink = pending.accept().await?;
+
+        // 订阅建立前交易可能已经确认，先查一次存量收据，命中就直接推送并结束
+        if let Some(receipt) = self.store.receipt(&hash) {
+            let _ = sink.send(SubscriptionMessage::from_json(&receipt)?).await;
+            return Ok(());
+        }
+
+        let store = Arc::clone(&self.store);
+        let mut confirmations = self.store.subscribe_confirmations();
+        tokio::spawn(async move {
+            loop {
+                match confirmations.recv().await {
+                    Ok((confirmed_hash, receipt)) if confirmed_hash == hash => {
+                        if let Ok(message) = SubscriptionMessage::from_json(&receipt) {
+                            let _ = sink.send(message).await;
+                        }
+                        break;
+                    }
+                    Ok(_) => continue,
+                    // 订阅者落后太多，被 broadcast channel 丢弃的历史事件里可能正好有
+                    // 自己这笔交易的确认：重新查一次存量收据兜底，命中就推送并结束，
+                    // 否则继续等待后续确认
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {
+                        if let Some(receipt) = store.receipt(&hash) {
+                            if let Ok(message) = SubscriptionMessage::from_json(&receipt) {
+                                let _ = sink.send(message).await;
+                            }
+                            break;
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        });
+
+        Ok(())
+    }
 }
 
-async fn start_rpc_server(args: Args) -> Result<ServerHandle> {
+/// 启动后返回的运行时句柄集合，用于在关闭时进行有序排空
+struct RunningServer {
+    handle: ServerHandle,
+    batch_processor: Option<Arc<BatchProcessor>>,
+    semaphore: Arc<Semaphore>,
+    max_concurrent_requests: usize,
+}
+
+async fn start_rpc_server(args: Args) -> Result<RunningServer> {
     let bind_addr = format!("{}:{}", args.rpc_host, args.rpc_port);
 
     info!("启动 JSON-RPC 服务器: {}", bind_addr);
@@ -508,6 +977,15 @@ async fn start_rpc_server(args: Args) -> Result<ServerHandle> {
     // 我们在应用层使用 Semaphore 来控制并发
     let server = Server::builder().build(&bind_addr).await?;
 
+    // 根据配置决定是否启用 REVM 执行路径
+    let execution = match &args.state_db {
+        Some(path) => {
+            info!("启用 REVM 执行路径, 状态数据库: {}", path);
+            Some(Arc::new(ExecutionEngine::new(path)?))
+        }
+        None => None,
+    };
+
     let rpc_impl = WalrusRpcServer::new(
         args.walrus_addr.clone(),
         args.default_topic.clone(),
@@ -516,8 +994,15 @@ async fn start_rpc_server(args: Args) -> Result<ServerHandle> {
         args.max_batch_size,
         Duration::from_secs(args.request_timeout_secs),
         args.batch_interval_ms > 0, // 启用批量处理
+        execution,
+        args.enable_debug_trace,
     );
 
+    // 在 into_rpc 消费 rpc_impl 之前，保留关闭时排空所需的句柄
+    let batch_processor = rpc_impl.batch_processor.clone();
+    let semaphore = Arc::clone(&rpc_impl.semaphore);
+    let max_concurrent_requests = args.max_concurrent_requests;
+
     let handle = server.start(rpc_impl.into_rpc());
 
     info!("✅ JSON-RPC 服务器已启动，监听地址: {}", bind_addr);
@@ -528,7 +1013,12 @@ async fn start_rpc_server(args: Args) -> Result<ServerHandle> {
         args.rpc_port + 1
     );
 
-    Ok(handle)
+    Ok(RunningServer {
+        handle,
+        batch_processor,
+        semaphore,
+        max_concurrent_requests,
+    })
 }
 
 /// 启动 Prometheus metrics HTTP 服务器
@@ -571,13 +1061,53 @@ async fn main() -> Result<()> {
     });
 
     // 启动 RPC 服务器
-    let handle = start_rpc_server(args).await?;
+    let RunningServer {
+        handle,
+        batch_processor,
+        semaphore,
+        max_concurrent_requests,
+    } = start_rpc_server(args).await?;
 
     info!("🚀 RPC Gateway 已完全启动");
     info!("💡 按 Ctrl+C 退出");
 
-    // 保持运行
+    // 等待 SIGINT/SIGTERM
+    wait_for_shutdown_signal().await;
+    info!("收到退出信号, 开始有序关闭...");
+
+    // 1. 先停止 RPC 服务器接收新请求
+    handle.stop()?;
+
+    // 2. flush 批量处理器中在途的交易
+    if let Some(processor) = batch_processor {
+        processor.shutdown().await;
+    }
+
+    // 3. 等待仍在处理中的请求释放并发许可（获取全部许可即代表已排空）
+    let _ = semaphore.acquire_many(max_concurrent_requests as u32).await;
+
+    // 4. 等待服务器完全停止
     handle.stopped().await;
 
+    info!("✅ 已安全关闭");
     Ok(())
 }
+
+/// 等待 SIGINT（Ctrl+C）或 SIGTERM 信号
+async fn wait_for_shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut sigterm =
+            signal(SignalKind::terminate()).expect("无法注册 SIGTERM 处理器");
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {},
+            _ = sigterm.recv() => {},
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
@@ -2,7 +2,10 @@
 ///
 /// 使用 Prometheus 格式的指标，用于监控 RPC Gateway 的性能
 use lazy_static::lazy_static;
-use prometheus::{register_counter_vec, register_histogram_vec, CounterVec, HistogramVec};
+use prometheus::{
+    register_counter_vec, register_histogram_vec, register_int_gauge, register_int_gauge_vec,
+    CounterVec, HistogramVec, IntGauge, IntGaugeVec,
+};
 
 lazy_static! {
     /// 交易总数（按类型统计：send_transaction, send_raw_transaction）
@@ -39,10 +42,10 @@ lazy_static! {
     )
     .expect("Failed to register walrus_write_duration metric");
 
-    /// 并发请求数
-    pub static ref CONCURRENT_REQUESTS: CounterVec = register_counter_vec!(
+    /// 当前正在处理的并发请求数（实时值，通过 [`ConcurrentRequestGuard`] 维护）
+    pub static ref CONCURRENT_REQUESTS: IntGaugeVec = register_int_gauge_vec!(
         "rpc_gateway_concurrent_requests",
-        "Number of concurrent requests being processed",
+        "Number of concurrent requests currently being processed",
         &["method"]
     )
     .expect("Failed to register concurrent_requests metric");
@@ -55,6 +58,32 @@ lazy_static! {
         vec![1.0, 5.0, 10.0, 20.0, 50.0, 100.0, 200.0, 500.0]
     )
     .expect("Failed to register batch_size metric");
+
+    /// 当前被跟踪的在途交易数量
+    pub static ref TRACKED_TRANSACTIONS: IntGauge = register_int_gauge!(
+        "rpc_gateway_tracked_transactions",
+        "Number of in-flight transactions currently tracked in the status store"
+    )
+    .expect("Failed to register tracked_transactions metric");
+}
+
+/// RAII 守卫：构造时把 `CONCURRENT_REQUESTS[method]` 加一，`Drop` 时减一，
+/// 使该指标反映当前真实的并发数，而不是单调递增的请求总数
+pub struct ConcurrentRequestGuard {
+    method: &'static str,
+}
+
+impl ConcurrentRequestGuard {
+    pub fn new(method: &'static str) -> Self {
+        CONCURRENT_REQUESTS.with_label_values(&[method]).inc();
+        Self { method }
+    }
+}
+
+impl Drop for ConcurrentRequestGuard {
+    fn drop(&mut self) {
+        CONCURRENT_REQUESTS.with_label_values(&[self.method]).dec();
+    }
 }
 
 /// 获取所有指标的文本格式输出（用于 /metrics 端点）
@@ -0,0 +1,45 @@
+//! 按发送方的 nonce 顺序闩锁
+//!
+//! 批量写入会把短时间内的交易并发提交到 Walrus，这会打乱同一发送方
+//! 多笔交易的顺序。本模块提供一个按发送方分片的闩锁管理器：同一 `from`
+//! 的交易在进入批量写入之前必须串行地通过闩锁，从而保证先提交的 nonce
+//! 先落盘，不同发送方之间互不阻塞。
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tokio::sync::{Mutex, OwnedMutexGuard};
+
+/// 按发送方的 nonce 顺序闩锁管理器
+pub struct NonceLatchManager {
+    /// 每个发送方一把锁，保证同一发送方的交易串行通过
+    latches: DashMap<String, Arc<Mutex<()>>>,
+}
+
+impl NonceLatchManager {
+    /// 创建管理器
+    pub fn new() -> Self {
+        Self {
+            latches: DashMap::new(),
+        }
+    }
+
+    /// 获取指定发送方的闩锁
+    ///
+    /// 返回的 guard 在持有期间保证该发送方的其它交易等待，
+    /// guard 释放（drop）后下一笔交易才能继续，从而维持提交顺序。
+    pub async fn acquire(&self, sender: &str) -> OwnedMutexGuard<()> {
+        let latch = self
+            .latches
+            .entry(sender.to_string())
+            .or_insert_with(|| Arc::new(Mutex::new(())))
+            .clone();
+        latch.lock_owned().await
+    }
+}
+
+impl Default for NonceLatchManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,157 @@
+//! 交易状态 / 收据跟踪
+//!
+//! 网关在返回交易哈希后需要能够回答“这笔交易后来怎么样了”。
+//! 本模块用一个分片并发映射（`dashmap`）按交易哈希记录其生命周期状态
+//! 以及执行后的收据摘要，并提供有界 TTL 淘汰，避免在持续负载下无限增长。
+
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tokio::sync::broadcast;
+
+use crate::metrics::TRACKED_TRANSACTIONS;
+
+/// 广播给等待中的 WebSocket 订阅者的确认事件（交易哈希, 收据摘要）
+///
+/// 容量足够吸收短时间内的突发确认，订阅者掉队（跟不上广播速度）时只会
+/// 丢失较早的事件，不影响后续确认的推送。
+const CONFIRMATION_CHANNEL_CAPACITY: usize = 1024;
+
+/// 交易生命周期状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TxStatus {
+    /// 已受理，尚未写入
+    Pending,
+    /// 已写入 Walrus
+    Written,
+    /// 已通过 EVM 执行并成功
+    Executed,
+    /// 执行或写入失败
+    Failed,
+}
+
+impl TxStatus {
+    /// 状态的字符串表示
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TxStatus::Pending => "pending",
+            TxStatus::Written => "written",
+            TxStatus::Executed => "executed",
+            TxStatus::Failed => "failed",
+        }
+    }
+}
+
+/// 单笔被跟踪的交易
+#[derive(Debug, Clone)]
+struct TrackedTx {
+    status: TxStatus,
+    /// 执行后的收据摘要（JSON），执行器运行后填充
+    receipt: Option<serde_json::Value>,
+    /// 最近一次更新时间，用于 TTL 淘汰
+    updated_at: Instant,
+}
+
+/// 交易状态存储
+///
+/// 线程安全、可在多个异步任务间共享；条目超过 `ttl` 后会在下次写入时被清理。
+pub struct TransactionStore {
+    map: DashMap<String, TrackedTx>,
+    ttl: Duration,
+    /// 收据落地时向 WebSocket 订阅者广播 `(tx_hash, receipt)`
+    confirmations: broadcast::Sender<(String, serde_json::Value)>,
+}
+
+impl TransactionStore {
+    /// 创建存储，`ttl` 为单条记录的最长保留时间
+    pub fn new(ttl: Duration) -> Self {
+        let (confirmations, _) = broadcast::channel(CONFIRMATION_CHANNEL_CAPACITY);
+        Self {
+            map: DashMap::new(),
+            ttl,
+            confirmations,
+        }
+    }
+
+    /// 订阅交易确认事件，供 `eth_subscribe` 风格的 WebSocket 端点推送
+    pub fn subscribe_confirmations(&self) -> broadcast::Receiver<(String, serde_json::Value)> {
+        self.confirmations.subscribe()
+    }
+
+    /// 记录一笔新受理的交易（`Pending`）
+    pub fn insert_pending(&self, hash: &str) {
+        self.sweep_expired();
+        self.map.insert(
+            hash.to_string(),
+            TrackedTx {
+                status: TxStatus::Pending,
+                receipt: None,
+                updated_at: Instant::now(),
+            },
+        );
+        self.update_gauge();
+    }
+
+    /// 仅更新状态（写入成功 / 失败等）
+    pub fn set_status(&self, hash: &str, status: TxStatus) {
+        if let Some(mut entry) = self.map.get_mut(hash) {
+            entry.status = status;
+            entry.updated_at = Instant::now();
+        }
+    }
+
+    /// 标记为已写入
+    ///
+    /// 仅在当前仍为 `Pending` 时生效，避免覆盖执行器已经写入的
+    /// `Executed`/`Failed` 终态。
+    pub fn mark_written(&self, hash: &str) {
+        if let Some(mut entry) = self.map.get_mut(hash) {
+            if entry.status == TxStatus::Pending {
+                entry.status = TxStatus::Written;
+                entry.updated_at = Instant::now();
+            }
+        }
+    }
+
+    /// 写入执行后的收据摘要并更新状态，随后向订阅者广播确认事件
+    ///
+    /// 广播在更新之后进行：订阅者收到事件时，通过 [`Self::receipt`] 轮询
+    /// 到的状态必然已经是最新的，不存在先收到推送、再读到旧状态的竞态。
+    pub fn set_receipt(&self, hash: &str, status: TxStatus, receipt: serde_json::Value) {
+        if let Some(mut entry) = self.map.get_mut(hash) {
+            entry.status = status;
+            entry.receipt = Some(receipt.clone());
+            entry.updated_at = Instant::now();
+        } else {
+            return;
+        }
+
+        // 没有订阅者时发送会返回 Err(SendError)，属于正常情况，忽略即可
+        let _ = self.confirmations.send((hash.to_string(), receipt));
+    }
+
+    /// 读取交易状态
+    pub fn status(&self, hash: &str) -> Option<TxStatus> {
+        self.map.get(hash).map(|e| e.status)
+    }
+
+    /// 读取交易收据摘要
+    pub fn receipt(&self, hash: &str) -> Option<serde_json::Value> {
+        self.map.get(hash).and_then(|e| e.receipt.clone())
+    }
+
+    /// 清理过期条目
+    fn sweep_expired(&self) {
+        let now = Instant::now();
+        self.map
+            .retain(|_, entry| now.duration_since(entry.updated_at) < self.ttl);
+        self.update_gauge();
+    }
+
+    /// 同步 Prometheus 指标（当前跟踪的在途交易数）
+    fn update_gauge(&self) {
+        TRACKED_TRANSACTIONS.set(self.map.len() as i64);
+    }
+}
@@ -0,0 +1,262 @@
+//! 网关压测 / 基准测试工具
+//!
+//! 以可复现的方式向 RPC Gateway 灌入合成交易，测量提交吞吐量、
+//! 单请求延迟分布与错误率。所有随机负载均由带种子的 ChaCha RNG 生成，
+//! 同一 `--seed` 多次运行得到完全相同的请求序列，便于对比调参
+//! （`batch_interval_ms`、`max_batch_size`）前后的 Prometheus
+//! `TRANSACTION_DURATION` / `BATCH_SIZE` 直方图。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use clap::{Parser, ValueEnum};
+use rand::{Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// 合成负载类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Payload {
+    /// 结构化交易，经 `eth_sendTransaction` 提交
+    Structured,
+    /// 随机字节原始负载，经 `eth_sendRawTransaction` 提交，用于覆盖
+    /// `ensure_hex_format`；随机字节不是网关接受的 hex(JSON) 交易信封，
+    /// 会在解码阶段被拒绝，不会进入批量写入路径
+    Raw,
+}
+
+/// 网关压测工具
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// RPC Gateway 地址
+    #[arg(long, default_value = "http://localhost:8545")]
+    rpc_url: String,
+
+    /// 发送的交易总数
+    #[arg(long, default_value = "1000")]
+    count: usize,
+
+    /// 并发请求数
+    #[arg(long, default_value = "32")]
+    concurrency: usize,
+
+    /// RNG 种子，保证负载可复现
+    #[arg(long, default_value = "42")]
+    seed: u64,
+
+    /// 负载类型
+    #[arg(long, value_enum, default_value_t = Payload::Structured)]
+    payload: Payload,
+}
+
+/// JSON-RPC 请求
+#[derive(Debug, Serialize)]
+struct JsonRpcRequest {
+    jsonrpc: String,
+    method: String,
+    params: Vec<serde_json::Value>,
+    id: u64,
+}
+
+/// JSON-RPC 响应
+#[derive(Debug, Deserialize)]
+struct JsonRpcResponse {
+    #[serde(default)]
+    result: Option<serde_json::Value>,
+    #[serde(default)]
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+/// 结构化交易负载（与网关的 `Transaction` 字段保持一致）
+#[derive(Debug, Serialize)]
+struct SyntheticTx {
+    from: String,
+    to: Option<String>,
+    value: String,
+    data: String,
+    gas: String,
+    nonce: String,
+}
+
+/// 单次请求的结果
+struct Sample {
+    latency: Duration,
+    ok: bool,
+}
+
+/// 从 RNG 生成一个 `0x` 前缀的十六进制地址
+fn random_address(rng: &mut ChaCha8Rng) -> String {
+    let mut bytes = [0u8; 20];
+    rng.fill_bytes(&mut bytes);
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// 构造一个请求体
+///
+/// 结构化负载走 `eth_sendTransaction`；原始负载生成一段随机长度的字节串
+/// 并**故意不加** `0x` 前缀，用于触发网关侧的 `ensure_hex_format` 补全逻辑。
+fn build_request(payload: Payload, rng: &mut ChaCha8Rng, id: u64) -> JsonRpcRequest {
+    match payload {
+        Payload::Structured => {
+            let from = random_address(rng);
+            let to = random_address(rng);
+            let value: u64 = rng.gen_range(1..1_000_000);
+            let nonce: u64 = rng.gen();
+            let tx = SyntheticTx {
+                from,
+                to: Some(to),
+                value: format!("0x{value:x}"),
+                data: "0x".to_string(),
+                gas: "0x5208".to_string(),
+                nonce: format!("0x{nonce:x}"),
+            };
+            JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "eth_sendTransaction".to_string(),
+                params: vec![serde_json::to_value(tx).unwrap()],
+                id,
+            }
+        }
+        Payload::Raw => {
+            let len = rng.gen_range(16..256);
+            let mut bytes = vec![0u8; len];
+            rng.fill_bytes(&mut bytes);
+            // 不带 0x 前缀，交由网关的 ensure_hex_format 处理
+            let raw = hex::encode(bytes);
+            JsonRpcRequest {
+                jsonrpc: "2.0".to_string(),
+                method: "eth_sendRawTransaction".to_string(),
+                params: vec![serde_json::json!(raw)],
+                id,
+            }
+        }
+    }
+}
+
+/// 发送单个请求并计时
+async fn fire(client: &reqwest::Client, rpc_url: &str, req: JsonRpcRequest) -> Sample {
+    let start = Instant::now();
+    let ok = match client.post(rpc_url).json(&req).send().await {
+        Ok(resp) => match resp.json::<JsonRpcResponse>().await {
+            Ok(body) => {
+                if let Some(err) = body.error {
+                    warn!("RPC 错误: {} ({})", err.message, err.code);
+                    false
+                } else {
+                    body.result.is_some()
+                }
+            }
+            Err(e) => {
+                warn!("响应解析失败: {}", e);
+                false
+            }
+        },
+        Err(e) => {
+            warn!("请求失败: {}", e);
+            false
+        }
+    };
+    Sample {
+        latency: start.elapsed(),
+        ok,
+    }
+}
+
+/// 计算百分位延迟（输入需已排序，单位毫秒）
+fn percentile(sorted_ms: &[f64], p: f64) -> f64 {
+    if sorted_ms.is_empty() {
+        return 0.0;
+    }
+    let rank = (p / 100.0 * (sorted_ms.len() - 1) as f64).round() as usize;
+    sorted_ms[rank]
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    fmt::Subscriber::builder()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
+        .init();
+
+    let args = Args::parse();
+
+    info!(
+        "🚀 开始压测: {} 笔交易, 并发 {}, 负载 {:?}, 种子 {}",
+        args.count, args.concurrency, args.payload, args.seed
+    );
+
+    // 预先用固定种子生成全部请求，保证运行可复现
+    let mut rng = ChaCha8Rng::seed_from_u64(args.seed);
+    let requests: Vec<JsonRpcRequest> = (0..args.count)
+        .map(|i| build_request(args.payload, &mut rng, i as u64 + 1))
+        .collect();
+
+    let client = reqwest::Client::new();
+    let semaphore = Arc::new(Semaphore::new(args.concurrency));
+    let rpc_url = Arc::new(args.rpc_url);
+
+    let wall_start = Instant::now();
+    let mut handles = Vec::with_capacity(requests.len());
+    for req in requests {
+        let permit = semaphore.clone().acquire_owned().await.unwrap();
+        let client = client.clone();
+        let rpc_url = rpc_url.clone();
+        handles.push(tokio::spawn(async move {
+            let sample = fire(&client, &rpc_url, req).await;
+            drop(permit);
+            sample
+        }));
+    }
+
+    let mut samples = Vec::with_capacity(handles.len());
+    for handle in handles {
+        if let Ok(sample) = handle.await {
+            samples.push(sample);
+        }
+    }
+    let wall = wall_start.elapsed();
+
+    // 汇总统计
+    let total = samples.len();
+    let errors = samples.iter().filter(|s| !s.ok).count();
+    let mut latencies_ms: Vec<f64> = samples
+        .iter()
+        .map(|s| s.latency.as_secs_f64() * 1000.0)
+        .collect();
+    latencies_ms.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+    let throughput = total as f64 / wall.as_secs_f64();
+    let error_rate = if total == 0 {
+        0.0
+    } else {
+        errors as f64 / total as f64 * 100.0
+    };
+
+    println!();
+    println!("📊 压测结果");
+    println!("  总请求:   {total}");
+    println!("  耗时:     {:.2} s", wall.as_secs_f64());
+    println!("  吞吐量:   {throughput:.1} tx/s");
+    println!("  错误率:   {error_rate:.2}% ({errors} 笔)");
+    println!("  延迟 p50: {:.2} ms", percentile(&latencies_ms, 50.0));
+    println!("  延迟 p90: {:.2} ms", percentile(&latencies_ms, 90.0));
+    println!("  延迟 p99: {:.2} ms", percentile(&latencies_ms, 99.0));
+    println!(
+        "  延迟 max: {:.2} ms",
+        latencies_ms.last().copied().unwrap_or(0.0)
+    );
+    println!();
+    println!("💡 可与网关 /metrics 中的 TRANSACTION_DURATION / BATCH_SIZE 直方图对比，验证批量调参效果");
+
+    Ok(())
+}
@@ -1,10 +1,26 @@
 use alloy::signers::local::PrivateKeySigner;
+use alloy_primitives::B256;
 use anyhow::Result;
+use block_producer::wallet::keystore;
 use std::fs::File;
 use std::io::Write;
 
+/// 所有生成的 keystore 共用的口令来源：部署时应当换成从环境变量/密钥管理
+/// 服务读取的真实口令，这里只是给批量生成脚本一个占位值。
+const KEYSTORE_PASSPHRASE_ENV: &str = "KEYSTORE_PASSPHRASE";
+const DEFAULT_DEV_PASSPHRASE: &str = "dev-only-insecure-passphrase";
+
 fn main() -> Result<()> {
-    println!("🔑 开始生成 50 个私钥和对应的钱包地址...");
+    println!("🔑 开始生成 50 个私钥、对应的钱包地址，并加密为 V3 keystore 文件...");
+
+    let passphrase =
+        std::env::var(KEYSTORE_PASSPHRASE_ENV).unwrap_or_else(|_| DEFAULT_DEV_PASSPHRASE.to_string());
+    if passphrase == DEFAULT_DEV_PASSPHRASE {
+        println!(
+            "⚠️  未设置 {} 环境变量，使用仅供开发测试的默认口令",
+            KEYSTORE_PASSPHRASE_ENV
+        );
+    }
 
     // 基础私钥：0x2222222222222222222222222222222222222222222222222222222222220000
     let base_private_key = "0x2222222222222222222222222222222222222222222222222222222222220000";
@@ -18,12 +34,11 @@ fn main() -> Result<()> {
         anyhow::bail!("基础私钥长度不正确，应该是 32 字节");
     }
 
-    // 创建输出文件
-    let mut csv_file = File::create("generated_keys.csv")?;
-    writeln!(csv_file, "index,private_key,address")?;
+    std::fs::create_dir_all("generated_keystores")?;
 
-    let mut json_file = File::create("generated_keys.json")?;
-    writeln!(json_file, "[")?;
+    // 地址索引文件：只记录索引 -> 地址的映射，不包含任何私钥材料
+    let mut index_file = File::create("generated_keys_index.csv")?;
+    writeln!(index_file, "index,address,keystore_file")?;
 
     let total = 50;
     for i in 0..total {
@@ -33,43 +48,31 @@ fn main() -> Result<()> {
         base_bytes[30] = (index >> 8) as u8;
         base_bytes[31] = (index & 0xFF) as u8;
 
-        // 将字节数组转换为十六进制字符串
-        let private_key_hex = format!("0x{}", hex::encode(&base_bytes));
-
         // 从私钥创建签名器（将 Vec<u8> 转换为 [u8; 32]）
         let mut key_bytes = [0u8; 32];
         key_bytes.copy_from_slice(&base_bytes);
         let signer = PrivateKeySigner::from_bytes(&key_bytes.into())?;
         let address = signer.address();
 
-        // 写入 CSV
-        writeln!(csv_file, "{},{},{:?}", i, private_key_hex, address)?;
-
-        // 写入 JSON（除了最后一个，其他后面加逗号）
-        if i < total - 1 {
-            writeln!(
-                json_file,
-                "  {{\"index\": {}, \"private_key\": \"{}\", \"address\": \"{:?}\"}},",
-                i, private_key_hex, address
-            )?;
-        } else {
-            writeln!(
-                json_file,
-                "  {{\"index\": {}, \"private_key\": \"{}\", \"address\": \"{:?}\"}}",
-                i, private_key_hex, address
-            )?;
-        }
+        // 加密为标准 V3 keystore JSON，不再把私钥明文落盘
+        let private_key = B256::from_slice(&base_bytes);
+        let keystore_json = keystore::encrypt(&private_key, &passphrase)
+            .map_err(|e| anyhow::anyhow!("keystore encryption failed for key {}: {}", i, e))?;
+
+        let keystore_path = format!("generated_keystores/key-{:03}.json", i);
+        let mut keystore_file = File::create(&keystore_path)?;
+        writeln!(keystore_file, "{}", serde_json::to_string_pretty(&keystore_json)?)?;
+
+        writeln!(index_file, "{},{:?},{}", i, address, keystore_path)?;
 
         if (i + 1) % 10 == 0 {
             println!("✅ 已生成 {} 个密钥...", i + 1);
         }
     }
 
-    writeln!(json_file, "]")?;
-
-    println!("✅ 完成！已生成 {} 个密钥", total);
-    println!("📄 CSV 文件: generated_keys.csv");
-    println!("📄 JSON 文件: generated_keys.json");
+    println!("✅ 完成！已生成 {} 个加密 keystore 文件", total);
+    println!("📄 地址索引: generated_keys_index.csv");
+    println!("📄 Keystore 目录: generated_keystores/");
 
     Ok(())
 }
@@ -1,15 +1,17 @@
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use alloy::{
-    consensus::{TxLegacy, TxEnvelope},
-    eips::eip2718::Encodable2718,
+    consensus::{Signed, TxEip1559, TxEip2930, TxLegacy, TxEnvelope},
+    eips::{eip2718::Encodable2718, eip2930::AccessList},
     network::TxSigner,
     primitives::{Address, Bytes, U256},
     signers::local::PrivateKeySigner,
 };
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Mutex;
 use tracing::{info, warn};
 use tracing_subscriber::{fmt, EnvFilter};
 
@@ -27,42 +29,126 @@ struct Args {
 enum Command {
     /// 生成新的密钥对
     GenerateKey,
-    
+
     /// 生成并发送单笔交易
     SendTx {
         /// 私钥（64 位十六进制，可选 0x 前缀）
         #[arg(long)]
         private_key: String,
-        
+
         /// 接收地址
         #[arg(long)]
         to: String,
-        
+
         /// 转账金额（ETH）
         #[arg(long, default_value = "1.0")]
         value: f64,
-        
+
         /// RPC Gateway 地址
         #[arg(long, default_value = "http://localhost:8545")]
         rpc_url: String,
+
+        /// 交易信封类型：legacy / eip2930（访问列表）/ eip1559
+        #[arg(long, value_enum, default_value_t = TxTypeArg::Legacy)]
+        tx_type: TxTypeArg,
+
+        /// nonce：`auto`（查询发送方的 pending nonce）或指定数值
+        #[arg(long, default_value = "auto")]
+        nonce: AutoOr<u64>,
+
+        /// gas 价格，单位 Gwei：`auto`（按 eth_gasPrice/eth_feeHistory 估算）或指定数值
+        #[arg(long, default_value = "auto")]
+        gas: AutoOr<u64>,
     },
-    
+
     /// 批量生成测试交易
     BatchGenerate {
         /// 批次大小
         #[arg(long, default_value = "10")]
         count: usize,
-        
+
         /// RPC Gateway 地址
         #[arg(long, default_value = "http://localhost:8545")]
         rpc_url: String,
-        
+
         /// 发送间隔（毫秒）
         #[arg(long, default_value = "100")]
         interval_ms: u64,
+
+        /// 交易信封类型：legacy / eip2930（访问列表）/ eip1559
+        #[arg(long, value_enum, default_value_t = TxTypeArg::Legacy)]
+        tx_type: TxTypeArg,
+
+        /// nonce：`auto`（每个发送方各自查询 pending nonce 起算）或指定起始数值
+        #[arg(long, default_value = "auto")]
+        nonce: AutoOr<u64>,
+
+        /// gas 价格，单位 Gwei：`auto`（按 eth_gasPrice/eth_feeHistory 估算，
+        /// 查询一次后在本批次内复用）或指定数值
+        #[arg(long, default_value = "auto")]
+        gas: AutoOr<u64>,
     },
 }
 
+/// `--nonce`/`--gas` 共用的“auto 或固定值”选择
+///
+/// 默认都是 `auto`：nonce 从网关的 `eth_getTransactionCount(.., "pending")`
+/// 起算，gas 价格从 `eth_gasPrice`/`eth_feeHistory` 估算；传具体数值时按
+/// 原样使用，方便测试钉死确定性的 nonce/gas，不依赖网络状态。
+#[derive(Debug, Clone, Copy)]
+enum AutoOr<T> {
+    Auto,
+    Fixed(T),
+}
+
+impl<T: FromStr> FromStr for AutoOr<T>
+where
+    T::Err: std::fmt::Display,
+{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("auto") {
+            Ok(AutoOr::Auto)
+        } else {
+            s.parse::<T>().map(AutoOr::Fixed).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// 生成的交易信封类型
+///
+/// 让生成器可以按需产出 EIP-2718 定义的三种编码：legacy RLP、EIP-2930
+/// 访问列表信封、EIP-1559 动态手续费信封，以便用真实的类型化交易格式压测
+/// 网关和下游 Walrus 存储/解码路径，而不是只测 legacy 这一条路径。
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TxTypeArg {
+    /// Legacy（RLP 编码，按 EIP-155 把 chain_id 计入签名）
+    Legacy,
+    /// EIP-2930（带访问列表的类型化信封）
+    Eip2930,
+    /// EIP-1559（动态手续费，base fee + 小费）
+    Eip1559,
+}
+
+/// 签名前的交易，按 [`TxTypeArg`] 区分具体信封类型
+enum UnsignedTransaction {
+    Legacy(TxLegacy),
+    Eip2930(TxEip2930),
+    Eip1559(TxEip1559),
+}
+
+/// 解析后的 gas 定价，单位 wei
+///
+/// legacy/EIP-2930 只有单一的 `gas_price`；EIP-1559 需要
+/// `max_fee_per_gas`（发送方愿意支付的上限）与 `max_priority_fee_per_gas`
+/// （给矿工/验证者的小费）两个独立的数值。
+#[derive(Debug, Clone, Copy)]
+enum GasPricing {
+    Legacy { gas_price: u128 },
+    Eip1559 { max_fee_per_gas: u128, max_priority_fee_per_gas: u128 },
+}
+
 /// JSON-RPC 请求
 #[derive(Debug, Serialize)]
 struct JsonRpcRequest {
@@ -89,10 +175,35 @@ struct JsonRpcError {
     message: String,
 }
 
+/// `TxGenerator` 自身产生的交易层错误：签名失败、网关拒绝交易、或响应里
+/// 塞进来的交易信封/结果格式不对——这些都不是 HTTP/网络层的问题，区分出来
+/// 方便调用方判断是该重试还是该放弃整笔交易。沿途函数仍然返回
+/// `anyhow::Result`，这里的具体变体经由 `?` 自动转换为 `anyhow::Error`。
+#[derive(Debug, thiserror::Error)]
+enum TransactionError {
+    #[error("交易签名失败: {0}")]
+    SigningFailed(String),
+    #[error("RPC Gateway 拒绝了交易: {message} ({code})")]
+    RpcRejected { code: i64, message: String },
+    #[error("交易信封/响应结果格式无效: {0}")]
+    InvalidEnvelope(String),
+}
+
 /// 交易生成器
 struct TxGenerator {
     rpc_url: String,
     client: reqwest::Client,
+
+    /// 每个发送方下一次应使用的 nonce，`auto` 模式下首次用网关查到的 pending
+    /// nonce 播种，此后在本次运行内本地自增，不必每笔交易都查一遍网关
+    nonce_cache: Mutex<HashMap<Address, u64>>,
+
+    /// `auto` 模式下的 legacy gas price，查询一次后在本次运行内复用
+    gas_price_cache: Mutex<Option<u128>>,
+
+    /// `auto` 模式下的 EIP-1559 (max_fee_per_gas, max_priority_fee_per_gas)，
+    /// 查询一次后在本次运行内复用
+    fee_history_cache: Mutex<Option<(u128, u128)>>,
 }
 
 impl TxGenerator {
@@ -100,6 +211,9 @@ impl TxGenerator {
         Self {
             rpc_url,
             client: reqwest::Client::new(),
+            nonce_cache: Mutex::new(HashMap::new()),
+            gas_price_cache: Mutex::new(None),
+            fee_history_cache: Mutex::new(None),
         }
     }
 
@@ -110,48 +224,107 @@ impl TxGenerator {
     }
 
     /// 创建交易
+    ///
+    /// 按 `tx_type` 选择的信封构建对应的未签名交易，gas 定价由调用方通过
+    /// [`TxGenerator::resolve_gas_pricing`] 解析后传入——legacy/EIP-2930 需要
+    /// [`GasPricing::Legacy`]，EIP-1559 需要 [`GasPricing::Eip1559`]，类型不
+    /// 匹配说明调用方传错了，直接 panic 暴露 bug。EIP-2930 带一个空访问
+    /// 列表——生成器本身不预热任何地址，这里只是把信封类型跑通。
     fn create_transaction(
+        tx_type: TxTypeArg,
         to: Address,
         value: U256,
         nonce: u64,
-    ) -> TxLegacy {
-        TxLegacy {
-            chain_id: Some(1337), // 测试链 ID
-            nonce,
-            gas_price: 20_000_000_000, // 20 Gwei
-            gas_limit: 21000,          // 标准转账 Gas
-            to: to.into(),
-            value,
-            input: Bytes::new(),
+        pricing: GasPricing,
+    ) -> UnsignedTransaction {
+        match (tx_type, pricing) {
+            (TxTypeArg::Legacy, GasPricing::Legacy { gas_price }) => {
+                UnsignedTransaction::Legacy(TxLegacy {
+                    chain_id: Some(1337), // 测试链 ID
+                    nonce,
+                    gas_price,
+                    gas_limit: 21000, // 标准转账 Gas
+                    to: to.into(),
+                    value,
+                    input: Bytes::new(),
+                })
+            }
+            (TxTypeArg::Eip2930, GasPricing::Legacy { gas_price }) => {
+                UnsignedTransaction::Eip2930(TxEip2930 {
+                    chain_id: 1337,
+                    nonce,
+                    gas_price,
+                    gas_limit: 21000,
+                    to: to.into(),
+                    value,
+                    input: Bytes::new(),
+                    access_list: AccessList::default(),
+                })
+            }
+            (
+                TxTypeArg::Eip1559,
+                GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas },
+            ) => UnsignedTransaction::Eip1559(TxEip1559 {
+                chain_id: 1337,
+                nonce,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                gas_limit: 21000,
+                to: to.into(),
+                value,
+                input: Bytes::new(),
+                access_list: AccessList::default(),
+            }),
+            (tx_type, pricing) => {
+                unreachable!("gas pricing {:?} does not match tx type {:?}", pricing, tx_type)
+            }
         }
     }
 
     /// 签名交易
+    ///
+    /// 通过 `TxSigner` trait 对对应的信封类型签名，再包进匹配的
+    /// `TxEnvelope` 变体，最后 `encoded_2718()` 得到可以交给
+    /// `eth_sendRawTransaction` 的原始字节。
     async fn sign_transaction(
         signer: &PrivateKeySigner,
-        tx: TxLegacy,
+        tx: UnsignedTransaction,
     ) -> Result<String> {
-        // 使用 TxSigner trait 的 sign_transaction 方法
-        let signature = signer.sign_transaction(&mut tx.clone()).await?;
-        
-        // 构建签名的交易 envelope
-        let envelope = TxEnvelope::Legacy(alloy::consensus::Signed::new_unchecked(
-            tx,
-            signature,
-            Default::default(),
-        ));
-        
+        let envelope = match tx {
+            UnsignedTransaction::Legacy(tx) => {
+                let signature = signer
+                    .sign_transaction(&mut tx.clone())
+                    .await
+                    .map_err(|e| TransactionError::SigningFailed(e.to_string()))?;
+                TxEnvelope::Legacy(Signed::new_unchecked(tx, signature, Default::default()))
+            }
+            UnsignedTransaction::Eip2930(tx) => {
+                let signature = signer
+                    .sign_transaction(&mut tx.clone())
+                    .await
+                    .map_err(|e| TransactionError::SigningFailed(e.to_string()))?;
+                TxEnvelope::Eip2930(Signed::new_unchecked(tx, signature, Default::default()))
+            }
+            UnsignedTransaction::Eip1559(tx) => {
+                let signature = signer
+                    .sign_transaction(&mut tx.clone())
+                    .await
+                    .map_err(|e| TransactionError::SigningFailed(e.to_string()))?;
+                TxEnvelope::Eip1559(Signed::new_unchecked(tx, signature, Default::default()))
+            }
+        };
+
         // 编码为原始交易
         let encoded = envelope.encoded_2718();
         Ok(format!("0x{}", hex::encode(encoded)))
     }
 
-    /// 发送交易到 RPC Gateway
-    async fn send_transaction(&self, raw_tx: &str) -> Result<String> {
+    /// 向 RPC Gateway 发起一次 JSON-RPC 调用，返回 `result` 字段
+    async fn rpc_call(&self, method: &str, params: Vec<serde_json::Value>) -> Result<serde_json::Value> {
         let request = JsonRpcRequest {
             jsonrpc: "2.0".to_string(),
-            method: "eth_sendRawTransaction".to_string(),
-            params: vec![serde_json::json!(raw_tx)],
+            method: method.to_string(),
+            params,
             id: 1,
         };
 
@@ -165,17 +338,151 @@ impl TxGenerator {
         let json_response: JsonRpcResponse = response.json().await?;
 
         if let Some(error) = json_response.error {
-            anyhow::bail!("RPC Error: {} ({})", error.message, error.code);
+            return Err(TransactionError::RpcRejected {
+                code: error.code,
+                message: error.message,
+            }
+            .into());
         }
 
-        let tx_hash = json_response
+        json_response
             .result
-            .ok_or_else(|| anyhow::anyhow!("No result in response"))?
+            .ok_or_else(|| anyhow::anyhow!("No result in response"))
+    }
+
+    /// 发送交易到 RPC Gateway
+    async fn send_transaction(&self, raw_tx: &str) -> Result<String> {
+        let result = self
+            .rpc_call("eth_sendRawTransaction", vec![serde_json::json!(raw_tx)])
+            .await?;
+
+        result
             .as_str()
-            .ok_or_else(|| anyhow::anyhow!("Invalid result format"))?
-            .to_string();
+            .ok_or_else(|| TransactionError::InvalidEnvelope("sendRawTransaction result was not a string".into()))
+            .map(|s| s.to_string())
+    }
 
-        Ok(tx_hash)
+    /// 查询 `address` 的 pending nonce（`eth_getTransactionCount(.., "pending")`）
+    async fn fetch_pending_nonce(&self, address: Address) -> Result<u64> {
+        let result = self
+            .rpc_call(
+                "eth_getTransactionCount",
+                vec![serde_json::json!(format!("{:?}", address)), serde_json::json!("pending")],
+            )
+            .await?;
+
+        let hex = result
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid eth_getTransactionCount result: {:?}", result))?;
+        u64::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .map_err(|e| anyhow::anyhow!("Invalid eth_getTransactionCount hex: {e}"))
+    }
+
+    /// 查询当前 gas price（`eth_gasPrice`），单位 wei
+    async fn fetch_gas_price(&self) -> Result<u128> {
+        let result = self.rpc_call("eth_gasPrice", vec![]).await?;
+
+        let hex = result
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Invalid eth_gasPrice result: {:?}", result))?;
+        u128::from_str_radix(hex.trim_start_matches("0x"), 16)
+            .map_err(|e| anyhow::anyhow!("Invalid eth_gasPrice hex: {e}"))
+    }
+
+    /// 通过 `eth_feeHistory` 估算 EIP-1559 的 `max_fee_per_gas`/`max_priority_fee_per_gas`
+    ///
+    /// 取最近一个区块的 base fee，小费取 50% 分位的 reward；`max_fee_per_gas`
+    /// 按 `2 * base_fee + priority_fee` 预留，覆盖下一区块 base fee 可能的涨幅。
+    async fn fetch_eip1559_fees(&self) -> Result<(u128, u128)> {
+        let result = self
+            .rpc_call(
+                "eth_feeHistory",
+                vec![serde_json::json!("0x1"), serde_json::json!("latest"), serde_json::json!([50])],
+            )
+            .await?;
+
+        let base_fee_hex = result["baseFeePerGas"]
+            .as_array()
+            .and_then(|arr| arr.last())
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| anyhow::anyhow!("Invalid eth_feeHistory result: missing baseFeePerGas"))?;
+        let base_fee = u128::from_str_radix(base_fee_hex.trim_start_matches("0x"), 16)
+            .map_err(|e| anyhow::anyhow!("Invalid eth_feeHistory baseFeePerGas hex: {e}"))?;
+
+        // 拿不到 reward（比如区块没有交易）时退化为 1 Gwei 的保守小费默认值
+        let priority_fee = result["reward"]
+            .as_array()
+            .and_then(|arr| arr.last())
+            .and_then(|rewards| rewards.as_array())
+            .and_then(|rewards| rewards.first())
+            .and_then(|v| v.as_str())
+            .and_then(|hex| u128::from_str_radix(hex.trim_start_matches("0x"), 16).ok())
+            .unwrap_or(1_000_000_000);
+
+        let max_fee = base_fee.saturating_mul(2).saturating_add(priority_fee);
+        Ok((max_fee, priority_fee))
+    }
+
+    /// 解析发送方本次应使用的 nonce
+    ///
+    /// `Fixed(n)` 直接返回 `n`，并把本地计数器推进到 `n + 1`，供本次运行内
+    /// 同一地址的后续调用复用（批量生成里会出现多笔来自同一地址的交易）。
+    /// `Auto` 时若本地计数器里已经有该地址，直接复用并自增；否则向网关查询
+    /// `eth_getTransactionCount(.., "pending")` 作为起始 nonce。
+    async fn resolve_nonce(&self, address: Address, nonce: AutoOr<u64>) -> Result<u64> {
+        if let AutoOr::Fixed(n) = nonce {
+            self.nonce_cache.lock().unwrap().insert(address, n + 1);
+            return Ok(n);
+        }
+
+        let cached = self.nonce_cache.lock().unwrap().get(&address).copied();
+        if let Some(next) = cached {
+            self.nonce_cache.lock().unwrap().insert(address, next + 1);
+            return Ok(next);
+        }
+
+        let pending = self.fetch_pending_nonce(address).await?;
+        self.nonce_cache.lock().unwrap().insert(address, pending + 1);
+        Ok(pending)
+    }
+
+    /// 解析本次交易应使用的 gas 定价
+    ///
+    /// `Fixed(gwei)` 直接按该值构造（legacy/EIP-2930 用作 `gas_price`；
+    /// EIP-1559 用作 `max_fee_per_gas`，小费固定给 1 Gwei，方便测试钉死
+    /// 确定性的结果）。`Auto` 时向网关查询并缓存：legacy/EIP-2930 查
+    /// `eth_gasPrice`，EIP-1559 查 `eth_feeHistory`；同一次运行内后续调用
+    /// 直接复用缓存，不重复请求。
+    async fn resolve_gas_pricing(&self, tx_type: TxTypeArg, gas: AutoOr<u64>) -> Result<GasPricing> {
+        const WEI_PER_GWEI: u128 = 1_000_000_000;
+
+        match (tx_type, gas) {
+            (TxTypeArg::Eip1559, AutoOr::Fixed(gwei)) => Ok(GasPricing::Eip1559 {
+                max_fee_per_gas: gwei as u128 * WEI_PER_GWEI,
+                max_priority_fee_per_gas: WEI_PER_GWEI,
+            }),
+            (_, AutoOr::Fixed(gwei)) => Ok(GasPricing::Legacy {
+                gas_price: gwei as u128 * WEI_PER_GWEI,
+            }),
+            (TxTypeArg::Eip1559, AutoOr::Auto) => {
+                if let Some((max_fee_per_gas, max_priority_fee_per_gas)) = *self.fee_history_cache.lock().unwrap() {
+                    return Ok(GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas });
+                }
+
+                let (max_fee_per_gas, max_priority_fee_per_gas) = self.fetch_eip1559_fees().await?;
+                *self.fee_history_cache.lock().unwrap() = Some((max_fee_per_gas, max_priority_fee_per_gas));
+                Ok(GasPricing::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas })
+            }
+            (_, AutoOr::Auto) => {
+                if let Some(gas_price) = *self.gas_price_cache.lock().unwrap() {
+                    return Ok(GasPricing::Legacy { gas_price });
+                }
+
+                let gas_price = self.fetch_gas_price().await?;
+                *self.gas_price_cache.lock().unwrap() = Some(gas_price);
+                Ok(GasPricing::Legacy { gas_price })
+            }
+        }
     }
 
     /// 生成并发送单笔交易
@@ -184,6 +491,9 @@ impl TxGenerator {
         private_key: &str,
         to_address: &str,
         value_eth: f64,
+        tx_type: TxTypeArg,
+        nonce: AutoOr<u64>,
+        gas: AutoOr<u64>,
     ) -> Result<String> {
         // 1. 加载签名器
         let signer = private_key.parse::<PrivateKeySigner>()?;
@@ -196,9 +506,10 @@ impl TxGenerator {
         // 3. 转换金额（ETH to Wei）
         let value = U256::from((value_eth * 1e18) as u64);
 
-        // 4. 创建交易（使用随机 nonce 用于测试）
-        let nonce = rand::thread_rng().gen::<u32>() as u64;
-        let tx = Self::create_transaction(to, value, nonce);
+        // 4. 向网关查询/解析 nonce 与 gas 定价，再创建交易
+        let nonce = self.resolve_nonce(from_address, nonce).await?;
+        let pricing = self.resolve_gas_pricing(tx_type, gas).await?;
+        let tx = Self::create_transaction(tx_type, to, value, nonce, pricing);
 
         info!("创建交易: {:?} -> {:?}, 金额: {} ETH", from_address, to, value_eth);
 
@@ -214,27 +525,38 @@ impl TxGenerator {
     }
 
     /// 批量生成测试交易
-    async fn batch_generate(&self, count: usize, interval_ms: u64) -> Result<()> {
-        info!("🚀 开始批量生成 {} 笔测试交易", count);
+    async fn batch_generate(
+        &self,
+        count: usize,
+        interval_ms: u64,
+        tx_type: TxTypeArg,
+        nonce: AutoOr<u64>,
+        gas: AutoOr<u64>,
+    ) -> Result<()> {
+        info!("🚀 开始批量生成 {} 笔测试交易（{:?}）", count, tx_type);
 
         for i in 0..count {
             // 生成随机密钥对
             let signer = Self::generate_keypair()?;
-            
+            let from_address = signer.address();
+
             // 生成随机接收地址
             let to_signer = Self::generate_keypair()?;
             let to_address = to_signer.address();
-            
+
             // 随机金额（0.1 - 10 ETH）
             let value_eth = rand::thread_rng().gen_range(0.1..10.0);
-            
-            // 创建交易
-            let nonce = i as u64;
+
+            // 查询/解析 nonce 与 gas 定价，再创建交易
+            let tx_nonce = self.resolve_nonce(from_address, nonce).await?;
+            let pricing = self.resolve_gas_pricing(tx_type, gas).await?;
             let value = U256::from((value_eth * 1e18) as u64);
             let tx = Self::create_transaction(
+                tx_type,
                 to_address,
                 value,
-                nonce,
+                tx_nonce,
+                pricing,
             );
 
             // 签名
@@ -292,9 +614,14 @@ async fn main() -> Result<()> {
             to,
             value,
             rpc_url,
+            tx_type,
+            nonce,
+            gas,
         } => {
             let generator = TxGenerator::new(rpc_url);
-            let tx_hash = generator.generate_and_send(&private_key, &to, value).await?;
+            let tx_hash = generator
+                .generate_and_send(&private_key, &to, value, tx_type, nonce, gas)
+                .await?;
             println!("✅ 交易哈希: {}", tx_hash);
         }
 
@@ -302,9 +629,14 @@ async fn main() -> Result<()> {
             count,
             rpc_url,
             interval_ms,
+            tx_type,
+            nonce,
+            gas,
         } => {
             let generator = TxGenerator::new(rpc_url);
-            generator.batch_generate(count, interval_ms).await?;
+            generator
+                .batch_generate(count, interval_ms, tx_type, nonce, gas)
+                .await?;
         }
     }
 